@@ -67,6 +67,25 @@ impl JsonSchema for BStringLossyUtf8 {
     }
 }
 
+/// A custom `serde` codec for `Option<bstr::BString>` that uses lossy UTF-8 encoding.
+///
+/// Use this with `#[serde(with = "bstring_serde::option_lossy_utf8")]`.
+/// For a JSON schema, pair it with `#[schemars(with = "Option<String>")]`.
+pub mod option_lossy_utf8 {
+    use bstr::BString;
+    use serde::{Deserialize, Serialize};
+
+    pub fn serialize<S: serde::Serializer>(v: &Option<BString>, s: S) -> Result<S::Ok, S::Error> {
+        v.as_ref().map(|b| String::from_utf8_lossy(b)).serialize(s)
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        d: D,
+    ) -> Result<Option<BString>, D::Error> {
+        Ok(Option::<String>::deserialize(d)?.map(BString::from))
+    }
+}
+
 /// A custom `serde` codec for `bstr::BString` that uses standard base64.
 #[derive(Deserialize, Serialize)]
 #[serde(remote = "BString")]
@@ -173,5 +192,16 @@ mod test {
             prop_assert_eq!(v1, v3);
             prop_assert_eq!(v2, v4);
         }
+
+        #[test]
+        fn test_roundtrip_option_lossyutf8_json_1(input: Option<String>) {
+            #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+            struct Test(#[serde(with="option_lossy_utf8")] Option<BString>);
+
+            let v0: Test = Test(input.map(BString::from));
+            let v1: String = serde_json::to_string(&v0).expect("should be able to serialize");
+            let v2: Test = serde_json::from_str(&v1).expect("should be able to deserialize");
+            prop_assert_eq!(v0, v2);
+        }
     }
 }
@@ -3,4 +3,7 @@ pub enum GuesserError {
     #[cfg(feature = "libmagic")]
     #[error("libmagic error: {0}")]
     MagicError(String),
+
+    #[error("invalid override glob: {0}")]
+    GlobError(#[from] globset::Error),
 }
@@ -1,3 +1,5 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use mime::Mime;
 use mime_guess::MimeGuess;
 
 use crate::{error::GuesserError, input::Input, output::Output};
@@ -5,6 +7,14 @@ use crate::{error::GuesserError, input::Input, output::Output};
 pub struct Guesser {
     #[cfg(feature = "libmagic")]
     magic_cookie: magic::cookie::Cookie<magic::cookie::Load>,
+
+    /// Path globs whose matches are always given the corresponding media type
+    overrides: Option<Overrides>,
+}
+
+struct Overrides {
+    globs: GlobSet,
+    mimes: Vec<Mime>,
 }
 
 // Public Implementation
@@ -20,12 +30,38 @@ impl Guesser {
         let magic_cookie = magic_cookie
             .load(&Default::default())
             .map_err(|e| GuesserError::MagicError(e.to_string()))?;
-        Ok(Guesser { magic_cookie })
+        Ok(Guesser {
+            magic_cookie,
+            overrides: None,
+        })
     }
 
     #[cfg(not(feature = "libmagic"))]
     pub fn new() -> Result<Self, GuesserError> {
-        Ok(Guesser {})
+        Ok(Guesser { overrides: None })
+    }
+
+    /// Use the given media types for inputs whose paths match the corresponding globs.
+    ///
+    /// Overrides are checked before any other guessing is done.
+    /// If several globs match a path, the first one given wins.
+    pub fn with_overrides(mut self, globs: Vec<(String, Mime)>) -> Result<Self, GuesserError> {
+        if globs.is_empty() {
+            self.overrides = None;
+            return Ok(self);
+        }
+
+        let mut builder = GlobSetBuilder::new();
+        let mut mimes = Vec::with_capacity(globs.len());
+        for (glob, mime) in globs {
+            builder.add(Glob::new(&glob)?);
+            mimes.push(mime);
+        }
+        self.overrides = Some(Overrides {
+            globs: builder.build()?,
+            mimes,
+        });
+        Ok(self)
     }
 
     pub fn guess<T>(&self, input: Input<T>) -> Output
@@ -34,6 +70,22 @@ impl Guesser {
     {
         let mime_guess = input.path.map(MimeGuess::from_path);
 
+        let override_guess = match (&self.overrides, input.path) {
+            (Some(overrides), Some(path)) => overrides
+                .globs
+                .matches(path)
+                .first()
+                .map(|&i| overrides.mimes[i].clone()),
+            _ => None,
+        };
+        if override_guess.is_some() {
+            return Output {
+                override_guess,
+                mime_guess,
+                magic_guess: None,
+            };
+        }
+
         #[cfg(feature = "libmagic")]
         let magic_guess = {
             use crate::input::{Content, PrefixContent};
@@ -51,6 +103,7 @@ impl Guesser {
         let magic_guess = None;
 
         Output {
+            override_guess,
             mime_guess,
             magic_guess,
         }
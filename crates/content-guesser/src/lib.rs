@@ -11,3 +11,6 @@ pub use error::GuesserError;
 
 mod guesser;
 pub use guesser::Guesser;
+
+mod minified;
+pub use minified::{is_minified, MINIFIED_PREFIX_LEN};
@@ -3,6 +3,9 @@ use mime_guess::MimeGuess;
 
 #[derive(Debug)]
 pub struct Output {
+    /// Media type from a matching path glob override
+    pub(crate) override_guess: Option<Mime>,
+
     /// Path-based media type guess
     pub(crate) mime_guess: Option<MimeGuess>,
 
@@ -23,11 +26,20 @@ impl Output {
         self.magic_guess.clone()
     }
 
+    /// Get the media type from a matching path glob override, if any
+    #[inline]
+    pub fn override_guess(&self) -> Option<Mime> {
+        self.override_guess.clone()
+    }
+
     /// Get the guessed mime type that is considered to be the best.
     ///
-    /// If a content-based guess is available, that is used.
+    /// If a path glob override matched, that is used.
+    /// Otherwise, if a content-based guess is available, that is used.
     /// Otherwise, the path-based guess is used.
     pub fn best_guess(&self) -> Option<Mime> {
-        self.content_guess().or_else(|| self.path_guess())
+        self.override_guess()
+            .or_else(|| self.content_guess())
+            .or_else(|| self.path_guess())
     }
 }
@@ -366,10 +366,16 @@ pub(crate) struct CommitBlobMetadata {
 }
 
 impl GitMetadataGraph {
+    /// Compute the set of blobs introduced by each commit.
+    ///
+    /// If `since` is given, results are only produced for commits that are not that commit or one
+    /// of its ancestors. Blobs that were already reachable at the boundary commit are not
+    /// considered to be introduced by any later commit.
     pub(crate) fn get_repo_metadata(
         self,
         repo_index: &RepositoryIndex,
         repo: &gix::Repository,
+        since: Option<ObjectId>,
     ) -> Result<Vec<CommitBlobMetadata>> {
         let _span =
             error_span!("get_repo_metadata", path = repo.path().display().to_string()).entered();
@@ -378,6 +384,25 @@ impl GitMetadataGraph {
         let cg = &self.commits;
         let num_commits = cg.node_count();
 
+        // The boundary commit and all its ancestors, which produce no results
+        let mut boundary_commits = FixedBitSet::with_capacity(num_commits);
+        if let Some(since) = since {
+            let since_idx = match self.commit_oid_to_node_idx.get(&since) {
+                Some(idx) => *idx,
+                None => bail!("Failed to find boundary commit {since} in commit graph"),
+            };
+            let mut worklist = vec![since_idx];
+            while let Some(idx) = worklist.pop() {
+                if !boundary_commits.put(idx.index()) {
+                    worklist.extend(cg.neighbors_directed(idx, Incoming));
+                }
+            }
+            debug!(
+                "{} commits at or before boundary commit {since}",
+                boundary_commits.count_ones(..)
+            );
+        }
+
         // An adapatation of Kahn's topological sorting algorithm, to visit the commit nodes in
         // topological order: <https://en.wikipedia.org/wiki/Topological_sorting#Kahn's_algorithm>
         // This algorithm naturally mantains a frontier of still-to-expand nodes.
@@ -576,7 +601,9 @@ impl GitMetadataGraph {
         let commit_metadata: Vec<CommitBlobMetadata> = cg
             .node_weights()
             .zip(blobs_introduced)
-            .map(|(md, introduced_blobs)| CommitBlobMetadata {
+            .enumerate()
+            .filter(|(idx, _)| !boundary_commits.contains(*idx))
+            .map(|(_idx, (md, introduced_blobs))| CommitBlobMetadata {
                 commit_oid: md.oid,
                 introduced_blobs,
             })
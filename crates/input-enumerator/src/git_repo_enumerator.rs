@@ -1,5 +1,9 @@
 use anyhow::{Context, Result};
-use gix::{hashtable::HashMap, ObjectId, Repository};
+use bstr::BString;
+use gix::{
+    hashtable::{HashMap, HashSet},
+    ObjectId, Repository,
+};
 use ignore::gitignore::Gitignore;
 use smallvec::SmallVec;
 use std::path::{Path, PathBuf};
@@ -31,6 +35,9 @@ pub struct GitRepoResult {
 pub struct BlobMetadata {
     pub blob_oid: ObjectId,
     pub first_seen: BlobAppearanceSet,
+
+    /// A representative path for the blob, when no first-seen commit metadata was collected
+    pub path: Option<BString>,
 }
 
 // -------------------------------------------------------------------------------------------------
@@ -40,14 +47,24 @@ pub struct GitRepoWithMetadataEnumerator<'a> {
     path: &'a Path,
     repo: Repository,
     gitignore: &'a Gitignore,
+    since: Option<ObjectId>,
 }
 
 impl<'a> GitRepoWithMetadataEnumerator<'a> {
-    pub fn new(path: &'a Path, repo: Repository, gitignore: &'a Gitignore) -> Self {
+    /// Create a new enumerator for the given repository.
+    ///
+    /// If `since` is given, only blobs introduced by commits after that commit are enumerated.
+    pub fn new(
+        path: &'a Path,
+        repo: Repository,
+        gitignore: &'a Gitignore,
+        since: Option<ObjectId>,
+    ) -> Self {
         Self {
             path,
             repo,
             gitignore,
+            since,
         }
     }
 
@@ -82,6 +99,16 @@ impl<'a> GitRepoWithMetadataEnumerator<'a> {
         let mut commit_metadata =
             HashMap::with_capacity_and_hasher(object_index.num_commits(), Default::default());
 
+        // In a shallow clone, the parents of the shallow boundary commits are absent, so those
+        // commits are treated as roots of the history.
+        let shallow_commits: HashSet<ObjectId> = match self.repo.shallow_commits() {
+            Ok(commits) => commits.iter().flat_map(|c| c.iter().copied()).collect(),
+            Err(e) => {
+                error!("Failed to read shallow commits; ignoring them: {e}");
+                HashSet::default()
+            }
+        };
+
         for commit_oid in object_index.commits() {
             let commit = unwrap_ok_or_continue!(odb.find_commit(commit_oid, &mut scratch), |e| {
                 error!("Failed to find commit {commit_oid}: {e}");
@@ -92,29 +119,21 @@ impl<'a> GitRepoWithMetadataEnumerator<'a> {
                 error!("Failed to find tree {tree_oid} for commit {commit_oid}");
             });
             let commit_idx = metadata_graph.get_commit_idx(*commit_oid, Some(tree_idx));
-            for parent_oid in commit.parents() {
-                let parent_idx = metadata_graph.get_commit_idx(parent_oid, None);
-                metadata_graph.add_commit_edge(parent_idx, commit_idx);
+            if !shallow_commits.contains(commit_oid) {
+                for parent_oid in commit.parents() {
+                    let parent_idx = metadata_graph.get_commit_idx(parent_oid, None);
+                    metadata_graph.add_commit_edge(parent_idx, commit_idx);
+                }
             }
 
-            let committer = &commit.committer;
-            let author = &commit.author;
-            let md = CommitMetadata {
-                commit_id: *commit_oid,
-                committer_name: committer.name.to_owned(),
-                committer_timestamp: committer.time,
-                committer_email: committer.email.to_owned(),
-                author_name: author.name.to_owned(),
-                author_timestamp: author.time,
-                author_email: author.email.to_owned(),
-                message: commit.message.to_owned(),
-            };
+            let md = make_commit_metadata(*commit_oid, &commit);
             commit_metadata.insert(*commit_oid, Arc::new(md));
         }
 
         debug!("Built metadata graph in {:.6}s", t1.elapsed().as_secs_f64());
 
-        match metadata_graph.get_repo_metadata(&object_index, &self.repo) {
+        match metadata_graph.get_repo_metadata(&object_index, &self.repo, self.since) {
+            Err(e) if self.since.is_some() => Err(e),
             Err(e) => {
                 error!("Failed to compute reachable blobs; ignoring metadata: {e}");
                 let blobs = object_index
@@ -123,6 +142,7 @@ impl<'a> GitRepoWithMetadataEnumerator<'a> {
                     .map(|blob_oid| BlobMetadata {
                         blob_oid,
                         first_seen: Default::default(),
+                        path: None,
                     })
                     .collect();
                 Ok(GitRepoResult {
@@ -170,14 +190,21 @@ impl<'a> GitRepoWithMetadataEnumerator<'a> {
                 //
                 // It's also possible (though rare) that a blob appears in a Git repository with
                 // _no_ path whatsoever.
+                //
+                // When enumerating only commits after a boundary commit, blobs with no commit
+                // metadata are suppressed: they were either introduced at or before the boundary,
+                // or are not reachable from any commit at all.
                 let blobs: Vec<BlobMetadata> = blob_to_appearance
                     .into_iter()
                     .filter_map(|(blob_oid, first_seen)| {
-                        if first_seen.is_empty() {
+                        if first_seen.is_empty() && self.since.is_some() {
+                            None
+                        } else if first_seen.is_empty() {
                             // no commit metadata at all for blob
                             Some(BlobMetadata {
                                 blob_oid,
                                 first_seen,
+                                path: None,
                             })
                         } else {
                             // filter out path-ignored provenance entries; suppress blob if all
@@ -211,6 +238,7 @@ impl<'a> GitRepoWithMetadataEnumerator<'a> {
                                 Some(BlobMetadata {
                                     blob_oid,
                                     first_seen,
+                                    path: None,
                                 })
                             }
                         }
@@ -227,17 +255,129 @@ impl<'a> GitRepoWithMetadataEnumerator<'a> {
     }
 }
 
+// -------------------------------------------------------------------------------------------------
+// git repo tree enumerator
+// -------------------------------------------------------------------------------------------------
+/// Enumerates only the blobs reachable from the tree of a single commit of a repository, which is
+/// the `HEAD` commit unless another is given.
+///
+/// This is useful for scanning the current content of a repository without scanning its entire
+/// history, such as for a bare repository, which has no working tree to scan.
+pub struct GitRepoTreeEnumerator<'a> {
+    path: &'a Path,
+    repo: Repository,
+    gitignore: &'a Gitignore,
+    commit: Option<ObjectId>,
+}
+
+impl<'a> GitRepoTreeEnumerator<'a> {
+    pub fn new(
+        path: &'a Path,
+        repo: Repository,
+        gitignore: &'a Gitignore,
+        commit: Option<ObjectId>,
+    ) -> Self {
+        Self {
+            path,
+            repo,
+            gitignore,
+            commit,
+        }
+    }
+
+    pub fn run(self) -> Result<GitRepoResult> {
+        use bstr::ByteSlice;
+        use gix::prelude::*;
+        use gix::traverse::tree::Recorder;
+
+        let _span = debug_span!("enumerate_git_tree", "{}", self.path.display()).entered();
+
+        let commit_oid = match self.commit {
+            Some(commit_oid) => commit_oid,
+            None => match self.repo.head_id() {
+                Ok(head_oid) => head_oid.detach(),
+                Err(e) => {
+                    debug!("Failed to resolve HEAD; no blobs to enumerate: {e}");
+                    return Ok(GitRepoResult {
+                        repository: self.repo,
+                        path: self.path.to_owned(),
+                        blobs: Vec::new(),
+                    });
+                }
+            },
+        };
+
+        let mut scratch = Vec::new();
+        let commit = self
+            .repo
+            .objects
+            .find_commit(&commit_oid, &mut scratch)
+            .with_context(|| format!("Failed to find commit {commit_oid}"))?;
+        let commit_metadata = Arc::new(make_commit_metadata(commit_oid, &commit));
+        let tree_oid = commit.tree();
+
+        let mut recorder = Recorder::default();
+        self.repo
+            .find_tree(tree_oid)
+            .with_context(|| format!("Failed to find tree {tree_oid} for commit {commit_oid}"))?
+            .traverse()
+            .breadthfirst(&mut recorder)
+            .with_context(|| format!("Failed to traverse tree {tree_oid}"))?;
+
+        // Apply path-based ignore rules, like the other enumerators do.
+        // A blob may appear under several paths in the tree; keep each non-ignored one.
+        let mut blob_to_appearance: HashMap<ObjectId, BlobAppearanceSet> = HashMap::default();
+        for entry in recorder.records {
+            if !entry.mode.is_blob() {
+                continue;
+            }
+            if let Ok(path) = entry.filepath.to_path() {
+                if self.gitignore.matched(path, false).is_ignore() {
+                    continue;
+                }
+            }
+            blob_to_appearance
+                .entry(entry.oid)
+                .or_default()
+                .push(BlobAppearance {
+                    commit_metadata: commit_metadata.clone(),
+                    path: entry.filepath,
+                });
+        }
+
+        let blobs = blob_to_appearance
+            .into_iter()
+            .map(|(blob_oid, first_seen)| BlobMetadata {
+                blob_oid,
+                first_seen,
+                path: None,
+            })
+            .collect();
+
+        Ok(GitRepoResult {
+            repository: self.repo,
+            path: self.path.to_owned(),
+            blobs,
+        })
+    }
+}
+
 // -------------------------------------------------------------------------------------------------
 // git repo enumerator, sans metadata
 // -------------------------------------------------------------------------------------------------
 pub struct GitRepoEnumerator<'a> {
     path: &'a Path,
     repo: Repository,
+    since: Option<ObjectId>,
 }
 
 impl<'a> GitRepoEnumerator<'a> {
-    pub fn new(path: &'a Path, repo: Repository) -> Self {
-        Self { path, repo }
+    /// Create a new enumerator for the given repository.
+    ///
+    /// If `since` is given, blobs reachable from that commit or any of its ancestors are not
+    /// enumerated.
+    pub fn new(path: &'a Path, repo: Repository, since: Option<ObjectId>) -> Self {
+        Self { path, repo, since }
     }
 
     pub fn run(self) -> Result<GitRepoResult> {
@@ -248,6 +388,10 @@ impl<'a> GitRepoEnumerator<'a> {
         let _span = debug_span!("enumerate_git", "{}", self.path.display()).entered();
 
         let odb = &self.repo.objects;
+        let excluded = match self.since {
+            Some(since) => blobs_reachable_from(&self.repo, since)?,
+            None => Default::default(),
+        };
 
         let mut blobs: Vec<ObjectId> = Vec::with_capacity(64 * 1024);
 
@@ -260,7 +404,7 @@ impl<'a> GitRepoEnumerator<'a> {
             let hdr = unwrap_ok_or_continue!(odb.header(oid), |e| error!(
                 "Failed to read object header for {oid}: {e}"
             ));
-            if hdr.kind() == Kind::Blob {
+            if hdr.kind() == Kind::Blob && !excluded.contains(&oid) {
                 blobs.push(oid);
             }
         }
@@ -270,8 +414,136 @@ impl<'a> GitRepoEnumerator<'a> {
             .map(|blob_oid| BlobMetadata {
                 blob_oid,
                 first_seen: Default::default(),
+                path: None,
+            })
+            .collect();
+        Ok(GitRepoResult {
+            repository: self.repo,
+            path: self.path.to_owned(),
+            blobs,
+        })
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// git repo enumerator, with representative paths
+// -------------------------------------------------------------------------------------------------
+/// Enumerates all blobs in a repository's object database, recording a single representative path
+/// for each one, but no commit metadata.
+///
+/// The path for a blob is reconstructed from the first tree entry found to refer to it, and
+/// likewise for each of its ancestor trees. This requires reading every tree object once, but
+/// unlike `GitRepoWithMetadataEnumerator`, it does not build or traverse the commit graph. The
+/// recorded path is therefore not necessarily the one with which the blob was first introduced.
+///
+/// Path-based ignore rules are applied to the representative path only: a blob whose
+/// representative path is ignored is not enumerated, even if it also appears under other paths
+/// that are not ignored.
+pub struct GitRepoWithPathsEnumerator<'a> {
+    path: &'a Path,
+    repo: Repository,
+    gitignore: &'a Gitignore,
+    since: Option<ObjectId>,
+}
+
+impl<'a> GitRepoWithPathsEnumerator<'a> {
+    /// Create a new enumerator for the given repository.
+    ///
+    /// If `since` is given, blobs reachable from that commit or any of its ancestors are not
+    /// enumerated.
+    pub fn new(
+        path: &'a Path,
+        repo: Repository,
+        gitignore: &'a Gitignore,
+        since: Option<ObjectId>,
+    ) -> Self {
+        Self {
+            path,
+            repo,
+            gitignore,
+            since,
+        }
+    }
+
+    pub fn run(self) -> Result<GitRepoResult> {
+        use gix::object::Kind;
+        use gix::objs::tree::EntryKind;
+        use gix::odb::store::iter::Ordering;
+        use gix::prelude::*;
+
+        let _span = debug_span!("enumerate_git_with_paths", "{}", self.path.display()).entered();
+
+        let odb = &self.repo.objects;
+        let excluded = match self.since {
+            Some(since) => blobs_reachable_from(&self.repo, since)?,
+            None => Default::default(),
+        };
+
+        let mut blobs: Vec<ObjectId> = Vec::with_capacity(64 * 1024);
+        let mut trees: Vec<ObjectId> = Vec::with_capacity(64 * 1024);
+
+        for oid in odb
+            .iter()
+            .context("Failed to iterate object database")?
+            .with_ordering(Ordering::PackAscendingOffsetThenLooseLexicographical)
+        {
+            let oid = unwrap_ok_or_continue!(oid, |e| error!("Failed to read object id: {e}"));
+            let hdr = unwrap_ok_or_continue!(odb.header(oid), |e| error!(
+                "Failed to read object header for {oid}: {e}"
+            ));
+            match hdr.kind() {
+                Kind::Blob if excluded.contains(&oid) => {}
+                Kind::Blob => blobs.push(oid),
+                Kind::Tree => trees.push(oid),
+                Kind::Commit | Kind::Tag => {}
+            }
+        }
+
+        // For each blob and tree, the first tree entry seen that refers to it: (parent tree, name)
+        let mut parents: HashMap<ObjectId, (ObjectId, BString)> =
+            HashMap::with_capacity_and_hasher(blobs.len() + trees.len(), Default::default());
+
+        let mut scratch: Vec<u8> = Vec::with_capacity(1024 * 1024);
+        for tree_oid in trees {
+            let tree_iter =
+                unwrap_ok_or_continue!(odb.find_tree_iter(&tree_oid, &mut scratch), |e| {
+                    error!("Failed to find tree {tree_oid}: {e}")
+                });
+            for child in tree_iter {
+                let child = unwrap_ok_or_continue!(child, |e| {
+                    error!("Failed to read tree entry from {tree_oid}: {e}")
+                });
+                match child.mode.kind() {
+                    EntryKind::Link | EntryKind::Commit => continue,
+                    EntryKind::Tree | EntryKind::Blob | EntryKind::BlobExecutable => {
+                        parents
+                            .entry(child.oid.to_owned())
+                            .or_insert_with(|| (tree_oid, child.filename.to_owned()));
+                    }
+                }
+            }
+        }
+
+        let blobs = blobs
+            .into_iter()
+            .filter_map(|blob_oid| {
+                let path = representative_path(&parents, blob_oid);
+                if let Some(path) = &path {
+                    use bstr::ByteSlice;
+                    if let Ok(path) = path.to_path() {
+                        if self.gitignore.matched(path, false).is_ignore() {
+                            return None;
+                        }
+                    }
+                }
+                Some(BlobMetadata {
+                    blob_oid,
+                    first_seen: Default::default(),
+                    path,
+                })
             })
             .collect();
+
         Ok(GitRepoResult {
             repository: self.repo,
             path: self.path.to_owned(),
@@ -279,3 +551,96 @@ impl<'a> GitRepoEnumerator<'a> {
         })
     }
 }
+
+/// Reconstruct a path for the given object by following the recorded parent tree entries up to a
+/// tree that has no recorded parent.
+fn representative_path(
+    parents: &HashMap<ObjectId, (ObjectId, BString)>,
+    oid: ObjectId,
+) -> Option<BString> {
+    let mut components: Vec<&BString> = Vec::new();
+    let mut cur = oid;
+    while let Some((parent, name)) = parents.get(&cur) {
+        components.push(name);
+        cur = *parent;
+    }
+    if components.is_empty() {
+        return None;
+    }
+
+    let mut path = BString::default();
+    for (i, name) in components.into_iter().rev().enumerate() {
+        if i > 0 {
+            path.push(b'/');
+        }
+        path.extend_from_slice(name);
+    }
+    Some(path)
+}
+
+// -------------------------------------------------------------------------------------------------
+// helpers
+// -------------------------------------------------------------------------------------------------
+/// Compute the set of blobs reachable from the given commit or any of its ancestors.
+fn blobs_reachable_from(repo: &Repository, since: ObjectId) -> Result<HashSet<ObjectId>> {
+    use gix::objs::tree::EntryKind;
+    use gix::prelude::*;
+
+    let odb = &repo.objects;
+    let mut blobs = HashSet::default();
+    let mut seen_trees = HashSet::default();
+    let mut tree_worklist: Vec<ObjectId> = Vec::new();
+    let mut scratch: Vec<u8> = Vec::with_capacity(1024 * 1024);
+
+    let walk = repo
+        .rev_walk([since])
+        .all()
+        .with_context(|| format!("Failed to walk ancestors of commit {since}"))?;
+    for info in walk {
+        let info = info.with_context(|| format!("Failed to walk ancestors of commit {since}"))?;
+        let commit_oid = info.id;
+        let tree_oid = odb
+            .find_commit(&commit_oid, &mut scratch)
+            .with_context(|| format!("Failed to find commit {commit_oid}"))?
+            .tree();
+        tree_worklist.push(tree_oid);
+
+        while let Some(tree_oid) = tree_worklist.pop() {
+            if !seen_trees.insert(tree_oid) {
+                continue;
+            }
+            let tree_iter = odb
+                .find_tree_iter(&tree_oid, &mut scratch)
+                .with_context(|| format!("Failed to find tree {tree_oid}"))?;
+            for child in tree_iter {
+                let child =
+                    child.with_context(|| format!("Failed to read tree entry from {tree_oid}"))?;
+                match child.mode.kind() {
+                    EntryKind::Link | EntryKind::Commit => {}
+                    EntryKind::Tree => tree_worklist.push(child.oid.to_owned()),
+                    EntryKind::Blob | EntryKind::BlobExecutable => {
+                        blobs.insert(child.oid.to_owned());
+                    }
+                }
+            }
+        }
+    }
+
+    debug!("Found {} blobs reachable from commit {since}", blobs.len());
+    Ok(blobs)
+}
+
+fn make_commit_metadata(commit_id: ObjectId, commit: &gix::objs::CommitRef<'_>) -> CommitMetadata {
+    let committer = &commit.committer;
+    let author = &commit.author;
+    CommitMetadata {
+        commit_id,
+        committer_name: committer.name.to_owned(),
+        committer_timestamp: committer.time,
+        committer_email: committer.email.to_owned(),
+        author_name: author.name.to_owned(),
+        author_timestamp: author.time,
+        author_email: author.email.to_owned(),
+        message: commit.message.to_owned(),
+    }
+}
@@ -4,12 +4,14 @@ pub mod git_commit_metadata;
 pub mod git_metadata_graph;
 pub use gix::{Repository, ThreadSafeRepository};
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use crossbeam_channel::Sender;
 pub use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use ignore::{DirEntry, WalkBuilder, WalkState};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use tracing::{debug, warn};
+use std::sync::{Arc, Mutex};
+use tracing::{debug, info, warn};
 
 // -------------------------------------------------------------------------------------------------
 // helper macros
@@ -46,23 +48,87 @@ pub(crate) use unwrap_ok_or_continue;
 
 // -------------------------------------------------------------------------------------------------
 mod git_repo_enumerator;
-pub use git_repo_enumerator::{GitRepoEnumerator, GitRepoResult, GitRepoWithMetadataEnumerator};
+pub use git_repo_enumerator::{
+    BlobMetadata, GitRepoEnumerator, GitRepoResult, GitRepoTreeEnumerator,
+    GitRepoWithMetadataEnumerator, GitRepoWithPathsEnumerator,
+};
 
 pub enum FoundInput {
     File(FileResult),
     Directory(DirectoryResult),
     EnumeratorFile(EnumeratorFileResult),
+    Archive(ArchiveResult),
+    Stdin(StdinResult),
 }
 
 pub struct FileResult {
     pub path: PathBuf,
     pub num_bytes: u64,
+
+    /// The input root that this file was found under, which may be the file itself
+    pub input_root: Arc<PathBuf>,
+}
+
+impl FileResult {
+    /// Get the path of this file relative to the parent of its input root, so that it starts with
+    /// the name of the input root.
+    ///
+    /// For example, the file `/home/me/project/src/x.rs` found under the input root
+    /// `/home/me/project` has the relative path `project/src/x.rs`.
+    pub fn root_relative_path(&self) -> PathBuf {
+        let rest = self
+            .path
+            .strip_prefix(self.input_root.as_path())
+            .unwrap_or(&self.path);
+        match self.input_root.file_name() {
+            Some(name) if rest.as_os_str().is_empty() => PathBuf::from(name),
+            Some(name) => Path::new(name).join(rest),
+            None => rest.to_owned(),
+        }
+    }
 }
 
 pub struct EnumeratorFileResult {
     pub path: PathBuf,
 }
 
+/// The content of standard input, to be read as a single blob
+pub struct StdinResult;
+
+pub struct ArchiveResult {
+    pub path: PathBuf,
+    pub kind: ArchiveKind,
+}
+
+/// The kinds of archive files whose members are enumerated
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ArchiveKind {
+    /// An uncompressed tar archive (`.tar`)
+    Tar,
+
+    /// A gzip-compressed tar archive (`.tar.gz` or `.tgz`)
+    TarGz,
+
+    /// A zstd-compressed tar archive (`.tar.zst` or `.tzst`)
+    TarZst,
+}
+
+impl ArchiveKind {
+    /// Determine the kind of archive at the given path from its extension, if it is one.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+            Some(Self::TarZst)
+        } else {
+            None
+        }
+    }
+}
+
 pub struct DirectoryResult {
     pub path: PathBuf,
 }
@@ -74,7 +140,13 @@ pub type Output = Sender<FoundInput>;
 // -------------------------------------------------------------------------------------------------
 struct VisitorBuilder<'t> {
     max_file_size: Option<u64>,
+    extension_max_file_sizes: &'t HashMap<String, Option<u64>>,
+    scan_hidden: bool,
+    enumerate_git_history: bool,
+    include: Option<&'t Gitignore>,
+    input_roots: &'t [Arc<PathBuf>],
     output: &'t Output,
+    seen_files: &'t SeenFiles,
 }
 
 impl<'s, 't> ignore::ParallelVisitorBuilder<'s> for VisitorBuilder<'t>
@@ -84,38 +156,153 @@ where
     fn build(&mut self) -> Box<dyn ignore::ParallelVisitor + 's> {
         Box::new(Visitor {
             max_file_size: self.max_file_size,
+            extension_max_file_sizes: self.extension_max_file_sizes,
+            scan_hidden: self.scan_hidden,
+            enumerate_git_history: self.enumerate_git_history,
+            include: self.include,
+            input_roots: self.input_roots,
             output: self.output,
+            seen_files: self.seen_files,
+            disconnected: false,
         })
     }
 }
 
+// -------------------------------------------------------------------------------------------------
+// SeenFiles
+// -------------------------------------------------------------------------------------------------
+/// The `(device, inode)` pairs of enumerated files that have multiple hard links, used to avoid
+/// enumerating the same file more than once under different paths.
+///
+/// On platforms without inode information, every file is considered distinct.
+#[derive(Default)]
+struct SeenFiles {
+    #[cfg_attr(not(unix), allow(dead_code))]
+    inner: Mutex<HashSet<(u64, u64)>>,
+}
+
+impl SeenFiles {
+    /// Record the file with the given metadata, returning `false` if it was already seen.
+    #[cfg(unix)]
+    fn insert(&self, metadata: &std::fs::Metadata) -> bool {
+        use std::os::unix::fs::MetadataExt;
+
+        // Files with a single link can only be reached through one path
+        if metadata.nlink() <= 1 {
+            return true;
+        }
+        self.inner
+            .lock()
+            .unwrap()
+            .insert((metadata.dev(), metadata.ino()))
+    }
+
+    #[cfg(not(unix))]
+    fn insert(&self, _metadata: &std::fs::Metadata) -> bool {
+        true
+    }
+}
+
 // -------------------------------------------------------------------------------------------------
 // Visitor
 // -------------------------------------------------------------------------------------------------
 struct Visitor<'t> {
     max_file_size: Option<u64>,
+    extension_max_file_sizes: &'t HashMap<String, Option<u64>>,
+    scan_hidden: bool,
+    enumerate_git_history: bool,
+    include: Option<&'t Gitignore>,
+    input_roots: &'t [Arc<PathBuf>],
     output: &'t Output,
+    seen_files: &'t SeenFiles,
+
+    /// Has the receiving end of `output` gone away? If so, enumeration stops.
+    disconnected: bool,
 }
 
 impl<'t> Visitor<'t> {
+    /// Get the maximum size for the file at the given path, taking its extension into account.
+    fn max_file_size_for(&self, path: &Path) -> Option<u64> {
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase());
+        match ext.and_then(|e| self.extension_max_file_sizes.get(&e).map(|s| (e, *s))) {
+            Some((ext, max_size)) => {
+                debug!(
+                    "Using max size {max_size:?} for {} from `.{ext}` extension override",
+                    path.display()
+                );
+                max_size
+            }
+            None => self.max_file_size,
+        }
+    }
+
+    /// If the file at the given path with the given size is too big to enumerate, return the
+    /// maximum size it exceeds.
     #[inline]
-    fn file_too_big(&self, size: u64) -> bool {
-        self.max_file_size.map_or(false, |max_size| size > max_size)
+    fn file_too_big(&self, path: &Path, size: u64) -> Option<u64> {
+        self.max_file_size_for(path)
+            .filter(|&max_size| size > max_size)
+    }
+
+    fn send(&mut self, input: FoundInput) {
+        if self.output.send(input).is_err() {
+            self.disconnected = true;
+        }
+    }
+
+    /// Get the input root that the given path was found under: the longest one that contains it.
+    fn input_root_of(&self, path: &Path) -> Arc<PathBuf> {
+        self.input_roots
+            .iter()
+            .filter(|root| path.starts_with(root.as_path()))
+            .max_by_key(|root| root.as_os_str().len())
+            .cloned()
+            .unwrap_or_else(|| Arc::new(path.to_owned()))
+    }
+
+    /// Does the given file entry match the include globs, if there are any?
+    ///
+    /// Input roots are always included.
+    fn is_included(&self, entry: &DirEntry) -> bool {
+        match self.include {
+            Some(include) if entry.depth() > 0 => {
+                let path = entry.path();
+                let input_root = self.input_root_of(path);
+                matches_include(include, path.strip_prefix(input_root.as_path()).unwrap_or(path))
+            }
+            _ => true,
+        }
     }
 
     fn found_file(&mut self, r: FileResult) {
-        self.output.send(FoundInput::File(r)).unwrap();
+        self.send(FoundInput::File(r));
+    }
+
+    fn found_archive(&mut self, r: ArchiveResult) {
+        self.send(FoundInput::Archive(r));
     }
 
     fn found_directory(&mut self, r: DirectoryResult) {
-        self.output.send(FoundInput::Directory(r)).unwrap();
+        self.send(FoundInput::Directory(r));
     }
 }
 
 impl<'t> ignore::ParallelVisitor for Visitor<'t> {
     fn visit(&mut self, result: Result<ignore::DirEntry, ignore::Error>) -> ignore::WalkState {
-        // FIXME: dedupe based on (device, inode) on platforms where available; see https://docs.rs/same-file/1.0.6/same_file/ for ideas
+        let state = self.visit_entry(result);
+        if self.disconnected {
+            debug!("Stopping enumeration: output channel disconnected");
+            WalkState::Quit
+        } else {
+            state
+        }
+    }
+}
 
+impl<'t> Visitor<'t> {
+    fn visit_entry(&mut self, result: Result<ignore::DirEntry, ignore::Error>) -> WalkState {
         let entry = match result {
             Err(e) => {
                 warn!("Skipping entry: {e}");
@@ -125,6 +312,23 @@ impl<'t> ignore::ParallelVisitor for Visitor<'t> {
         };
 
         let path = entry.path();
+
+        // Skip hidden entries if requested, but never input roots.
+        //
+        // Git repositories are discovered through their `.git` directories, so those are still
+        // reported when Git history is to be enumerated; their contents are skipped, though.
+        if !self.scan_hidden && entry.depth() > 0 && is_hidden(&entry) {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            if is_dir && self.enumerate_git_history && entry.file_name() == ".git" {
+                self.found_directory(DirectoryResult {
+                    path: path.to_owned(),
+                });
+            } else {
+                debug!("Skipping {}: hidden", path.display());
+            }
+            return WalkState::Skip;
+        }
+
         let metadata = match entry.metadata() {
             Err(e) => {
                 warn!("Skipping {}: failed to get metadata: {e}", path.display());
@@ -134,20 +338,47 @@ impl<'t> ignore::ParallelVisitor for Visitor<'t> {
         };
 
         if metadata.is_file() {
+            if !self.is_included(&entry) {
+                debug!("Skipping {}: does not match any include glob", path.display());
+                return WalkState::Continue;
+            }
+
+            if !self.seen_files.insert(&metadata) {
+                debug!("Skipping {}: hard link to an already-enumerated file", path.display());
+                return WalkState::Continue;
+            }
+
+            // Archives are not subject to the maximum file size, as their members are read
+            // incrementally; the maximum applies to each member instead
+            if let Some(kind) = ArchiveKind::from_path(path) {
+                self.found_archive(ArchiveResult {
+                    path: path.to_owned(),
+                    kind,
+                });
+                return WalkState::Continue;
+            }
+
             let num_bytes = metadata.len();
-            if self.file_too_big(num_bytes) {
-                debug!("Skipping {}: size {num_bytes} exceeds max size", path.display());
-            } else {
-                let path = path.to_owned();
-                self.found_file(FileResult { path, num_bytes });
+            match self.file_too_big(path, num_bytes) {
+                Some(max_size) => {
+                    info!(
+                        "Skipping {}: size of {num_bytes} bytes exceeds maximum of {max_size} bytes",
+                        path.display()
+                    );
+                }
+                _ => {
+                    let input_root = self.input_root_of(path);
+                    let path = path.to_owned();
+                    self.found_file(FileResult {
+                        path,
+                        num_bytes,
+                        input_root,
+                    });
+                }
             }
         } else if metadata.is_dir() {
             // Skip things that look like Nosey Parker datastores
-            if path.join("datastore.db").is_file()
-                && path.join("scratch").is_dir()
-                && path.join("clones").is_dir()
-                && path.join("blobs").is_dir()
-            {
+            if looks_like_datastore(path) {
                 debug!("Skipping {}: looks like a Nosey Parker datastore", path.display());
                 return WalkState::Skip;
             } else {
@@ -167,12 +398,50 @@ impl<'t> ignore::ParallelVisitor for Visitor<'t> {
     }
 }
 
+/// Does the given directory look like a Nosey Parker datastore?
+///
+/// This requires the datastore subdirectories to be present and `datastore.db` to start with the
+/// SQLite file header, so that arbitrary directories with the same entry names aren't matched.
+fn looks_like_datastore(path: &Path) -> bool {
+    use std::io::Read;
+
+    const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+    if !(path.join("scratch").is_dir()
+        && path.join("clones").is_dir()
+        && path.join("blobs").is_dir())
+    {
+        return false;
+    }
+
+    let mut header = [0u8; 16];
+    match std::fs::File::open(path.join("datastore.db")) {
+        Ok(mut f) => f.read_exact(&mut header).is_ok() && &header == SQLITE_HEADER,
+        Err(_) => false,
+    }
+}
+
+/// Does the given path, relative to its input root, match one of the given include globs?
+///
+/// As with gitignore rules, a glob that matches a directory includes everything within it.
+fn matches_include(include: &Gitignore, relative_path: &Path) -> bool {
+    include
+        .matched_path_or_any_parents(relative_path, false)
+        .is_ignore()
+}
+
+/// Does the given entry have a name that starts with a `.`?
+fn is_hidden(entry: &DirEntry) -> bool {
+    entry.file_name().as_encoded_bytes().starts_with(b".")
+}
+
 /// Provides capabitilies to recursively enumerate a filesystem.
 ///
 /// This provides a handful of features, including:
 ///
 /// - Enumeration of found files
 /// - Enumeration of blobs found in Git repositories
+/// - Discovery of tar archives, whose members can be enumerated
 /// - Support for ignoring files based on size or using path-based gitignore-style rules
 pub struct FilesystemEnumerator {
     /// The inner filesystem walker builder
@@ -185,23 +454,50 @@ pub struct FilesystemEnumerator {
     /// `WalkBuilder` type today.
     gitignore_builder: GitignoreBuilder,
 
-    /// We store the max file size here in addition to inside the `walk_builder` to work around a
-    /// bug in `ignore` where max filesize is not applied to top-level file inputs, only inputs that
-    /// appear under a directory.
+    /// The maximum size of files to enumerate, if any
+    ///
+    /// This is handled by the visitor rather than `WalkBuilder::max_filesize`, both because the
+    /// latter does not apply to top-level file inputs and so that skipped files can be logged.
     max_file_size: Option<u64>,
 
+    /// Maximum file size overrides for specific file extensions, keyed by lowercase extension
+    extension_max_file_sizes: HashMap<String, Option<u64>>,
+
+    /// Should hidden files and directories be enumerated?
+    ///
+    /// This is handled by the visitor rather than `WalkBuilder::hidden`, so that `.git`
+    /// directories can still be reported when Git history is enumerated.
+    scan_hidden: bool,
+
     /// Should git metadata (commit and path information) be collected?
     collect_git_metadata: bool,
 
     /// Should git history be scanned at all?
     enumerate_git_history: bool,
+
+    /// Should the rules from `.noseyparkerignore` files in enumerated directories be used?
+    discover_ignore_files: bool,
+
+    /// The include globs, if any, which files must match in order to be enumerated
+    ///
+    /// This is handled by the visitor rather than `WalkBuilder::overrides`, as overrides take
+    /// precedence over ignore rules.
+    include: Option<Gitignore>,
+
+    /// The input roots, used to determine the root that each file is found under
+    input_roots: Vec<Arc<PathBuf>>,
 }
 
 impl FilesystemEnumerator {
     pub const DEFAULT_MAX_FILESIZE: u64 = 100 * 1024 * 1024;
     pub const DEFAULT_FOLLOW_LINKS: bool = false;
+    pub const DEFAULT_SCAN_HIDDEN: bool = true;
     pub const DEFAULT_COLLECT_GIT_METADATA: bool = true;
     pub const DEFAULT_ENUMERATE_GIT_HISTORY: bool = true;
+    pub const DEFAULT_DISCOVER_IGNORE_FILES: bool = false;
+
+    /// The name of the ignore files used with `discover_ignore_files`
+    pub const IGNORE_FILENAME: &'static str = ".noseyparkerignore";
 
     /// Create a new `FilesystemEnumerator` with the given set of input roots using default
     /// settings.
@@ -209,6 +505,8 @@ impl FilesystemEnumerator {
     /// The default maximum file size is 100 MiB.
     ///
     /// The default behavior is to not follow symlinks.
+    ///
+    /// The default behavior is to enumerate hidden files and directories.
     pub fn new<T: AsRef<Path>>(inputs: &[T]) -> Result<Self> {
         if inputs.is_empty() {
             bail!("No inputs provided");
@@ -220,15 +518,22 @@ impl FilesystemEnumerator {
         }
         let max_file_size = Some(Self::DEFAULT_MAX_FILESIZE);
         builder.follow_links(Self::DEFAULT_FOLLOW_LINKS);
-        builder.max_filesize(max_file_size);
         builder.standard_filters(false);
 
         Ok(FilesystemEnumerator {
             walk_builder: builder,
             max_file_size,
+            extension_max_file_sizes: HashMap::new(),
+            scan_hidden: Self::DEFAULT_SCAN_HIDDEN,
             collect_git_metadata: Self::DEFAULT_COLLECT_GIT_METADATA,
             enumerate_git_history: Self::DEFAULT_ENUMERATE_GIT_HISTORY,
+            discover_ignore_files: Self::DEFAULT_DISCOVER_IGNORE_FILES,
+            include: None,
             gitignore_builder: GitignoreBuilder::new(""),
+            input_roots: inputs
+                .iter()
+                .map(|p| Arc::new(p.as_ref().to_owned()))
+                .collect(),
         })
     }
 
@@ -262,11 +567,30 @@ impl FilesystemEnumerator {
     ///
     /// Files larger than this value will be skipped.
     pub fn max_filesize(&mut self, max_filesize: Option<u64>) -> &mut Self {
-        self.walk_builder.max_filesize(max_filesize);
         self.max_file_size = max_filesize;
         self
     }
 
+    /// Set the maximum file size for enumerated files with the given extension, overriding the
+    /// one set with `max_filesize`.
+    ///
+    /// The extension is matched case-insensitively, and should be given without a leading `.`.
+    pub fn max_filesize_for_extension(&mut self, ext: &str, size: Option<u64>) -> &mut Self {
+        self.extension_max_file_sizes
+            .insert(ext.to_ascii_lowercase(), size);
+        self
+    }
+
+    /// Enable or disable whether hidden files and directories are enumerated.
+    ///
+    /// Hidden input roots are always enumerated.
+    /// When Git history is enumerated, `.git` directories are still found so that their history
+    /// can be scanned, but their contents are not otherwise enumerated.
+    pub fn scan_hidden(&mut self, scan_hidden: bool) -> &mut Self {
+        self.scan_hidden = scan_hidden;
+        self
+    }
+
     /// Enable or disable whether detailed Git metadata will be collected.
     pub fn collect_git_metadata(&mut self, collect_git_metadata: bool) -> &mut Self {
         self.collect_git_metadata = collect_git_metadata;
@@ -279,6 +603,33 @@ impl FilesystemEnumerator {
         self
     }
 
+    /// Enable or disable whether the gitignore-style rules from `.noseyparkerignore` files in
+    /// enumerated directories are used.
+    ///
+    /// Like those from `.gitignore` files, the rules from such a file apply only to the contents
+    /// of the directory that contains it, and take precedence over rules added with `add_ignore`.
+    /// They do not apply to Git history.
+    pub fn discover_ignore_files(&mut self, discover_ignore_files: bool) -> &mut Self {
+        self.discover_ignore_files = discover_ignore_files;
+        self
+    }
+
+    /// Only enumerate files that match at least one of the given gitignore-style globs.
+    ///
+    /// The globs are matched against paths relative to their input root.
+    /// Directories are still traversed, so that matching files within them are found, and input
+    /// roots are always enumerated.
+    /// Giving no globs enumerates all files.
+    pub fn include_globs<T: AsRef<str>>(&mut self, globs: &[T]) -> Result<&mut Self> {
+        let mut builder = GitignoreBuilder::new("");
+        for glob in globs {
+            builder.add_line(None, glob.as_ref())?;
+        }
+        let include = builder.build()?;
+        self.include = (!include.is_empty()).then_some(include);
+        Ok(self)
+    }
+
     /// Specify an ad-hoc filtering function to control which entries are enumerated.
     /// Only entries that satisfy the predicate will be enumerated.
     ///
@@ -297,14 +648,23 @@ impl FilesystemEnumerator {
     }
 
     pub fn run(&self, output: Output) -> Result<()> {
+        let seen_files = SeenFiles::default();
         let mut visitor_builder = VisitorBuilder {
             max_file_size: self.max_file_size,
+            extension_max_file_sizes: &self.extension_max_file_sizes,
+            scan_hidden: self.scan_hidden,
+            enumerate_git_history: self.enumerate_git_history,
+            include: self.include.as_ref(),
+            input_roots: &self.input_roots,
             output: &output,
+            seen_files: &seen_files,
         };
 
-        self.walk_builder
-            .build_parallel()
-            .visit(&mut visitor_builder);
+        let mut walk_builder = self.walk_builder.clone();
+        if self.discover_ignore_files {
+            walk_builder.add_custom_ignore_filename(Self::IGNORE_FILENAME);
+        }
+        walk_builder.build_parallel().visit(&mut visitor_builder);
 
         Ok(())
     }
@@ -319,3 +679,71 @@ pub fn open_git_repo(path: &Path) -> Result<Option<Repository>> {
         Ok(repo) => Ok(Some(repo)),
     }
 }
+
+/// Resolves the given revision specification to a commit in the given Git repository.
+pub fn resolve_git_commit(repo: &Repository, rev: &str) -> Result<gix::ObjectId> {
+    let commit = repo
+        .rev_parse_single(rev)
+        .with_context(|| format!("Failed to resolve revision `{rev}`"))?
+        .object()
+        .with_context(|| format!("Failed to find object for revision `{rev}`"))?
+        .peel_to_commit()
+        .with_context(|| format!("Revision `{rev}` does not refer to a commit"))?;
+    Ok(commit.id)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn root_relative_path(root: &str, path: &str) -> PathBuf {
+        FileResult {
+            path: PathBuf::from(path),
+            num_bytes: 0,
+            input_root: Arc::new(PathBuf::from(root)),
+        }
+        .root_relative_path()
+    }
+
+    #[test]
+    fn root_relative_path_in_directory() {
+        assert_eq!(
+            root_relative_path("/home/me/project", "/home/me/project/src/x.rs"),
+            Path::new("project/src/x.rs")
+        );
+        assert_eq!(
+            root_relative_path("/home/me/project/", "/home/me/project/src/x.rs"),
+            Path::new("project/src/x.rs")
+        );
+    }
+
+    #[test]
+    fn root_relative_path_of_root_file() {
+        assert_eq!(root_relative_path("/home/me/x.rs", "/home/me/x.rs"), Path::new("x.rs"));
+    }
+
+    #[test]
+    fn root_relative_path_in_current_directory() {
+        assert_eq!(root_relative_path(".", "./src/x.rs"), Path::new("src/x.rs"));
+    }
+
+    #[test]
+    fn include_globs() {
+        let mut builder = GitignoreBuilder::new("");
+        for glob in ["*.env", "config/", "/top.yaml"] {
+            builder.add_line(None, glob).unwrap();
+        }
+        let include = builder.build().unwrap();
+        let included = |path: &str| matches_include(&include, Path::new(path));
+
+        assert!(included("prod.env"));
+        assert!(included("deploy/nested/prod.env"));
+        assert!(included("config/settings.json"));
+        assert!(included("src/config/settings.json"));
+        assert!(included("top.yaml"));
+        assert!(!included("src/top.yaml"));
+        assert!(!included("src/main.rs"));
+        assert!(!included("prod.env.bak"));
+    }
+}
@@ -7,6 +7,8 @@ use clap::{
 };
 use lazy_static::lazy_static;
 use noseyparker::git_url::GitUrl;
+#[cfg(feature = "s3")]
+use noseyparker::s3::S3Url;
 use std::io::IsTerminal;
 use std::path::PathBuf;
 use strum::Display;
@@ -192,6 +194,7 @@ impl CommandLineArgs {
 }
 
 #[derive(Subcommand, Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum Command {
     #[cfg(feature = "github")]
     /// Scan content for secrets
@@ -222,6 +225,9 @@ pub enum Command {
     /// By default, when cloning repositories from GitHub or enumerating GitHub users or organizations, unauthenticated access is used.
     /// An optional personal access token can be specified using the `NP_GITHUB_TOKEN` environment variable.
     /// Using a personal access token gives higher rate limits and may make additional content accessible.
+    ///
+    /// A token for a specific GitHub API host can be given in an environment variable named after that host, which takes precedence over `NP_GITHUB_TOKEN` for API access.
+    /// The variable name is `NP_GITHUB_TOKEN_` followed by the host in uppercase, with characters other than letters and digits replaced by underscores, e.g., `NP_GITHUB_TOKEN_GITHUB_EXAMPLE_COM` for `--github-api-url=https://github.example.com/api/v3`.
     #[command(display_order = 1)]
     Scan(ScanArgs),
 
@@ -279,6 +285,9 @@ pub enum Command {
     /// By default, unauthenticated access is used.
     /// An optional personal access token can be specified using the `NP_GITHUB_TOKEN` environment variable.
     /// Using a personal access token gives higher rate limits and may make additional content accessible.
+    ///
+    /// A token for a specific GitHub API host can be given in an environment variable named after that host, which takes precedence over `NP_GITHUB_TOKEN` for API access.
+    /// The variable name is `NP_GITHUB_TOKEN_` followed by the host in uppercase, with characters other than letters and digits replaced by underscores, e.g., `NP_GITHUB_TOKEN_GITHUB_EXAMPLE_COM` for `--github-api-url=https://github.example.com/api/v3`.
     #[command(display_order = 4, name = "github")]
     GitHub(GitHubArgs),
 
@@ -336,6 +345,13 @@ pub struct GlobalArgs {
     #[arg(global=true, long, default_value_t=Mode::Auto, value_name="MODE")]
     pub progress: Mode,
 
+    /// Write log messages in the specified format
+    ///
+    /// The `json` format writes each message to stderr as a single line of JSON, which is suitable for log aggregation systems.
+    /// The `NP_LOG` environment variable and the `--verbose` and `--quiet` options apply to either format.
+    #[arg(global=true, long, default_value_t=LogFormat::Human, value_name="FORMAT")]
+    pub log_format: LogFormat,
+
     /// Ignore validation of TLS certificates
     #[arg(global = true, long)]
     pub ignore_certs: bool,
@@ -410,6 +426,17 @@ pub enum Mode {
     Always,
 }
 
+/// The format of log messages
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq, ValueEnum)]
+#[strum(serialize_all = "kebab-case")]
+pub enum LogFormat {
+    /// Human-readable text
+    Human,
+
+    /// JSON, one object per line
+    Json,
+}
+
 // -----------------------------------------------------------------------------
 // `github` command
 // -----------------------------------------------------------------------------
@@ -500,6 +527,20 @@ pub struct GitHubRepoSpecifiers {
         default_value_t = GitHubRepoType::Source,
     )]
     pub repo_type: GitHubRepoType,
+
+    /// Record the progress of repository enumeration in the specified file
+    ///
+    /// If enumeration fails partway through, such as from a transient network error, rerunning
+    /// with the same file resumes from where it left off rather than fetching every page of
+    /// results again.
+    /// The file is removed once enumeration completes.
+    #[arg(
+        long,
+        visible_alias = "github-cursor-cache",
+        value_name = "PATH",
+        value_hint = ValueHint::FilePath
+    )]
+    pub cursor_cache: Option<PathBuf>,
 }
 
 #[cfg(feature = "github")]
@@ -523,10 +564,31 @@ pub enum RulesCommand {
     /// Check rules for problems
     ///
     /// If errors are detected or if warnings are detected and `--warnings-as-errors` is specified, the program will exit with a nonzero exit code.
+    ///
+    /// To check only your own rules and rulesets, such as from a pre-commit hook in a repository of custom rules, use `--load-builtins=false` along with `--rules-path=PATH`.
     Check(RulesCheckArgs),
 
     /// List available rules
     List(RulesListArgs),
+
+    /// Test rules against their examples
+    ///
+    /// All loaded rules are compiled together, just as when scanning, and each rule's examples are scanned.
+    /// A failure is reported for each example that its rule does not match, and for each negative example that its rule does match.
+    ///
+    /// If any example fails, the program will exit with a nonzero exit code.
+    ///
+    /// To test only your own rules, use `--load-builtins=false` along with `--rules-path=PATH`.
+    Test(RulesTestArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct RulesTestArgs {
+    #[command(flatten)]
+    pub rules: RuleSpecifierArgs,
+
+    #[command(flatten)]
+    pub output_args: OutputArgs<RulesTestOutputFormat>,
 }
 
 #[derive(Args, Debug)]
@@ -539,8 +601,17 @@ pub struct RulesCheckArgs {
     /// Perform additional nit-picking checks
     pub pedantic: bool,
 
+    #[arg(long)]
+    /// Report the size of the compiled matching database for the combined rules
+    ///
+    /// The size is included in the `human` output format only.
+    pub show_db_size: bool,
+
     #[command(flatten)]
     pub rules: RuleSpecifierArgs,
+
+    #[command(flatten)]
+    pub output_args: OutputArgs<RulesCheckOutputFormat>,
 }
 
 #[derive(Args, Debug)]
@@ -548,10 +619,53 @@ pub struct RulesListArgs {
     #[command(flatten)]
     pub rules: RuleSpecifierArgs,
 
+    /// Only print the number of loaded rules and the number of rules in each ruleset
+    #[arg(long)]
+    pub count_only: bool,
+
+    /// Print the full definition of the rule with the given ID or name instead of listing all
+    /// rules
+    ///
+    /// In JSON format, the pattern is additionally given with comments removed, as it is compiled.
+    ///
+    /// This option can be repeated.
+    #[arg(long, value_name = "RULE", conflicts_with = "count_only")]
+    pub rule: Vec<String>,
+
     #[command(flatten)]
     pub output_args: OutputArgs<RulesListOutputFormat>,
 }
 
+// -----------------------------------------------------------------------------
+// rules check output format
+// -----------------------------------------------------------------------------
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+#[strum(serialize_all = "kebab-case")]
+pub enum RulesCheckOutputFormat {
+    /// A text-based format designed for humans
+    Human,
+
+    /// Pretty-printed JSON format
+    ///
+    /// This is an array of objects, one for each detected problem, with `rule_id`, `ruleset_id`, `severity`, `message`, `file`, and `line` fields.
+    Json,
+}
+
+// -----------------------------------------------------------------------------
+// rules test output format
+// -----------------------------------------------------------------------------
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+#[strum(serialize_all = "kebab-case")]
+pub enum RulesTestOutputFormat {
+    /// A text-based format designed for humans
+    Human,
+
+    /// Pretty-printed JSON format
+    ///
+    /// This is an array of objects, one for each failed example, in the same form as the JSON output of `rules check`.
+    Json,
+}
+
 // -----------------------------------------------------------------------------
 // rules list output format
 // -----------------------------------------------------------------------------
@@ -581,6 +695,95 @@ pub enum DatastoreCommand {
 
     /// Export a datastore
     Export(DatastoreExportArgs),
+
+    /// Import findings into a datastore
+    ///
+    /// This reads rules, blobs, and matches in the JSON Lines format written by `datastore export
+    /// --format=jsonl`, and records them into the datastore as if they had been found by a scan.
+    /// Matches that are already present in the datastore are not duplicated.
+    /// This can be used to merge the results of scans run on several machines.
+    ///
+    /// Input must begin with the header record that `datastore export` writes; input without one,
+    /// or whose header gives an unsupported schema version, is rejected.
+    ///
+    /// Match statuses and comments are not imported; use the `annotations` command for those.
+    Import(DatastoreImportArgs),
+
+    /// Inspect the scan runs recorded in a datastore
+    Runs(DatastoreRunsArgs),
+
+    /// Check the integrity of a datastore
+    ///
+    /// This checks the integrity of the datastore's SQLite database, that its schema version is
+    /// supported, that its foreign keys are consistent, and that its subdirectories exist.
+    /// The datastore is opened read-only, so this is safe to run at any time.
+    ///
+    /// The exit code is nonzero if any problems are found.
+    Check(DatastoreCheckArgs),
+
+    /// Reclaim unused space in a datastore
+    ///
+    /// This rebuilds the datastore's SQLite database, which can grow much larger than its live
+    /// contents after repeated scans, and truncates its write-ahead log.
+    /// The sizes of the database file before and after are printed.
+    ///
+    /// This fails if another process, such as a running scan, is using the datastore.
+    Vacuum(DatastoreVacuumArgs),
+
+    /// Delete the findings of specific rules from a datastore
+    ///
+    /// This removes the findings of the given rules along with their matches, statuses, comments,
+    /// and scores, such as after disabling a noisy rule, without needing to rescan anything.
+    /// Use the `vacuum` command afterward to reclaim the space they used.
+    Prune(DatastorePruneArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct DatastorePruneArgs {
+    /// Prune the specified datastore
+    #[arg(
+        long,
+        short,
+        value_name = "PATH",
+        value_hint = ValueHint::DirPath,
+        env("NP_DATASTORE"),
+        default_value=DEFAULT_DATASTORE,
+    )]
+    pub datastore: PathBuf,
+
+    /// Delete the findings of the rule with the given name or ID
+    ///
+    /// This option can be repeated.
+    #[arg(long, value_name = "RULE", required = true)]
+    pub rule: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct DatastoreVacuumArgs {
+    /// Vacuum the specified datastore
+    #[arg(
+        long,
+        short,
+        value_name = "PATH",
+        value_hint = ValueHint::DirPath,
+        env("NP_DATASTORE"),
+        default_value=DEFAULT_DATASTORE,
+    )]
+    pub datastore: PathBuf,
+}
+
+#[derive(Args, Debug)]
+pub struct DatastoreCheckArgs {
+    /// Check the specified datastore
+    #[arg(
+        long,
+        short,
+        value_name = "PATH",
+        value_hint = ValueHint::DirPath,
+        env("NP_DATASTORE"),
+        default_value=DEFAULT_DATASTORE,
+    )]
+    pub datastore: PathBuf,
 }
 
 #[derive(Args, Debug)]
@@ -617,6 +820,16 @@ pub struct DatastoreExportArgs {
     /// Write output in the specified format
     #[arg(long, short, value_name = "FORMAT", default_value = "tgz")]
     pub format: DatastoreExportOutputFormat,
+
+    /// Exclude matches with the assigned status
+    ///
+    /// This option can be repeated.
+    /// Findings are excluded only when none of their matches remain.
+    /// The status `mixed` excludes the `accept` and `reject` matches of findings that have both.
+    /// The matches are removed from a copy of the datastore's database in its scratch directory, which is then exported; the datastore itself is not modified.
+    /// Blobs copied into the datastore with `scan --copy-blobs` are exported regardless.
+    #[arg(long, value_name = "STATUS")]
+    pub exclude_finding_status: Vec<FindingStatus>,
 }
 
 // -----------------------------------------------------------------------------
@@ -627,6 +840,83 @@ pub struct DatastoreExportArgs {
 pub enum DatastoreExportOutputFormat {
     /// gzipped tarball
     Tgz,
+
+    /// JSON Lines format of rules, blobs, and matches, which can be read by `datastore import`
+    Jsonl,
+}
+
+#[derive(Args, Debug)]
+pub struct DatastoreImportArgs {
+    /// Import into the specified datastore
+    ///
+    /// The datastore is created if it does not exist.
+    #[arg(
+        long,
+        short,
+        value_name = "PATH",
+        value_hint = ValueHint::DirPath,
+        env("NP_DATASTORE"),
+        default_value=DEFAULT_DATASTORE,
+    )]
+    pub datastore: PathBuf,
+
+    /// Read records from the specified path
+    ///
+    /// If this argument is not provided, stdin will be used.
+    #[arg(
+        long,
+        short,
+        value_name = "PATH",
+        value_hint = ValueHint::FilePath,
+    )]
+    pub input: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct DatastoreRunsArgs {
+    #[command(subcommand)]
+    pub command: DatastoreRunsCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DatastoreRunsCommand {
+    /// List the scan runs recorded in a datastore, along with their versions, totals, and labels
+    List(DatastoreRunsListArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct DatastoreRunsListArgs {
+    /// Use the specified datastore
+    #[arg(
+        long,
+        short,
+        value_name = "PATH",
+        value_hint = ValueHint::DirPath,
+        env("NP_DATASTORE"),
+        default_value=DEFAULT_DATASTORE,
+    )]
+    pub datastore: PathBuf,
+
+    #[command(flatten)]
+    pub output_args: OutputArgs<DatastoreRunsListOutputFormat>,
+}
+
+// -----------------------------------------------------------------------------
+// datastore runs list output format
+// -----------------------------------------------------------------------------
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+#[strum(serialize_all = "kebab-case")]
+pub enum DatastoreRunsListOutputFormat {
+    /// A text-based format designed for humans
+    Human,
+
+    /// Pretty-printed JSON format
+    Json,
+
+    /// JSON Lines format
+    ///
+    /// This is a sequence of JSON objects, one per line.
+    Jsonl,
 }
 
 // -----------------------------------------------------------------------------
@@ -638,6 +928,11 @@ pub struct ScanArgs {
     /// Use the specified datastore
     ///
     /// The datastore will be created if it does not exist.
+    ///
+    /// This option can be repeated to record findings into several datastores at once.
+    /// Only the first datastore receives Git repository clones and blobs copied with `--copy-blobs`.
+    /// If writing to one datastore fails, the others continue to be written, and the failure is
+    /// reported once scanning finishes.
     #[arg(
         long,
         short,
@@ -646,15 +941,102 @@ pub struct ScanArgs {
         env("NP_DATASTORE"),
         default_value=DEFAULT_DATASTORE,
     )]
-    pub datastore: PathBuf,
+    pub datastore: Vec<PathBuf>,
 
     /// Use N parallel scanning threads
     #[arg(long("jobs"), short('j'), value_name="N", default_value_t=default_scan_jobs())]
     pub num_jobs: usize,
 
+    /// Use N parallel threads for enumerating filesystem inputs
+    ///
+    /// This can be set lower than `--jobs` to avoid thrashing slow storage, such as a network
+    /// filesystem, while still using many threads for matching.
+    ///
+    /// By default, the `--jobs` value is used.
+    #[arg(long, value_name = "N")]
+    pub jobs_enumeration: Option<usize>,
+
+    /// Print a histogram of the sizes of blobs seen after scanning
+    ///
+    /// The histogram uses power-of-two size buckets.
+    /// This can help explain scanning performance, such as when a few very large blobs take up most of the scan time.
+    #[arg(long)]
+    pub size_histogram: bool,
+
+    /// Print the rules with the most raw matches after scanning
+    ///
+    /// Raw matches are those from the first stage of matching, before second-stage regex
+    /// matching and deduplication.
+    /// This can help identify rules that are expensive to match, such as ones with broad patterns.
+    #[arg(long)]
+    pub show_rule_stats: bool,
+
+    /// Exit with status 3 if the scan finds any new matches
+    ///
+    /// This allows scripts to distinguish a scan that found secrets from one that failed:
+    /// exit status 2 indicates an error, and takes precedence over this option.
+    /// Only matches that were not already in the datastore are considered.
+    /// The output of the scan is unaffected.
+    #[arg(long)]
+    pub fail_on_findings: bool,
+
+    /// Use the scoring model at the given path to assign a score to each match
+    ///
+    /// The model is a JSON file describing a logistic regression over simple features of the
+    /// matched content, such as its length and entropy, with optional per-rule offsets.
+    /// Scores range from 0 to 1, and can be used to suppress likely false positives with the
+    /// `--min-score` option of the `report` command.
+    ///
+    /// When no model is given, matches are not scored.
+    #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+    pub scoring_model: Option<PathBuf>,
+
+    /// Consider overlapping matches redundant when at least this fraction of each overlaps
+    ///
+    /// The value must be between 0.0 and 1.0.
+    /// Lower values cause more overlapping matches to be considered redundant.
+    /// Redundant matches are determined after scanning, for all matches in the datastore, and are
+    /// suppressed by the `report` command unless `--suppress-redundant=false` is given.
+    ///
+    /// The value is recorded in the datastore, and is reused by later scans that do not give this
+    /// option and by `datastore import`.
+    /// If no value has been recorded, 0.2 is used.
+    #[arg(long, value_name = "FRACTION", value_parser = parse_redundancy_overlap)]
+    pub redundancy_overlap: Option<f64>,
+
+    /// Use the specified algorithm to compute the IDs of blobs that do not come from Git
+    /// repositories
+    ///
+    /// Blob IDs are used to avoid scanning duplicate content and to identify blobs in the
+    /// datastore.
+    /// Blobs from Git repositories always use the IDs that Git assigns them.
+    /// IDs computed with `blake3` do not match those from Git or from scans using `sha1`, so they
+    /// will not match blob IDs given to `--exclude-blob-ids`, for example.
+    /// A datastore records the algorithm it uses, and scans into it must use the same one.
+    #[arg(long, value_name = "ALGORITHM", default_value_t = BlobIdAlgorithm::Sha1)]
+    pub blob_id_algorithm: BlobIdAlgorithm,
+
     #[command(flatten)]
     pub rules: RuleSpecifierArgs,
 
+    /// Log details of how the rule with the specified ID matches
+    ///
+    /// For the specified rule, every raw match from the first-stage Vectorscan matcher is logged,
+    /// along with the reason for every one that is discarded, such as a failed second-stage regex
+    /// match or being contained within another match.
+    /// This is useful for debugging rules that do not match as expected.
+    ///
+    /// These messages are logged at the debug level, so `-vv` is needed to see them.
+    /// Alternatively, use `NP_LOG=noseyparker::matcher=debug` to see only these messages.
+    #[arg(long, value_name = "ID", help_heading = "Rule Selection Options")]
+    pub trace_rule: Option<String>,
+
+    /// Scan every blob that is encountered, even ones with content that was already scanned
+    ///
+    /// This is intended for benchmarking matching throughput, and has no effect on the findings.
+    #[arg(long, hide = true)]
+    pub no_dedupe_blobs: bool,
+
     #[command(flatten)]
     pub input_specifier_args: InputSpecifierArgs,
 
@@ -668,6 +1050,8 @@ pub struct ScanArgs {
     ///
     /// The default value typically gives between 4 and 7 lines of context before and after each
     /// match.
+    /// The `--snippet-length-before` and `--snippet-length-after` options override this for just
+    /// one side of matches.
     #[arg(
         long,
         value_name = "BYTES",
@@ -676,6 +1060,40 @@ pub struct ScanArgs {
     )]
     pub snippet_length: usize,
 
+    /// Include up to the specified number of bytes before each match, instead of the
+    /// `--snippet-length` value
+    #[arg(long, value_name = "BYTES", help_heading = "Data Collection Options")]
+    pub snippet_length_before: Option<usize>,
+
+    /// Include up to the specified number of bytes after each match, instead of the
+    /// `--snippet-length` value
+    #[arg(long, value_name = "BYTES", help_heading = "Data Collection Options")]
+    pub snippet_length_after: Option<usize>,
+
+    /// Use the specified mode for delimiting the context included before and after each match
+    #[arg(
+        long,
+        value_name = "MODE",
+        default_value_t = SnippetMode::Bytes,
+        help_heading = "Data Collection Options"
+    )]
+    pub snippet_mode: SnippetMode,
+
+    /// Record at most N matches from each rule in each blob
+    ///
+    /// This keeps pathological inputs, such as generated files with tens of thousands of matches,
+    /// from dominating the datastore.
+    /// When matches are omitted, the number omitted is recorded in the blob's metadata.
+    ///
+    /// By default, all matches are recorded.
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help_heading = "Data Collection Options"
+    )]
+    pub max_matches_per_blob: Option<u64>,
+
     /// Specify which blobs will be copied in entirety to the datastore
     ///
     /// If this option is enabled, corresponding blobs will be written to the `blobs` directory within the datastore.
@@ -695,15 +1113,49 @@ pub struct ScanArgs {
     /// Specify the format for blobs copied by the `--copy-blobs` option
     #[arg(long, value_name="FORMAT", default_value_t=DEFAULT_COPY_BLOBS_FORMAT)]
     pub copy_blobs_format: CopyBlobsFormat,
+
+    /// Specify the layout for blobs copied by the `--copy-blobs` option
+    ///
+    /// This option is only supported with `--copy-blobs-format=files`.
+    #[arg(long, value_name="LAYOUT", default_value_t=CopyBlobsLayout::Hash)]
+    pub copy_blobs_layout: CopyBlobsLayout,
+
+    /// Only copy blobs of at least the specified size with the `--copy-blobs` option
+    ///
+    /// The size is given as for `--max-file-size`.
+    /// Blobs that are too small are still scanned and recorded as usual.
+    #[arg(
+        long,
+        default_value = "none",
+        value_name = "SIZE",
+        value_parser = parse_max_file_size,
+        allow_negative_numbers = true
+    )]
+    pub copy_blobs_min_size: MaxFileSize,
+
+    /// Only copy blobs of at most the specified size with the `--copy-blobs` option
+    ///
+    /// The size is given as for `--max-file-size`.
+    /// Blobs that are too large are still scanned and recorded as usual.
+    #[arg(
+        long,
+        default_value = "none",
+        value_name = "SIZE",
+        value_parser = parse_max_file_size,
+        allow_negative_numbers = true
+    )]
+    pub copy_blobs_max_size: MaxFileSize,
 }
 
 #[derive(Args, Debug)]
 #[command(next_help_heading = "Rule Selection Options")]
 pub struct RuleSpecifierArgs {
-    /// Load additional rules and rulesets from the specified file or directory
+    /// Load additional rules and rulesets from the specified file, directory, or URL
     ///
     /// The paths can be either files or directories.
-    /// Directories are recursively walked and all discovered YAML files of rules and rulesets will be loaded.
+    /// Directories are recursively walked and all discovered YAML and TOML files of rules and rulesets will be loaded.
+    /// HTTP and HTTPS URLs of YAML and TOML files are fetched and loaded like local files.
+    /// When scanning, fetched files are cached in the datastore, and are only downloaded again when they have changed.
     ///
     /// This option can be repeated.
 
@@ -724,6 +1176,15 @@ pub struct RuleSpecifierArgs {
     #[arg(long, value_name = "ID", default_values_t=["default".to_string()])]
     pub ruleset: Vec<String>,
 
+    /// Disable the rule with the specified ID or name
+    ///
+    /// Excluded rules are removed after the enabled rulesets have been resolved.
+    /// A warning is emitted if the value does not match any loaded rule.
+    ///
+    /// This option can be repeated.
+    #[arg(long, value_name = "ID")]
+    pub exclude_rule: Vec<String>,
+
     /// Control whether built-in rules and rulesets are loaded.
     #[arg(long, default_value_t=true, action=ArgAction::Set, value_name="BOOL")]
     pub load_builtins: bool,
@@ -789,12 +1250,99 @@ pub struct MetadataArgs {
     #[arg(long, default_value_t=BlobMetadataMode::Matching, value_name="MODE")]
     pub blob_metadata: BlobMetadataMode,
 
+    /// Always use the given media type for blobs whose paths match the given glob
+    ///
+    /// This overrides the path-based and content-based media type guesses that are recorded in
+    /// blob metadata, such as `--mime-override '*.pem=application/x-pem-file'`.
+    /// Globs are matched against the entire path of a blob.
+    ///
+    /// This option can be repeated. If several globs match a path, the first one given wins.
+    #[arg(long, value_name = "GLOB=MIME", value_parser = parse_mime_override)]
+    pub mime_override: Vec<(String, mime::Mime)>,
+
     /// Specify which Git commit provenance metadata will be collected
     ///
+    /// The `first-seen` mode is the most expensive, as it requires traversing the entire commit
+    /// graph of each repository.
+    /// The `repo-and-path` mode reads each tree object once to find a representative path for each
+    /// blob, which is much cheaper, but records no commit information.
+    /// In this mode, path-based ignore rules apply only to each blob's representative path.
+    /// The `minimal` mode only lists the objects in each repository.
+    ///
     /// This should not need to be changed unless you are running into performance problems on a
     /// problematic Git repository input.
     #[arg(long, default_value_t=GitBlobProvenanceMode::FirstSeen, value_name="MODE")]
     pub git_blob_provenance: GitBlobProvenanceMode,
+
+    /// Record at most N provenance entries for each blob
+    ///
+    /// Unlike the `--max-provenance` option of the `report` command, which only limits what is
+    /// displayed, this limits what is stored in the datastore: provenance entries beyond the limit
+    /// are discarded and can never be reported.
+    /// This can substantially reduce datastore size when scanning inputs with many duplicate files.
+    ///
+    /// By default, all provenance entries are recorded.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub max_provenance_recorded: Option<u64>,
+
+    /// Record the paths of files relative to the input roots they were found under
+    ///
+    /// Each path starts with the name of its input root, so that scanning `/home/me/project`
+    /// records `project/src/x.rs` rather than `/home/me/project/src/x.rs`.
+    /// This makes datastores more portable, and avoids revealing local directory structure when
+    /// they are shared.
+    ///
+    /// This applies only to plain files; the paths of Git repositories and archives are unaffected.
+    #[arg(long)]
+    pub input_root_relative_paths: bool,
+
+    /// Attach the given label to the recorded scan run
+    ///
+    /// A label is an arbitrary `KEY=VALUE` pair, such as `env=prod` or `ticket=SEC-1234`.
+    /// Labels can be shown later with the `datastore runs list` command.
+    ///
+    /// This option can be repeated.
+    #[arg(long, value_name = "KEY=VALUE", value_parser = parse_run_label)]
+    pub run_label: Vec<(String, String)>,
+
+    /// Record information about the scanning host and Nosey Parker build as scan run labels
+    ///
+    /// This records the following labels, which document where and how a shared datastore was
+    /// produced:
+    ///
+    /// - `np.host`: the hostname of the scanning system
+    ///
+    /// - `np.os`: the operating system of the scanning system
+    ///
+    /// - `np.version`: the Nosey Parker version and Git commit
+    ///
+    /// - `np.features`: the Cargo features Nosey Parker was built with
+    ///
+    /// - `np.target`: the target triple Nosey Parker was built for
+    ///
+    /// Labels given with `--run-label` take precedence over these.
+    #[arg(long)]
+    pub record_host_info: bool,
+}
+
+/// Parse a `KEY=VALUE` scan run label.
+fn parse_run_label(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err("expected a label of the form KEY=VALUE".to_string()),
+    }
+}
+
+/// Parse a `GLOB=MIME` media type override.
+fn parse_mime_override(s: &str) -> Result<(String, mime::Mime), String> {
+    let (glob, mime) = match s.rsplit_once('=') {
+        Some((glob, mime)) if !glob.is_empty() => (glob, mime),
+        _ => return Err("expected an override of the form GLOB=MIME".to_string()),
+    };
+    let mime = mime
+        .parse()
+        .map_err(|e| format!("invalid media type {mime:?}: {e}"))?;
+    Ok((glob.to_string(), mime))
 }
 
 #[derive(Copy, Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
@@ -810,6 +1358,30 @@ pub enum BlobMetadataMode {
     None,
 }
 
+/// The mode to use for delimiting the context of snippets
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+#[strum(serialize_all = "kebab-case")]
+pub enum SnippetMode {
+    /// Include up to `--snippet-length` bytes of context
+    Bytes,
+
+    /// Include up to `--snippet-length` bytes of context, trimmed to whole lines when possible
+    Line,
+
+    /// Include up to `--snippet-length` bytes of context, but only from the lines containing the
+    /// match
+    ///
+    /// This keeps snippets small for matches in files with long runs of lines, such as embedded
+    /// private keys.
+    MatchingLines,
+
+    /// Include the enclosing object, array, or block for blobs detected as JSON or YAML
+    ///
+    /// Context falls back to `bytes` mode for other blobs, or when the enclosing structure cannot
+    /// be determined or extends much further than `--snippet-length` bytes.
+    Structured,
+}
+
 #[derive(Copy, Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
 #[strum(serialize_all = "kebab-case")]
 pub enum CopyBlobsMode {
@@ -834,43 +1406,114 @@ pub enum CopyBlobsFormat {
     Files,
 }
 
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+#[strum(serialize_all = "kebab-case")]
+pub enum CopyBlobsLayout {
+    /// Name copied blobs by their hex-encoded blob ID only
+    Hash,
+
+    /// Name copied blobs by their hex-encoded blob ID, and additionally record the provenance of
+    /// each copied blob in a `provenance.jsonl` index file within the `blobs` directory
+    ///
+    /// Each line of the index is a JSON object with the blob ID, the path of the copied blob
+    /// relative to the `blobs` directory, and the provenance entries through which the blob was
+    /// encountered.
+    /// A blob that is encountered multiple times gets multiple index lines.
+    ByProvenance,
+}
+
 #[cfg(feature = "parquet")]
 const DEFAULT_COPY_BLOBS_FORMAT: CopyBlobsFormat = CopyBlobsFormat::Parquet;
 #[cfg(not(feature = "parquet"))]
 const DEFAULT_COPY_BLOBS_FORMAT: CopyBlobsFormat = CopyBlobsFormat::Files;
 
-#[derive(Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
 #[strum(serialize_all = "kebab-case")]
 pub enum GitBlobProvenanceMode {
     /// The Git repository and set of commits and accompanying pathnames in which a blob is first
     /// seen
     FirstSeen,
 
+    /// The Git repository in which a blob is seen and a representative pathname for it, without
+    /// any commit information
+    RepoAndPath,
+
     /// Only the Git repository in which a blob is seen
     Minimal,
 }
 
+/// The algorithm to use to compute blob IDs
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq, ValueEnum)]
+#[strum(serialize_all = "kebab-case")]
+pub enum BlobIdAlgorithm {
+    /// The SHA-1 digest of the blob, as computed by Git
+    Sha1,
+
+    /// The BLAKE3 digest of the blob, truncated to the length of a SHA-1 digest, which is faster
+    /// to compute
+    Blake3,
+}
+
+impl From<BlobIdAlgorithm> for noseyparker::blob::BlobIdAlgorithm {
+    fn from(val: BlobIdAlgorithm) -> Self {
+        match val {
+            BlobIdAlgorithm::Sha1 => noseyparker::blob::BlobIdAlgorithm::Sha1,
+            BlobIdAlgorithm::Blake3 => noseyparker::blob::BlobIdAlgorithm::Blake3,
+        }
+    }
+}
+
+/// The IDs of the input specifier arguments other than path inputs, any of which can be given
+/// instead of path inputs
+#[cfg(feature = "github")]
+const NON_PATH_INPUT_ARGS: &[&str] = &[
+    "github_user",
+    "github_organization",
+    "git_url",
+    "git_url_file",
+    "all_github_organizations",
+    "enumerators",
+    #[cfg(feature = "kubernetes")]
+    "k8s_secrets",
+    #[cfg(feature = "s3")]
+    "s3_urls",
+];
+
 #[derive(Args, Debug)]
 #[command(next_help_heading = "Input Specifier Options")]
 pub struct InputSpecifierArgs {
     #[cfg(feature = "github")]
-    /// Scan the specified file, directory, or local Git repository
+    /// Scan the specified file, directory, local Git repository, or Git bundle file
+    ///
+    /// A Git bundle file, as created by `git bundle create` and named with a `.bundle` extension,
+    /// is cloned into the datastore using the `git` binary, and then scanned like a Git repository.
+    /// Its provenance records the bundle file path.
+    ///
+    /// Tar archives, named with a `.tar`, `.tar.gz`, `.tgz`, `.tar.zst`, or `.tzst` extension, have
+    /// each of their members scanned, subject to the maximum file size.
+    ///
+    /// An input of `-` reads standard input and scans it as a single blob, recorded with a path of
+    /// `<stdin>`.
     #[arg(
         value_name="INPUT",
         value_hint=ValueHint::AnyPath,
-        required_unless_present_any([
-            "github_user",
-            "github_organization",
-            "git_url",
-            "all_github_organizations",
-            "enumerators",
-        ]),
+        required_unless_present_any(NON_PATH_INPUT_ARGS),
         display_order=1,
     )]
     pub path_inputs: Vec<PathBuf>,
 
     #[cfg(not(feature = "github"))]
-    /// Scan the specified file, directory, or local Git repository
+    /// Scan the specified file, directory, local Git repository, or Git bundle file
+    ///
+    /// A Git bundle file, as created by `git bundle create` and named with a `.bundle` extension,
+    /// is cloned into the datastore using the `git` binary, and then scanned like a Git repository.
+    /// Its provenance records the bundle file path.
+    ///
+    /// Tar archives, named with a `.tar`, `.tar.gz`, `.tgz`, `.tar.zst`, or `.tzst` extension, have
+    /// each of their members scanned, subject to the maximum file size.
+    ///
+    /// An input of `-` reads standard input and scans it as a single blob, recorded with a path of
+    /// `<stdin>`.
     #[arg(
         value_name="INPUT",
         value_hint=ValueHint::AnyPath,
@@ -880,7 +1523,9 @@ pub struct InputSpecifierArgs {
 
     /// Clone and scan the Git repository at the specified URL
     ///
-    /// Only https URLs without credentials, query parameters, or fragment identifiers are supported.
+    /// Only https and ssh URLs without passwords, query parameters, or fragment identifiers are supported.
+    /// scp-like SSH addresses such as `git@github.com:praetorian-inc/noseyparker.git` are also accepted.
+    /// Credentials from `--netrc` are only used for https URLs.
     ///
     /// This option can be repeated.
     #[arg(
@@ -891,6 +1536,21 @@ pub struct InputSpecifierArgs {
     )]
     pub git_url: Vec<GitUrl>,
 
+    /// Clone and scan the Git repositories at the URLs listed in the specified file
+    ///
+    /// The file lists one URL per line, with the same requirements as `--git-url`.
+    /// Blank lines and lines starting with `#` are ignored.
+    /// Lines with invalid URLs are skipped with a warning.
+    ///
+    /// This option can be repeated.
+    #[arg(
+        long,
+        value_name = "PATH",
+        value_hint = ValueHint::FilePath,
+        display_order = 10,
+    )]
+    pub git_url_file: Vec<PathBuf>,
+
     /// Read inputs from a JSONL enumerator file (experimental)
     ///
     /// This can be used to stream inputs from other processes without having to write them to disk.
@@ -902,6 +1562,11 @@ pub struct InputSpecifierArgs {
     ///     { "content_base64": "base64-encoded bytestring to scan", "provenance": <arbitrary object> }
     ///     { "content": "utf8 string to scan", "provenance": <arbitrary object> }
     ///
+    /// An object can also have a `blob_id` field giving the hex-encoded Git blob ID of its content, if already known.
+    /// The blob ID is then used as is rather than computed.
+    ///
+    /// Enumerator files named with a `.gz` or `.zst` extension are decompressed with gzip or zstd.
+    ///
     /// This option can be repeated.
     #[arg(
         long("enumerator"),
@@ -967,17 +1632,134 @@ pub struct InputSpecifierArgs {
     )]
     pub github_repo_type: GitHubRepoType,
 
+    #[cfg(feature = "github")]
+    /// Record the progress of GitHub repository enumeration in the specified file
+    ///
+    /// If enumeration fails partway through, such as from a transient network error, rerunning
+    /// with the same file resumes from where it left off rather than fetching every page of
+    /// results again.
+    /// The file is removed once enumeration completes.
+    #[arg(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+    pub github_cursor_cache: Option<PathBuf>,
+
+    #[cfg(feature = "kubernetes")]
+    /// Scan the data values of Secret objects listed through the Kubernetes API
+    ///
+    /// The API server and credentials are determined from the ambient configuration, i.e., from
+    /// the kubeconfig file or from the in-cluster environment.
+    /// Each data value is scanned as a separate blob, with provenance indicating the namespace,
+    /// name, and key of its Secret object.
+    #[arg(long, display_order = 35)]
+    pub k8s_secrets: bool,
+
+    #[cfg(feature = "kubernetes")]
+    /// Scan Kubernetes secrets only from the specified namespace
+    ///
+    /// If this is not specified, secrets from all namespaces are scanned.
+    ///
+    /// This option can be repeated.
+    #[arg(
+        long,
+        value_name = "NAME",
+        requires = "k8s_secrets",
+        display_order = 35
+    )]
+    pub k8s_namespace: Vec<String>,
+
+    #[cfg(feature = "s3")]
+    /// Scan the objects in the S3 bucket at the specified URL
+    ///
+    /// The URL has the form `s3://bucket/prefix`, and all objects whose keys start with the prefix
+    /// are scanned, subject to the maximum file size.
+    /// Credentials and region are determined from the standard AWS environment variables,
+    /// configuration files, and credential providers.
+    /// Each object is scanned as a separate blob, with provenance indicating its `s3://bucket/key`
+    /// URL.
+    ///
+    /// This option can be repeated.
+    #[arg(long("s3-url"), value_name = "URL", value_hint = ValueHint::Url, display_order = 36)]
+    pub s3_urls: Vec<S3Url>,
+
     /// Use the specified method for cloning Git repositories
     #[arg(long, value_name = "MODE", display_order = 40, default_value_t=GitCloneMode::Bare, alias="git-clone-mode")]
     pub git_clone: GitCloneMode,
 
+    /// Fetch only refs matching the given refspec when cloning Git repositories
+    ///
+    /// This option can be repeated.
+    /// When given, these refspecs replace the ones that would otherwise be fetched according to `--git-clone`.
+    /// For example, `--git-fetch-refspec='+refs/heads/*:refs/heads/*' --git-fetch-refspec='+refs/tags/*:refs/tags/*'` fetches all branches and tags, but not other refs such as those for GitHub pull requests.
+    ///
+    /// This only affects new clones; existing clones within the datastore are updated using the refspecs they were created with.
+    #[arg(long, value_name = "REFSPEC", display_order = 40)]
+    pub git_fetch_refspec: Vec<String>,
+
+    /// Use credentials from the given netrc file when cloning Git repositories
+    ///
+    /// Credentials from a `machine` entry are only offered to Git servers at that host.
+    /// Any `default` entry in the file is ignored.
+    /// This can be used to clone from self-hosted Git servers that require HTTP basic authentication.
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath, display_order = 40)]
+    pub netrc: Option<PathBuf>,
+
+    /// Abandon cloning or updating a Git repository if it takes longer than the given number of seconds
+    ///
+    /// The `git` process is killed, and the repository is skipped as with any other clone failure.
+    /// By default, there is no timeout.
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        value_parser = clap::value_parser!(u64).range(1..),
+        display_order = 40
+    )]
+    pub git_clone_timeout: Option<u64>,
+
+    /// Fetch only the given number of most recent commits when cloning or updating Git repositories
+    ///
+    /// This creates shallow clones, which are much faster to create for repositories with long histories.
+    /// Only the history that is fetched is scanned, so secrets that were removed before the most recent commits will be missed.
+    /// This applies to repositories cloned from URLs, such as those specified with `--git-url=<URL>`, but not to Git bundle files.
+    /// By default, the full history is fetched.
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        display_order = 40
+    )]
+    pub git_clone_depth: Option<u32>,
+
     /// Use the specified mode for handling Git history
     ///
     /// Git history can be completely ignored when scanning by using `--git-history=none`.
-    /// Note that this will interfere with other input specifiers that cause Git repositories to be automatically cloned.
-    /// For example, specifying an input with `--git-url=<URL>` while simultaneously using `--git-history=none` will not result in useful scanning.
+    /// In that case, bare repositories have no working tree to scan, so only the blobs from the tree of their `HEAD` commit are scanned.
+    /// This includes repositories that are automatically cloned, such as those specified with `--git-url=<URL>`.
     #[arg(long, value_name = "MODE", display_order = 50, default_value_t=GitHistoryMode::Full)]
     pub git_history: GitHistoryMode,
+
+    /// Only scan Git history introduced after the given revision
+    ///
+    /// The revision, such as a commit hash, branch, or tag name, is resolved separately within each Git repository that is scanned.
+    /// Blobs that are reachable from the resolved commit or any of its ancestors are not scanned.
+    /// If the revision cannot be resolved to a commit in a repository, that repository is not scanned, and the scan fails after the other inputs are scanned.
+    ///
+    /// This has no effect when `--git-history=none` is specified.
+    #[arg(long, value_name = "REV", display_order = 50)]
+    pub git_since: Option<String>,
+
+    /// Only scan the blobs in the tree of the given revision, instead of Git history
+    ///
+    /// The revision, such as a commit hash, branch, or tag name, is resolved separately within each Git repository that is scanned.
+    /// Only the files present in the resolved commit are scanned, which is much faster than scanning the entire history.
+    /// If the revision cannot be resolved to a commit in a repository, that repository is not scanned, and the scan fails after the other inputs are scanned.
+    ///
+    /// When `--git-history=none` is specified, this selects the tree that is scanned in bare repositories instead of the tree of their `HEAD` commit.
+    #[arg(
+        long,
+        value_name = "REV",
+        display_order = 50,
+        conflicts_with = "git_since"
+    )]
+    pub git_ref: Option<String>,
 }
 
 /// This struct represents options to control content discovery.
@@ -986,16 +1768,34 @@ pub struct InputSpecifierArgs {
 pub struct ContentFilteringArgs {
     /// Do not scan files larger than the specified size
     ///
-    /// The value is parsed as a floating point literal, and hence fractional values can be supplied.
-    /// A non-positive value means "no limit".
+    /// The size is a number with an optional unit suffix, such as `512KiB`, `10MiB`, or `1GB`.
+    /// The `B`, `KB`, `MB`, and `GB` units are powers of 1000, and the `KiB`, `MiB`, and `GiB`
+    /// units are powers of 1024.
+    /// A number without a unit is interpreted as mebibytes, and fractional values can be supplied.
+    ///
+    /// The value `none`, `0`, or any negative value means "no limit".
+    /// Files that are skipped because of their size are logged at the INFO level.
+    ///
     /// Note that scanning requires reading the entire contents of each file into memory, so using an excessively large limit may be problematic.
     #[arg(
-        long("max-file-size"),
-        default_value_t = 100.0,
-        value_name = "MEGABYTES",
+        long,
+        default_value = "100MiB",
+        value_name = "SIZE",
+        value_parser = parse_max_file_size,
         allow_negative_numbers = true
     )]
-    pub max_file_size_mb: f64,
+    pub max_file_size: MaxFileSize,
+
+    /// Use a different maximum file size for files with the specified extension
+    ///
+    /// The value is of the form `EXT=SIZE`, such as `sql=500MiB` or `log=none`, where `SIZE` is
+    /// given as for `--max-file-size`.
+    /// Extensions are matched case-insensitively.
+    /// Files with other extensions use the `--max-file-size` limit.
+    ///
+    /// This option can be repeated.
+    #[arg(long, value_name = "EXT=SIZE", value_parser = parse_max_file_size_ext)]
+    pub max_file_size_ext: Vec<(String, MaxFileSize)>,
 
     /// Use custom path-based ignore rules from the specified file
     ///
@@ -1004,6 +1804,73 @@ pub struct ContentFilteringArgs {
     /// This option can be repeated.
     #[arg(long, short, value_name = "FILE", value_hint = ValueHint::FilePath)]
     pub ignore: Vec<PathBuf>,
+
+    /// Only scan files whose paths match the specified gitignore-style glob
+    ///
+    /// Globs are matched against paths relative to their input root, so a glob without a `/`,
+    /// such as `*.env`, matches files at any depth.
+    /// Directories are still traversed so that matching files within them are found, and ignore
+    /// rules still apply.
+    /// Files given explicitly on the command line and blobs from Git history are always scanned.
+    ///
+    /// This option can be repeated; files that match any of the globs are scanned.
+    #[arg(long, value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// Control whether `.noseyparkerignore` files in input directories are used
+    ///
+    /// When this is `true`, the rules in a `.noseyparkerignore` file apply to the contents of the
+    /// directory that contains it, like those of a `.gitignore` file.
+    /// They take precedence over the rules given with `--ignore`, but do not apply to Git history.
+    /// This allows project-specific ignore rules to be kept alongside the code being scanned.
+    #[arg(long, default_value_t=true, action=ArgAction::Set, value_name="BOOL")]
+    pub discover_ignore_files: bool,
+
+    /// Control whether hidden files and directories are scanned
+    ///
+    /// A hidden file or directory is one whose name starts with a `.`.
+    /// Inputs given explicitly on the command line are always scanned, even if hidden.
+    ///
+    /// When this is `false`, `.git` directories are still used to find Git history to scan,
+    /// but their contents are not otherwise scanned.
+    #[arg(long, default_value_t=true, action=ArgAction::Set, value_name="BOOL")]
+    pub scan_hidden: bool,
+
+    /// Follow symbolic links to files and directories
+    ///
+    /// By default, symbolic links are skipped, including those given explicitly on the command
+    /// line.
+    /// When following symbolic links, loops are detected and skipped with a warning.
+    #[arg(long)]
+    pub follow_symlinks: bool,
+
+    /// Do not scan blobs whose IDs are listed in the specified file
+    ///
+    /// The file should contain one hex-encoded blob ID per line, i.e., the SHA-1 digest computed as Git does for blobs.
+    /// Blank lines and lines starting with `#` are ignored.
+    /// This can be used to skip known-benign content that appears repeatedly, such as vendored third-party bundles.
+    ///
+    /// This option can be repeated.
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    pub exclude_blob_ids: Vec<PathBuf>,
+
+    /// Do not scan blobs that appear to be minified or machine-generated
+    ///
+    /// A blob is considered minified if the beginning of its content has very long lines on
+    /// average and very little whitespace, as is typical of bundled JavaScript and CSS.
+    /// Such content tends to produce many low-value matches.
+    #[arg(long)]
+    pub skip_minified: bool,
+
+    /// Fail if fewer than N blobs are scanned
+    ///
+    /// This can be used to notice misconfiguration that results in nothing being scanned, such as
+    /// a mistyped input path or overly broad ignore rules.
+    /// Scan results are still recorded to the datastore when this check fails.
+    ///
+    /// The default value of `0` disables this check.
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub min_blobs: u64,
     /*
     /// Do not scan files that appear to be binary
     #[arg(long)]
@@ -1011,13 +1878,80 @@ pub struct ContentFilteringArgs {
     */
 }
 
+impl ScanArgs {
+    /// The number of bytes of context to include before and after each match
+    pub fn snippet_lengths(&self) -> (usize, usize) {
+        (
+            self.snippet_length_before.unwrap_or(self.snippet_length),
+            self.snippet_length_after.unwrap_or(self.snippet_length),
+        )
+    }
+}
+
 impl ContentFilteringArgs {
     pub fn max_file_size_bytes(&self) -> Option<u64> {
-        if self.max_file_size_mb < 0.0 {
-            None
-        } else {
-            Some((self.max_file_size_mb * 1024.0 * 1024.0) as u64)
+        self.max_file_size.0
+    }
+}
+
+/// A maximum file size in bytes, where `None` means "no limit"
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MaxFileSize(pub Option<u64>);
+
+/// Parse a redundancy overlap fraction, which must be between 0.0 and 1.0.
+fn parse_redundancy_overlap(s: &str) -> Result<f64, String> {
+    match s.trim().parse::<f64>() {
+        Ok(v) if (0.0..=1.0).contains(&v) => Ok(v),
+        _ => Err(format!("invalid fraction `{s}`; expected a value between 0.0 and 1.0")),
+    }
+}
+
+/// Parse an `EXT=SIZE` maximum file size override for an extension.
+fn parse_max_file_size_ext(s: &str) -> Result<(String, MaxFileSize), String> {
+    match s.split_once('=') {
+        Some((ext, size)) => {
+            let ext = ext.strip_prefix('.').unwrap_or(ext);
+            if ext.is_empty() {
+                return Err("expected a value of the form EXT=SIZE".to_string());
+            }
+            Ok((ext.to_string(), parse_max_file_size(size)?))
         }
+        None => Err("expected a value of the form EXT=SIZE".to_string()),
+    }
+}
+
+/// Parse a maximum file size, such as `none`, `5`, `5.5MiB`, or `1GB`.
+fn parse_max_file_size(s: &str) -> Result<MaxFileSize, String> {
+    let s = s.trim();
+    if s.eq_ignore_ascii_case("none") {
+        return Ok(MaxFileSize(None));
+    }
+
+    let split = s.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = match number.trim().parse() {
+        Ok(n) if f64::is_finite(n) => n,
+        _ => return Err(format!("invalid size `{s}`")),
+    };
+    let multiplier: f64 = match unit.to_ascii_lowercase().as_str() {
+        "" | "mib" => 1024.0 * 1024.0,
+        "b" => 1.0,
+        "kb" => 1e3,
+        "kib" => 1024.0,
+        "mb" => 1e6,
+        "gb" => 1e9,
+        "gib" => 1024.0 * 1024.0 * 1024.0,
+        _ => {
+            return Err(format!(
+                "invalid size unit `{unit}`; expected one of B, KB, KiB, MB, MiB, GB, or GiB"
+            ))
+        }
+    };
+
+    if number <= 0.0 {
+        Ok(MaxFileSize(None))
+    } else {
+        Ok(MaxFileSize(Some((number * multiplier) as u64)))
     }
 }
 
@@ -1037,6 +1971,27 @@ pub struct SummarizeArgs {
     )]
     pub datastore: PathBuf,
 
+    /// Include only findings with the assigned status
+    #[arg(long, value_name = "STATUS")]
+    pub finding_status: Option<FindingStatus>,
+
+    /// Continuously redraw the summary, re-reading the datastore every given number of seconds
+    ///
+    /// This can be used to watch findings accumulate while a scan writes to the datastore.
+    /// The datastore is opened read-only.
+    /// Press Ctrl-C to exit.
+    ///
+    /// This can only be used with the `human` format written to standard output.
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "2",
+        value_parser = clap::value_parser!(u64).range(1..),
+    )]
+    pub watch: Option<u64>,
+
     #[command(flatten)]
     pub output_args: OutputArgs<SummarizeOutputFormat>,
 }
@@ -1060,6 +2015,87 @@ pub struct ReportArgs {
     #[command(flatten)]
     pub filter_args: ReportFilterArgs,
 
+    /// Include counts of where each finding was seen
+    ///
+    /// Each finding is reported once regardless of how many files, commits, or repositories it
+    /// appears in.
+    /// When this is enabled, each finding additionally reports its total number of occurrences,
+    /// i.e., provenance entries across all of its matches, and the number of distinct files,
+    /// repositories, or other sources among them.
+    /// These appear as the `occurrence_count` and `distinct_source_count` fields in JSON output.
+    #[arg(long)]
+    pub show_occurrence_counts: bool,
+
+    /// Include an indicator of how well-tested the rule behind each finding is
+    ///
+    /// This is derived from checking the rule, as recorded in the datastore, against its examples
+    /// and negative examples, as `rules check` does.
+    /// Findings from rules with more examples that all pass can be given more weight than findings
+    /// from rules with few or failing examples.
+    /// This appears as the `rule_quality` field in JSON output.
+    #[arg(long)]
+    pub rule_quality: bool,
+
+    /// Only print the number of findings that remain after filtering
+    ///
+    /// The output format is ignored in this case: a single integer is printed.
+    #[arg(long)]
+    pub count: bool,
+
+    /// Report each distinct matched value once instead of reporting findings
+    ///
+    /// Each capture group value from the findings that remain after filtering is reported once,
+    /// along with the number of matches it appears in and the names of the rules that matched it.
+    /// This gives a deduplicated checklist of secrets, e.g., for credential rotation.
+    ///
+    /// Because this output consists of plaintext secrets, `--allow-plaintext-values` must also be
+    /// given, unless `--redact` is used.
+    /// The `sarif` output format is not supported in this case.
+    #[arg(long, conflicts_with = "count")]
+    pub distinct_values: bool,
+
+    /// Acknowledge that `--distinct-values` output contains plaintext secrets
+    #[arg(long, requires = "distinct_values")]
+    pub allow_plaintext_values: bool,
+
+    /// Mask matched values in the output
+    ///
+    /// Each capture group value and the matching portion of each snippet is replaced with a
+    /// fixed-length mask, keeping only its first and last 4 characters if it is long enough to do
+    /// so.
+    /// The surrounding snippet context, rule names, and locations are left intact.
+    /// This also applies to `--distinct-values` output.
+    #[arg(long)]
+    pub redact: bool,
+
+    /// Control how matches are grouped in the output
+    ///
+    /// When this is `blob`, each blob with matches from the findings that remain after filtering
+    /// is reported once, with all of its matches across rules beneath it.
+    /// In that case, `--max-matches` limits the number of matches reported per blob, and the
+    /// `sarif` output format is not supported.
+    #[arg(
+        long,
+        default_value_t = ReportGroupBy::Finding,
+        value_name = "GROUPING",
+        conflicts_with_all = ["count", "distinct_values"]
+    )]
+    pub group_by: ReportGroupBy,
+
+    /// Control whether `json` format output is laid out for readability
+    ///
+    /// When `true`, each finding is written on its own line.
+    /// When `false`, the entire output is written on a single line, which is more compact.
+    /// Either way, the output is a JSON array of findings; other output formats are unaffected.
+    #[arg(
+        long,
+        default_value_t = true,
+        action = ArgAction::Set,
+        value_name = "BOOL",
+        help_heading = "Output Options"
+    )]
+    pub pretty: bool,
+
     #[command(flatten)]
     pub output_args: OutputArgs<ReportOutputFormat>,
 }
@@ -1102,14 +2138,31 @@ pub struct ReportFilterArgs {
     #[arg(long, value_name = "STATUS")]
     pub finding_status: Option<FindingStatus>,
 
+    /// Include only findings from the rule with the given ID or name
+    ///
+    /// This option can be repeated.
+    #[arg(long, value_name = "RULE")]
+    pub rule: Vec<String>,
+
     /// Suppress redundant matches and findings
     ///
     /// A match is considered redundant to another if they overlap significantly within the same
     /// blob and satisfy a handful of heuristics.
+    /// The required amount of overlap is set by the `--redundancy-overlap` option of `scan`.
     #[arg(long, default_value_t=true, action=ArgAction::Set, value_name="BOOL")]
     pub suppress_redundant: bool,
 }
 
+#[derive(ValueEnum, Debug, Display, Clone, Copy, PartialEq, Eq)]
+#[clap(rename_all = "lower")]
+#[strum(serialize_all = "lowercase")]
+pub enum ReportGroupBy {
+    /// Report each finding once, with its matches beneath it
+    Finding,
+    /// Report each blob once, with its matches from all findings beneath it
+    Blob,
+}
+
 #[derive(ValueEnum, Debug, Display, Clone, Copy)]
 #[clap(rename_all = "lower")]
 #[strum(serialize_all = "lowercase")]
@@ -1190,6 +2243,17 @@ pub struct AnnotationsImportArgs {
         value_hint = ValueHint::FilePath,
     )]
     pub input: Option<PathBuf>,
+
+    /// Read annotations as CSV with `finding_id,status,comment` columns
+    ///
+    /// The first row must be a header naming the columns.
+    /// Each row assigns its status, if any, to every match of the finding with the given ID, and
+    /// its comment, if any, to the finding itself.
+    /// The status is either `accept` or `reject`; empty fields are ignored.
+    ///
+    /// This can be used to triage findings in a spreadsheet and then apply the results in bulk.
+    #[arg(long)]
+    pub from_csv: bool,
 }
 
 // -----------------------------------------------------------------------------
@@ -1226,6 +2290,7 @@ pub enum ShellFormat {
     Fish,
     PowerShell,
     Elvish,
+    Nushell,
 }
 
 #[derive(Args, Debug)]
@@ -1303,6 +2368,8 @@ pub enum ReportOutputFormat {
     /// This is the Static Analysis Results Interchange Format, a standardized JSON-based format used by many tools.
     /// See the spec at <https://docs.oasis-open.org/sarif/sarif/v2.1.0/cs01/sarif-v2.1.0-cs01.html>.
     ///
+    /// Matched content is not included; each result gives the locations of its matches instead.
+    ///
     /// Support for SARIF output is experimental.
     /// If you run into problems when using this, please create an issue in the GitHub project: <https://github.com/praetorian-inc/noseyparker>.
     Sarif,
@@ -1324,6 +2391,11 @@ pub enum SummarizeOutputFormat {
     ///
     /// This is a sequence of JSON objects, one per line.
     Jsonl,
+
+    /// CSV format
+    ///
+    /// This has a header row followed by one row per rule, with `Rule`, `Findings`, `Matches`, `Accepted`, `Rejected`, `Mixed`, and `Unlabeled` columns.
+    Csv,
 }
 
 #[cfg(feature = "github")]
@@ -1,12 +1,11 @@
 use anyhow::{Context, Result};
-// use tracing::info;
-use tracing::debug;
+use tracing::{debug, info};
 
 use crate::args::{AnnotationsArgs, AnnotationsExportArgs, AnnotationsImportArgs, GlobalArgs};
 use crate::util::{get_reader_for_file_or_stdin, get_writer_for_file_or_stdout};
 
-use noseyparker::datastore::Annotations;
 use noseyparker::datastore::Datastore;
+use noseyparker::datastore::{Annotations, BulkFindingAnnotation};
 
 pub fn run(global_args: &GlobalArgs, args: &AnnotationsArgs) -> Result<()> {
     use crate::args::AnnotationsCommand::*;
@@ -22,8 +21,23 @@ fn cmd_annotations_import(global_args: &GlobalArgs, args: &AnnotationsImportArgs
 
     let input = get_reader_for_file_or_stdin(args.input.as_ref())?;
 
-    let annotations: Annotations =
-        serde_json::from_reader(input).context("Failed to read JSON input")?;
+    let annotations: Annotations = if args.from_csv {
+        let mut reader = csv::Reader::from_reader(input);
+        let bulk_annotations = reader
+            .deserialize()
+            .collect::<Result<Vec<BulkFindingAnnotation>, _>>()
+            .context("Failed to read CSV input")?;
+        let (annotations, num_missing) = datastore
+            .expand_finding_annotations(&bulk_annotations)
+            .context("Failed to expand finding annotations")?;
+        info!(
+            "Bulk finding annotations: {} rows; {num_missing} missing",
+            bulk_annotations.len()
+        );
+        annotations
+    } else {
+        serde_json::from_reader(input).context("Failed to read JSON input")?
+    };
     debug!(
         "Read {} match and {} finding annotations",
         annotations.match_annotations.len(),
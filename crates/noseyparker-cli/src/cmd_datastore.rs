@@ -1,14 +1,30 @@
-use anyhow::{Context, Result};
-use tracing::info;
+use anyhow::{bail, Context, Result};
+use tracing::{info, warn};
 
-use crate::args::{DatastoreArgs, DatastoreExportArgs, DatastoreInitArgs, GlobalArgs};
-use noseyparker::datastore::Datastore;
+use crate::args::{
+    DatastoreArgs, DatastoreCheckArgs, DatastoreExportArgs, DatastoreImportArgs, DatastoreInitArgs,
+    DatastorePruneArgs, DatastoreRunsArgs, DatastoreRunsListArgs, DatastoreRunsListOutputFormat,
+    DatastoreVacuumArgs, FindingStatus, GlobalArgs,
+};
+use crate::cmd_report::statuses_match;
+use crate::reportable::Reportable;
+use crate::util::get_reader_for_file_or_stdin;
+use noseyparker::blob::BlobIdAlgorithm;
+use noseyparker::datastore::{
+    BatchEntry, Datastore, ExportRecord, MatchIdInt, ScanRun, Status, DEFAULT_REDUNDANCY_OVERLAP,
+};
+use noseyparker_rules::Rule;
 
 pub fn run(global_args: &GlobalArgs, args: &DatastoreArgs) -> Result<()> {
     use crate::args::DatastoreCommand::*;
     match &args.command {
         Init(args) => cmd_datastore_init(global_args, args),
         Export(args) => cmd_datastore_export(global_args, args),
+        Import(args) => cmd_datastore_import(global_args, args),
+        Runs(args) => cmd_datastore_runs(global_args, args),
+        Check(args) => cmd_datastore_check(global_args, args),
+        Vacuum(args) => cmd_datastore_vacuum(global_args, args),
+        Prune(args) => cmd_datastore_prune(global_args, args),
     }
 }
 
@@ -23,6 +39,27 @@ fn cmd_datastore_export(global_args: &GlobalArgs, args: &DatastoreExportArgs) ->
         .with_context(|| format!("Failed to open datastore at {}", args.datastore.display()))?;
     let output_path = &args.output;
 
+    // When excluding findings, export the database from a filtered copy instead. The copy is
+    // removed when `filtered_dir` is dropped, which must happen after `filtered` is dropped.
+    let filtered_dir;
+    let filtered;
+    let db_datastore = if args.exclude_finding_status.is_empty() {
+        &datastore
+    } else {
+        filtered_dir = tempfile::Builder::new()
+            .prefix("export-")
+            .tempdir_in(datastore.scratch_dir())
+            .context("Failed to create temporary directory for filtered datastore")?;
+        filtered = filtered_datastore_copy(
+            &datastore,
+            filtered_dir.path(),
+            &args.exclude_finding_status,
+            global_args.advanced.sqlite_cache_size,
+        )
+        .context("Failed to create filtered copy of datastore")?;
+        &filtered
+    };
+
     // XXX Move this code into datastore.rs?
 
     use crate::args::DatastoreExportOutputFormat::*;
@@ -46,7 +83,10 @@ fn cmd_datastore_export(global_args: &GlobalArgs, args: &DatastoreExportArgs) ->
 
                 let root_dir = datastore.root_dir();
                 tar.append_path_with_name(root_dir.join(".gitignore"), ".gitignore")?;
-                tar.append_path_with_name(root_dir.join("datastore.db"), "datastore.db")?;
+                tar.append_path_with_name(
+                    db_datastore.root_dir().join("datastore.db"),
+                    "datastore.db",
+                )?;
                 tar.append_dir_all("blobs", datastore.blobs_dir())?;
                 let tmp_output = tar.into_inner()?.finish()?;
 
@@ -56,14 +96,375 @@ fn cmd_datastore_export(global_args: &GlobalArgs, args: &DatastoreExportArgs) ->
             };
 
             write_tar(output_path).context("Failed to write tarfile")?;
+        }
+
+        Jsonl => {
+            use std::io::{BufWriter, Write};
+
+            let write_jsonl = |output_path: &std::path::Path| -> Result<()> {
+                let records = db_datastore.get_export_records()?;
+                let mut output = BufWriter::new(std::fs::File::create(output_path)?);
+                for record in records {
+                    serde_json::to_writer(&mut output, &record)?;
+                    writeln!(output)?;
+                }
+                output.flush()?;
+                Ok(())
+            };
+
+            write_jsonl(output_path).context("Failed to write JSON Lines output")?;
+        }
+    }
+
+    info!(
+        "Exported datastore at {} to {}",
+        &datastore.root_dir().display(),
+        output_path.display()
+    );
 
-            info!(
-                "Exported datastore at {} to {}",
-                &datastore.root_dir().display(),
-                output_path.display()
-            );
+    Ok(())
+}
+
+/// Copy the database of `datastore` into a new datastore at `root_dir`, removing the matches
+/// with any of the given statuses, and the findings that are left without any matches.
+///
+/// A status of `mixed` removes the `accept` and `reject` matches of findings that have both.
+///
+/// The copy is vacuumed afterward, so that no trace of the removed matches remains in it.
+fn filtered_datastore_copy(
+    datastore: &Datastore,
+    root_dir: &std::path::Path,
+    excluded_statuses: &[FindingStatus],
+    cache_size: i64,
+) -> Result<Datastore> {
+    let mut copy = datastore.copy_database_to(root_dir, cache_size)?;
+
+    let excluded_match_ids: Vec<MatchIdInt> = copy.iter_findings(false, &[], |findings| {
+        let mut excluded_match_ids = Vec::new();
+        for metadata in findings {
+            let metadata = metadata?;
+            let statuses = metadata.statuses.0.as_slice();
+            let exclude_mixed = excluded_statuses
+                .iter()
+                .any(|status| matches!(status, FindingStatus::Mixed))
+                && statuses_match(FindingStatus::Mixed, statuses);
+            let excluded = |match_status: Option<Status>| {
+                excluded_statuses
+                    .iter()
+                    .any(|status| match (status, match_status) {
+                        (FindingStatus::Accept, Some(Status::Accept))
+                        | (FindingStatus::Reject, Some(Status::Reject))
+                        | (FindingStatus::Null, None) => true,
+                        (FindingStatus::Mixed, Some(_)) => exclude_mixed,
+                        _ => false,
+                    })
+            };
+            for entry in copy.get_finding_data(&metadata, None, Some(1), false)? {
+                if excluded(entry.match_status) {
+                    excluded_match_ids.push(entry.match_id);
+                }
+            }
         }
+        Ok(excluded_match_ids)
+    })?;
+
+    let (num_matches_deleted, num_findings_deleted) = copy.delete_matches(&excluded_match_ids)?;
+    copy.vacuum()?;
+    info!("Excluded {num_matches_deleted} matches and {num_findings_deleted} findings from export");
+    Ok(copy)
+}
+
+/// The number of blob records to record to the datastore in each transaction when importing
+const IMPORT_BATCH_SIZE: usize = 1024;
+
+fn cmd_datastore_import(global_args: &GlobalArgs, args: &DatastoreImportArgs) -> Result<()> {
+    use std::io::BufRead;
+
+    let mut datastore =
+        Datastore::create_or_open(&args.datastore, global_args.advanced.sqlite_cache_size)
+            .with_context(|| format!("Failed to open datastore at {}", args.datastore.display()))?;
+
+    let input_name = match &args.input {
+        Some(p) => p.display().to_string(),
+        None => "stdin".to_string(),
+    };
+    let input = get_reader_for_file_or_stdin(args.input.as_ref())
+        .with_context(|| format!("Failed to open {input_name}"))?;
+    let mut lines = std::io::BufReader::new(input).lines().enumerate();
+
+    // The structural IDs of the rules that imported matches can refer to
+    let mut known_rules: std::collections::HashSet<String> = datastore
+        .get_rules()
+        .context("Failed to get rules from datastore")?
+        .iter()
+        .map(|r| r.structural_id().to_owned())
+        .collect();
+
+    // The overlap threshold used to determine redundant matches in the exported datastore
+    let mut redundancy_overlap = None;
+    // The algorithm used to compute blob IDs in the exported datastore; exports without one
+    // predate the choice of algorithm, and use SHA-1
+    let mut blob_id_algorithm = BlobIdAlgorithm::Sha1;
+    let mut seen_header = false;
+
+    let mut num_rules = 0;
+    let mut num_blobs = 0;
+    let mut num_new_matches = 0;
+    let mut done = false;
+    while !done {
+        // Read the next batch of records
+        let mut rules: Vec<Rule> = Vec::new();
+        let mut batch: Vec<BatchEntry> = Vec::new();
+        while batch.len() < IMPORT_BATCH_SIZE {
+            let Some((line_num, line)) = lines.next() else {
+                done = true;
+                break;
+            };
+            let location = || format!("{input_name}:{}", line_num + 1);
+            let line = line.with_context(|| format!("Failed to read {}", location()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: ExportRecord = serde_json::from_str(&line)
+                .with_context(|| format!("Failed to parse record at {}", location()))?;
+            // Without the header, the schema version of the input is unknown
+            if !seen_header && !matches!(record, ExportRecord::Header(_)) {
+                bail!(
+                    "Failed to import record at {}: expected an export header as the first record",
+                    location()
+                );
+            }
+            match record {
+                ExportRecord::Header(header) => {
+                    header
+                        .check_schema_version()
+                        .with_context(|| format!("Failed to import record at {}", location()))?;
+                    redundancy_overlap = header.redundancy_overlap;
+                    blob_id_algorithm = header.blob_id_algorithm.unwrap_or(blob_id_algorithm);
+                    seen_header = true;
+                }
+                ExportRecord::Rule(syntax) => {
+                    let rule = Rule::new(syntax);
+                    known_rules.insert(rule.structural_id().to_owned());
+                    rules.push(rule);
+                }
+                ExportRecord::Blob(blob) => {
+                    for m in blob.matches.iter() {
+                        let m = &m.match_val;
+                        if !known_rules.contains(&m.rule_structural_id) {
+                            bail!(
+                                "Failed to import record at {}: match refers to rule `{}` ({}), \
+                                 which is neither in the datastore nor earlier in the input",
+                                location(),
+                                m.rule_text_id,
+                                m.rule_structural_id,
+                            );
+                        }
+                    }
+                    let entry = blob
+                        .into_batch_entry()
+                        .with_context(|| format!("Failed to import record at {}", location()))?;
+                    batch.push(entry);
+                }
+            }
+        }
+
+        // Record the batch
+        let tx = datastore.begin()?;
+        if !batch.is_empty() {
+            tx.record_blob_id_algorithm(blob_id_algorithm)
+                .with_context(|| format!("Failed to import blobs from {input_name}"))?;
+        }
+        tx.record_rules(&rules)
+            .context("Failed to record rules to datastore")?;
+        num_new_matches += tx
+            .record(&batch)
+            .context("Failed to record blobs to datastore")?;
+        tx.commit()?;
+        num_rules += rules.len();
+        num_blobs += batch.len();
+    }
+    if !seen_header {
+        bail!("Failed to import {input_name}: no export header found");
+    }
+
+    // Prefer the threshold already recorded in the datastore, so that importing does not change
+    // how its existing matches are considered redundant
+    let redundancy_overlap = datastore
+        .get_redundancy_overlap()?
+        .or(redundancy_overlap)
+        .unwrap_or(DEFAULT_REDUNDANCY_OVERLAP);
+    datastore.check_match_redundancies(redundancy_overlap)?;
+    datastore.analyze()?;
+
+    println!(
+        "Imported {num_rules} rules and {num_blobs} blobs with {num_new_matches} new matches \
+         from {input_name} into {}",
+        datastore.root_dir().display()
+    );
+    Ok(())
+}
+
+fn cmd_datastore_check(global_args: &GlobalArgs, args: &DatastoreCheckArgs) -> Result<()> {
+    let checks = Datastore::check(&args.datastore, global_args.advanced.sqlite_cache_size)
+        .with_context(|| format!("Failed to check datastore at {}", args.datastore.display()))?;
+
+    println!("Checking datastore at {}", args.datastore.display());
+    for check in checks.iter() {
+        if check.passed() {
+            println!("  {}: ok", check.name);
+        } else {
+            println!("  {}: FAILED", check.name);
+            for problem in check.problems.iter() {
+                println!("    - {problem}");
+            }
+        }
+    }
+
+    let num_failed = checks.iter().filter(|c| !c.passed()).count();
+    if num_failed > 0 {
+        bail!("Datastore check failed: {num_failed} of {} checks found problems", checks.len());
     }
+    println!("Datastore check passed");
+    Ok(())
+}
+
+fn cmd_datastore_vacuum(global_args: &GlobalArgs, args: &DatastoreVacuumArgs) -> Result<()> {
+    use indicatif::HumanBytes;
+
+    let mut datastore = Datastore::open(&args.datastore, global_args.advanced.sqlite_cache_size)
+        .with_context(|| format!("Failed to open datastore at {}", args.datastore.display()))?;
+
+    let db_path = datastore.root_dir().join("datastore.db");
+    let db_size = || -> Result<u64> {
+        let md = std::fs::metadata(&db_path)
+            .with_context(|| format!("Failed to get size of {}", db_path.display()))?;
+        Ok(md.len())
+    };
+
+    let size_before = db_size()?;
+    datastore
+        .vacuum()
+        .with_context(|| format!("Failed to vacuum datastore at {}", args.datastore.display()))?;
+    let size_after = db_size()?;
+
+    println!("Vacuumed datastore at {}", args.datastore.display());
+    println!("  datastore.db before: {}", HumanBytes(size_before));
+    println!("  datastore.db after:  {}", HumanBytes(size_after));
+    Ok(())
+}
 
+fn cmd_datastore_prune(global_args: &GlobalArgs, args: &DatastorePruneArgs) -> Result<()> {
+    let mut datastore = Datastore::open(&args.datastore, global_args.advanced.sqlite_cache_size)
+        .with_context(|| format!("Failed to open datastore at {}", args.datastore.display()))?;
+
+    for rule in &args.rule {
+        let num_deleted = datastore
+            .delete_findings_by_rule(rule)
+            .with_context(|| format!("Failed to delete findings of rule {rule:?}"))?;
+        if num_deleted == 0 {
+            warn!("No findings of rule {rule:?} in the datastore");
+        }
+        println!("Deleted {num_deleted} findings of rule {rule:?}");
+    }
     Ok(())
 }
+
+fn cmd_datastore_runs(global_args: &GlobalArgs, args: &DatastoreRunsArgs) -> Result<()> {
+    use crate::args::DatastoreRunsCommand::*;
+    match &args.command {
+        List(args) => cmd_datastore_runs_list(global_args, args),
+    }
+}
+
+fn cmd_datastore_runs_list(global_args: &GlobalArgs, args: &DatastoreRunsListArgs) -> Result<()> {
+    let datastore = Datastore::open(&args.datastore, global_args.advanced.sqlite_cache_size)
+        .with_context(|| format!("Failed to open datastore at {}", args.datastore.display()))?;
+    let output = args
+        .output_args
+        .get_writer()
+        .context("Failed to get output writer")?;
+    let runs = datastore
+        .get_scan_runs()
+        .context("Failed to get scan runs")?;
+    ScanRunsReporter(runs).report(args.output_args.format, output)
+}
+
+struct ScanRunsReporter(Vec<ScanRun>);
+
+impl Reportable for ScanRunsReporter {
+    type Format = DatastoreRunsListOutputFormat;
+
+    fn report<W: std::io::Write>(&self, format: Self::Format, writer: W) -> Result<()> {
+        match format {
+            DatastoreRunsListOutputFormat::Human => self.human_format(writer),
+            DatastoreRunsListOutputFormat::Json => self.json_format(writer),
+            DatastoreRunsListOutputFormat::Jsonl => self.jsonl_format(writer),
+        }
+    }
+}
+
+impl ScanRunsReporter {
+    fn human_format<W: std::io::Write>(&self, mut writer: W) -> Result<()> {
+        use prettytable::format::{FormatBuilder, LinePosition, LineSeparator};
+        use prettytable::row;
+
+        let f = FormatBuilder::new()
+            .column_separator(' ')
+            .separators(&[LinePosition::Title], LineSeparator::new('─', '─', '─', '─'))
+            .padding(1, 1)
+            .build();
+
+        let mut table: prettytable::Table = self
+            .0
+            .iter()
+            .map(|run| {
+                let labels = run
+                    .labels
+                    .iter()
+                    .map(|(k, v)| format!("{k}={v}"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let count = |n: Option<u64>| n.map_or("-".to_string(), |n| n.to_string());
+                row![
+                    r -> run.id,
+                    l -> &run.started_at,
+                    l -> run.finished_at.as_deref().unwrap_or("-"),
+                    l -> run.version.as_deref().unwrap_or("-"),
+                    l -> run.hostname.as_deref().unwrap_or("-"),
+                    r -> count(run.num_blobs),
+                    r -> count(run.num_matches),
+                    l -> labels,
+                ]
+            })
+            .collect();
+        table.set_format(f);
+        table.set_titles(row![
+            rb -> "Run",
+            lb -> "Started",
+            lb -> "Finished",
+            lb -> "Version",
+            lb -> "Host",
+            rb -> "Blobs",
+            rb -> "Matches",
+            lb -> "Labels",
+        ]);
+
+        writeln!(writer)?;
+        table.print(&mut writer)?;
+        Ok(())
+    }
+
+    fn json_format<W: std::io::Write>(&self, writer: W) -> Result<()> {
+        serde_json::to_writer_pretty(writer, &self.0)?;
+        Ok(())
+    }
+
+    fn jsonl_format<W: std::io::Write>(&self, mut writer: W) -> Result<()> {
+        for run in self.0.iter() {
+            serde_json::to_writer(&mut writer, run)?;
+            writeln!(&mut writer)?;
+        }
+        Ok(())
+    }
+}
@@ -4,6 +4,7 @@ use clap::{Command, CommandFactory};
 use clap_complete::{
     generate, shells::Bash, shells::Elvish, shells::Fish, shells::PowerShell, shells::Zsh,
 };
+use clap_complete_nushell::Nushell;
 
 pub fn run(_global_args: &GlobalArgs, args: &ShellCompletionsArgs) -> Result<()> {
     let mut cmd = CommandLineArgs::command();
@@ -20,6 +21,7 @@ fn generate_completions_for_shell(shell: &ShellFormat, cmd: &mut Command) -> Res
         ShellFormat::Fish => generate(Fish, cmd, bin_name, std_out),
         ShellFormat::PowerShell => generate(PowerShell, cmd, bin_name, std_out),
         ShellFormat::Elvish => generate(Elvish, cmd, bin_name, std_out),
+        ShellFormat::Nushell => generate(Nushell, cmd, bin_name, std_out),
     }
 
     Ok(())
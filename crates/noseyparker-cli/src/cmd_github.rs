@@ -4,7 +4,6 @@ use url::Url;
 use crate::args::{
     validate_github_api_url, GitHubArgs, GitHubOutputFormat, GitHubReposListArgs, GlobalArgs,
 };
-use crate::reportable::Reportable;
 use noseyparker::github;
 
 pub fn run(global_args: &GlobalArgs, args: &GitHubArgs) -> Result<()> {
@@ -19,54 +18,65 @@ fn list_repos(global_args: &GlobalArgs, args: &GitHubReposListArgs, api_url: Url
         bail!("No repositories specified");
     }
     validate_github_api_url(&api_url, args.repo_specifiers.all_organizations);
-    let repo_urls = github::enumerate_repo_urls(
-        &github::RepoSpecifiers {
-            user: args.repo_specifiers.user.clone(),
-            organization: args.repo_specifiers.organization.clone(),
-            all_organizations: args.repo_specifiers.all_organizations,
-            repo_filter: args.repo_specifiers.repo_type.into(),
-        },
+    let repo_specifiers = github::RepoSpecifiers {
+        user: args.repo_specifiers.user.clone(),
+        organization: args.repo_specifiers.organization.clone(),
+        all_organizations: args.repo_specifiers.all_organizations,
+        repo_filter: args.repo_specifiers.repo_type.into(),
+    };
+    let cursor_cache = args.repo_specifiers.cursor_cache.as_deref();
+    let format = args.output_args.format;
+
+    if format == GitHubOutputFormat::Human {
+        let repo_urls = github::enumerate_repo_urls(
+            &repo_specifiers,
+            api_url,
+            global_args.ignore_certs,
+            cursor_cache,
+            None,
+        )
+        .context("Failed to enumerate GitHub repositories")?;
+        let mut output = args
+            .output_args
+            .get_writer()
+            .context("Failed to get output writer")?;
+        for repo_url in repo_urls {
+            writeln!(output, "{repo_url}")?;
+        }
+        return Ok(());
+    }
+
+    // The JSON formats are written as repositories are enumerated rather than all at once, so that
+    // listing a very large organization does not need to hold all its repositories in memory.
+    // Unlike the human format, the URLs are given in the order they are enumerated.
+    let mut output = args
+        .output_args
+        .get_writer()
+        .context("Failed to get output writer")?;
+    let mut num_written: usize = 0;
+    github::for_each_repo_url(
+        &repo_specifiers,
         api_url,
         global_args.ignore_certs,
+        cursor_cache,
         None,
+        |repo_url| {
+            if format == GitHubOutputFormat::Json {
+                // The opening bracket is written along with the first URL, so that nothing is
+                // written if enumeration fails right away
+                write!(output, "{}\n  ", if num_written == 0 { "[" } else { "," })?;
+                serde_json::to_writer(&mut output, &repo_url)?;
+            } else {
+                serde_json::to_writer(&mut output, &repo_url)?;
+                writeln!(output)?;
+            }
+            num_written += 1;
+            Ok(())
+        },
     )
     .context("Failed to enumerate GitHub repositories")?;
-    let output = args
-        .output_args
-        .get_writer()
-        .context("Failed to get output writer")?;
-    RepoReporter(repo_urls).report(args.output_args.format, output)
-}
-
-struct RepoReporter(Vec<String>);
-
-impl Reportable for RepoReporter {
-    type Format = GitHubOutputFormat;
-
-    fn report<W: std::io::Write>(&self, format: Self::Format, mut writer: W) -> Result<()> {
-        match format {
-            GitHubOutputFormat::Human => {
-                let repo_urls = &self.0;
-                for repo_url in repo_urls {
-                    writeln!(writer, "{repo_url}")?;
-                }
-                Ok(())
-            }
-
-            GitHubOutputFormat::Json => {
-                let repo_urls = &self.0;
-                serde_json::to_writer_pretty(writer, repo_urls)?;
-                Ok(())
-            }
-
-            GitHubOutputFormat::Jsonl => {
-                let repo_urls = &self.0;
-                for repo_url in repo_urls {
-                    serde_json::to_writer(&mut writer, repo_url)?;
-                    writeln!(&mut writer)?;
-                }
-                Ok(())
-            }
-        }
+    if format == GitHubOutputFormat::Json {
+        write!(output, "{}", if num_written == 0 { "[]" } else { "\n]" })?;
     }
+    Ok(())
 }
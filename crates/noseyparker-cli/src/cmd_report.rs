@@ -3,20 +3,26 @@ use bstr::{BStr, ByteSlice};
 use indenter::indented;
 use schemars::JsonSchema;
 use serde::Serialize;
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter, Write};
 use tracing::info;
 
 use noseyparker::blob_metadata::BlobMetadata;
 use noseyparker::bstring_escape::Escaped;
-use noseyparker::datastore::{Datastore, FindingDataEntry, FindingMetadata, Status};
-use noseyparker::defaults::get_builtin_rules;
+use noseyparker::datastore::{
+    Datastore, FindingDataEntry, FindingMetadata, FindingOccurrences, Status,
+};
 use noseyparker::match_type::{Group, Groups, Match};
 use noseyparker::provenance::Provenance;
 use noseyparker::provenance_set::ProvenanceSet;
+use noseyparker_rules::ExampleStats;
 
-use crate::args::{FindingStatus, GlobalArgs, ReportArgs, ReportOutputFormat};
+use crate::args::{FindingStatus, GlobalArgs, ReportArgs, ReportGroupBy, ReportOutputFormat};
 use crate::reportable::Reportable;
+use crate::rule_loader::warn_if_rules_changed;
 
+mod by_blob;
+mod distinct_values;
 mod human_format;
 mod sarif_format;
 mod styles;
@@ -26,7 +32,8 @@ use styles::{StyledObject, Styles};
 pub fn run(global_args: &GlobalArgs, args: &ReportArgs) -> Result<()> {
     let datastore = Datastore::open(&args.datastore, global_args.advanced.sqlite_cache_size)
         .with_context(|| format!("Failed to open datastore at {}", args.datastore.display()))?;
-    let output = args
+    warn_if_rules_changed(&datastore)?;
+    let mut output = args
         .output_args
         .get_writer()
         .context("Failed to get output writer")?;
@@ -60,6 +67,12 @@ pub fn run(global_args: &GlobalArgs, args: &ReportArgs) -> Result<()> {
 
     let styles = Styles::new(styles_enabled);
 
+    let rule_quality = if args.rule_quality {
+        Some(get_rule_quality(&datastore)?)
+    } else {
+        None
+    };
+
     let reporter = DetailsReporter {
         datastore,
         max_matches,
@@ -67,8 +80,38 @@ pub fn run(global_args: &GlobalArgs, args: &ReportArgs) -> Result<()> {
         suppress_redundant: args.filter_args.suppress_redundant,
         min_score,
         finding_status: args.filter_args.finding_status,
+        rules: args.filter_args.rule.clone(),
+        show_occurrences: args.show_occurrence_counts,
+        rule_quality,
+        redact: args.redact,
+        pretty: args.pretty,
         styles,
     };
+
+    if args.distinct_values {
+        if !args.allow_plaintext_values && !args.redact {
+            bail!(
+                "`--distinct-values` outputs plaintext secrets; \
+                 rerun with `--allow-plaintext-values` to acknowledge this, or with `--redact`"
+            );
+        }
+        return reporter.distinct_values(args.output_args.format, output);
+    }
+
+    if args.count {
+        let mut num_findings: usize = 0;
+        reporter.for_each_finding_metadata(|_| {
+            num_findings += 1;
+            Ok(())
+        })?;
+        writeln!(output, "{num_findings}")?;
+        return Ok(());
+    }
+
+    if args.group_by == ReportGroupBy::Blob {
+        return reporter.report_by_blob(args.output_args.format, output);
+    }
+
     reporter.report(args.output_args.format, output)
 }
 
@@ -79,11 +122,54 @@ struct DetailsReporter {
     min_score: Option<f64>,
     suppress_redundant: bool,
     finding_status: Option<FindingStatus>,
+    /// Only report findings from rules with these IDs or names, if nonempty
+    rules: Vec<String>,
+    show_occurrences: bool,
+    /// Example-derived quality of each rule, by structural ID, if requested
+    rule_quality: Option<HashMap<String, RuleQuality>>,
+    /// Whether to mask matched values
+    redact: bool,
+    /// Whether to write each finding on its own line in `json` format
+    pretty: bool,
     styles: Styles,
 }
 
+/// Check each rule recorded in the datastore against its examples.
+fn get_rule_quality(datastore: &Datastore) -> Result<HashMap<String, RuleQuality>> {
+    let rules = datastore
+        .get_rules()
+        .context("Failed to get rules from datastore")?;
+    let mut rule_quality = HashMap::with_capacity(rules.len());
+    for rule in rules {
+        let stats = rule
+            .syntax()
+            .check_examples()
+            .with_context(|| format!("Failed to check examples for rule {}", rule.id()))?;
+        rule_quality.insert(rule.structural_id().to_owned(), stats.into());
+    }
+    Ok(rule_quality)
+}
+
+/// The number of characters kept at each end of a redacted value
+const REDACT_KEEP: usize = 4;
+
+/// The mask that replaces the middle of a redacted value
+const REDACT_MASK: &str = "********";
+
+/// Mask a value, keeping only its first and last few characters if it is long enough.
+pub(crate) fn redact(value: &[u8]) -> String {
+    let value = String::from_utf8_lossy(value);
+    let chars: Vec<char> = value.chars().collect();
+    if chars.len() < 3 * REDACT_KEEP {
+        return REDACT_MASK.to_string();
+    }
+    let head: String = chars[..REDACT_KEEP].iter().collect();
+    let tail: String = chars[chars.len() - REDACT_KEEP..].iter().collect();
+    format!("{head}{REDACT_MASK}{tail}")
+}
+
 /// Does `requested_status` match the given set of statuses?
-fn statuses_match(requested_status: FindingStatus, statuses: &[Status]) -> bool {
+pub(crate) fn statuses_match(requested_status: FindingStatus, statuses: &[Status]) -> bool {
     matches!(
         (requested_status, statuses),
         (FindingStatus::Accept, &[Status::Accept])
@@ -95,54 +181,100 @@ fn statuses_match(requested_status: FindingStatus, statuses: &[Status]) -> bool
 }
 
 impl DetailsReporter {
-    /// Get the metadata for all the findings that remain after filtering.
-    fn get_finding_metadata(&self) -> Result<Vec<FindingMetadata>> {
-        let datastore = &self.datastore;
-        let mut group_metadata = datastore
-            .get_finding_metadata(self.suppress_redundant)
-            .context("Failed to get match group metadata from datastore")?;
-
-        // Suppress findings with non-matching status
-        if let Some(status) = self.finding_status {
-            let old_len = group_metadata.len();
-            group_metadata.retain(|md| statuses_match(status, md.statuses.0.as_slice()));
-            let num_suppressed = old_len - group_metadata.len();
+    /// Call `f` with the metadata of each finding that remains after filtering, reading the
+    /// findings from the datastore one at a time.
+    fn for_each_finding_metadata<F>(&self, f: F) -> Result<()>
+    where
+        F: FnMut(FindingMetadata) -> Result<()>,
+    {
+        self.visit_finding_metadata(true, f)
+    }
+
+    /// Count the findings that remain after filtering, reading the findings from the datastore one
+    /// at a time.
+    ///
+    /// Unlike `for_each_finding_metadata`, this does not note how many findings were suppressed,
+    /// so that a count can be taken before visiting the findings without noting them twice.
+    fn count_findings(&self) -> Result<usize> {
+        let mut num_findings: usize = 0;
+        self.visit_finding_metadata(false, |_| {
+            num_findings += 1;
+            Ok(())
+        })?;
+        Ok(num_findings)
+    }
 
-            if num_suppressed == 1 {
+    /// Call `f` with the metadata of each finding that remains after filtering, and, if
+    /// `note_suppressed` is set, note how many findings were suppressed by filtering.
+    fn visit_finding_metadata<F>(&self, note_suppressed: bool, mut f: F) -> Result<()>
+    where
+        F: FnMut(FindingMetadata) -> Result<()>,
+    {
+        let mut num_status_suppressed: usize = 0;
+        let mut num_score_suppressed: usize = 0;
+
+        self.datastore
+            .iter_findings(self.suppress_redundant, &self.rules, |findings| {
+                for metadata in findings {
+                    let metadata =
+                        metadata.context("Failed to get match group metadata from datastore")?;
+
+                    // Suppress findings with non-matching status
+                    if let Some(status) = self.finding_status {
+                        if !statuses_match(status, metadata.statuses.0.as_slice()) {
+                            num_status_suppressed += 1;
+                            continue;
+                        }
+                    }
+
+                    // Suppress findings with non-matching score
+                    if let Some(min_score) = self.min_score {
+                        if !metadata.mean_score.map(|s| s >= min_score).unwrap_or(true) {
+                            num_score_suppressed += 1;
+                            continue;
+                        }
+                    }
+
+                    f(metadata)?;
+                }
+                Ok(())
+            })?;
+
+        if !note_suppressed {
+            return Ok(());
+        }
+
+        if let Some(status) = self.finding_status {
+            if num_status_suppressed == 1 {
                 info!(
                     "Note: 1 finding with status not matching {status} was suppressed; \
                        rerun without `--finding-status={status}` to show it"
                 );
-            } else if num_suppressed > 1 {
+            } else if num_status_suppressed > 1 {
                 info!(
-                    "Note: {num_suppressed} findings with status not matching \
+                    "Note: {num_status_suppressed} findings with status not matching \
                        `{status}` were suppressed; \
                        rerun without `--finding-status={status}` to show them"
                 );
             }
         }
 
-        // Suppress findings with non-matching score
         if let Some(min_score) = self.min_score {
-            let old_len = group_metadata.len();
-            group_metadata.retain(|md| md.mean_score.map(|s| s >= min_score).unwrap_or(true));
-            let num_suppressed = old_len - group_metadata.len();
-
-            if num_suppressed == 1 {
+            if num_score_suppressed == 1 {
                 info!(
                     "Note: 1 finding with meanscore less than {min_score} was suppressed; \
                        rerun with `--min-score=0` to show it"
                 );
-            } else if num_suppressed > 1 {
+            } else if num_score_suppressed > 1 {
                 info!(
-                    "Note: {num_suppressed} findings with mean score less than \
+                    "Note: {num_score_suppressed} findings with mean score less than \
                        {min_score} were suppressed; \
                        rerun with `--min-score=0` to show them"
                 );
             }
         }
 
-        Ok(group_metadata)
+        Ok(())
     }
 
     /// Get the matches associated with the given finding.
@@ -162,6 +294,36 @@ impl DetailsReporter {
             .collect())
     }
 
+    /// Get the finding for the given metadata, including its matches and, if enabled, its
+    /// occurrence counts.
+    fn get_finding(&self, metadata: FindingMetadata) -> Result<Finding> {
+        let matches = self.get_matches(&metadata)?;
+        let occurrences = if self.show_occurrences {
+            let occurrences = self
+                .datastore
+                .get_finding_occurrences(&metadata, self.suppress_redundant)
+                .with_context(|| format!("Failed to count occurrences for finding {metadata:?}"))?;
+            Some(occurrences)
+        } else {
+            None
+        };
+        let rule_quality = self
+            .rule_quality
+            .as_ref()
+            .and_then(|q| q.get(&metadata.rule_structural_id).copied());
+        let mut finding = Finding {
+            schema_version: REPORT_SCHEMA_VERSION,
+            metadata,
+            occurrences,
+            rule_quality,
+            matches,
+        };
+        if self.redact {
+            finding.redact();
+        }
+        Ok(finding)
+    }
+
     fn style_finding_heading<D>(&self, val: D) -> StyledObject<D> {
         self.styles.style_finding_heading.apply_to(val)
     }
@@ -216,15 +378,13 @@ impl DetailsReporter {
         sep: Option<&str>,
         end: Option<&str>,
     ) -> Result<()> {
-        let group_metadata = self.get_finding_metadata()?;
-
         if let Some(begin) = begin {
             write!(writer, "{}", begin)?;
         }
 
         let mut first = true;
 
-        for metadata in group_metadata {
+        self.for_each_finding_metadata(|metadata| {
             if !first {
                 if let Some(sep) = sep {
                     write!(writer, "{}", sep)?;
@@ -232,10 +392,10 @@ impl DetailsReporter {
             }
             first = false;
 
-            let matches = self.get_matches(&metadata)?;
-            let f = Finding::new(metadata, matches);
+            let f = self.get_finding(metadata)?;
             serde_json::to_writer(&mut writer, &f)?;
-        }
+            Ok(())
+        })?;
 
         if let Some(end) = end {
             write!(writer, "{}", end)?;
@@ -245,7 +405,11 @@ impl DetailsReporter {
     }
 
     fn json_format<W: std::io::Write>(&self, writer: W) -> Result<()> {
-        self.write_json_findings(writer, Some("[\n"), Some(",\n"), Some("\n]"))
+        if self.pretty {
+            self.write_json_findings(writer, Some("[\n"), Some(",\n"), Some("\n]"))
+        } else {
+            self.write_json_findings(writer, Some("["), Some(","), Some("]"))
+        }
     }
 
     fn jsonl_format<W: std::io::Write>(&self, writer: W) -> Result<()> {
@@ -253,14 +417,56 @@ impl DetailsReporter {
     }
 }
 
+/// The version of the JSON report format, included in each finding.
+///
+/// This is incremented whenever the format changes in a way that could break consumers, such as
+/// when a field is removed or changes meaning.
+const REPORT_SCHEMA_VERSION: u32 = 1;
+
 /// A group of matches that all have the same rule and capture group content
 #[derive(Serialize, JsonSchema)]
 pub(crate) struct Finding {
+    /// The version of the JSON report format, which is incremented whenever the format changes
+    /// incompatibly
+    schema_version: u32,
+
     #[serde(flatten)]
     metadata: FindingMetadata,
+
+    /// Counts of where the matches of this finding were seen, if requested
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    occurrences: Option<FindingOccurrences>,
+
+    /// How well-tested the rule that produced this finding is, if requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rule_quality: Option<RuleQuality>,
+
     matches: Vec<ReportMatch>,
 }
 
+/// How well-tested a rule is, according to its examples
+#[derive(Serialize, JsonSchema, Clone, Copy)]
+struct RuleQuality {
+    /// The number of examples the rule is expected to match
+    num_examples: usize,
+
+    /// The number of examples the rule is expected not to match
+    num_negative_examples: usize,
+
+    /// The number of examples and negative examples for which the rule behaves as expected
+    num_passed: usize,
+}
+
+impl From<ExampleStats> for RuleQuality {
+    fn from(s: ExampleStats) -> Self {
+        RuleQuality {
+            num_examples: s.num_examples,
+            num_negative_examples: s.num_negative_examples,
+            num_passed: s.num_passed,
+        }
+    }
+}
+
 /// A match produced by one of Nosey Parker's rules.
 /// This corresponds to a single location.
 #[derive(Serialize, JsonSchema)]
@@ -302,8 +508,19 @@ impl From<FindingDataEntry> for ReportMatch {
 }
 
 impl Finding {
-    fn new(metadata: FindingMetadata, matches: Vec<ReportMatch>) -> Self {
-        Self { metadata, matches }
+    /// Mask the matched values of this finding, leaving their surrounding context intact.
+    fn redact(&mut self) {
+        fn redact_groups(groups: &mut Groups) {
+            for group in groups.0.iter_mut() {
+                group.0 = redact(&group.0).into();
+            }
+        }
+
+        redact_groups(&mut self.metadata.groups);
+        for m in self.matches.iter_mut() {
+            redact_groups(&mut m.m.groups);
+            m.m.snippet.matching = redact(&m.m.snippet.matching).into();
+        }
     }
 
     /// The name of the rule that produced this finding
@@ -0,0 +1,278 @@
+use super::*;
+use std::collections::HashSet;
+
+use noseyparker::datastore::{BlobData, BlobDataEntry};
+
+use super::human_format::{format_blob_metadata, write_provenance};
+
+/// The matches within a single blob, from all findings
+#[derive(Serialize)]
+struct BlobReport {
+    /// The version of the JSON report format, which is incremented whenever the format changes
+    /// incompatibly
+    schema_version: u32,
+
+    blob_metadata: BlobMetadata,
+
+    provenance: ProvenanceSet,
+
+    /// The total number of matches in the blob
+    num_matches: usize,
+
+    matches: Vec<BlobReportMatch>,
+}
+
+/// A match within a blob, along with the finding it belongs to
+#[derive(Serialize)]
+struct BlobReportMatch {
+    /// The ID of the finding that the match belongs to
+    finding_id: String,
+
+    #[serde(flatten)]
+    m: Match,
+
+    /// An optional score assigned to the match
+    score: Option<f64>,
+
+    /// An optional comment assigned to the match
+    comment: Option<String>,
+
+    /// An optional status assigned to the match
+    status: Option<Status>,
+
+    /// The match structural IDs that this match is considered redundant to
+    redundant_to: Vec<String>,
+}
+
+impl From<BlobDataEntry> for BlobReportMatch {
+    fn from(e: BlobDataEntry) -> Self {
+        BlobReportMatch {
+            finding_id: e.finding_id,
+            m: e.match_val,
+            score: e.match_score,
+            comment: e.match_comment,
+            status: e.match_status,
+            redundant_to: e.redundant_to,
+        }
+    }
+}
+
+impl BlobReport {
+    /// Mask the matched values of this blob's matches, leaving their surrounding context intact.
+    fn redact(&mut self) {
+        for m in self.matches.iter_mut() {
+            for group in m.m.groups.0.iter_mut() {
+                group.0 = redact(&group.0).into();
+            }
+            m.m.snippet.matching = redact(&m.m.snippet.matching).into();
+        }
+    }
+}
+
+impl DetailsReporter {
+    /// Call `f` with the report for each blob that has matches from the findings that remain after
+    /// filtering, in order of blob ID.
+    fn for_each_blob_report<F>(&self, mut f: F) -> Result<()>
+    where
+        F: FnMut(BlobReport) -> Result<()>,
+    {
+        let mut finding_ids = HashSet::new();
+        self.for_each_finding_metadata(|metadata| {
+            finding_ids.insert(metadata.finding_id);
+            Ok(())
+        })?;
+
+        self.datastore.iter_blob_data(
+            self.max_provenance,
+            self.suppress_redundant,
+            &self.rules,
+            |blob: BlobData| {
+                let mut matches: Vec<BlobReportMatch> = blob
+                    .matches
+                    .into_iter()
+                    .filter(|e| finding_ids.contains(&e.finding_id))
+                    .map(|e| e.into())
+                    .collect();
+                if matches.is_empty() {
+                    return Ok(());
+                }
+                let num_matches = matches.len();
+                if let Some(max_matches) = self.max_matches {
+                    matches.truncate(max_matches);
+                }
+                let mut report = BlobReport {
+                    schema_version: REPORT_SCHEMA_VERSION,
+                    blob_metadata: blob.blob_metadata,
+                    provenance: blob.provenance,
+                    num_matches,
+                    matches,
+                };
+                if self.redact {
+                    report.redact();
+                }
+                f(report)
+            },
+        )
+    }
+
+    /// Write each blob with matches from the findings that remain after filtering, with its
+    /// matches beneath it.
+    pub(super) fn report_by_blob<W: std::io::Write>(
+        &self,
+        format: ReportOutputFormat,
+        mut writer: W,
+    ) -> Result<()> {
+        match format {
+            ReportOutputFormat::Human => {
+                // Blobs are written as they are read, so the total number is not known up front
+                let mut blob_num: usize = 0;
+                self.for_each_blob_report(|blob| {
+                    blob_num += 1;
+                    writeln!(
+                        writer,
+                        "{} (id {})",
+                        self.style_finding_heading(format!("Blob {blob_num}")),
+                        self.style_id(&blob.blob_metadata.id),
+                    )?;
+                    writeln!(writer, "{}", PrettyBlob(self, &blob))?;
+                    Ok(())
+                })?;
+            }
+            ReportOutputFormat::Json => {
+                let (begin, sep, end) = if self.pretty {
+                    ("[\n", ",\n", "\n]")
+                } else {
+                    ("[", ",", "]")
+                };
+                write!(writer, "{begin}")?;
+                let mut first = true;
+                self.for_each_blob_report(|blob| {
+                    if !first {
+                        write!(writer, "{sep}")?;
+                    }
+                    first = false;
+                    serde_json::to_writer(&mut writer, &blob)?;
+                    Ok(())
+                })?;
+                write!(writer, "{end}")?;
+            }
+            ReportOutputFormat::Jsonl => {
+                self.for_each_blob_report(|blob| {
+                    serde_json::to_writer(&mut writer, &blob)?;
+                    writeln!(writer)?;
+                    Ok(())
+                })?;
+            }
+            ReportOutputFormat::Sarif => {
+                bail!("The `sarif` format is not supported with `--group-by=blob`");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A wrapper type to allow human-oriented pretty-printing of a `BlobReport`.
+struct PrettyBlob<'a>(&'a DetailsReporter, &'a BlobReport);
+
+impl Display for PrettyBlob<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let PrettyBlob(reporter, blob) = self;
+        writeln!(
+            f,
+            "{} {}",
+            reporter.style_heading("Blob:"),
+            reporter.style_metadata(format_blob_metadata(&blob.blob_metadata)),
+        )?;
+
+        write_provenance(reporter, f, &blob.provenance)?;
+
+        // write out count if not all matches are displayed
+        if blob.matches.len() != blob.num_matches {
+            writeln!(
+                f,
+                "{}",
+                reporter.style_heading(format!(
+                    "Showing {}/{} matches:",
+                    blob.matches.len(),
+                    blob.num_matches
+                ))
+            )?;
+        }
+        writeln!(f)?;
+
+        // write out matches
+        let mut f = indented(f).with_str("    ");
+        for (i, rm) in blob.matches.iter().enumerate() {
+            let i = i + 1;
+            let BlobReportMatch {
+                finding_id,
+                m,
+                score,
+                comment,
+                status,
+                redundant_to,
+            } = rm;
+
+            writeln!(
+                f,
+                "{} (id {})",
+                reporter.style_heading(format!("Match {i}/{}", blob.num_matches)),
+                reporter.style_id(&m.structural_id),
+            )?;
+
+            writeln!(
+                f,
+                "{} {}",
+                reporter.style_heading("Rule:"),
+                reporter.style_rule(&m.rule_name)
+            )?;
+            writeln!(
+                f,
+                "{} {}",
+                reporter.style_heading("Finding:"),
+                reporter.style_id(finding_id)
+            )?;
+
+            if !redundant_to.is_empty() {
+                writeln!(
+                    f,
+                    "{} {}",
+                    reporter.style_heading("Redundant to:"),
+                    redundant_to.join(", "),
+                )?;
+            }
+
+            // write out match status if set
+            if let Some(status) = status {
+                let status = match status {
+                    Status::Accept => "Accept",
+                    Status::Reject => "Reject",
+                };
+                writeln!(f, "{} {status}", reporter.style_heading("Status:"))?;
+            }
+
+            // write out match score if set
+            if let Some(score) = score {
+                writeln!(f, "{} {score:.3}", reporter.style_heading("Score:"))?;
+            };
+
+            // write out match comment if set
+            if let Some(comment) = comment {
+                writeln!(f, "{} {comment}", reporter.style_heading("Comment:"))?;
+            };
+
+            writeln!(f, "{} {}", reporter.style_heading("Lines:"), &m.location.source_span)?;
+            writeln!(f)?;
+            writeln!(
+                indented(&mut f).with_str("    "),
+                "{}{}{}",
+                Escaped(&m.snippet.before),
+                reporter.style_match(Escaped(&m.snippet.matching)),
+                Escaped(&m.snippet.after)
+            )?;
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
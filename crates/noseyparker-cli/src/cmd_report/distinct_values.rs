@@ -0,0 +1,91 @@
+use super::*;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A distinct matched value, aggregated across findings
+#[derive(Serialize)]
+struct DistinctValue {
+    /// The value, possibly redacted
+    value: String,
+
+    /// The number of matches the value appears in
+    num_matches: usize,
+
+    /// The names of the rules that matched the value
+    rules: BTreeSet<String>,
+}
+
+impl DetailsReporter {
+    /// Write each distinct capture group value from the findings that remain after filtering.
+    pub(super) fn distinct_values<W: std::io::Write>(
+        &self,
+        format: ReportOutputFormat,
+        mut writer: W,
+    ) -> Result<()> {
+        let mut values: BTreeMap<bstr::BString, (usize, BTreeSet<String>)> = BTreeMap::new();
+        self.for_each_finding_metadata(|md| {
+            for group in md.groups.0 {
+                let (num_matches, rules) = values.entry(group.0).or_default();
+                *num_matches += md.num_matches;
+                rules.insert(md.rule_name.clone());
+            }
+            Ok(())
+        })?;
+
+        let values = values
+            .into_iter()
+            .map(|(value, (num_matches, rules))| DistinctValue {
+                value: if self.redact {
+                    redact(&value)
+                } else {
+                    String::from_utf8_lossy(&value).into_owned()
+                },
+                num_matches,
+                rules,
+            });
+
+        match format {
+            ReportOutputFormat::Human => {
+                for v in values {
+                    let rules = v.rules.into_iter().collect::<Vec<_>>().join(", ");
+                    writeln!(
+                        writer,
+                        "{} ({} {}; {})",
+                        self.style_match(Escaped(v.value.as_bytes())),
+                        v.num_matches,
+                        if v.num_matches == 1 {
+                            "match"
+                        } else {
+                            "matches"
+                        },
+                        self.style_rule(rules),
+                    )?;
+                }
+            }
+            ReportOutputFormat::Json => {
+                let (begin, sep, end) = if self.pretty {
+                    ("[\n", ",\n", "\n]")
+                } else {
+                    ("[", ",", "]")
+                };
+                write!(writer, "{begin}")?;
+                for (i, v) in values.enumerate() {
+                    if i > 0 {
+                        write!(writer, "{sep}")?;
+                    }
+                    serde_json::to_writer(&mut writer, &v)?;
+                }
+                write!(writer, "{end}")?;
+            }
+            ReportOutputFormat::Jsonl => {
+                for v in values {
+                    serde_json::to_writer(&mut writer, &v)?;
+                    writeln!(writer)?;
+                }
+            }
+            ReportOutputFormat::Sarif => {
+                bail!("The `sarif` format is not supported with `--distinct-values`");
+            }
+        }
+        Ok(())
+    }
+}
@@ -2,12 +2,37 @@ use super::*;
 
 impl DetailsReporter {
     pub fn human_format<W: std::io::Write>(&self, mut writer: W) -> Result<()> {
-        let group_metadata = self.get_finding_metadata()?;
-        let num_findings = group_metadata.len();
-        for (finding_num, metadata) in group_metadata.into_iter().enumerate() {
-            let finding_num = finding_num + 1;
-            let matches = self.get_matches(&metadata)?;
-            let finding = Finding { metadata, matches };
+        // Describe the labels of the scan runs that produced the findings, so that a report can be
+        // correlated with where it came from
+        let scan_runs = self
+            .datastore
+            .get_scan_runs()
+            .context("Failed to get scan runs from datastore")?;
+        let labeled_runs: Vec<_> = scan_runs.iter().filter(|r| !r.labels.is_empty()).collect();
+        if !labeled_runs.is_empty() {
+            writeln!(&mut writer, "{}", self.style_finding_heading("Scan Run Labels"))?;
+            for run in labeled_runs {
+                let labels = run
+                    .labels
+                    .iter()
+                    .map(|(k, v)| format!("{k}={v}"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                writeln!(
+                    &mut writer,
+                    "{} {labels}",
+                    self.style_heading(format!("Run {} ({}):", run.id, run.started_at))
+                )?;
+            }
+            writeln!(&mut writer)?;
+        }
+
+        // Count the findings first, so that they can be written as they are read
+        let num_findings = self.count_findings()?;
+        let mut finding_num = 0;
+        self.for_each_finding_metadata(|metadata| {
+            finding_num += 1;
+            let finding = self.get_finding(metadata)?;
             writeln!(
                 &mut writer,
                 "{} (id {})",
@@ -15,8 +40,8 @@ impl DetailsReporter {
                 self.style_id(&finding.metadata.finding_id),
             )?;
             writeln!(&mut writer, "{}", PrettyFinding(self, &finding))?;
-        }
-        Ok(())
+            Ok(())
+        })
     }
 }
 
@@ -33,6 +58,23 @@ impl<'a> Display for PrettyFinding<'a> {
             reporter.style_rule(finding.rule_name())
         )?;
 
+        // write out rule quality if requested
+        if let Some(q) = &finding.rule_quality {
+            let num_total = q.num_examples + q.num_negative_examples;
+            if num_total == 0 {
+                writeln!(f, "{} no examples", reporter.style_heading("Rule Quality:"))?;
+            } else {
+                writeln!(
+                    f,
+                    "{} {}/{num_total} examples pass ({} positive, {} negative)",
+                    reporter.style_heading("Rule Quality:"),
+                    q.num_passed,
+                    q.num_examples,
+                    q.num_negative_examples,
+                )?;
+            }
+        }
+
         // write out status if set: either `Accept`, `Reject`, or `Mixed` (when there are
         // conflicting match statuses within the finding)
         let statuses = &finding.metadata.statuses.0;
@@ -85,6 +127,22 @@ impl<'a> Display for PrettyFinding<'a> {
             write_group(group_heading, &gs[0])?;
         }
 
+        // write out occurrence counts if requested
+        if let Some(occurrences) = &finding.occurrences {
+            writeln!(
+                f,
+                "{} {} across {} {}",
+                reporter.style_heading("Occurrences:"),
+                occurrences.occurrence_count,
+                occurrences.distinct_source_count,
+                if occurrences.distinct_source_count == 1 {
+                    "source"
+                } else {
+                    "sources"
+                },
+            )?;
+        }
+
         // write out count if not all matches are displayed
         if finding.num_matches_available() != finding.total_matches() {
             writeln!(
@@ -148,80 +206,14 @@ impl<'a> Display for PrettyFinding<'a> {
                 writeln!(f, "{} {comment}", reporter.style_heading("Comment:"))?;
             };
 
-            let blob_metadata = {
-                format!(
-                    "{} bytes, {}, {}",
-                    blob_metadata.num_bytes(),
-                    blob_metadata.mime_essence().unwrap_or("unknown type"),
-                    blob_metadata.charset().unwrap_or("unknown charset"),
-                )
-            };
-
-            for p in provenance.iter() {
-                match p {
-                    Provenance::File(e) => {
-                        writeln!(
-                            f,
-                            "{} {}",
-                            reporter.style_heading("File:"),
-                            reporter.style_metadata(e.path.display()),
-                        )?;
-                    }
-                    Provenance::GitRepo(e) => {
-                        writeln!(
-                            f,
-                            "{} {}",
-                            reporter.style_heading("Git repo:"),
-                            reporter.style_metadata(e.repo_path.display()),
-                        )?;
-                        if let Some(cs) = &e.first_commit {
-                            let cmd = &cs.commit_metadata;
-                            let msg = BStr::new(cmd.message.lines().next().unwrap_or(&[]));
-                            let atime = cmd.author_timestamp.format(gix::date::time::format::SHORT);
-                            writeln!(
-                                f,
-                                "{} first seen in {}",
-                                reporter.style_heading("Commit:"),
-                                reporter.style_metadata(cmd.commit_id),
-                            )?;
-                            writeln!(f)?;
-                            writeln!(
-                                indented(&mut f).with_str("    "),
-                                "{}     {} <{}>\n\
-                                 {}       {}\n\
-                                 {}    {}\n\
-                                 {}       {}",
-                                reporter.style_heading("Author:"),
-                                cmd.author_name,
-                                cmd.author_email,
-                                reporter.style_heading("Date:"),
-                                atime,
-                                reporter.style_heading("Summary:"),
-                                msg,
-                                reporter.style_heading("Path:"),
-                                cs.blob_path,
-                            )?;
-                            writeln!(f)?;
-                        }
-                    }
-                    // FIXME: implement this case properly
-                    Provenance::Extended(e) => {
-                        writeln!(
-                            f,
-                            "{} {}",
-                            reporter.style_heading("Extended Provenance:"),
-                            reporter.style_metadata(e),
-                        )?;
-                    }
-                }
-            }
+            write_provenance(reporter, &mut f, provenance)?;
 
             writeln!(
                 f,
                 "{} {} ({})",
                 reporter.style_heading("Blob:"),
                 reporter.style_metadata(&m.blob_id),
-                reporter.style_metadata(blob_metadata),
+                reporter.style_metadata(format_blob_metadata(blob_metadata)),
             )?;
 
             writeln!(f, "{} {}", reporter.style_heading("Lines:"), &m.location.source_span,)?;
@@ -239,3 +231,91 @@ impl<'a> Display for PrettyFinding<'a> {
         Ok(())
     }
 }
+
+/// Describe the size, type, and charset of a blob for humans.
+pub(super) fn format_blob_metadata(blob_metadata: &BlobMetadata) -> String {
+    let mut s = format!(
+        "{} bytes, {}, {}",
+        blob_metadata.num_bytes(),
+        blob_metadata.mime_essence().unwrap_or("unknown type"),
+        blob_metadata.charset().unwrap_or("unknown charset"),
+    );
+    if let Some(n) = blob_metadata.num_truncated_matches {
+        s.push_str(&format!(", {n} matches not recorded"));
+    }
+    s
+}
+
+/// Write out each of the given provenance entries for humans.
+pub(super) fn write_provenance<W: Write>(
+    reporter: &DetailsReporter,
+    f: &mut W,
+    provenance: &ProvenanceSet,
+) -> std::fmt::Result {
+    for p in provenance.iter() {
+        match p {
+            Provenance::File(e) => {
+                writeln!(
+                    f,
+                    "{} {}",
+                    reporter.style_heading("File:"),
+                    reporter.style_metadata(e.path.display()),
+                )?;
+            }
+            Provenance::GitRepo(e) => {
+                writeln!(
+                    f,
+                    "{} {}",
+                    reporter.style_heading("Git repo:"),
+                    reporter.style_metadata(e.repo_path.display()),
+                )?;
+                if let Some(cs) = &e.first_commit {
+                    let cmd = &cs.commit_metadata;
+                    let msg = BStr::new(cmd.message.lines().next().unwrap_or(&[]));
+                    let atime = cmd.author_timestamp.format(gix::date::time::format::SHORT);
+                    writeln!(
+                        f,
+                        "{} first seen in {}",
+                        reporter.style_heading("Commit:"),
+                        reporter.style_metadata(cmd.commit_id),
+                    )?;
+                    writeln!(f)?;
+                    writeln!(
+                        indented(f).with_str("    "),
+                        "{}     {} <{}>\n\
+                         {}       {}\n\
+                         {}    {}\n\
+                         {}       {}",
+                        reporter.style_heading("Author:"),
+                        cmd.author_name,
+                        cmd.author_email,
+                        reporter.style_heading("Date:"),
+                        atime,
+                        reporter.style_heading("Summary:"),
+                        msg,
+                        reporter.style_heading("Path:"),
+                        cs.blob_path,
+                    )?;
+                    writeln!(f)?;
+                } else if let Some(p) = &e.blob_path {
+                    writeln!(
+                        f,
+                        "{} {}",
+                        reporter.style_heading("Path:"),
+                        reporter.style_metadata(p),
+                    )?;
+                }
+            }
+            // FIXME: implement this case properly
+            Provenance::Extended(e) => {
+                writeln!(
+                    f,
+                    "{} {}",
+                    reporter.style_heading("Extended Provenance:"),
+                    reporter.style_metadata(e),
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use serde_sarif::sarif;
+use std::collections::BTreeMap;
 
 use super::*;
 
@@ -72,11 +73,9 @@ impl DetailsReporter {
                                         .byte_offset(offset_span.start as i64)
                                         .byte_length(offset_span.len() as i64)
                                         */
-                                        .snippet(
-                                            sarif::ArtifactContent::builder()
-                                                .text(m.snippet.matching.to_string())
-                                                .build(),
-                                        )
+                                        // The matching input is deliberately not included as a
+                                        // snippet, so that secrets are not copied into SARIF
+                                        // consumers; the region identifies where it is.
                                         .build(),
                                 )
                                 .build(),
@@ -97,7 +96,7 @@ impl DetailsReporter {
 
         // Build the result for the match
         let result = sarif::Result::builder()
-            .rule_id(&metadata.rule_name)
+            .rule_id(&metadata.rule_text_id)
             // .occurrence_count(locations.len() as i64)  // FIXME: enable?
             .message(message)
             .kind(sarif::ResultKind::Review.to_string())
@@ -109,19 +108,22 @@ impl DetailsReporter {
     }
 
     pub fn sarif_format<W: std::io::Write>(&self, mut writer: W) -> Result<()> {
-        let group_metadata = self.get_finding_metadata()?;
-
-        let mut findings = Vec::with_capacity(group_metadata.len());
-        for metadata in group_metadata {
-            let matches = self.get_matches(&metadata)?;
-            let finding = Finding::new(metadata, matches);
+        // The SARIF results are built in memory, as the output is written as a single document,
+        // but each finding is read from the datastore only when its result is built
+        let mut findings = Vec::new();
+        self.for_each_finding_metadata(|metadata| {
+            let finding = self.get_finding(metadata)?;
             findings.push(self.make_sarif_result(&finding)?);
-        }
+            Ok(())
+        })?;
 
         let run = sarif::Run::builder()
-            .tool(noseyparker_sarif_tool()?)
-            .results(findings)
-            .build();
+            .tool(self.noseyparker_sarif_tool()?)
+            .results(findings);
+        let run = match self.noseyparker_sarif_run_properties()? {
+            Some(properties) => run.properties(properties).build(),
+            None => run.build(),
+        };
 
         let sarif = sarif::Sarif::builder()
             .version(sarif::Version::V2_1_0.to_string())
@@ -135,56 +137,103 @@ impl DetailsReporter {
 
         Ok(())
     }
-}
 
-/// Load the rules used during the scan for the runs.tool.driver.rules array property
-fn noseyparker_sarif_rules() -> Result<Vec<sarif::ReportingDescriptor>> {
-    // FIXME: this ignores any non-builtin rules
-    get_builtin_rules()
-        .context("Failed to load builtin rules")?
-        .iter_rules()
-        .map(|rule| {
-            let help = sarif::MultiformatMessageString::builder()
-                .text(rule.references.join("\n"))
-                .build();
-
-            // FIXME: add better descriptions to Nosey Parker rules
-            let description = sarif::MultiformatMessageString::builder()
-                .text(&rule.pattern)
-                .build();
-
-            let rule = sarif::ReportingDescriptor::builder()
-                .id(&rule.name) // FIXME: nosey parker rules need to have stable, unique IDs, preferably without spaces
-                // .name(&rule.name)  // FIXME: populate this once we have proper IDs
-                .short_description(description)
-                // .full_description(description)  // FIXME: populate this
-                .help(help) // FIXME: provide better help messages for NP rules that we can include here
-                // .help_uri() // FIXME: populate this
-                .build();
-            Ok(rule)
-        })
-        .collect::<Result<Vec<_>>>()
-}
+    /// Load the labels of the scan runs recorded in the datastore for the runs.properties property,
+    /// keyed by scan run ID, if any scan run has labels
+    fn noseyparker_sarif_run_properties(&self) -> Result<Option<sarif::PropertyBag>> {
+        let scan_runs = self
+            .datastore
+            .get_scan_runs()
+            .context("Failed to get scan runs from datastore")?;
+        let labels: BTreeMap<String, &BTreeMap<String, String>> = scan_runs
+            .iter()
+            .filter(|run| !run.labels.is_empty())
+            .map(|run| (run.id.to_string(), &run.labels))
+            .collect();
+        if labels.is_empty() {
+            return Ok(None);
+        }
+        let additional_properties =
+            BTreeMap::from([("scan_run_labels".to_string(), serde_json::to_value(labels)?)]);
+        let properties = sarif::PropertyBag::builder()
+            .additional_properties(additional_properties)
+            .build();
+        Ok(Some(properties))
+    }
 
-fn noseyparker_sarif_tool() -> Result<sarif::Tool> {
-    let tool = sarif::Tool::builder()
-        .driver(
-            sarif::ToolComponent::builder()
-                .name(env!("CARGO_PKG_NAME").to_string())
-                .semantic_version(env!("CARGO_PKG_VERSION").to_string())
-                .full_name(concat!("Nosey Parker ", env!("CARGO_PKG_VERSION"))) // FIXME: move into cargo.toml metadata, extract here; see https://docs.rs/cargo_metadata/latest/cargo_metadata/
-                .organization("Praetorian, Inc") // FIXME: move into cargo.toml metadata, extract here
-                .information_uri(env!("CARGO_PKG_HOMEPAGE").to_string())
-                .download_uri(env!("CARGO_PKG_REPOSITORY").to_string())
-                // .full_description() // FIXME: populate with some long description, like the text from the README.md
-                .short_description(
-                    sarif::MultiformatMessageString::builder()
-                        .text(env!("CARGO_PKG_DESCRIPTION"))
-                        .build(),
-                )
-                .rules(noseyparker_sarif_rules()?)
-                .build(),
-        )
-        .build();
-    Ok(tool)
+    /// Load the rules recorded in the datastore for the runs.tool.driver.rules array property
+    fn noseyparker_sarif_rules(&self) -> Result<Vec<sarif::ReportingDescriptor>> {
+        // The datastore can contain several versions of the same rule from different scans; use
+        // the version used by the most recent scan run for each rule ID, and otherwise, such as
+        // for imported rules, any recorded version
+        let mut rules = BTreeMap::new();
+        let scan_runs = self
+            .datastore
+            .get_scan_runs()
+            .context("Failed to get scan runs from datastore")?;
+        for run in scan_runs.iter().rev() {
+            for rule in self
+                .datastore
+                .get_scan_run_rules(run.id)
+                .context("Failed to get scan run rules from datastore")?
+            {
+                rules.entry(rule.id().to_owned()).or_insert(rule);
+            }
+        }
+        for rule in self
+            .datastore
+            .get_rules()
+            .context("Failed to get rules from datastore")?
+        {
+            rules.entry(rule.id().to_owned()).or_insert(rule);
+        }
+
+        rules
+            .values()
+            .map(|rule| {
+                let syntax = rule.syntax();
+                let help = sarif::MultiformatMessageString::builder()
+                    .text(syntax.references.join("\n"))
+                    .build();
+
+                let description = sarif::MultiformatMessageString::builder()
+                    .text(syntax.description.as_deref().unwrap_or(&syntax.pattern))
+                    .build();
+
+                let builder = sarif::ReportingDescriptor::builder()
+                    .id(rule.id())
+                    .name(rule.name())
+                    .short_description(description)
+                    .help(help);
+                let descriptor = match syntax.references.first() {
+                    Some(uri) => builder.help_uri(uri).build(),
+                    None => builder.build(),
+                };
+                Ok(descriptor)
+            })
+            .collect::<Result<Vec<_>>>()
+    }
+
+    fn noseyparker_sarif_tool(&self) -> Result<sarif::Tool> {
+        let tool = sarif::Tool::builder()
+            .driver(
+                sarif::ToolComponent::builder()
+                    .name(env!("CARGO_PKG_NAME").to_string())
+                    .semantic_version(env!("CARGO_PKG_VERSION").to_string())
+                    .full_name(concat!("Nosey Parker ", env!("CARGO_PKG_VERSION"))) // FIXME: move into cargo.toml metadata, extract here; see https://docs.rs/cargo_metadata/latest/cargo_metadata/
+                    .organization("Praetorian, Inc") // FIXME: move into cargo.toml metadata, extract here
+                    .information_uri(env!("CARGO_PKG_HOMEPAGE").to_string())
+                    .download_uri(env!("CARGO_PKG_REPOSITORY").to_string())
+                    // .full_description() // FIXME: populate with some long description, like the text from the README.md
+                    .short_description(
+                        sarif::MultiformatMessageString::builder()
+                            .text(env!("CARGO_PKG_DESCRIPTION"))
+                            .build(),
+                    )
+                    .rules(self.noseyparker_sarif_rules()?)
+                    .build(),
+            )
+            .build();
+        Ok(tool)
+    }
 }
@@ -1,31 +1,45 @@
 use anyhow::{bail, Context, Result};
+use indicatif::HumanBytes;
 use regex::Regex;
-use std::collections::HashSet;
+use serde::Serialize;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use tracing::{debug, error, error_span, info, warn};
 use vectorscan_rs::{BlockDatabase, Flag, Pattern, Scan};
 
 use noseyparker::rules_database::RulesDatabase;
-use noseyparker_rules::{Rule, RulesetSyntax};
+use noseyparker_rules::{Rule, RulesetSyntax, SourceLocation};
 
-use crate::args::{GlobalArgs, RulesCheckArgs};
-use crate::rule_loader::RuleLoader;
+use crate::args::{GlobalArgs, RulesCheckArgs, RulesCheckOutputFormat};
+use crate::reportable::Reportable;
+use crate::rule_loader::{LoadedRules, RuleLoader};
 use crate::util::Counted;
 
-pub fn run(_global_args: &GlobalArgs, args: &RulesCheckArgs) -> Result<()> {
+pub fn run(global_args: &GlobalArgs, args: &RulesCheckArgs) -> Result<()> {
     let _span = error_span!("cmd_rules_check").entered();
 
+    let output = args
+        .output_args
+        .get_writer()
+        .context("Failed to get output writer")?;
+
     let loaded = RuleLoader::from_rule_specifiers(&args.rules)
+        .ignore_certs(global_args.ignore_certs)
         .load()
         .context("Failed to load rules")?;
 
+    if loaded.num_rules() == 0 && loaded.num_rulesets() == 0 {
+        bail!("No rules or rulesets to check");
+    }
+
     let mut rules: Vec<&Rule> = loaded.iter_rules().collect();
     rules.sort_by(|r1, r2| r1.id().cmp(r2.id()));
 
     let mut rulesets: Vec<&RulesetSyntax> = loaded.iter_rulesets().collect();
     rulesets.sort_by(|r1, r2| r1.id.cmp(&r2.id));
 
-    let mut num_errors = 0;
-    let mut num_warnings = 0;
+    let mut diagnostics = Diagnostics::new(&loaded);
 
     let id_validator_pat = Regex::new(r"^[a-zA-Z0-9]+(?:[.-][a-zA-Z0-9]+)*$")
         .expect("ID validator pattern should compile");
@@ -37,8 +51,7 @@ pub fn run(_global_args: &GlobalArgs, args: &RulesCheckArgs) -> Result<()> {
         for ruleset in rulesets.iter() {
             let id = &ruleset.id;
             if !seen_ids.insert(id) {
-                error!("Ruleset ID {id} is not unique");
-                num_errors += 1;
+                diagnostics.ruleset_error(id, format!("Ruleset ID {id} is not unique"));
             }
         }
     }
@@ -49,20 +62,24 @@ pub fn run(_global_args: &GlobalArgs, args: &RulesCheckArgs) -> Result<()> {
             let id = &ruleset.id;
             let id_len = id.len();
             if id_len > ID_LIMIT {
-                error!(
-                    "Ruleset ID {id} is too long ({id_len} characters: \
-                       should be {ID_LIMIT} characters max)"
+                diagnostics.ruleset_error(
+                    id,
+                    format!(
+                        "Ruleset ID {id} is too long ({id_len} characters: \
+                           should be {ID_LIMIT} characters max)"
+                    ),
                 );
-                num_errors += 1;
             }
 
             if !id_validator_pat.is_match(id) {
-                error!(
-                    "Ruleset ID {id} is not well-formed: \
-                       it should consist only of alphanumeric sections \
-                       delimited by hyphens or periods"
+                diagnostics.ruleset_error(
+                    id,
+                    format!(
+                        "Ruleset ID {id} is not well-formed: \
+                           it should consist only of alphanumeric sections \
+                           delimited by hyphens or periods"
+                    ),
                 );
-                num_errors += 1;
             }
         }
     }
@@ -73,8 +90,7 @@ pub fn run(_global_args: &GlobalArgs, args: &RulesCheckArgs) -> Result<()> {
         for rule in rules.iter() {
             let id = rule.id();
             if !seen_ids.insert(id) {
-                error!("Rule ID {id} is not unique");
-                num_errors += 1;
+                diagnostics.rule_error(id, format!("Rule ID {id} is not unique"));
             }
         }
     }
@@ -85,20 +101,24 @@ pub fn run(_global_args: &GlobalArgs, args: &RulesCheckArgs) -> Result<()> {
             let id = rule.id();
             let id_len = id.len();
             if id_len > ID_LIMIT {
-                error!(
-                    "Rule ID {id} is too long ({id_len} characters: \
-                       should be {ID_LIMIT} characters max)"
+                diagnostics.rule_error(
+                    id,
+                    format!(
+                        "Rule ID {id} is too long ({id_len} characters: \
+                           should be {ID_LIMIT} characters max)"
+                    ),
                 );
-                num_errors += 1;
             }
 
             if !id_validator_pat.is_match(id) {
-                error!(
-                    "Rule ID {id} is not well-formed: \
-                       it should consist only of alphanumeric sections \
-                       delimited by hyphens or periods"
+                diagnostics.rule_error(
+                    id,
+                    format!(
+                        "Rule ID {id} is not well-formed: \
+                           it should consist only of alphanumeric sections \
+                           delimited by hyphens or periods"
+                    ),
                 );
-                num_errors += 1;
             }
         }
     }
@@ -110,15 +130,13 @@ pub fn run(_global_args: &GlobalArgs, args: &RulesCheckArgs) -> Result<()> {
         for ruleset in rulesets.iter() {
             let _span = error_span!("ruleset", "{}", ruleset.id).entered();
             if let Err(e) = loaded.resolve_ruleset_rules(ruleset) {
-                error!("Failed to resolve rules: {e}");
-                num_errors += 1;
+                diagnostics.ruleset_error(&ruleset.id, format!("Failed to resolve rules: {e}"));
             }
 
             let mut seen_ids = HashSet::<&str>::new();
             for id in ruleset.include_rule_ids.iter() {
                 if !seen_ids.insert(id) {
-                    warn!("Rule ID {id} is not unique");
-                    num_warnings += 1;
+                    diagnostics.ruleset_warning(&ruleset.id, format!("Rule ID {id} is not unique"));
                 }
             }
         }
@@ -126,9 +144,34 @@ pub fn run(_global_args: &GlobalArgs, args: &RulesCheckArgs) -> Result<()> {
 
     // check the rules individually
     for rule in rules.iter() {
-        let stats = check_rule(rule, args)?;
-        num_errors += stats.num_errors;
-        num_warnings += stats.num_warnings;
+        check_rule(rule, args, &mut diagnostics)?;
+    }
+
+    // check that no two rules have the same pattern, as they would produce redundant matches
+    if args.pedantic {
+        let mut rule_ids_by_pattern = HashMap::<String, &str>::new();
+        for rule in rules.iter() {
+            // patterns that fail to parse have already been reported by `check_rule`
+            let Some(pattern) = normalized_pattern(rule) else {
+                continue;
+            };
+            let id = rule.id();
+            match rule_ids_by_pattern.entry(pattern) {
+                Entry::Vacant(e) => {
+                    e.insert(id);
+                }
+                Entry::Occupied(e) => {
+                    diagnostics.rule_warning(
+                        id,
+                        format!(
+                            "Rule ID {id} ({}) has the same pattern as rule ID {}",
+                            rule.name(),
+                            e.get()
+                        ),
+                    );
+                }
+            }
+        }
     }
 
     // check that every rule is included in at least one ruleset
@@ -141,32 +184,33 @@ pub fn run(_global_args: &GlobalArgs, args: &RulesCheckArgs) -> Result<()> {
         for rule in rules.iter() {
             let id = &rule.syntax().id;
             if !seen_rule_ids.contains(id) {
-                warn!("Rule ID {id} ({}) is not referenced from any known ruleset", rule.name());
-                num_warnings += 1;
+                diagnostics.rule_warning(
+                    id,
+                    format!(
+                        "Rule ID {id} ({}) is not referenced from any known ruleset",
+                        rule.name()
+                    ),
+                );
             }
         }
     }
 
     // check that the rules can all compile together
-    let rules: Vec<Rule> = rules.into_iter().cloned().collect();
-    let _rules_db =
-        RulesDatabase::from_rules(rules).context("Failed to compile combined rules database")?;
+    if !rules.is_empty() {
+        let rules: Vec<Rule> = rules.into_iter().cloned().collect();
+        let rules_db = RulesDatabase::from_rules(rules)
+            .context("Failed to compile combined rules database")?;
+        if args.show_db_size {
+            diagnostics.db_size = Some(rules_db.database_size()?);
+        }
+    }
 
     // XXX: if args.pedantic, should check that all rules compile together with SOM_LEFTMOST
 
-    if num_warnings == 0 && num_errors == 0 {
-        println!(
-            "{} and {}: no issues detected",
-            Counted::regular(loaded.num_rules(), "rule"),
-            Counted::regular(loaded.num_rulesets(), "ruleset"),
-        );
-    } else {
-        println!(
-            "{} and {}: {num_errors} errors and {num_warnings} warnings",
-            Counted::regular(loaded.num_rules(), "rule"),
-            Counted::regular(loaded.num_rulesets(), "ruleset"),
-        );
-    }
+    let num_errors = diagnostics.num_errors();
+    let num_warnings = diagnostics.num_warnings();
+
+    diagnostics.report(args.output_args.format, output)?;
 
     if num_errors != 0 {
         bail!("{}", Counted::regular(num_errors, "error"));
@@ -198,28 +242,36 @@ fn hs_compile_pattern_som_leftmost(pat: &str) -> Result<BlockDatabase> {
     Ok(db)
 }
 
-struct CheckStats {
-    num_warnings: usize,
-    num_errors: usize,
+/// Get a normalized form of a rule's pattern, so that patterns that differ only in insignificant
+/// ways, such as comments or whitespace in verbose mode, compare equal.
+///
+/// Returns `None` if the pattern cannot be parsed.
+fn normalized_pattern(rule: &Rule) -> Option<String> {
+    regex_syntax::ParserBuilder::new()
+        .unicode(false)
+        .utf8(false)
+        .build()
+        .parse(&rule.syntax().uncommented_pattern())
+        .ok()
+        .map(|hir| hir.to_string())
 }
 
-fn check_rule(rule: &Rule, args: &RulesCheckArgs) -> Result<CheckStats> {
+fn check_rule(rule: &Rule, args: &RulesCheckArgs, diagnostics: &mut Diagnostics) -> Result<()> {
     let syntax = rule.syntax();
-    let _span = error_span!("rule", "{}", syntax.id).entered();
+    let id = &syntax.id;
+    let _span = error_span!("rule", "{}", id).entered();
 
-    let mut num_warnings = 0;
-    let mut num_errors = 0;
+    let initial_num_warnings = diagnostics.num_warnings();
+    let initial_num_errors = diagnostics.num_errors();
 
     let num_examples = syntax.examples.len();
     if num_examples == 0 {
-        warn!("Rule has no examples");
-        num_warnings += 1;
+        diagnostics.rule_warning(id, "Rule has no examples".into());
     }
 
     match syntax.as_regex() {
         Err(e) => {
-            error!("Regex: failed to compile pattern: {e}");
-            num_errors += 1;
+            diagnostics.rule_error(id, format!("Regex: failed to compile pattern: {e}"));
         }
         Ok(pat) => {
             // Check that the rule has at least one capture group
@@ -227,13 +279,12 @@ fn check_rule(rule: &Rule, args: &RulesCheckArgs) -> Result<CheckStats> {
                 // the default is a single capture group for the entire match
                 // not sure if 0 can actually happen
                 Some(0) | Some(1) => {
-                    error!("Rule has no capture groups");
-                    num_errors += 1;
+                    diagnostics.rule_error(id, "Rule has no capture groups".into());
                 }
                 Some(_len) => {}
                 None => {
-                    error!("Rule has a variable number of capture groups");
-                    num_errors += 1;
+                    diagnostics
+                        .rule_error(id, "Rule has a variable number of capture groups".into());
                 }
             }
 
@@ -243,9 +294,11 @@ fn check_rule(rule: &Rule, args: &RulesCheckArgs) -> Result<CheckStats> {
             // Check positive examples
             for (example_num, example) in syntax.examples.iter().enumerate() {
                 if pat.find(example.as_bytes()).is_none() {
-                    error!("Regex: failed to match example {example_num}: {example:?}");
+                    diagnostics.rule_error(
+                        id,
+                        format!("Regex: failed to match example {example_num}: {example:?}"),
+                    );
                     num_failed += 1;
-                    num_errors += 1;
                 } else {
                     num_succeeded += 1;
                 }
@@ -254,11 +307,13 @@ fn check_rule(rule: &Rule, args: &RulesCheckArgs) -> Result<CheckStats> {
             // Check negative examples
             for (example_num, example) in syntax.negative_examples.iter().enumerate() {
                 if pat.find(example.as_bytes()).is_some() {
-                    error!(
-                        "Regex: incorrectly matched negative example {example_num}: {example:?}"
+                    diagnostics.rule_error(
+                        id,
+                        format!(
+                            "Regex: incorrectly matched negative example {example_num}: {example:?}"
+                        ),
                     );
                     num_failed += 1;
-                    num_errors += 1;
                 } else {
                     num_succeeded += 1;
                 }
@@ -274,8 +329,7 @@ fn check_rule(rule: &Rule, args: &RulesCheckArgs) -> Result<CheckStats> {
     let uncommented_pattern = syntax.uncommented_pattern();
     match hs_compile_pattern(&uncommented_pattern) {
         Err(e) => {
-            error!("Vectorscan: failed to compile pattern: {e}");
-            num_errors += 1;
+            diagnostics.rule_error(id, format!("Vectorscan: failed to compile pattern: {e}"));
         }
         Ok(db) => {
             debug!("{} regex bytes -> {} vectorscan bytes", uncommented_pattern.len(), db.size()?);
@@ -293,9 +347,11 @@ fn check_rule(rule: &Rule, args: &RulesCheckArgs) -> Result<CheckStats> {
                     Scan::Continue
                 })?;
                 if !matched {
-                    error!("Vectorscan: failed to match example {example_num}: {example:?}");
+                    diagnostics.rule_error(
+                        id,
+                        format!("Vectorscan: failed to match example {example_num}: {example:?}"),
+                    );
                     num_failed += 1;
-                    num_errors += 1;
                 } else {
                     num_succeeded += 1;
                 }
@@ -309,9 +365,13 @@ fn check_rule(rule: &Rule, args: &RulesCheckArgs) -> Result<CheckStats> {
                     Scan::Continue
                 })?;
                 if matched {
-                    error!("Vectorscan: incorrectly matched negative example {example_num}: {example:?}");
+                    diagnostics.rule_error(
+                        id,
+                        format!(
+                            "Vectorscan: incorrectly matched negative example {example_num}: {example:?}"
+                        ),
+                    );
                     num_failed += 1;
-                    num_errors += 1;
                 } else {
                     num_succeeded += 1;
                 }
@@ -326,24 +386,179 @@ fn check_rule(rule: &Rule, args: &RulesCheckArgs) -> Result<CheckStats> {
 
     if args.pedantic {
         if let Err(e) = hs_compile_pattern_som_leftmost(&syntax.uncommented_pattern()) {
-            error!("Vectorscan: failed to compile pattern with start-of-match reporting: {}", e);
-            num_errors += 1;
+            diagnostics.rule_error(
+                id,
+                format!("Vectorscan: failed to compile pattern with start-of-match reporting: {e}"),
+            );
         }
     }
 
+    if let Err(e) = syntax.path_filter() {
+        diagnostics.rule_error(id, format!("Failed to compile path constraints: {e:#}"));
+    }
+
     if args.pedantic && syntax.description.is_none() {
-        error!("Rule has no description");
-        num_errors += 1;
+        diagnostics.rule_error(id, "Rule has no description".into());
     }
 
+    let num_warnings = diagnostics.num_warnings() - initial_num_warnings;
+    let num_errors = diagnostics.num_errors() - initial_num_errors;
     if num_warnings == 0 && num_errors == 0 {
         info!("No issues detected");
     } else {
         info!("{num_errors} errors and {num_warnings} warnings");
     }
 
-    Ok(CheckStats {
-        num_warnings,
-        num_errors,
-    })
+    Ok(())
+}
+
+// -------------------------------------------------------------------------------------------------
+// Diagnostics
+// -------------------------------------------------------------------------------------------------
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem detected by the checker
+#[derive(Serialize)]
+pub(super) struct Diagnostic {
+    /// The ID of the rule the problem was detected in, if any
+    pub(super) rule_id: Option<String>,
+
+    /// The ID of the ruleset the problem was detected in, if any
+    ruleset_id: Option<String>,
+
+    severity: Severity,
+
+    pub(super) message: String,
+
+    /// The file that the rule or ruleset was loaded from, if known
+    file: Option<PathBuf>,
+
+    /// The line within `file` where the rule or ruleset is defined, if known
+    line: Option<usize>,
+}
+
+/// The problems detected by the checker.
+///
+/// Each problem is logged as it is recorded, in addition to being available for structured output.
+/// This is also used by `rules test` to record failed examples.
+pub(super) struct Diagnostics<'a> {
+    loaded: &'a LoadedRules,
+    entries: Vec<Diagnostic>,
+
+    /// The size in bytes of the combined rules database, if requested
+    db_size: Option<usize>,
+}
+
+impl<'a> Diagnostics<'a> {
+    pub(super) fn new(loaded: &'a LoadedRules) -> Self {
+        Self {
+            loaded,
+            entries: Vec::new(),
+            db_size: None,
+        }
+    }
+
+    pub(super) fn num_errors(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .count()
+    }
+
+    fn num_warnings(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|d| d.severity == Severity::Warning)
+            .count()
+    }
+
+    pub(super) fn rule_error(&mut self, rule_id: &str, message: String) {
+        error!("{message}");
+        let source = self.loaded.rule_source(rule_id);
+        self.push(Some(rule_id), None, Severity::Error, message, source);
+    }
+
+    fn rule_warning(&mut self, rule_id: &str, message: String) {
+        warn!("{message}");
+        let source = self.loaded.rule_source(rule_id);
+        self.push(Some(rule_id), None, Severity::Warning, message, source);
+    }
+
+    fn ruleset_error(&mut self, ruleset_id: &str, message: String) {
+        error!("{message}");
+        let source = self.loaded.ruleset_source(ruleset_id);
+        self.push(None, Some(ruleset_id), Severity::Error, message, source);
+    }
+
+    fn ruleset_warning(&mut self, ruleset_id: &str, message: String) {
+        warn!("{message}");
+        let source = self.loaded.ruleset_source(ruleset_id);
+        self.push(None, Some(ruleset_id), Severity::Warning, message, source);
+    }
+
+    pub(super) fn entries(&self) -> &[Diagnostic] {
+        &self.entries
+    }
+
+    fn push(
+        &mut self,
+        rule_id: Option<&str>,
+        ruleset_id: Option<&str>,
+        severity: Severity,
+        message: String,
+        source: Option<&SourceLocation>,
+    ) {
+        self.entries.push(Diagnostic {
+            rule_id: rule_id.map(str::to_owned),
+            ruleset_id: ruleset_id.map(str::to_owned),
+            severity,
+            message,
+            file: source.map(|s| s.path.clone()),
+            line: source.and_then(|s| s.line),
+        });
+    }
+}
+
+impl Reportable for Diagnostics<'_> {
+    type Format = RulesCheckOutputFormat;
+
+    fn report<W: std::io::Write>(&self, format: Self::Format, mut writer: W) -> Result<()> {
+        match format {
+            RulesCheckOutputFormat::Human => {
+                let num_errors = self.num_errors();
+                let num_warnings = self.num_warnings();
+                if num_warnings == 0 && num_errors == 0 {
+                    writeln!(
+                        writer,
+                        "{} and {}: no issues detected",
+                        Counted::regular(self.loaded.num_rules(), "rule"),
+                        Counted::regular(self.loaded.num_rulesets(), "ruleset"),
+                    )?;
+                } else {
+                    writeln!(
+                        writer,
+                        "{} and {}: {num_errors} errors and {num_warnings} warnings",
+                        Counted::regular(self.loaded.num_rules(), "rule"),
+                        Counted::regular(self.loaded.num_rulesets(), "ruleset"),
+                    )?;
+                }
+                if let Some(db_size) = self.db_size {
+                    writeln!(
+                        writer,
+                        "Combined rules database size: {} ({db_size} bytes)",
+                        HumanBytes(db_size as u64),
+                    )?;
+                }
+            }
+            RulesCheckOutputFormat::Json => {
+                serde_json::to_writer_pretty(writer, &self.entries)?;
+            }
+        }
+        Ok(())
+    }
 }
@@ -1,13 +1,15 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use noseyparker_rules::{Rule, RuleSyntax, RulesetSyntax};
 use serde::Serialize;
+use std::borrow::Cow;
 use tracing::debug_span;
 
 use crate::args::{GlobalArgs, RulesListArgs, RulesListOutputFormat};
 use crate::reportable::Reportable;
 use crate::rule_loader::{LoadedRules, RuleLoader};
+use crate::util::Counted;
 
-pub fn run(_global_args: &GlobalArgs, args: &RulesListArgs) -> Result<()> {
+pub fn run(global_args: &GlobalArgs, args: &RulesListArgs) -> Result<()> {
     let _span = debug_span!("cmd_rules_list").entered();
 
     let output = args
@@ -16,15 +18,33 @@ pub fn run(_global_args: &GlobalArgs, args: &RulesListArgs) -> Result<()> {
         .context("Failed to get output writer")?;
 
     let loaded = RuleLoader::from_rule_specifiers(&args.rules)
+        .ignore_certs(global_args.ignore_certs)
         .load()
         .context("Failed to load rules")?;
 
-    let reporter = RulesReporter { loaded };
+    for rule in &args.rule {
+        if !loaded
+            .iter_rules()
+            .any(|r| r.id() == rule || r.name() == rule)
+        {
+            bail!("Unknown rule `{rule}`");
+        }
+    }
+
+    let reporter = RulesReporter {
+        loaded,
+        count_only: args.count_only,
+        rules: args.rule.clone(),
+    };
     reporter.report(args.output_args.format, output)
 }
 
 struct RulesReporter {
     loaded: LoadedRules,
+    count_only: bool,
+
+    /// If nonempty, the IDs or names of the rules whose full definitions should be reported
+    rules: Vec<String>,
 }
 
 impl Reportable for RulesReporter {
@@ -49,7 +69,68 @@ impl RulesReporter {
         Entries { rules, rulesets }
     }
 
+    /// Get the rules selected by ID or name, sorted by ID.
+    fn get_selected_rules(&self) -> Vec<RuleEntry<'_>> {
+        let mut rules: Vec<_> = self
+            .loaded
+            .iter_rules()
+            .filter(|r| self.rules.iter().any(|s| s == r.id() || s == r.name()))
+            .map(|r| RuleEntry {
+                compiled_pattern: Some(r.syntax().uncommented_pattern()),
+                ..RuleEntry::new(r)
+            })
+            .collect();
+        rules.sort_by(|r1, r2| r1.id.cmp(r2.id));
+        rules
+    }
+
+    fn get_counts(&self) -> Result<Counts<'_>> {
+        let num_enabled_rules = self.loaded.resolve_enabled_rules()?.len();
+
+        let mut rulesets = Vec::with_capacity(self.loaded.num_rulesets());
+        for ruleset in self.loaded.iter_rulesets() {
+            let num_rules = self.loaded.resolve_ruleset_rules(ruleset)?.len();
+            rulesets.push(RulesetEntry {
+                id: &ruleset.id,
+                name: &ruleset.name,
+                num_rules,
+            });
+        }
+        rulesets.sort_by(|r1, r2| r1.id.cmp(r2.id));
+
+        Ok(Counts {
+            num_rules: self.loaded.num_rules(),
+            num_enabled_rules,
+            rulesets,
+        })
+    }
+
     fn human_format<W: std::io::Write>(&self, mut writer: W) -> Result<()> {
+        if self.count_only {
+            let counts = self.get_counts()?;
+            writeln!(
+                writer,
+                "{} loaded; {} enabled",
+                Counted::regular(counts.num_rules, "rule"),
+                counts.num_enabled_rules
+            )?;
+
+            writeln!(writer)?;
+            rulesets_table(&counts.rulesets).print(&mut writer)?;
+
+            return Ok(());
+        }
+
+        if !self.rules.is_empty() {
+            for (i, rule) in self.get_selected_rules().iter().enumerate() {
+                if i > 0 {
+                    writeln!(writer)?;
+                }
+                write_rule_details(&mut writer, rule)?;
+            }
+            return Ok(());
+        }
+
         let entries = self.get_entries();
 
         let rules_table = entries.rules_table();
@@ -64,6 +145,17 @@ impl RulesReporter {
     }
 
     fn json_format<W: std::io::Write>(&self, writer: W) -> Result<()> {
+        if self.count_only {
+            let counts = self.get_counts()?;
+            serde_json::to_writer_pretty(writer, &counts)?;
+            return Ok(());
+        }
+
+        if !self.rules.is_empty() {
+            serde_json::to_writer_pretty(writer, &self.get_selected_rules())?;
+            return Ok(());
+        }
+
         let entries = self.get_entries();
         serde_json::to_writer_pretty(writer, &entries)?;
         Ok(())
@@ -76,12 +168,23 @@ struct Entries<'r> {
     rulesets: Vec<RulesetEntry<'r>>,
 }
 
+#[derive(Serialize)]
+struct Counts<'r> {
+    num_rules: usize,
+    num_enabled_rules: usize,
+    rulesets: Vec<RulesetEntry<'r>>,
+}
+
 #[derive(Serialize)]
 struct RuleEntry<'r> {
     id: &'r str,
     structural_id: &'r str,
     name: &'r str,
     syntax: &'r RuleSyntax,
+
+    /// The pattern with comments removed, as it is compiled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compiled_pattern: Option<Cow<'r, str>>,
 }
 
 impl<'r> RuleEntry<'r> {
@@ -91,10 +194,57 @@ impl<'r> RuleEntry<'r> {
             name: rule.name(),
             structural_id: rule.structural_id(),
             syntax: rule.syntax(),
+            compiled_pattern: None,
         }
     }
 }
 
+/// Write the full definition of a rule in human-readable form.
+fn write_rule_details<W: std::io::Write>(mut writer: W, rule: &RuleEntry<'_>) -> Result<()> {
+    let syntax = rule.syntax;
+
+    writeln!(writer, "Rule ID:       {}", rule.id)?;
+    writeln!(writer, "Rule name:     {}", rule.name)?;
+    writeln!(writer, "Structural ID: {}", rule.structural_id)?;
+    if !syntax.categories.is_empty() {
+        writeln!(writer, "Categories:    {}", syntax.categories.join(", "))?;
+    }
+
+    writeln!(writer, "\nPattern:")?;
+    for line in syntax.pattern.lines() {
+        writeln!(writer, "    {line}")?;
+    }
+
+    if let Some(description) = &syntax.description {
+        writeln!(writer, "\nDescription:")?;
+        for line in description.trim_end().lines() {
+            writeln!(writer, "    {line}")?;
+        }
+    }
+
+    let lists = [
+        ("Examples", &syntax.examples),
+        ("Negative examples", &syntax.negative_examples),
+        ("References", &syntax.references),
+        ("Path includes", &syntax.path_include),
+        ("Path excludes", &syntax.path_exclude),
+    ];
+    for (title, items) in lists {
+        if items.is_empty() {
+            continue;
+        }
+        writeln!(writer, "\n{title}:")?;
+        for item in items {
+            for (i, line) in item.trim_end().lines().enumerate() {
+                let prefix = if i == 0 { "  - " } else { "    " };
+                writeln!(writer, "{prefix}{line}")?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
 #[derive(Serialize)]
 struct RulesetEntry<'r> {
     id: &'r str,
@@ -141,24 +291,27 @@ impl<'r> Entries<'r> {
     }
 
     fn rulesets_table(&self) -> prettytable::Table {
-        use prettytable::format::{FormatBuilder, LinePosition, LineSeparator};
-        use prettytable::row;
+        rulesets_table(&self.rulesets)
+    }
+}
 
-        let f = FormatBuilder::new()
-            // .column_separator('│')
-            // .separators(&[LinePosition::Title], LineSeparator::new('─', '┼', '├', '┤'))
-            .column_separator(' ')
-            .separators(&[LinePosition::Title], LineSeparator::new('─', '─', '─', '─'))
-            .padding(1, 1)
-            .build();
+fn rulesets_table(rulesets: &[RulesetEntry<'_>]) -> prettytable::Table {
+    use prettytable::format::{FormatBuilder, LinePosition, LineSeparator};
+    use prettytable::row;
 
-        let mut table: prettytable::Table = self
-            .rulesets
-            .iter()
-            .map(|r| row![l -> &r.id, l -> &r.name, r -> r.num_rules])
-            .collect();
-        table.set_format(f);
-        table.set_titles(row![lb -> "Ruleset ID", lb -> "Ruleset Name", rb -> "Rules"]);
-        table
-    }
+    let f = FormatBuilder::new()
+        // .column_separator('│')
+        // .separators(&[LinePosition::Title], LineSeparator::new('─', '┼', '├', '┤'))
+        .column_separator(' ')
+        .separators(&[LinePosition::Title], LineSeparator::new('─', '─', '─', '─'))
+        .padding(1, 1)
+        .build();
+
+    let mut table: prettytable::Table = rulesets
+        .iter()
+        .map(|r| row![l -> &r.id, l -> &r.name, r -> r.num_rules])
+        .collect();
+    table.set_format(f);
+    table.set_titles(row![lb -> "Ruleset ID", lb -> "Ruleset Name", rb -> "Rules"]);
+    table
 }
@@ -0,0 +1,175 @@
+use anyhow::{bail, Context, Result};
+use std::collections::{HashMap, HashSet};
+use tracing::{error_span, info};
+
+use noseyparker::blob::Blob;
+use noseyparker::blob_id_map::BlobIdMap;
+use noseyparker::matcher::{Matcher, ScanResult};
+use noseyparker::provenance::Provenance;
+use noseyparker::provenance_set::ProvenanceSet;
+use noseyparker::rules_database::RulesDatabase;
+use noseyparker_rules::Rule;
+
+use super::cmd_rules_check::Diagnostics;
+use crate::args::{GlobalArgs, RulesTestArgs, RulesTestOutputFormat};
+use crate::reportable::Reportable;
+use crate::rule_loader::{LoadedRules, RuleLoader};
+use crate::util::Counted;
+
+pub fn run(global_args: &GlobalArgs, args: &RulesTestArgs) -> Result<()> {
+    let _span = error_span!("cmd_rules_test").entered();
+
+    let output = args
+        .output_args
+        .get_writer()
+        .context("Failed to get output writer")?;
+
+    let loaded = RuleLoader::from_rule_specifiers(&args.rules)
+        .ignore_certs(global_args.ignore_certs)
+        .load()
+        .context("Failed to load rules")?;
+
+    if loaded.num_rules() == 0 {
+        bail!("No rules to test");
+    }
+
+    let mut rules: Vec<Rule> = loaded.iter_rules().cloned().collect();
+    rules.sort_by(|r1, r2| r1.id().cmp(r2.id()));
+
+    // Compile the rules together, just as when scanning, so that the examples are checked
+    // using the same matching machinery that produces findings.
+    let rules_db = RulesDatabase::from_rules(rules).context("Failed to compile rules database")?;
+    let seen_blobs = BlobIdMap::new();
+    let mut matcher = Matcher::new(&rules_db, &seen_blobs, None)?;
+
+    // The IDs of the rules that match each distinct example
+    let mut example_matches: HashMap<&str, HashSet<String>> = HashMap::new();
+    for rule in rules_db.rules() {
+        let syntax = rule.syntax();
+        for example in syntax
+            .examples
+            .iter()
+            .chain(syntax.negative_examples.iter())
+        {
+            if example_matches.contains_key(example.as_str()) {
+                continue;
+            }
+            let blob = Blob::from_bytes(example.as_bytes().to_vec());
+            let provenance = ProvenanceSet::single(Provenance::from_extended(
+                serde_json::json!({ "rule_id": rule.id() }),
+            ));
+            let matched_rule_ids = match matcher.scan_blob(&blob, &provenance)? {
+                ScanResult::New(matches) => {
+                    matches.iter().map(|m| m.rule.id().to_owned()).collect()
+                }
+                _ => HashSet::new(),
+            };
+            example_matches.insert(example, matched_rule_ids);
+        }
+    }
+
+    let mut results = TestResults::new(&loaded);
+    for rule in rules_db.rules() {
+        let syntax = rule.syntax();
+        let id = rule.id();
+        let _span = error_span!("rule", "{}", id).entered();
+
+        let matches = |example: &str| example_matches[example].contains(id);
+
+        for (example_num, example) in syntax.examples.iter().enumerate() {
+            results.num_examples += 1;
+            if !matches(example) {
+                results
+                    .failure(rule, format!("Failed to match example {example_num}: {example:?}"));
+            }
+        }
+
+        for (example_num, example) in syntax.negative_examples.iter().enumerate() {
+            results.num_examples += 1;
+            if matches(example) {
+                results.failure(
+                    rule,
+                    format!("Incorrectly matched negative example {example_num}: {example:?}"),
+                );
+            }
+        }
+    }
+    info!(
+        "Tested {} from {}",
+        Counted::regular(results.num_examples, "example"),
+        Counted::regular(loaded.num_rules(), "rule")
+    );
+
+    let num_failures = results.num_failures();
+    results.report(args.output_args.format, output)?;
+
+    if num_failures != 0 {
+        bail!("{}", Counted::regular(num_failures, "example failure"));
+    }
+
+    Ok(())
+}
+
+// -------------------------------------------------------------------------------------------------
+// TestResults
+// -------------------------------------------------------------------------------------------------
+/// The results of testing rules against their examples, with each failed example recorded as an
+/// error diagnostic.
+struct TestResults<'a> {
+    loaded: &'a LoadedRules,
+    num_examples: usize,
+    diagnostics: Diagnostics<'a>,
+}
+
+impl<'a> TestResults<'a> {
+    fn new(loaded: &'a LoadedRules) -> Self {
+        Self {
+            loaded,
+            num_examples: 0,
+            diagnostics: Diagnostics::new(loaded),
+        }
+    }
+
+    fn failure(&mut self, rule: &Rule, message: String) {
+        self.diagnostics.rule_error(rule.id(), message);
+    }
+
+    fn num_failures(&self) -> usize {
+        self.diagnostics.num_errors()
+    }
+}
+
+impl Reportable for TestResults<'_> {
+    type Format = RulesTestOutputFormat;
+
+    fn report<W: std::io::Write>(&self, format: Self::Format, mut writer: W) -> Result<()> {
+        match format {
+            RulesTestOutputFormat::Human => {
+                let num_failures = self.num_failures();
+                if num_failures == 0 {
+                    writeln!(
+                        writer,
+                        "{} and {}: all passed",
+                        Counted::regular(self.loaded.num_rules(), "rule"),
+                        Counted::regular(self.num_examples, "example"),
+                    )?;
+                } else {
+                    for failure in self.diagnostics.entries() {
+                        let rule_id = failure.rule_id.as_deref().unwrap_or_default();
+                        writeln!(writer, "{rule_id}: {}", failure.message)?;
+                    }
+                    writeln!(
+                        writer,
+                        "{} and {}: {num_failures} failed",
+                        Counted::regular(self.loaded.num_rules(), "rule"),
+                        Counted::regular(self.num_examples, "example"),
+                    )?;
+                }
+            }
+            RulesTestOutputFormat::Json => {
+                serde_json::to_writer_pretty(writer, self.diagnostics.entries())?;
+            }
+        }
+        Ok(())
+    }
+}
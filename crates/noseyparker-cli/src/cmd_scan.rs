@@ -1,32 +1,40 @@
 use anyhow::{bail, Context, Result};
 use indicatif::{HumanBytes, HumanCount, HumanDuration};
 use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::time::{Duration, Instant};
 use tracing::{debug, error, error_span, info, trace, warn};
 
+use crate::util::Counted;
 use crate::{args, rule_loader::RuleLoader};
 
 use content_guesser::Guesser;
 use input_enumerator::{FilesystemEnumerator, FoundInput};
-use progress::Progress;
+use progress::{MultiProgress, Progress};
 
-use noseyparker::blob::{Blob, BlobId};
+use noseyparker::blob::{Blob, BlobId, BlobIdAlgorithm};
 use noseyparker::blob_id_map::BlobIdMap;
 use noseyparker::blob_metadata::BlobMetadata;
-use noseyparker::datastore::Datastore;
+use noseyparker::datastore::{
+    Datastore, ScanRunIdInt, ScanRunMetadata, DEFAULT_REDUNDANCY_OVERLAP,
+};
 use noseyparker::defaults::DEFAULT_IGNORE_RULES;
-use noseyparker::git_binary::{CloneMode, Git};
+use noseyparker::git_binary::{CloneMode, Git, Netrc};
 use noseyparker::git_url::GitUrl;
 use noseyparker::location;
 use noseyparker::match_type::Match;
 use noseyparker::matcher::{Matcher, ScanResult};
-use noseyparker::matcher_stats::MatcherStats;
+use noseyparker::matcher_stats::{MatcherStats, RuleMatchCounts, SizeHistogram};
 use noseyparker::provenance::Provenance;
 use noseyparker::provenance_set::ProvenanceSet;
 use noseyparker::rules_database::RulesDatabase;
+use noseyparker::scoring::{LinearModel, Scorer};
+use noseyparker::snippet::{SnippetMode, StructuredFormat};
+use noseyparker_rules::rules_fingerprint;
 
 // -------------------------------------------------------------------------------------------------
 /// Something that can be turned into a parallel iterator of blobs
@@ -64,24 +72,50 @@ struct EnumeratorBlobResult {
     pub content: Content,
 
     pub provenance: serde_json::Value,
+
+    /// The Git blob ID of the content, if already known, which avoids computing it
+    #[serde(default)]
+    pub blob_id: Option<BlobId>,
 }
 
 // -------------------------------------------------------------------------------------------------
 /// A parallel iterator for an `input_enumerator::EnumeratorFileResult`.
 struct EnumeratorFileIter {
     inner: input_enumerator::EnumeratorFileResult,
-    reader: std::io::BufReader<std::fs::File>,
+    reader: Box<dyn std::io::BufRead + Send>,
+    blob_id_algorithm: BlobIdAlgorithm,
 }
 
-impl ParallelBlobIterator for input_enumerator::EnumeratorFileResult {
+impl ParallelBlobIterator for (&EnumeratorConfig, input_enumerator::EnumeratorFileResult) {
     type Iter = EnumeratorFileIter;
 
     fn into_blob_iter(self) -> Result<Option<Self::Iter>> {
-        let file = std::fs::File::open(&self.path)?;
-        let reader = std::io::BufReader::new(file);
+        use std::io::BufReader;
+
+        let (cfg, inner) = self;
+        let file = std::fs::File::open(&inner.path)?;
+        let file = BufReader::new(file);
+
+        // Enumerator files compressed with gzip or zstd are decompressed transparently, according
+        // to their extension
+        let name = inner
+            .path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        let reader: Box<dyn std::io::BufRead + Send> = if name.ends_with(".gz") {
+            Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(file)))
+        } else if name.ends_with(".zst") {
+            Box::new(BufReader::new(zstd::Decoder::with_buffer(file).with_context(|| {
+                format!("Failed to initialize decompression for {}", inner.path.display())
+            })?))
+        } else {
+            Box::new(file)
+        };
         Ok(Some(EnumeratorFileIter {
-            inner: self,
+            inner,
             reader,
+            blob_id_algorithm: cfg.blob_id_algorithm,
         }))
     }
 }
@@ -108,27 +142,210 @@ impl ParallelIterator for EnumeratorFileIter {
                     format!("Error in enumerator {}:{line_num}", self.inner.path.display())
                 })?;
                 let provenance = Provenance::from_extended(e.provenance).into();
-                let blob = Blob::from_bytes(e.content.as_bytes().to_owned());
+                let bytes = e.content.as_bytes().to_owned();
+                let blob = match e.blob_id {
+                    None => Blob::from_bytes_with_algorithm(bytes, self.blob_id_algorithm),
+                    Some(blob_id) => {
+                        if cfg!(debug_assertions) {
+                            let computed = BlobId::compute_from_bytes(&bytes);
+                            if computed != blob_id {
+                                bail!(
+                                    "Error in enumerator {}:{line_num}: given blob ID {blob_id} \
+                                     does not match content blob ID {computed}",
+                                    self.inner.path.display()
+                                );
+                            }
+                        }
+                        Blob::new(blob_id, bytes)
+                    }
+                };
                 Ok((provenance, blob))
             })
             .drive_unindexed(consumer)
     }
 }
 
+// --------------------------------------------------------------------------------
+/// A parallel iterator for an `input_enumerator::ArchiveResult`, which yields the archive's
+/// members as blobs.
+struct ArchiveIter {
+    inner: input_enumerator::ArchiveResult,
+    reader: Box<dyn std::io::Read + Send>,
+    max_file_size: Option<u64>,
+    blob_id_algorithm: BlobIdAlgorithm,
+}
+
+impl ParallelBlobIterator for (&EnumeratorConfig, input_enumerator::ArchiveResult) {
+    type Iter = ArchiveIter;
+
+    fn into_blob_iter(self) -> Result<Option<Self::Iter>> {
+        use input_enumerator::ArchiveKind;
+
+        let (cfg, inner) = self;
+        let file = std::fs::File::open(&inner.path)
+            .with_context(|| format!("Failed to open archive {}", inner.path.display()))?;
+        let file = std::io::BufReader::new(file);
+        let reader: Box<dyn std::io::Read + Send> = match inner.kind {
+            ArchiveKind::Tar => Box::new(file),
+            ArchiveKind::TarGz => Box::new(flate2::read::GzDecoder::new(file)),
+            ArchiveKind::TarZst => {
+                Box::new(zstd::Decoder::with_buffer(file).with_context(|| {
+                    format!("Failed to initialize decompression for {}", inner.path.display())
+                })?)
+            }
+        };
+        Ok(Some(ArchiveIter {
+            inner,
+            reader,
+            max_file_size: cfg.max_file_size,
+            blob_id_algorithm: cfg.blob_id_algorithm,
+        }))
+    }
+}
+
+// Archive parallelism approach:
+//
+// - Read and decompress members sequentially in a separate thread, as compressed archives cannot
+//   be read otherwise, and as the `tar` crate's entry iterator cannot be shared between threads
+// - Parallelize everything downstream of that
+impl ParallelIterator for ArchiveIter {
+    type Item = Result<(ProvenanceSet, Blob)>;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: rayon::iter::plumbing::UnindexedConsumer<Self::Item>,
+    {
+        let (send, recv) = crossbeam_channel::bounded(64);
+        std::thread::scope(|s| {
+            s.spawn(move || {
+                let ArchiveIter {
+                    inner,
+                    reader,
+                    max_file_size,
+                    blob_id_algorithm,
+                } = self;
+                read_archive_members(
+                    &inner.path,
+                    reader,
+                    max_file_size,
+                    blob_id_algorithm,
+                    |item| send.send(item).is_ok(),
+                );
+            });
+            recv.into_iter().par_bridge().drive_unindexed(consumer)
+        })
+    }
+}
+
+/// The maximum number of bytes to allocate up front for reading an archive member
+const MAX_ARCHIVE_MEMBER_PREALLOC: u64 = 64 * 1024 * 1024;
+
+/// Read the members of a tar archive, passing each regular file member that does not exceed
+/// `max_file_size` to `f` as a blob, with its ID computed using `blob_id_algorithm`.
+///
+/// Reading stops early if `f` returns `false`.
+fn read_archive_members<F>(
+    archive_path: &Path,
+    reader: Box<dyn std::io::Read + Send>,
+    max_file_size: Option<u64>,
+    blob_id_algorithm: BlobIdAlgorithm,
+    mut f: F,
+) where
+    F: FnMut(Result<(ProvenanceSet, Blob)>) -> bool,
+{
+    use bstr::ByteSlice;
+    use std::io::Read;
+
+    let mut archive = tar::Archive::new(reader);
+    let entries = match archive.entries() {
+        Ok(entries) => entries,
+        Err(e) => {
+            f(Err(anyhow::Error::new(e)
+                .context(format!("Failed to read archive {}", archive_path.display()))));
+            return;
+        }
+    };
+
+    for entry in entries {
+        let mut entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                // The archive cannot be read any further after a malformed member
+                f(Err(anyhow::Error::new(e).context(format!(
+                    "Failed to read member of archive {}",
+                    archive_path.display()
+                ))));
+                return;
+            }
+        };
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let member = entry.path_bytes().to_str_lossy().into_owned();
+        let size = entry.size();
+        if let Some(max_size) = max_file_size {
+            if size > max_size {
+                debug!(
+                    "Skipping {}:{member}: size of {size} bytes exceeds maximum of {max_size} bytes",
+                    archive_path.display()
+                );
+                continue;
+            }
+        }
+
+        // The size comes from the member's header, which may be wrong, so don't trust it for more
+        // than a modest initial allocation
+        let mut content = Vec::with_capacity(size.min(MAX_ARCHIVE_MEMBER_PREALLOC) as usize);
+        let item = match entry.read_to_end(&mut content) {
+            Ok(_) => {
+                let provenance = Provenance::from_extended(serde_json::json!({
+                    "path": format!("{}:{member}", archive_path.display()),
+                    "archive": {
+                        "path": archive_path,
+                        "member": member,
+                    }
+                }));
+                Ok((provenance.into(), Blob::from_bytes_with_algorithm(content, blob_id_algorithm)))
+            }
+            Err(e) => Err(anyhow::Error::new(e).context(format!(
+                "Failed to read {member} from archive {}",
+                archive_path.display()
+            ))),
+        };
+        if !f(item) {
+            return;
+        }
+    }
+}
+
 // --------------------------------------------------------------------------------
 /// A parallel iterator for in `input_enumerator::FileResult`
 struct FileResultIter {
-    inner: input_enumerator::FileResult,
+    /// The path recorded in provenance, which may be relative to the file's input root
+    provenance_path: PathBuf,
     blob: Blob,
 }
 
-impl ParallelBlobIterator for input_enumerator::FileResult {
+impl ParallelBlobIterator for (&EnumeratorConfig, input_enumerator::FileResult) {
     type Iter = FileResultIter;
 
     fn into_blob_iter(self) -> Result<Option<Self::Iter>> {
-        let blob = Blob::from_file(&self.path)
-            .with_context(|| format!("Failed to load blob from {}", self.path.display()))?;
-        Ok(Some(FileResultIter { inner: self, blob }))
+        let (cfg, file) = self;
+        // The enumerated size is the expected size, allowing the blob ID to be computed as the
+        // file is read
+        let blob = std::fs::File::open(&file.path)
+            .map_err(anyhow::Error::from)
+            .and_then(|f| Blob::from_reader(f, file.num_bytes, cfg.blob_id_algorithm))
+            .with_context(|| format!("Failed to load blob from {}", file.path.display()))?;
+        let provenance_path = if cfg.input_root_relative_paths {
+            file.root_relative_path()
+        } else {
+            file.path
+        };
+        Ok(Some(FileResultIter {
+            provenance_path,
+            blob,
+        }))
     }
 }
 
@@ -141,7 +358,62 @@ impl ParallelIterator for FileResultIter {
     {
         use rayon::iter::plumbing::Folder;
 
-        let item = Ok((Provenance::from_file(self.inner.path).into(), self.blob));
+        let item = Ok((Provenance::from_file(self.provenance_path).into(), self.blob));
+        consumer.into_folder().consume(item).complete()
+    }
+}
+
+// --------------------------------------------------------------------------------
+/// A parallel iterator for an `input_enumerator::StdinResult`
+struct StdinResultIter {
+    blob: Blob,
+}
+
+impl ParallelBlobIterator for (&EnumeratorConfig, input_enumerator::StdinResult) {
+    type Iter = StdinResultIter;
+
+    fn into_blob_iter(self) -> Result<Option<Self::Iter>> {
+        use std::io::Read;
+
+        let (cfg, _stdin) = self;
+        let mut bytes = Vec::new();
+        match cfg.max_file_size {
+            Some(max_size) => {
+                // Read one byte past the maximum to tell whether it is exceeded, without reading
+                // all of an arbitrarily large input
+                std::io::stdin()
+                    .lock()
+                    .take(max_size.saturating_add(1))
+                    .read_to_end(&mut bytes)
+                    .context("Failed to read from stdin")?;
+                if bytes.len() as u64 > max_size {
+                    info!("Skipping stdin: size exceeds maximum of {max_size} bytes");
+                    return Ok(None);
+                }
+            }
+            None => {
+                std::io::stdin()
+                    .lock()
+                    .read_to_end(&mut bytes)
+                    .context("Failed to read from stdin")?;
+            }
+        }
+        Ok(Some(StdinResultIter {
+            blob: Blob::from_bytes_with_algorithm(bytes, cfg.blob_id_algorithm),
+        }))
+    }
+}
+
+impl ParallelIterator for StdinResultIter {
+    type Item = Result<(ProvenanceSet, Blob)>;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: rayon::iter::plumbing::UnindexedConsumer<Self::Item>,
+    {
+        use rayon::iter::plumbing::Folder;
+
+        let item = Ok((Provenance::from_stdin().into(), self.blob));
         consumer.into_folder().consume(item).complete()
     }
 }
@@ -160,6 +432,13 @@ impl ParallelBlobIterator for input_enumerator::GitRepoResult {
     }
 }
 
+// Git repo parallelism approach:
+//
+// - Group blobs by the packfile that contains them, ordered by their offset within it, and split
+//   each group into chunks that are read sequentially on their own rayon task.
+//   gix does allow a packfile to be read by multiple threads, but doesn't scale linearly;
+//   reading each chunk in pack order keeps decompression local to a region of one packfile.
+// - Read loose objects (and any objects not found in a pack index) with fine-grained parallelism
 impl ParallelIterator for GitRepoResultIter {
     type Item = Result<(ProvenanceSet, Blob)>;
 
@@ -167,71 +446,310 @@ impl ParallelIterator for GitRepoResultIter {
     where
         C: rayon::iter::plumbing::UnindexedConsumer<Self::Item>,
     {
+        let (packed_chunks, loose) =
+            group_blobs_by_packfile(&self.inner.repository, self.inner.blobs);
         let repo = self.inner.repository.into_sync();
         let repo_path = Arc::new(self.inner.path.clone());
-        self.inner
-            .blobs
+
+        let packed = packed_chunks.into_par_iter().flat_map_iter({
+            let repo = &repo;
+            let repo_path = &repo_path;
+            move |chunk| {
+                let repo = repo.to_thread_local();
+                chunk
+                    .into_iter()
+                    .map(move |md| read_git_blob(&repo, repo_path, md))
+            }
+        });
+
+        let loose = loose
             .into_par_iter()
-            // XXX try to be more conservative with parallelism here; use
-            // explicitly larger granularity.
-            //
-            // Git repos are typically represented with packfiles on disk, and
-            // oftentimes with just a single packfile.
-            //
-            // gix _does_ allow packfiles to be read by multiple threads with
-            // decent parallel speedup up to a few threads, but it doesn't scale
-            // linearly.
-            //
-            // The optimal efficiency for reading all blobs from a Git repo would
-            // probably involve one thread per packfile. Doing that would require
-            // restructuring this code.
             .with_min_len(1024)
-            .map_init(
-                || repo.to_thread_local(),
-                |repo, md| -> Result<(ProvenanceSet, Blob)> {
-                    let blob_id = md.blob_oid;
-
-                    let blob = || -> Result<Blob> {
-                        let mut blob = repo.find_object(blob_id)?.try_into_blob()?;
-                        let data = std::mem::take(&mut blob.data); // avoid a copy
-                        Ok(Blob::new(BlobId::from(&blob_id), data))
-                    }()
-                    .with_context(|| {
-                        format!(
-                            "Failed to read blob {blob_id} from Git repository at {}",
-                            repo_path.display(),
-                        )
-                    })?;
+            .map_init(|| repo.to_thread_local(), |repo, md| read_git_blob(repo, &repo_path, md));
 
-                    let provenance =
-                        ProvenanceSet::try_from_iter(md.first_seen.into_iter().map(|e| {
-                            Provenance::from_git_repo_with_first_commit(
-                                repo_path.clone(),
-                                e.commit_metadata,
-                                e.path,
-                            )
-                        }))
-                        .unwrap_or_else(|| Provenance::from_git_repo(repo_path.clone()).into());
+        packed.chain(loose).drive_unindexed(consumer)
+    }
+}
 
-                    Ok((provenance, blob))
-                },
-            )
-            .drive_unindexed(consumer)
+/// The maximum number of packed blobs to read sequentially in a single task
+const PACKED_BLOB_CHUNK_SIZE: usize = 1024;
+
+/// Group the given blobs according to the packfile that contains them.
+///
+/// This returns the packed blobs as chunks of at most `PACKED_BLOB_CHUNK_SIZE` blobs from a single
+/// packfile, ordered by pack offset, and separately, the blobs that were not found in any pack
+/// index.
+fn group_blobs_by_packfile(
+    repo: &gix::Repository,
+    blobs: Vec<input_enumerator::BlobMetadata>,
+) -> (Vec<Vec<input_enumerator::BlobMetadata>>, Vec<input_enumerator::BlobMetadata>) {
+    use gix::odb::pack;
+
+    let pack_dir = repo.common_dir().join("objects").join("pack");
+    let mut idx_paths: Vec<PathBuf> = match std::fs::read_dir(&pack_dir) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|e| e == "idx"))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    idx_paths.sort();
+
+    let indexes: Vec<pack::index::File> = idx_paths
+        .iter()
+        .filter_map(|p| match pack::index::File::at(p, repo.object_hash()) {
+            Ok(index) => Some(index),
+            Err(e) => {
+                debug!("Failed to open pack index {}: {e}", p.display());
+                None
+            }
+        })
+        .collect();
+
+    if indexes.is_empty() {
+        return (Vec::new(), blobs);
+    }
+
+    let mut packed = Vec::new();
+    let mut loose = Vec::new();
+    for md in blobs {
+        let location = indexes.iter().enumerate().find_map(|(pack_num, index)| {
+            index
+                .lookup(md.blob_oid)
+                .map(|entry| (pack_num, index.pack_offset_at_index(entry)))
+        });
+        match location {
+            Some(location) => packed.push((location, md)),
+            None => loose.push(md),
+        }
     }
+    packed.sort_unstable_by_key(|(location, _md)| *location);
+
+    let mut chunks: Vec<Vec<input_enumerator::BlobMetadata>> = Vec::new();
+    let mut prev_pack_num = None;
+    for ((pack_num, _offset), md) in packed {
+        match chunks.last_mut() {
+            Some(chunk)
+                if prev_pack_num == Some(pack_num) && chunk.len() < PACKED_BLOB_CHUNK_SIZE =>
+            {
+                chunk.push(md)
+            }
+            _ => chunks.push(vec![md]),
+        }
+        prev_pack_num = Some(pack_num);
+    }
+
+    debug!(
+        "Grouped {} packed blobs from {} into {} chunks; {} blobs are not packed",
+        chunks.iter().map(Vec::len).sum::<usize>(),
+        Counted::regular(indexes.len(), "packfile"),
+        chunks.len(),
+        loose.len(),
+    );
+
+    (chunks, loose)
+}
+
+/// Read a blob from the given Git repository, along with its provenance.
+fn read_git_blob(
+    repo: &gix::Repository,
+    repo_path: &Arc<PathBuf>,
+    md: input_enumerator::BlobMetadata,
+) -> Result<(ProvenanceSet, Blob)> {
+    let blob_id = md.blob_oid;
+
+    let blob = || -> Result<Blob> {
+        let mut blob = repo.find_object(blob_id)?.try_into_blob()?;
+        let data = std::mem::take(&mut blob.data); // avoid a copy
+        Ok(Blob::new(BlobId::from(&blob_id), data))
+    }()
+    .with_context(|| {
+        format!("Failed to read blob {blob_id} from Git repository at {}", repo_path.display(),)
+    })?;
+
+    let provenance = ProvenanceSet::try_from_iter(md.first_seen.into_iter().map(|e| {
+        Provenance::from_git_repo_with_first_commit(repo_path.clone(), e.commit_metadata, e.path)
+    }))
+    .unwrap_or_else(|| {
+        match md.path {
+            Some(p) => Provenance::from_git_repo_with_path(repo_path.clone(), p),
+            None => Provenance::from_git_repo(repo_path.clone()),
+        }
+        .into()
+    });
+
+    Ok((provenance, blob))
 }
 
 // -------------------------------------------------------------------------------------------------
 struct EnumeratorConfig {
     enumerate_git_history: bool,
-    collect_git_metadata: bool,
+    git_blob_provenance: args::GitBlobProvenanceMode,
     gitignore: input_enumerator::Gitignore,
+
+    /// A revision to scan Git history after, if any
+    git_since: Option<String>,
+
+    /// A revision whose tree to scan instead of Git history, if any
+    git_ref: Option<String>,
+
+    /// The number of Git repositories that were not scanned because a given revision could not
+    /// be resolved in them
+    num_unresolved_revisions: AtomicU64,
+
+    /// The maximum size of archive members and of input from stdin to scan, if any
+    max_file_size: Option<u64>,
+
+    /// Whether to record file paths relative to their input roots
+    input_root_relative_paths: bool,
+
+    /// The algorithm to compute the IDs of blobs that do not come from Git repositories
+    blob_id_algorithm: BlobIdAlgorithm,
+
+    /// The Git bundle files that clones were made from, keyed by clone path
+    bundle_paths: HashMap<PathBuf, PathBuf>,
+
+    /// The total size of the content to scan, accumulated as inputs are enumerated
+    scan_total: ScanTotal,
+}
+
+/// The total size of the content to scan, which is accumulated into the length of the scanning
+/// progress bar as inputs are enumerated and read.
+///
+/// The progress bar is switched to show a percentage only once the total is final, that is, once
+/// input enumeration has finished and the size of every enumerated input has been added.
+#[derive(Clone)]
+struct ScanTotal {
+    progress: Progress,
+
+    /// The number of enumerated inputs whose sizes have not been completely added yet, plus one
+    /// until input enumeration finishes
+    num_pending: Arc<AtomicU64>,
+}
+
+impl ScanTotal {
+    fn new(progress: Progress) -> Self {
+        Self {
+            progress,
+            num_pending: Arc::new(AtomicU64::new(1)),
+        }
+    }
+
+    /// Add to the total size of the content to scan.
+    fn inc(&self, num_bytes: u64) {
+        self.progress.inc_length(num_bytes);
+    }
+
+    /// Note that an input has been enumerated, whose size has not yet been added.
+    fn add_input(&self) {
+        self.num_pending.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Note that the size of an enumerated input has been completely added, or that input
+    /// enumeration has finished.
+    fn finish_input(&self) {
+        if self.num_pending.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.progress.switch_to_bytes_bar();
+        }
+    }
+}
+
+impl EnumeratorConfig {
+    /// Record the original bundle file path in a result for a clone of a Git bundle, so that it
+    /// appears in provenance instead of the clone path.
+    fn with_bundle_path(
+        &self,
+        mut result: input_enumerator::GitRepoResult,
+    ) -> input_enumerator::GitRepoResult {
+        if let Some(bundle_path) = self.bundle_paths.get(&result.path) {
+            result.path = bundle_path.clone();
+        }
+        result
+    }
+
+    /// Resolve a revision given to the named option to a commit in a Git repository, counting the
+    /// repositories in which it cannot be resolved.
+    fn resolve_git_commit(
+        &self,
+        option: &str,
+        path: &Path,
+        repository: &gix::Repository,
+        rev: &str,
+    ) -> Result<gix::ObjectId> {
+        let commit = input_enumerator::resolve_git_commit(repository, rev);
+        if commit.is_err() {
+            self.num_unresolved_revisions
+                .fetch_add(1, Ordering::Relaxed);
+        }
+        commit.with_context(|| {
+            format!("Failed to resolve {option} revision in Git repository at {}", path.display())
+        })
+    }
+
+    /// Enumerate the blobs in the tree of the `--git-ref` commit of a Git repository, or of its
+    /// `HEAD` commit if no `--git-ref` was given, adding them to the scanning progress.
+    fn enumerate_git_tree(
+        &self,
+        path: &Path,
+        repository: gix::Repository,
+    ) -> Result<input_enumerator::GitRepoResult> {
+        let commit = match &self.git_ref {
+            Some(rev) => Some(self.resolve_git_commit("--git-ref", path, &repository, rev)?),
+            None => None,
+        };
+        let result =
+            input_enumerator::GitRepoTreeEnumerator::new(path, repository, &self.gitignore, commit)
+                .run()?;
+        self.add_git_repo_progress(&result);
+        Ok(result)
+    }
+
+    /// Add the total size of the blobs in an enumerated Git repository to the scanning progress.
+    ///
+    /// This requires reading the header of each blob, which is done in parallel, and only if
+    /// progress is displayed.
+    fn add_git_repo_progress(&self, result: &input_enumerator::GitRepoResult) {
+        use gix::prelude::HeaderExt;
+
+        if !self.scan_total.progress.is_enabled() {
+            return;
+        }
+        let repo = result.repository.clone().into_sync();
+        let num_bytes: u64 = result
+            .blobs
+            .par_iter()
+            .with_min_len(1024)
+            .map_init(
+                || repo.to_thread_local(),
+                |repo, md| repo.objects.header(md.blob_oid).map_or(0, |hdr| hdr.size()),
+            )
+            .sum();
+        self.scan_total.inc(num_bytes);
+    }
 }
 
 // --------------------------------------------------------------------------------
 enum FoundInputIter {
     File(FileResultIter),
     GitRepo(GitRepoResultIter),
-    EnumeratorFile(EnumeratorFileIter),
+
+    /// Blobs from enumerator files, archives, and stdin are added to the scanning progress as
+    /// they are produced, as their sizes are not known in advance
+    EnumeratorFile(EnumeratorFileIter, ScanTotal),
+    Archive(ArchiveIter, ScanTotal),
+    Stdin(StdinResultIter, ScanTotal),
+}
+
+impl FoundInputIter {
+    /// Is the total size of this input only known once it has been read through?
+    fn is_streamed(&self) -> bool {
+        match self {
+            FoundInputIter::File(_) | FoundInputIter::GitRepo(_) => false,
+            FoundInputIter::EnumeratorFile(..)
+            | FoundInputIter::Archive(..)
+            | FoundInputIter::Stdin(..) => true,
+        }
+    }
 }
 
 impl ParallelBlobIterator for (&EnumeratorConfig, FoundInput) {
@@ -240,7 +758,10 @@ impl ParallelBlobIterator for (&EnumeratorConfig, FoundInput) {
     fn into_blob_iter(self) -> Result<Option<Self::Iter>> {
         let (cfg, input) = self;
         match input {
-            FoundInput::File(i) => Ok(i.into_blob_iter()?.map(FoundInputIter::File)),
+            FoundInput::File(i) => {
+                cfg.scan_total.inc(i.num_bytes);
+                Ok((cfg, i).into_blob_iter()?.map(FoundInputIter::File))
+            }
 
             FoundInput::Directory(i) => {
                 let path = &i.path;
@@ -250,15 +771,49 @@ impl ParallelBlobIterator for (&EnumeratorConfig, FoundInput) {
                             let t1 = Instant::now();
                             debug!("Found Git repository at {}", path.display());
 
-                            let result = if cfg.collect_git_metadata {
-                                input_enumerator::GitRepoWithMetadataEnumerator::new(
+                            if cfg.git_ref.is_some() {
+                                let result = cfg.enumerate_git_tree(path, repository)?;
+                                return cfg
+                                    .with_bundle_path(result)
+                                    .into_blob_iter()
+                                    .map(|i| i.map(FoundInputIter::GitRepo));
+                            }
+
+                            let since = match &cfg.git_since {
+                                Some(rev) => Some(cfg.resolve_git_commit(
+                                    "--git-since",
                                     path,
-                                    repository,
-                                    &cfg.gitignore,
-                                )
-                                .run()?
-                            } else {
-                                input_enumerator::GitRepoEnumerator::new(path, repository).run()?
+                                    &repository,
+                                    rev,
+                                )?),
+                                None => None,
+                            };
+
+                            let result = match cfg.git_blob_provenance {
+                                args::GitBlobProvenanceMode::FirstSeen => {
+                                    input_enumerator::GitRepoWithMetadataEnumerator::new(
+                                        path,
+                                        repository,
+                                        &cfg.gitignore,
+                                        since,
+                                    )
+                                    .run()?
+                                }
+                                args::GitBlobProvenanceMode::RepoAndPath => {
+                                    input_enumerator::GitRepoWithPathsEnumerator::new(
+                                        path,
+                                        repository,
+                                        &cfg.gitignore,
+                                        since,
+                                    )
+                                    .run()?
+                                }
+                                args::GitBlobProvenanceMode::Minimal => {
+                                    input_enumerator::GitRepoEnumerator::new(
+                                        path, repository, since,
+                                    )
+                                    .run()?
+                                }
                             };
 
                             debug!(
@@ -267,20 +822,43 @@ impl ParallelBlobIterator for (&EnumeratorConfig, FoundInput) {
                                 t1.elapsed().as_secs_f64()
                             );
 
-                            result
+                            cfg.add_git_repo_progress(&result);
+                            cfg.with_bundle_path(result)
                                 .into_blob_iter()
                                 .map(|i| i.map(FoundInputIter::GitRepo))
                         }
                         None => Ok(None),
                     }
                 } else {
-                    Ok(None)
+                    // A bare repository has no working tree that would otherwise get scanned, so
+                    // scan the blobs from its `HEAD` tree instead of scanning nothing.
+                    match input_enumerator::open_git_repo(path)? {
+                        Some(repository) if repository.is_bare() => {
+                            debug!(
+                                "Found bare Git repository at {}; enumerating a single tree only",
+                                path.display()
+                            );
+                            let result = cfg.enumerate_git_tree(path, repository)?;
+                            cfg.with_bundle_path(result)
+                                .into_blob_iter()
+                                .map(|i| i.map(FoundInputIter::GitRepo))
+                        }
+                        _ => Ok(None),
+                    }
                 }
             }
 
-            FoundInput::EnumeratorFile(i) => {
-                Ok(i.into_blob_iter()?.map(FoundInputIter::EnumeratorFile))
-            }
+            FoundInput::EnumeratorFile(i) => Ok((cfg, i)
+                .into_blob_iter()?
+                .map(|i| FoundInputIter::EnumeratorFile(i, cfg.scan_total.clone()))),
+
+            FoundInput::Archive(i) => Ok((cfg, i)
+                .into_blob_iter()?
+                .map(|i| FoundInputIter::Archive(i, cfg.scan_total.clone()))),
+
+            FoundInput::Stdin(i) => Ok((cfg, i)
+                .into_blob_iter()?
+                .map(|i| FoundInputIter::Stdin(i, cfg.scan_total.clone()))),
         }
     }
 }
@@ -295,11 +873,31 @@ impl ParallelIterator for FoundInputIter {
         match self {
             FoundInputIter::File(i) => i.drive_unindexed(consumer),
             FoundInputIter::GitRepo(i) => i.drive_unindexed(consumer),
-            FoundInputIter::EnumeratorFile(i) => i.drive_unindexed(consumer),
+            FoundInputIter::EnumeratorFile(i, total) => drive_streamed(i, total, consumer),
+            FoundInputIter::Archive(i, total) => drive_streamed(i, total, consumer),
+            FoundInputIter::Stdin(i, total) => drive_streamed(i, total, consumer),
         }
     }
 }
 
+/// Drive an input whose total size is not known in advance, adding the size of each blob to the
+/// scanning progress as it is produced.
+fn drive_streamed<I, C>(iter: I, total: ScanTotal, consumer: C) -> C::Result
+where
+    I: ParallelIterator<Item = Result<(ProvenanceSet, Blob)>>,
+    C: rayon::iter::plumbing::UnindexedConsumer<I::Item>,
+{
+    let result = iter
+        .inspect(|entry| {
+            if let Ok((_provenance, blob)) = entry {
+                total.inc(blob.len().try_into().unwrap());
+            }
+        })
+        .drive_unindexed(consumer);
+    total.finish_input();
+    result
+}
+
 // --------------------------------------------------------------------------------
 
 /// This command scans multiple filesystem inputs for secrets.
@@ -315,11 +913,30 @@ pub fn run(global_args: &args::GlobalArgs, args: &args::ScanArgs) -> Result<()>
         args.input_specifier_args.all_github_organizations,
     );
 
+    #[cfg(feature = "parquet")]
+    if args.copy_blobs_layout != args::CopyBlobsLayout::Hash
+        && args.copy_blobs_format != args::CopyBlobsFormat::Files
+    {
+        bail!(
+            "`--copy-blobs-layout={}` requires `--copy-blobs-format=files`",
+            args.copy_blobs_layout
+        );
+    }
+
+    if let (Some(min), Some(max)) = (args.copy_blobs_min_size.0, args.copy_blobs_max_size.0) {
+        if min > max {
+            bail!("`--copy-blobs-min-size` must not be greater than `--copy-blobs-max-size`");
+        }
+    }
+
     debug!("Args:\n{global_args:#?}\n{args:#?}");
 
     let progress_enabled = global_args.use_progress();
     let mut init_progress = Progress::new_spinner("Initializing...", progress_enabled);
 
+    // The progress bars for each of the subsequent phases of scanning, displayed together
+    let multi_progress = MultiProgress::new(progress_enabled);
+
     // ---------------------------------------------------------------------------------------------
     // Configure the Rayon global thread pool
     // ---------------------------------------------------------------------------------------------
@@ -331,21 +948,27 @@ pub fn run(global_args: &args::GlobalArgs, args: &args::ScanArgs) -> Result<()>
         .context("Failed to initialize Rayon")?;
 
     // ---------------------------------------------------------------------------------------------
-    // Open datastore
+    // Open datastores
     // ---------------------------------------------------------------------------------------------
     init_progress.set_message("Initializing (datastore)...");
-    let mut datastore =
-        Datastore::create_or_open(&args.datastore, global_args.advanced.sqlite_cache_size)
-            .with_context(|| {
-                format!("Failed to open datastore at {}", &args.datastore.display())
-            })?;
+    let datastores = args
+        .datastore
+        .iter()
+        .map(|path| {
+            Datastore::create_or_open(path, global_args.advanced.sqlite_cache_size)
+                .with_context(|| format!("Failed to open datastore at {}", path.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
 
     // ---------------------------------------------------------------------------------------------
-    // Load rules and record them to the datastore
+    // Load rules and record them to the datastores, along with the start of this scan run
     // ---------------------------------------------------------------------------------------------
     init_progress.set_message("Initializing (rules)...");
-    let rules_db = {
+    let (rules_db, trace_rule_index, targets) = {
+        // Rules fetched from URLs are cached in the first datastore only
         let loaded = RuleLoader::from_rule_specifiers(&args.rules)
+            .remote_rules_cache_dir(datastores[0].scratch_dir())
+            .ignore_certs(global_args.ignore_certs)
             .load()
             .context("Failed to load rules")?;
         let resolved = loaded
@@ -354,42 +977,139 @@ pub fn run(global_args: &args::GlobalArgs, args: &args::ScanArgs) -> Result<()>
         let rules_db = RulesDatabase::from_rules(resolved.into_iter().cloned().collect())
             .context("Failed to compile rules")?;
 
-        || -> Result<()> {
+        let trace_rule_index = match &args.trace_rule {
+            None => None,
+            Some(rule_id) => Some(
+                rules_db
+                    .rules()
+                    .iter()
+                    .position(|r| r.id() == rule_id)
+                    .with_context(|| {
+                        format!(
+                            "Rule `{rule_id}` given to `--trace-rule` is not among the enabled rules"
+                        )
+                    })?,
+            ),
+        };
+
+        let start_scan_run = |datastore: &mut Datastore| -> Result<_> {
             let tx = datastore.begin()?;
-            tx.record_rules(rules_db.rules())?;
-            tx.commit()
-        }()
-        .context("Failed to record rules to the datastore")?;
+            tx.record_blob_id_algorithm(args.blob_id_algorithm.into())?;
+            let mut labels = Vec::new();
+            if args.metadata_args.record_host_info {
+                labels.extend(host_info_labels());
+            }
+            labels.extend(args.metadata_args.run_label.iter().cloned());
+            let fingerprint = rules_fingerprint(rules_db.rules());
+            let version = version_string();
+            let argv: Vec<String> = std::env::args().collect();
+            let hostname = sysinfo::System::host_name();
+            let scan_run_id = tx.record_scan_run(&ScanRunMetadata {
+                labels: &labels,
+                rules_fingerprint: Some(&fingerprint),
+                version: Some(&version),
+                argv: &argv,
+                hostname: hostname.as_deref(),
+                rules: rules_db.rules(),
+            })?;
+            tx.commit()?;
+            Ok(scan_run_id)
+        };
+        let targets = datastores
+            .into_iter()
+            .map(|mut datastore| {
+                let scan_run_id = start_scan_run(&mut datastore).with_context(|| {
+                    format!(
+                        "Failed to start scan run in the datastore at {}",
+                        datastore.root_dir().display()
+                    )
+                })?;
+                Ok(ScanTarget::new(datastore, scan_run_id))
+            })
+            .collect::<Result<Vec<_>>>()?;
 
-        rules_db
+        (rules_db, trace_rule_index, targets)
     };
+
+    // Git repositories are cloned into, and blobs are copied into, the first datastore only
+    let datastore = &targets[0].datastore;
     drop(init_progress);
 
+    let excluded_blobs = read_blob_id_files(&args.content_filtering_args.exclude_blob_ids)?;
+
+    let scorer: Option<Arc<dyn Scorer>> = match &args.scoring_model {
+        Some(path) => Some(Arc::new(LinearModel::from_json_file(path)?)),
+        None => None,
+    };
+
     // ---------------------------------------------------------------------------------------------
     // Gather list of all git repos to clone or update
     // ---------------------------------------------------------------------------------------------
     let repo_urls = {
         let mut repo_urls = args.input_specifier_args.git_url.clone();
-        repo_urls.extend(enumerate_github_repos(global_args, args)?);
-        repo_urls.sort();
-        repo_urls.dedup();
+        repo_urls.extend(read_git_url_files(&args.input_specifier_args.git_url_file)?);
+        repo_urls.extend(enumerate_github_repos(global_args, args, &multi_progress)?);
+        // Collapse equivalent URLs, such as ones that differ only in a `.git` suffix, keeping the
+        // first one given
+        repo_urls.sort_by_cached_key(GitUrl::normalized);
+        repo_urls.dedup_by_key(|url| url.normalized());
         repo_urls
     };
 
     // ---------------------------------------------------------------------------------------------
     // Clone or update all mentioned Git URLs; gather set of input roots for scanning
     // ---------------------------------------------------------------------------------------------
-    let input_roots = {
-        let mut input_roots = args.input_specifier_args.path_inputs.clone();
+    let (input_roots, bundle_paths, scan_stdin) = {
+        // An input of `-` means to read a single blob from stdin
+        let (stdin_inputs, path_inputs): (Vec<PathBuf>, Vec<PathBuf>) = args
+            .input_specifier_args
+            .path_inputs
+            .iter()
+            .cloned()
+            .partition(|p| p.as_os_str() == "-");
+        let scan_stdin = !stdin_inputs.is_empty();
+
+        let (bundles, mut input_roots): (Vec<PathBuf>, Vec<PathBuf>) =
+            path_inputs.into_iter().partition(|p| is_git_bundle(p));
+        let mut bundle_paths = HashMap::new();
+        if !bundles.is_empty() {
+            for (clone_path, bundle_path) in
+                clone_git_bundles(global_args, args, datastore, bundles, &multi_progress)?
+            {
+                input_roots.push(clone_path.clone());
+                bundle_paths.insert(clone_path, bundle_path);
+            }
+        }
         if !repo_urls.is_empty() {
-            input_roots.extend(clone_git_repo_urls(global_args, args, &datastore, repo_urls)?);
+            input_roots.extend(clone_git_repo_urls(
+                global_args,
+                args,
+                datastore,
+                repo_urls,
+                &multi_progress,
+            )?);
         }
         input_roots.sort();
         input_roots.dedup();
-        input_roots
+        (input_roots, bundle_paths, scan_stdin)
     };
 
-    if input_roots.is_empty() && args.input_specifier_args.enumerators.is_empty() {
+    // ---------------------------------------------------------------------------------------------
+    // Gather Kubernetes secrets to scan; these are scanned directly from memory
+    // ---------------------------------------------------------------------------------------------
+    let k8s_secrets = enumerate_k8s_secrets(global_args, args, &multi_progress)?;
+
+    // ---------------------------------------------------------------------------------------------
+    // List S3 objects to scan; these are fetched as they are scanned
+    // ---------------------------------------------------------------------------------------------
+    let s3_objects = enumerate_s3_objects(global_args, args, &multi_progress)?;
+
+    if input_roots.is_empty()
+        && !scan_stdin
+        && args.input_specifier_args.enumerators.is_empty()
+        && k8s_secrets.is_empty()
+        && s3_objects.is_empty()
+    {
         bail!("No inputs to scan");
     }
 
@@ -398,10 +1118,39 @@ pub fn run(global_args: &args::GlobalArgs, args: &args::ScanArgs) -> Result<()>
 
     // ---------------------------------------------------------------------------------------------
     // Kick off input enumeration in a separate thread, writing results to a channel
+    //
+    // The total size of the content to scan is accumulated into the scanning progress as inputs
+    // are enumerated. Once enumeration finishes and the sizes of all enumerated inputs are known,
+    // the scanning progress is switched to show a percentage.
     // ---------------------------------------------------------------------------------------------
+    let mut enum_progress = multi_progress
+        .add(Progress::new_countup_spinner("Enumerating inputs...", progress_enabled));
+    let mut progress =
+        multi_progress.add(Progress::new_bytes_spinner("Scanning content", progress_enabled));
+    let scan_total = ScanTotal::new(progress.clone());
+    scan_total.inc(k8s_secrets.iter().map(|(_, blob)| blob.len() as u64).sum());
+    scan_total.inc(s3_objects.iter().map(|o| o.size).sum());
+
+    // Stop scanning on Ctrl-C, so that the matches found so far are still committed to the
+    // datastore. A second Ctrl-C exits immediately.
+    //
+    // The handler only sets a flag; the scanners check it, and the interruption is reported once
+    // they have stopped.
+    let interrupted = Arc::new(AtomicBool::new(false));
+    {
+        let interrupted = interrupted.clone();
+        ctrlc::set_handler(move || {
+            if interrupted.swap(true, Ordering::SeqCst) {
+                std::process::exit(130);
+            }
+        })
+        .context("Failed to set Ctrl-C handler")?;
+    }
+    let interrupted = &*interrupted;
+
     let scan_start = Instant::now();
     let (enum_thread, input_recv, gitignore) = {
-        let (fs_enumerator, gitignore) = make_fs_enumerator(args, &datastore, input_roots)
+        let (fs_enumerator, gitignore) = make_fs_enumerator(args, datastore, input_roots)
             .context("Failed to initialize filesystem enumerator")?;
 
         // Create a pair of channels for the input enumeration
@@ -413,6 +1162,10 @@ pub fn run(global_args: &args::GlobalArgs, args: &args::ScanArgs) -> Result<()>
         let input_enumerator_thread = std::thread::Builder::new()
             .name("input_enumerator".to_string())
             .spawn(move || -> Result<_> {
+                if scan_stdin {
+                    input_send.send(FoundInput::Stdin(input_enumerator::StdinResult))?;
+                }
+
                 // Inject input enumerator files; to be enumerated downstream
                 for path in enumerators {
                     let ef = input_enumerator::EnumeratorFileResult { path };
@@ -434,14 +1187,20 @@ pub fn run(global_args: &args::GlobalArgs, args: &args::ScanArgs) -> Result<()>
     // ---------------------------------------------------------------------------------------------
     // Kick off datastore persistence in a separate thread, providing a channel for scanners to
     // write into. (SQLite works best with a single writer)
+    //
+    // Each batch of results is recorded into every datastore.
     // ---------------------------------------------------------------------------------------------
     let (datastore_thread, send_ds) = {
         let channel_size = std::cmp::max(args.num_jobs, 64) * DATASTORE_BATCH_SIZE;
         let (send_ds, recv_ds) = crossbeam_channel::bounded::<DatastoreMessage>(channel_size);
+        let max_provenance_recorded = args
+            .metadata_args
+            .max_provenance_recorded
+            .map(|n| n as usize);
 
         let datastore_thread = std::thread::Builder::new()
             .name("datastore".to_string())
-            .spawn(move || datastore_writer(datastore, recv_ds))?;
+            .spawn(move || datastore_writer(targets, recv_ds, max_provenance_recorded))?;
 
         (datastore_thread, send_ds)
     };
@@ -452,25 +1211,34 @@ pub fn run(global_args: &args::GlobalArgs, args: &args::ScanArgs) -> Result<()>
     // Don't check the overall result until after checking the other threads,
     // in order to give more comprehensible error reporting when something goes wrong.
     // ---------------------------------------------------------------------------------------------
-    let mut progress = Progress::new_bytes_spinner("Scanning content", progress_enabled);
-
     let enum_cfg = EnumeratorConfig {
         enumerate_git_history: match args.input_specifier_args.git_history {
             args::GitHistoryMode::Full => true,
             args::GitHistoryMode::None => false,
         },
-        collect_git_metadata: match args.metadata_args.git_blob_provenance {
-            args::GitBlobProvenanceMode::FirstSeen => true,
-            args::GitBlobProvenanceMode::Minimal => false,
-        },
+        git_blob_provenance: args.metadata_args.git_blob_provenance,
         gitignore,
+        git_since: args.input_specifier_args.git_since.clone(),
+        git_ref: args.input_specifier_args.git_ref.clone(),
+        num_unresolved_revisions: AtomicU64::new(0),
+        max_file_size: args.content_filtering_args.max_file_size_bytes(),
+        input_root_relative_paths: args.metadata_args.input_root_relative_paths,
+        blob_id_algorithm: args.blob_id_algorithm.into(),
+        bundle_paths,
+        scan_total: scan_total.clone(),
     };
 
     let t1 = Instant::now();
     let num_blob_processors = Mutex::new(0u64); // how many blob processors have been initialized?
     let matcher_stats = Mutex::new(MatcherStats::default());
     let seen_blobs = BlobIdMap::new();
-    let matcher = Matcher::new(&rules_db, &seen_blobs, Some(&matcher_stats))?;
+    let mut matcher = Matcher::new(&rules_db, &seen_blobs, Some(&matcher_stats))?;
+    matcher.set_trace_rule(trace_rule_index);
+    matcher.set_dedupe_blobs(!args.no_dedupe_blobs);
+    matcher.set_skip_minified(args.content_filtering_args.skip_minified);
+    if !excluded_blobs.is_empty() {
+        matcher.set_excluded_blobs(Some(&excluded_blobs));
+    }
 
     let blob_copier = match args.copy_blobs {
         args::CopyBlobsMode::All | args::CopyBlobsMode::Matching => match args.copy_blobs_format {
@@ -478,45 +1246,90 @@ pub fn run(global_args: &args::GlobalArgs, args: &args::ScanArgs) -> Result<()>
             args::CopyBlobsFormat::Parquet => {
                 BlobCopier::Parquet(ParquetBlobCopier::new(blobs_dir, args.num_jobs)?)
             }
-            args::CopyBlobsFormat::Files => BlobCopier::Files(FilesBlobCopier::new(blobs_dir)),
+            args::CopyBlobsFormat::Files => {
+                BlobCopier::Files(FilesBlobCopier::new(blobs_dir, args.copy_blobs_layout)?)
+            }
         },
         args::CopyBlobsMode::None => BlobCopier::Noop,
     };
 
     let blob_processor_init_time = Mutex::new(t1.elapsed());
 
+    // Check the media type overrides once up front, so that invalid globs are reported as errors
+    // rather than panics in the blob processors
+    let make_guesser = || -> Result<Guesser> {
+        Ok(Guesser::new()?.with_overrides(args.metadata_args.mime_override.clone())?)
+    };
+    make_guesser().context("Failed to initialize filetype guesser")?;
+
     let make_blob_processor = || -> BlobProcessor {
         let t1 = Instant::now();
         let matcher = matcher.clone();
         *num_blob_processors.lock().unwrap() += 1;
-        let guesser = Guesser::new().expect("should be able to create filetype guessser");
+        let guesser = make_guesser().expect("should be able to create filetype guessser");
         let proc = BlobProcessor {
             matcher,
             guesser,
-            snippet_length: args.snippet_length,
+            snippet_lengths: args.snippet_lengths(),
+            snippet_mode: args.snippet_mode,
+            max_matches_per_blob: args.max_matches_per_blob.map(|n| n as usize),
             blob_metadata_recording_mode: args.metadata_args.blob_metadata,
             blob_copier: blob_copier.clone(),
             copy_blobs_mode: args.copy_blobs,
+            copy_blobs_size_range: (args.copy_blobs_min_size.0, args.copy_blobs_max_size.0),
+            scorer: scorer.clone(),
         };
         *blob_processor_init_time.lock().unwrap() += t1.elapsed();
 
         proc
     };
 
-    let scan_res: Result<()> = input_recv
-        .into_iter()
+    let mut num_inputs: u64 = 0;
+    let mut enumeration_finished = false;
+    let inputs = std::iter::from_fn(|| match input_recv.recv() {
+        Ok(input) => {
+            num_inputs += 1;
+            enum_progress.inc(1);
+            scan_total.add_input();
+            Some(input)
+        }
+        // The enumerator thread has finished, and all its inputs have been received
+        Err(_) => {
+            if !enumeration_finished {
+                enumeration_finished = true;
+                scan_total.finish_input();
+            }
+            None
+        }
+    });
+    let scan_res: Result<()> = inputs
         .par_bridge()
-        .filter_map(|input: FoundInput| match (&enum_cfg, input).into_blob_iter() {
-            Err(e) => {
-                error!("Error enumerating input: {e:#}");
-                None
+        .filter_map(|input: FoundInput| {
+            let blob_iter = (&enum_cfg, input).into_blob_iter();
+            // The size of a streamed input is completely added only once it has been read through
+            if !matches!(&blob_iter, Ok(Some(i)) if i.is_streamed()) {
+                scan_total.finish_input();
+            }
+            match blob_iter {
+                Err(e) => {
+                    error!("Error enumerating input: {e:#}");
+                    None
+                }
+                Ok(blob_iter) => blob_iter,
             }
-            Ok(blob_iter) => blob_iter,
         })
         .flatten()
+        .chain(k8s_secrets.into_par_iter().map(Ok))
+        .chain(s3_objects.into_par_iter().map(|o| (o.fetch)()))
         .try_for_each_init(
             || (make_blob_processor(), progress.clone()),
             move |(processor, progress), entry| {
+                // Stop producing new work; once all scanners have stopped, `send_ds` is dropped,
+                // and the datastore thread commits what it has received and exits.
+                if interrupted.load(Ordering::Relaxed) {
+                    bail!(ScanInterrupted);
+                }
+
                 let (provenance, blob) = match entry {
                     Err(e) => {
                         error!("Error loading input: {e:#}");
@@ -544,24 +1357,58 @@ pub fn run(global_args: &args::GlobalArgs, args: &args::ScanArgs) -> Result<()>
     // ---------------------------------------------------------------------------------------------
     // Wait for all inputs to be enumerated and scanned and the database thread to finish
     // ---------------------------------------------------------------------------------------------
-    enum_thread
-        .join()
-        .unwrap()
-        .context("Failed to enumerate inputs")?;
+    // A Ctrl-C that arrives after all inputs have been scanned does not stop anything, so only
+    // consider the scan interrupted if the scanners stopped early
+    let interrupted = matches!(&scan_res, Err(e) if e.is::<ScanInterrupted>());
+    if interrupted {
+        progress.suspend(|| {
+            warn!("Interrupted; finishing up (press Ctrl-C again to exit immediately)...")
+        });
+    }
 
-    let (mut datastore, num_matches, num_new_matches) = datastore_thread
-        .join()
-        .unwrap()
-        .context("Failed to save results to the datastore")?;
+    // When interrupted, enumeration stops with an error once the input channel is closed
+    let enum_res = enum_thread.join().unwrap();
+    if !interrupted {
+        enum_res.context("Failed to enumerate inputs")?;
+    }
+
+    let mut targets = datastore_thread.join().unwrap();
+    if targets.iter().all(|t| t.error.is_some()) {
+        let error = targets
+            .swap_remove(0)
+            .error
+            .expect("error should be present");
+        return Err(error.context("Failed to save results to the datastore"));
+    }
 
     blob_copier.close()?;
 
     // now finally check the result of the scanners
-    scan_res.context("Failed to scan inputs")?;
+    match scan_res {
+        Err(e) if e.is::<ScanInterrupted>() => {}
+        res => res.context("Failed to scan inputs")?,
+    }
 
+    enum_progress.finish_with_message(format!(
+        "Enumerated {} files and directories",
+        HumanCount(num_inputs)
+    ));
     progress.finish();
 
-    datastore.check_match_redundancies()?;
+    for target in targets.iter_mut().filter(|t| t.error.is_none()) {
+        // Without `--redundancy-overlap`, reuse the threshold recorded in the datastore
+        let datastore = &mut target.datastore;
+        let res = match args.redundancy_overlap {
+            Some(overlap_threshold) => Ok(overlap_threshold),
+            None => datastore
+                .get_redundancy_overlap()
+                .map(|t| t.unwrap_or(DEFAULT_REDUNDANCY_OVERLAP)),
+        }
+        .and_then(|overlap_threshold| datastore.check_match_redundancies(overlap_threshold));
+        if let Err(e) = res {
+            target.fail(e);
+        }
+    }
 
     // ---------------------------------------------------------------------------------------------
     // Finalize and report
@@ -576,6 +1423,27 @@ pub fn run(global_args: &args::GlobalArgs, args: &args::ScanArgs) -> Result<()>
 
         drop(matcher);
         let matcher_stats = matcher_stats.into_inner()?;
+        for target in targets.iter_mut().filter(|t| t.error.is_none()) {
+            if let Err(e) = target
+                .datastore
+                .finish_scan_run(target.scan_run_id, matcher_stats.blobs_seen, target.num_matches)
+                .context("Failed to record scan run to the datastore")
+            {
+                target.fail(e);
+            }
+        }
+
+        // Report statistics from the first datastore that was written successfully
+        let Some(index) = targets.iter().position(|t| t.error.is_none()) else {
+            let error = targets
+                .swap_remove(0)
+                .error
+                .expect("error should be present");
+            return Err(error.context("Failed to save results to the datastore"));
+        };
+        let target = &targets[index];
+        let num_matches = target.num_matches;
+        let num_new_matches = target.num_new_matches;
         let scan_duration = scan_start.elapsed();
         let seen_bytes_per_sec =
             (matcher_stats.bytes_seen as f64 / scan_duration.as_secs_f64()) as u64;
@@ -589,42 +1457,236 @@ pub fn run(global_args: &args::GlobalArgs, args: &args::ScanArgs) -> Result<()>
             HumanCount(num_new_matches),
             HumanCount(num_matches),
         );
+        if matcher_stats.blobs_with_matches > 0 {
+            println!(
+                "Found matches in {} of {} scanned blobs",
+                HumanCount(matcher_stats.blobs_with_matches),
+                HumanCount(matcher_stats.blobs_scanned),
+            );
+        }
+        if matcher_stats.blobs_excluded > 0 {
+            println!(
+                "Skipped {} blobs listed by `--exclude-blob-ids`",
+                HumanCount(matcher_stats.blobs_excluded)
+            );
+        }
+        if matcher_stats.blobs_minified > 0 {
+            println!(
+                "Skipped {} blobs that looked minified",
+                HumanCount(matcher_stats.blobs_minified)
+            );
+        }
+
+        if args.size_histogram {
+            print_size_histogram(&matcher_stats.blob_sizes);
+        }
+
+        if args.show_rule_stats {
+            print_rule_stats(&matcher_stats.rule_match_counts, &rules_db);
+        }
+
+        #[cfg(feature = "rule_profiling")]
+        {
+            println!("Rule stats:");
+            let mut entries = matcher_stats.rule_stats.get_entries();
+            entries.retain(|e| e.raw_match_count > 0);
+            entries.sort_by_key(|e| e.stage2_duration);
+            entries.reverse();
+            for entry in entries {
+                let rule_name = &rules_db
+                    .get_rule(entry.rule_id)
+                    .expect("rule index should be valid")
+                    .name();
+                println!(
+                    "{:>50} {:>10} {:>10.4}s",
+                    rule_name,
+                    entry.raw_match_count,
+                    entry.stage2_duration.as_secs_f64()
+                );
+            }
+        }
+
+        if num_matches > 0 {
+            let summary = target
+                .datastore
+                .get_summary()
+                .context("Failed to get finding summary")
+                .unwrap();
+            let table = crate::cmd_summarize::summary_table(&summary, /* simple= */ true);
+            println!();
+            table.print_tty(global_args.use_color(std::io::stdout()))?;
+        }
+
+        println!("\nRun the `report` command next to show finding details.");
+
+        let failed: Vec<_> = targets
+            .iter()
+            .filter(|t| t.error.is_some())
+            .map(|t| t.datastore.root_dir().display().to_string())
+            .collect();
+        if !failed.is_empty() {
+            bail!(
+                "Failed to save results to {} of {} datastores: {}",
+                failed.len(),
+                targets.len(),
+                failed.join(", ")
+            );
+        }
+
+        if interrupted {
+            bail!(
+                "Scan was interrupted; findings from the content scanned so far were recorded in \
+                 the datastore"
+            );
+        }
+
+        let num_unresolved_revisions = enum_cfg.num_unresolved_revisions.load(Ordering::Relaxed);
+        if num_unresolved_revisions > 0 {
+            let option = if enum_cfg.git_ref.is_some() {
+                "--git-ref"
+            } else {
+                "--git-since"
+            };
+            bail!(
+                "Failed to resolve the `{option}` revision in {} Git repositories",
+                HumanCount(num_unresolved_revisions)
+            );
+        }
+
+        let min_blobs = args.content_filtering_args.min_blobs;
+        if matcher_stats.blobs_seen < min_blobs {
+            bail!(
+                "Scanned {} blobs, fewer than the {} required by `--min-blobs`",
+                HumanCount(matcher_stats.blobs_seen),
+                HumanCount(min_blobs),
+            );
+        }
+
+        if args.fail_on_findings && num_new_matches > 0 {
+            bail!(FindingsPresent);
+        }
+    }
+
+    Ok(())
+}
+
+/// The error used to stop scanning early when interrupted with Ctrl-C
+#[derive(Debug)]
+struct ScanInterrupted;
+
+impl std::fmt::Display for ScanInterrupted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "scan interrupted")
+    }
+}
+
+impl std::error::Error for ScanInterrupted {}
+
+/// The error returned when `--fail-on-findings` is given and new matches were found
+///
+/// This is not reported as an error; instead, the process exits with `FindingsPresent::EXIT_CODE`.
+#[derive(Debug)]
+pub struct FindingsPresent;
+
+impl FindingsPresent {
+    /// The process exit status used when new matches were found, distinct from the status 2
+    /// used for errors
+    pub const EXIT_CODE: i32 = 3;
+}
 
-        #[cfg(feature = "rule_profiling")]
-        {
-            println!("Rule stats:");
-            let mut entries = matcher_stats.rule_stats.get_entries();
-            entries.retain(|e| e.raw_match_count > 0);
-            entries.sort_by_key(|e| e.stage2_duration);
-            entries.reverse();
-            for entry in entries {
-                let rule_name = &rules_db
-                    .get_rule(entry.rule_id)
-                    .expect("rule index should be valid")
-                    .name();
-                println!(
-                    "{:>50} {:>10} {:>10.4}s",
-                    rule_name,
-                    entry.raw_match_count,
-                    entry.stage2_duration.as_secs_f64()
-                );
+impl std::fmt::Display for FindingsPresent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "new matches were found")
+    }
+}
+
+impl std::error::Error for FindingsPresent {}
+
+/// Read the Git URLs listed in the given files, one per line.
+/// Blank lines and lines starting with `#` are ignored; invalid URLs are skipped with a warning.
+fn read_git_url_files(paths: &[PathBuf]) -> Result<Vec<GitUrl>> {
+    let mut repo_urls = Vec::new();
+    for path in paths {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read Git URLs from {}", path.display()))?;
+        for (line_num, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.parse() {
+                Ok(url) => repo_urls.push(url),
+                Err(e) => {
+                    warn!(
+                        "Skipping invalid Git URL at {}:{}: {line:?}: {e}",
+                        path.display(),
+                        line_num + 1
+                    )
+                }
             }
         }
+    }
+    debug!("Loaded {} Git URLs from files", repo_urls.len());
+    Ok(repo_urls)
+}
 
-        if num_matches > 0 {
-            let summary = datastore
-                .get_summary()
-                .context("Failed to get finding summary")
-                .unwrap();
-            let table = crate::cmd_summarize::summary_table(&summary, /* simple= */ true);
-            println!();
-            table.print_tty(global_args.use_color(std::io::stdout()))?;
+/// Read the blob IDs listed in the given files, one hex-encoded ID per line.
+/// Blank lines and lines starting with `#` are ignored.
+fn read_blob_id_files(paths: &[PathBuf]) -> Result<HashSet<BlobId>> {
+    let mut blob_ids = HashSet::new();
+    for path in paths {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read blob IDs from {}", path.display()))?;
+        for (line_num, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let blob_id = BlobId::from_hex(line).with_context(|| {
+                format!("Invalid blob ID at {}:{}: {line:?}", path.display(), line_num + 1)
+            })?;
+            blob_ids.insert(blob_id);
         }
+    }
+    debug!("Loaded {} blob IDs to exclude from scanning", blob_ids.len());
+    Ok(blob_ids)
+}
 
-        println!("\nRun the `report` command next to show finding details.");
+/// Print a histogram of blob sizes, with a bar for each bucket scaled relative to the largest.
+fn print_size_histogram(histogram: &SizeHistogram) {
+    const MAX_BAR_WIDTH: u64 = 40;
+
+    let max_count = histogram.buckets().map(|(_, _, c)| c).max().unwrap_or(0);
+    println!("\nBlob size histogram:");
+    for (min_size, max_size, count) in histogram.buckets() {
+        let bar_width = (count * MAX_BAR_WIDTH).div_ceil(max_count);
+        // show half-open ranges, as the inclusive upper bounds round confusingly
+        println!(
+            "{:>10} to < {:<10} {:>12}  {}",
+            HumanBytes(min_size).to_string(),
+            HumanBytes(max_size.saturating_add(1)).to_string(),
+            HumanCount(count).to_string(),
+            "#".repeat(bar_width as usize),
+        );
     }
+}
 
-    Ok(())
+/// The number of rules shown by `--show-rule-stats`
+const RULE_STATS_TOP_N: usize = 20;
+
+/// Print the rules with the most raw matches, in descending order.
+fn print_rule_stats(counts: &RuleMatchCounts, rules_db: &RulesDatabase) {
+    let entries = counts.sorted_entries();
+    println!("\nRules with the most raw matches:");
+    if entries.is_empty() {
+        println!("  (none)");
+    }
+    for (rule_id, count) in entries.into_iter().take(RULE_STATS_TOP_N) {
+        let rule = rules_db
+            .get_rule(rule_id)
+            .expect("rule index should be valid");
+        println!("{:>12}  {} ({})", HumanCount(count).to_string(), rule.name(), rule.id());
+    }
 }
 
 #[derive(Clone)]
@@ -636,18 +1698,29 @@ enum BlobCopier {
 }
 
 impl BlobCopier {
-    fn copy(&self, blob: &Blob) -> Result<()> {
+    fn copy(&self, blob: &Blob, provenance: &ProvenanceSet) -> Result<()> {
         match self {
             BlobCopier::Noop => Ok(()),
-            BlobCopier::Files(c) => c.copy(blob),
+            BlobCopier::Files(c) => c.copy(blob, provenance),
             #[cfg(feature = "parquet")]
             BlobCopier::Parquet(c) => c.copy(blob),
         }
     }
 
+    /// Note additional provenance for a blob that was copied earlier.
+    fn record_provenance(&self, blob_id: &BlobId, provenance: &ProvenanceSet) -> Result<()> {
+        match self {
+            BlobCopier::Noop => Ok(()),
+            BlobCopier::Files(c) => c.record_provenance(blob_id, provenance),
+            #[cfg(feature = "parquet")]
+            BlobCopier::Parquet(_) => Ok(()),
+        }
+    }
+
     fn close(self) -> Result<()> {
         match self {
-            BlobCopier::Noop | BlobCopier::Files(_) => Ok(()),
+            BlobCopier::Noop => Ok(()),
+            BlobCopier::Files(c) => c.close(),
             #[cfg(feature = "parquet")]
             BlobCopier::Parquet(c) => c.close(),
         }
@@ -657,16 +1730,44 @@ impl BlobCopier {
 #[derive(Clone)]
 struct FilesBlobCopier {
     blobs_dir: PathBuf,
+
+    /// The provenance index writer, present when using the `by-provenance` layout
+    provenance_index: Option<Arc<Mutex<std::io::BufWriter<std::fs::File>>>>,
 }
 
 impl FilesBlobCopier {
-    fn new(blobs_dir: PathBuf) -> Self {
-        Self { blobs_dir }
+    fn new(blobs_dir: PathBuf, layout: args::CopyBlobsLayout) -> Result<Self> {
+        let provenance_index = match layout {
+            args::CopyBlobsLayout::Hash => None,
+            args::CopyBlobsLayout::ByProvenance => {
+                let index_path = blobs_dir.join("provenance.jsonl");
+                let file = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&index_path)
+                    .with_context(|| {
+                        format!("Failed to open blob provenance index at {}", index_path.display())
+                    })?;
+                Some(Arc::new(Mutex::new(std::io::BufWriter::new(file))))
+            }
+        };
+        Ok(Self {
+            blobs_dir,
+            provenance_index,
+        })
     }
 }
 
+/// An entry in the provenance index written by the `by-provenance` copied blob layout
+#[derive(serde::Serialize)]
+struct ProvenanceIndexEntry<'a> {
+    blob_id: &'a BlobId,
+    path: String,
+    provenance: &'a ProvenanceSet,
+}
+
 impl FilesBlobCopier {
-    fn copy(&self, blob: &Blob) -> Result<()> {
+    fn copy(&self, blob: &Blob, provenance: &ProvenanceSet) -> Result<()> {
         let blob_id = blob.id.hex();
         let output_dir = self.blobs_dir.join(&blob_id[..2]);
         let output_path = output_dir.join(&blob_id[2..]);
@@ -682,6 +1783,41 @@ impl FilesBlobCopier {
             format!("Failed to write blob contents to {}", output_path.display())
         })?;
 
+        self.record_provenance(&blob.id, provenance)
+    }
+
+    fn record_provenance(&self, blob_id: &BlobId, provenance: &ProvenanceSet) -> Result<()> {
+        use std::io::Write;
+
+        let Some(index) = &self.provenance_index else {
+            return Ok(());
+        };
+        let hex = blob_id.hex();
+        let entry = ProvenanceIndexEntry {
+            blob_id,
+            path: format!("{}/{}", &hex[..2], &hex[2..]),
+            provenance,
+        };
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        index
+            .lock()
+            .unwrap()
+            .write_all(&line)
+            .context("Failed to write to blob provenance index")?;
+        Ok(())
+    }
+
+    fn close(self) -> Result<()> {
+        use std::io::Write;
+
+        if let Some(index) = self.provenance_index {
+            index
+                .lock()
+                .unwrap()
+                .flush()
+                .context("Failed to flush blob provenance index")?;
+        }
         Ok(())
     }
 }
@@ -828,13 +1964,28 @@ struct BlobProcessor<'a> {
     matcher: Matcher<'a>,
     guesser: Guesser,
 
-    snippet_length: usize,
+    snippet_lengths: (usize, usize),
+    snippet_mode: args::SnippetMode,
+    /// The maximum number of matches to record from each rule in a blob, where `None` means "no
+    /// limit"
+    max_matches_per_blob: Option<usize>,
     blob_metadata_recording_mode: args::BlobMetadataMode,
     copy_blobs_mode: args::CopyBlobsMode,
+    /// The inclusive minimum and maximum sizes in bytes of blobs to copy, where `None` means
+    /// "no limit"
+    copy_blobs_size_range: (Option<u64>, Option<u64>),
     blob_copier: BlobCopier,
+    scorer: Option<Arc<dyn Scorer>>,
 }
 
 impl<'a> BlobProcessor<'a> {
+    /// Is the given blob within the size range of blobs to copy?
+    fn copy_size_ok(&self, blob: &Blob) -> bool {
+        let len = blob.len() as u64;
+        let (min, max) = self.copy_blobs_size_range;
+        min.map_or(true, |min| len >= min) && max.map_or(true, |max| len <= max)
+    }
+
     fn run(&mut self, provenance: ProvenanceSet, blob: Blob) -> Result<Option<DatastoreMessage>> {
         let blob_id = blob.id.hex();
         let _span = error_span!("matcher", blob_id, bytes = blob.len()).entered();
@@ -853,33 +2004,72 @@ impl<'a> BlobProcessor<'a> {
             // blob already seen, but with no matches; nothing to do!
             ScanResult::SeenSansMatches => {
                 trace!(us = scan_us, mbps = scan_mbps, status = "seen_nomatch");
+                if self.copy_blobs_mode == args::CopyBlobsMode::All && self.copy_size_ok(&blob) {
+                    self.blob_copier
+                        .record_provenance(&blob.id, &provenance)
+                        .context("Failed to record copied blob provenance")?;
+                }
+                Ok(None)
+            }
+
+            // blob explicitly excluded from scanning; nothing to do!
+            ScanResult::Excluded => {
+                trace!(us = scan_us, mbps = scan_mbps, status = "excluded");
                 Ok(None)
             }
 
             // blob already seen; all we need to do is record its provenance
             ScanResult::SeenWithMatches => {
                 trace!(us = scan_us, mbps = scan_mbps, status = "seen_match");
+                if self.copy_blobs_mode != args::CopyBlobsMode::None && self.copy_size_ok(&blob) {
+                    self.blob_copier
+                        .record_provenance(&blob.id, &provenance)
+                        .context("Failed to record copied blob provenance")?;
+                }
                 let metadata = BlobMetadata {
                     id: blob.id,
                     num_bytes: blob.len(),
                     mime_essence: None,
                     charset: None,
+                    num_truncated_matches: None,
                 };
                 Ok(Some((provenance, metadata, Vec::new())))
             }
 
             // blob has not been seen; need to record blob metadata, provenance, and matches
-            ScanResult::New(matches) => {
+            ScanResult::New(mut matches) => {
                 trace!(us = scan_us, mbps = scan_mbps, status = "new", matches = matches.len());
 
+                // Keep only the first matches from each rule, if limited
+                let mut num_truncated_matches = 0;
+                if let Some(max_matches) = self.max_matches_per_blob {
+                    let mut rule_counts: HashMap<&str, usize> = HashMap::new();
+                    matches.retain(|m| {
+                        let count = rule_counts.entry(m.rule.id()).or_default();
+                        *count += 1;
+                        let keep = *count <= max_matches;
+                        if !keep {
+                            num_truncated_matches += 1;
+                        }
+                        keep
+                    });
+                    if num_truncated_matches > 0 {
+                        debug!(
+                            "Omitted {num_truncated_matches} matches beyond {max_matches} per rule"
+                        );
+                    }
+                }
+                let num_truncated_matches =
+                    (num_truncated_matches > 0).then_some(num_truncated_matches);
+
                 let do_copy = match self.copy_blobs_mode {
                     args::CopyBlobsMode::All => true,
                     args::CopyBlobsMode::Matching => !matches.is_empty(),
                     args::CopyBlobsMode::None => false,
-                };
+                } && self.copy_size_ok(&blob);
                 if do_copy {
                     self.blob_copier
-                        .copy(&blob)
+                        .copy(&blob, &provenance)
                         .context("Failed to copy blob")?;
                 }
 
@@ -898,6 +2088,7 @@ impl<'a> BlobProcessor<'a> {
                         num_bytes: blob.len(),
                         mime_essence: None,
                         charset: None,
+                        num_truncated_matches,
                     },
                     _ => {
                         let md = MetadataResult::from_blob_and_provenance(
@@ -910,6 +2101,33 @@ impl<'a> BlobProcessor<'a> {
                             num_bytes: blob.len(),
                             mime_essence: md.mime_essence,
                             charset: md.charset,
+                            num_truncated_matches,
+                        }
+                    }
+                };
+
+                let snippet_mode = match self.snippet_mode {
+                    args::SnippetMode::Bytes => SnippetMode::Bytes,
+                    args::SnippetMode::Line => SnippetMode::Line,
+                    args::SnippetMode::MatchingLines => SnippetMode::MatchingLines,
+                    args::SnippetMode::Structured => {
+                        let mime_essence = match &metadata.mime_essence {
+                            Some(m) => Some(m.clone()),
+                            None => {
+                                MetadataResult::from_blob_and_provenance(
+                                    &self.guesser,
+                                    &blob,
+                                    &provenance,
+                                )
+                                .mime_essence
+                            }
+                        };
+                        match mime_essence
+                            .as_deref()
+                            .and_then(StructuredFormat::from_mime_essence)
+                        {
+                            Some(format) => SnippetMode::Structured(format),
+                            None => SnippetMode::Bytes,
                         }
                     }
                 };
@@ -926,11 +2144,12 @@ impl<'a> BlobProcessor<'a> {
 
                         let capacity: usize = matches.iter().map(|m| m.captures.len() - 1).sum();
                         let mut new_matches = Vec::with_capacity(capacity);
-                        new_matches.extend(
-                            matches.iter().map(|m| {
-                                (None, Match::convert(&loc_mapping, m, self.snippet_length))
-                            }),
-                        );
+                        new_matches.extend(matches.iter().map(|m| {
+                            let (before, after) = self.snippet_lengths;
+                            let m = Match::convert(&loc_mapping, m, before, after, snippet_mode);
+                            let score = self.scorer.as_ref().and_then(|s| s.score(&m));
+                            (score, m)
+                        }));
                         new_matches
                     }
                     None => {
@@ -945,6 +2164,29 @@ impl<'a> BlobProcessor<'a> {
     }
 }
 
+// -------------------------------------------------------------------------------------------------
+/// The Nosey Parker version and Git commit, as recorded for scan runs.
+fn version_string() -> String {
+    format!("{} ({})", env!("CARGO_PKG_VERSION"), env!("VERGEN_GIT_SHA"))
+}
+
+/// Get scan run labels that describe the scanning host and this Nosey Parker build.
+fn host_info_labels() -> Vec<(String, String)> {
+    use sysinfo::System;
+
+    let mut labels = Vec::new();
+    if let Some(host) = System::host_name() {
+        labels.push(("np.host".to_string(), host));
+    }
+    if let Some(os) = System::long_os_version() {
+        labels.push(("np.os".to_string(), os));
+    }
+    labels.push(("np.version".to_string(), version_string()));
+    labels.push(("np.features".to_string(), env!("VERGEN_CARGO_FEATURES").to_string()));
+    labels.push(("np.target".to_string(), env!("VERGEN_CARGO_TARGET_TRIPLE").to_string()));
+    labels
+}
+
 // -------------------------------------------------------------------------------------------------
 /// Initialize a `FilesystemEnumerator` based on the command-line arguments and datastore.
 /// Also initialize a `Gitignore` that is the same as that used by the filesystem enumerator.
@@ -953,6 +2195,30 @@ fn make_fs_enumerator(
     datastore: &Datastore,
     input_roots: Vec<PathBuf>,
 ) -> Result<(Option<FilesystemEnumerator>, input_enumerator::Gitignore)> {
+    // The filesystem walker never follows symlinks given as inputs, so resolve those up front
+    let input_roots: Vec<PathBuf> = if args.content_filtering_args.follow_symlinks {
+        input_roots
+            .into_iter()
+            .map(|p| {
+                if !p.is_symlink() {
+                    return p;
+                }
+                match std::fs::canonicalize(&p) {
+                    Ok(target) => {
+                        debug!("Following symlink {} to {}", p.display(), target.display());
+                        target
+                    }
+                    Err(e) => {
+                        debug!("Failed to resolve symlink {}: {e}", p.display());
+                        p
+                    }
+                }
+            })
+            .collect()
+    } else {
+        input_roots
+    };
+
     // FIXME: eliminate this code duplication: logic repeated 2x in input-enumerator
     let mut gitignore_builder = input_enumerator::GitignoreBuilder::new("");
 
@@ -981,8 +2247,15 @@ fn make_fs_enumerator(
     } else {
         let mut ie = FilesystemEnumerator::new(&input_roots)?;
 
-        ie.threads(args.num_jobs);
+        ie.threads(args.jobs_enumeration.unwrap_or(args.num_jobs));
         ie.max_filesize(args.content_filtering_args.max_file_size_bytes());
+        for (ext, size) in &args.content_filtering_args.max_file_size_ext {
+            ie.max_filesize_for_extension(ext, size.0);
+        }
+        ie.scan_hidden(args.content_filtering_args.scan_hidden);
+        ie.follow_links(args.content_filtering_args.follow_symlinks);
+        ie.include_globs(&args.content_filtering_args.include)
+            .context("Failed to parse `--include` globs")?;
         if args.input_specifier_args.git_history == args::GitHistoryMode::None {
             ie.enumerate_git_history(false);
         }
@@ -999,10 +2272,14 @@ fn make_fs_enumerator(
             })?;
         }
 
+        ie.discover_ignore_files(args.content_filtering_args.discover_ignore_files);
+
         // Determine whether to collect git metadata or not
         let collect_git_metadata = match args.metadata_args.git_blob_provenance {
             args::GitBlobProvenanceMode::FirstSeen => true,
-            args::GitBlobProvenanceMode::Minimal => false,
+            args::GitBlobProvenanceMode::RepoAndPath | args::GitBlobProvenanceMode::Minimal => {
+                false
+            }
         };
         ie.collect_git_metadata(collect_git_metadata);
 
@@ -1016,6 +2293,7 @@ fn make_fs_enumerator(
 fn enumerate_github_repos(
     global_args: &args::GlobalArgs,
     args: &args::ScanArgs,
+    multi_progress: &MultiProgress,
 ) -> Result<Vec<GitUrl>> {
     let mut repo_urls = vec![];
 
@@ -1029,10 +2307,10 @@ fn enumerate_github_repos(
     };
 
     if !repo_specifiers.is_empty() {
-        let mut progress = Progress::new_countup_spinner(
+        let mut progress = multi_progress.add(Progress::new_countup_spinner(
             "Enumerating GitHub repositories...",
             global_args.use_progress(),
-        );
+        ));
         let mut num_found: u64 = 0;
         let api_url = args.input_specifier_args.github_api_url.clone();
 
@@ -1040,6 +2318,7 @@ fn enumerate_github_repos(
             &repo_specifiers,
             api_url,
             global_args.ignore_certs,
+            args.input_specifier_args.github_cursor_cache.as_deref(),
             Some(&mut progress),
         )
         .context("Failed to enumerate GitHub repositories")?
@@ -1071,10 +2350,156 @@ fn enumerate_github_repos(
 fn enumerate_github_repos(
     _global_args: &args::GlobalArgs,
     _args: &args::ScanArgs,
+    _multi_progress: &MultiProgress,
 ) -> Result<Vec<GitUrl>> {
     Ok(vec![])
 }
 
+/// Enumerate the data values of Kubernetes secrets via the Kubernetes API, returning a vector of
+/// blobs to scan
+#[cfg(feature = "kubernetes")]
+fn enumerate_k8s_secrets(
+    global_args: &args::GlobalArgs,
+    args: &args::ScanArgs,
+    multi_progress: &MultiProgress,
+) -> Result<Vec<(ProvenanceSet, Blob)>> {
+    use noseyparker::kubernetes;
+
+    if !args.input_specifier_args.k8s_secrets {
+        return Ok(vec![]);
+    }
+
+    let mut progress = multi_progress.add(Progress::new_countup_spinner(
+        "Enumerating Kubernetes secrets...",
+        global_args.use_progress(),
+    ));
+
+    let values = kubernetes::enumerate_secrets(
+        &args.input_specifier_args.k8s_namespace,
+        global_args.ignore_certs,
+        Some(&mut progress),
+    )
+    .context("Failed to enumerate Kubernetes secrets")?;
+
+    progress.finish_with_message(format!(
+        "Found {} values from Kubernetes secrets",
+        HumanCount(values.len() as u64)
+    ));
+
+    Ok(values
+        .into_iter()
+        .map(|v| {
+            let provenance = Provenance::from_extended(serde_json::json!({
+                "kubernetes_secret": {
+                    "namespace": v.namespace,
+                    "name": v.name,
+                    "key": v.key,
+                }
+            }));
+            (
+                provenance.into(),
+                Blob::from_bytes_with_algorithm(v.value, args.blob_id_algorithm.into()),
+            )
+        })
+        .collect())
+}
+
+/// Enumerate the data values of Kubernetes secrets via the Kubernetes API, returning a vector of
+/// blobs to scan
+#[cfg(not(feature = "kubernetes"))]
+fn enumerate_k8s_secrets(
+    _global_args: &args::GlobalArgs,
+    _args: &args::ScanArgs,
+    _multi_progress: &MultiProgress,
+) -> Result<Vec<(ProvenanceSet, Blob)>> {
+    Ok(vec![])
+}
+
+/// An input whose content is only fetched when it is scanned
+// This is only constructed when S3 support is enabled
+#[cfg_attr(not(feature = "s3"), allow(dead_code))]
+struct DeferredBlob {
+    /// The size of the content in bytes, used for progress reporting
+    size: u64,
+
+    /// Fetch the content
+    fetch: Box<dyn FnOnce() -> Result<(ProvenanceSet, Blob)> + Send>,
+}
+
+/// List the objects at the S3 URLs given on the command line, returning a vector of blobs to
+/// fetch and scan
+#[cfg(feature = "s3")]
+fn enumerate_s3_objects(
+    global_args: &args::GlobalArgs,
+    args: &args::ScanArgs,
+    multi_progress: &MultiProgress,
+) -> Result<Vec<DeferredBlob>> {
+    use noseyparker::s3::S3Client;
+
+    let s3_urls = &args.input_specifier_args.s3_urls;
+    if s3_urls.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut progress = multi_progress.add(Progress::new_countup_spinner(
+        "Enumerating S3 objects...",
+        global_args.use_progress(),
+    ));
+
+    let client = Arc::new(S3Client::new().context("Failed to initialize S3 client")?);
+    let mut objects = Vec::new();
+    for url in s3_urls {
+        objects.extend(client.list_objects(url, Some(&mut progress))?);
+    }
+
+    let blob_id_algorithm: BlobIdAlgorithm = args.blob_id_algorithm.into();
+    let max_file_size = args.content_filtering_args.max_file_size_bytes();
+    objects.retain(|o| {
+        let ok = max_file_size.map_or(true, |max| o.size <= max);
+        if !ok {
+            debug!("Skipping {}: size {} exceeds maximum file size", o.url(), o.size);
+        }
+        ok
+    });
+
+    progress.finish_with_message(format!("Found {} S3 objects", HumanCount(objects.len() as u64)));
+
+    Ok(objects
+        .into_iter()
+        .map(|object| {
+            let client = client.clone();
+            DeferredBlob {
+                size: object.size,
+                fetch: Box::new(move || {
+                    let bytes = client.get_object(&object)?;
+                    let provenance = Provenance::from_extended(serde_json::json!({
+                        "s3_object": {
+                            "url": object.url(),
+                            "bucket": object.bucket,
+                            "key": object.key,
+                        }
+                    }));
+                    Ok((
+                        provenance.into(),
+                        Blob::from_bytes_with_algorithm(bytes, blob_id_algorithm),
+                    ))
+                }),
+            }
+        })
+        .collect())
+}
+
+/// List the objects at the S3 URLs given on the command line, returning a vector of blobs to
+/// fetch and scan
+#[cfg(not(feature = "s3"))]
+fn enumerate_s3_objects(
+    _global_args: &args::GlobalArgs,
+    _args: &args::ScanArgs,
+    _multi_progress: &MultiProgress,
+) -> Result<Vec<DeferredBlob>> {
+    Ok(vec![])
+}
+
 // -------------------------------------------------------------------------------------------------
 type DatastoreMessage = (ProvenanceSet, BlobMetadata, Vec<(Option<f64>, Match)>);
 
@@ -1083,20 +2508,104 @@ const DATASTORE_BATCH_SIZE: usize = 16 * 1024;
 const DATASTORE_COMMIT_INTERVAL: Duration = Duration::from_secs(1);
 
 // -------------------------------------------------------------------------------------------------
-/// Read messages from a channel, and write them into the datastore.
+/// A datastore that scan results are recorded into
+struct ScanTarget {
+    datastore: Datastore,
+
+    /// The ID of this scan run within the datastore
+    scan_run_id: ScanRunIdInt,
+
+    /// The total number of matches in the datastore once recording finishes
+    num_matches: u64,
+
+    /// The number of matches newly added to the datastore
+    num_new_matches: u64,
+
+    /// The first error that occurred writing to the datastore; nothing further is written once
+    /// this is set
+    error: Option<anyhow::Error>,
+}
+
+impl ScanTarget {
+    fn new(datastore: Datastore, scan_run_id: ScanRunIdInt) -> Self {
+        Self {
+            datastore,
+            scan_run_id,
+            num_matches: 0,
+            num_new_matches: 0,
+            error: None,
+        }
+    }
+
+    /// Record that writing to this datastore failed.
+    fn fail(&mut self, error: anyhow::Error) {
+        error!(
+            "Failed to write to the datastore at {}; continuing without it: {error:#}",
+            self.datastore.root_dir().display()
+        );
+        self.error = Some(error);
+    }
+
+    /// Record a batch of messages into the datastore in a single transaction.
+    fn record_batch(&mut self, batch: &[DatastoreMessage], max_provenance_recorded: Option<usize>) {
+        if self.error.is_some() {
+            return;
+        }
+        let _span = error_span!("datastore", "{}", self.datastore.root_dir().display()).entered();
+        let t1 = Instant::now();
+        let res = || -> Result<u64> {
+            let tx = self.datastore.begin()?;
+            let num_added = tx
+                .record_with_max_provenance(batch, max_provenance_recorded)
+                .context("Failed to record batch")?;
+            tx.commit()?;
+            Ok(num_added)
+        }();
+        match res {
+            Ok(num_added) => {
+                self.num_new_matches += num_added;
+                trace!(
+                    "Recorded {num_added} matches from {} messages in {:.6}s",
+                    batch.len(),
+                    t1.elapsed().as_secs_f64()
+                );
+            }
+            Err(e) => self.fail(e),
+        }
+    }
+
+    /// Count the matches in the datastore and update its query planner statistics.
+    fn finish(&mut self) {
+        if self.error.is_some() {
+            return;
+        }
+        let res = || -> Result<u64> {
+            let num_matches = self.datastore.get_num_matches()?;
+            self.datastore.analyze()?;
+            Ok(num_matches)
+        }();
+        match res {
+            Ok(num_matches) => self.num_matches = num_matches,
+            Err(e) => self.fail(e),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+/// Read messages from a channel, and write them into each of the datastores.
 ///
-/// Big idea: read until all the senders hang up; panic if recording matches fails.
+/// Big idea: read until all the senders hang up, or until writing to every datastore has failed.
+/// A datastore that fails to record a batch is not written to again, but the others are.
 ///
 /// Record all messages chunked transactions, trying to commit at least every
 /// `DATASTORE_COMMIT_INTERVAL`.
 fn datastore_writer(
-    mut datastore: Datastore,
+    mut targets: Vec<ScanTarget>,
     recv_ds: crossbeam_channel::Receiver<DatastoreMessage>,
-) -> Result<(Datastore, u64, u64)> {
-    let _span = error_span!("datastore", "{}", datastore.root_dir().display()).entered();
+    max_provenance_recorded: Option<usize>,
+) -> Vec<ScanTarget> {
     let mut total_recording_time: std::time::Duration = Default::default();
 
-    let mut num_matches_added: u64 = 0;
     let mut total_messages: u64 = 0;
 
     let mut batch: Vec<DatastoreMessage> = Vec::with_capacity(DATASTORE_BATCH_SIZE);
@@ -1112,70 +2621,128 @@ fn datastore_writer(
             || matches_in_batch >= DATASTORE_BATCH_SIZE
             || last_commit_time.elapsed() >= DATASTORE_COMMIT_INTERVAL
         {
-            let t1 = std::time::Instant::now();
-            let batch_len = batch.len();
-            let tx = datastore.begin()?;
-            let num_added = tx
-                .record(batch.as_slice())
-                .context("Failed to record batch")?;
-            tx.commit()?;
+            let t1 = Instant::now();
+            for target in targets.iter_mut() {
+                target.record_batch(&batch, max_provenance_recorded);
+            }
+            total_recording_time += t1.elapsed();
             last_commit_time = Instant::now();
-            num_matches_added += num_added;
             batch.clear();
             matches_in_batch = 0;
-            let elapsed = t1.elapsed();
-            trace!(
-                "Recorded {num_added} matches from {batch_len} messages in {:.6}s",
-                elapsed.as_secs_f64()
-            );
-            total_recording_time += elapsed;
+
+            // Stop receiving once nothing can be written, so that the scanners stop too
+            if targets.iter().all(|t| t.error.is_some()) {
+                return targets;
+            }
         }
     }
 
     // record any remaining messages
     if !batch.is_empty() {
-        let t1 = std::time::Instant::now();
-
-        let batch_len = batch.len();
-        let tx = datastore.begin()?;
-        let num_added = tx
-            .record(batch.as_slice())
-            .context("Failed to record batch")?;
-        tx.commit()?;
-        num_matches_added += num_added;
-        // batch.clear();
-        // matches_in_batch = 0;
-
-        let elapsed = t1.elapsed();
-        trace!(
-            "Recorded {num_added} matches from {batch_len} messages in {:.6}s",
-            elapsed.as_secs_f64()
-        );
-        total_recording_time += elapsed;
+        let t1 = Instant::now();
+        for target in targets.iter_mut() {
+            target.record_batch(&batch, max_provenance_recorded);
+        }
+        total_recording_time += t1.elapsed();
     }
 
-    let num_matches = datastore.get_num_matches()?;
-    let t1 = std::time::Instant::now();
-    datastore.analyze()?;
+    let t1 = Instant::now();
+    for target in targets.iter_mut() {
+        target.finish();
+    }
     let analyzed_elapsed = t1.elapsed();
 
     debug!(
-        "Summary: recorded {num_matches} matches from {total_messages} messages \
+        "Summary: recorded {total_messages} messages into {} datastores \
                      in {:.6}s; analyzed in {:.6}s",
+        targets.len(),
         total_recording_time.as_secs_f64(),
         analyzed_elapsed.as_secs_f64()
     );
 
-    Ok((datastore, num_matches, num_matches_added))
+    targets
 }
 
 // -------------------------------------------------------------------------------------------------
+/// Is the given path a Git bundle file, as created by `git bundle create`?
+///
+/// Only files with a `.bundle` extension are considered, and they must begin with a Git bundle
+/// header.
+fn is_git_bundle(path: &Path) -> bool {
+    use std::io::Read;
+
+    if path.extension() != Some(std::ffi::OsStr::new("bundle")) || !path.is_file() {
+        return false;
+    }
+    let mut header = [0u8; 16];
+    match std::fs::File::open(path).and_then(|mut f| f.read_exact(&mut header)) {
+        Ok(()) => header == *b"# v2 git bundle\n" || header == *b"# v3 git bundle\n",
+        Err(_) => false,
+    }
+}
+
+/// Clone the given Git bundle files into the datastore, returning the paths of the clones along
+/// with the bundle files they came from.
+fn clone_git_bundles(
+    global_args: &args::GlobalArgs,
+    args: &args::ScanArgs,
+    datastore: &Datastore,
+    bundles: Vec<PathBuf>,
+    multi_progress: &MultiProgress,
+) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let mut paths = Vec::with_capacity(bundles.len());
+
+    let clone_mode = match args.input_specifier_args.git_clone {
+        args::GitCloneMode::Mirror => CloneMode::Mirror,
+        args::GitCloneMode::Bare => CloneMode::Bare,
+    };
+    let git = Git::new(global_args.ignore_certs);
+
+    let mut progress = multi_progress.add(Progress::new_bar(
+        bundles.len() as u64,
+        "Cloning Git bundles",
+        global_args.use_progress(),
+    ));
+
+    for bundle in bundles {
+        progress.set_message(format!("Cloning Git bundles ({})", bundle.display()));
+
+        let output_dir = datastore.bundle_clone_destination(&bundle)?;
+
+        // Bundles are cheap to clone from, so always make a fresh clone rather than updating
+        if output_dir.is_dir() {
+            std::fs::remove_dir_all(&output_dir).with_context(|| {
+                format!("Failed to remove clone directory at {}", output_dir.display())
+            })?;
+        }
+
+        progress.suspend(|| info!("Cloning bundle {}...", bundle.display()));
+        if let Err(e) = git.clone_bundle(&bundle, &output_dir, clone_mode) {
+            progress.suspend(|| {
+                error!(
+                    "Failed to clone bundle {} to {}: {e}; skipping scan",
+                    bundle.display(),
+                    output_dir.display()
+                );
+            });
+            progress.inc(1);
+            continue;
+        }
+        paths.push((output_dir, bundle));
+        progress.inc(1);
+    }
+
+    progress.finish_with_message("Cloning Git bundles");
+    Ok(paths)
+}
+
 /// Clone the repos given in `repo_urls` inside of the datastore's clones directory.
 fn clone_git_repo_urls(
     global_args: &args::GlobalArgs,
     args: &args::ScanArgs,
     datastore: &Datastore,
     repo_urls: Vec<GitUrl>,
+    multi_progress: &MultiProgress,
 ) -> Result<Vec<PathBuf>> {
     let mut paths = Vec::with_capacity(repo_urls.len());
 
@@ -1188,10 +2755,22 @@ fn clone_git_repo_urls(
         args::GitCloneMode::Mirror => CloneMode::Mirror,
         args::GitCloneMode::Bare => CloneMode::Bare,
     };
-    let git = Git::new(global_args.ignore_certs);
+    let mut git = Git::new(global_args.ignore_certs);
+    if let Some(path) = &args.input_specifier_args.netrc {
+        git = git.with_netrc(Netrc::from_file(path)?);
+    }
+    if let Some(secs) = args.input_specifier_args.git_clone_timeout {
+        git = git.with_clone_timeout(Duration::from_secs(secs));
+    }
+    if let Some(depth) = args.input_specifier_args.git_clone_depth {
+        git = git.with_clone_depth(depth);
+    }
 
-    let mut progress =
-        Progress::new_bar(repo_urls.len() as u64, "Fetching Git repos", global_args.use_progress());
+    let mut progress = multi_progress.add(Progress::new_bar(
+        repo_urls.len() as u64,
+        "Fetching Git repos",
+        global_args.use_progress(),
+    ));
 
     let cloning_repos = Mutex::new(vec![]);
 
@@ -1244,7 +2823,12 @@ fn clone_git_repo_urls(
         }
 
         progress.suspend(|| info!("Cloning {repo_url}..."));
-        if let Err(e) = git.create_fresh_clone(&repo_url, &output_dir, clone_mode) {
+        if let Err(e) = git.create_fresh_clone(
+            &repo_url,
+            &output_dir,
+            clone_mode,
+            &args.input_specifier_args.git_fetch_refspec,
+        ) {
             progress.suspend(|| {
                 error!(
                     "Failed to clone {repo_url} to {}: {e}; skipping scan",
@@ -1,10 +1,20 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use console::Term;
 use indicatif::HumanCount;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use noseyparker::datastore::{Datastore, FindingSummary};
+use noseyparker::datastore::{
+    Datastore, FindingMetadata, FindingSummary, FindingSummaryEntry, Status,
+};
 
-use crate::args::{GlobalArgs, SummarizeArgs, SummarizeOutputFormat};
+use crate::args::{FindingStatus, GlobalArgs, SummarizeArgs, SummarizeOutputFormat};
+use crate::cmd_report::statuses_match;
 use crate::reportable::Reportable;
+use crate::rule_loader::warn_if_rules_changed;
 
 struct FindingSummaryReporter {
     summary: FindingSummary,
@@ -19,6 +29,7 @@ impl Reportable for FindingSummaryReporter {
             SummarizeOutputFormat::Human => self.human_format(writer),
             SummarizeOutputFormat::Json => self.json_format(writer),
             SummarizeOutputFormat::Jsonl => self.jsonl_format(writer),
+            SummarizeOutputFormat::Csv => self.csv_format(writer),
         }
     }
 }
@@ -43,19 +54,47 @@ impl FindingSummaryReporter {
         }
         Ok(())
     }
+
+    fn csv_format<W: std::io::Write>(&self, writer: W) -> Result<()> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record([
+            "Rule",
+            "Findings",
+            "Matches",
+            "Accepted",
+            "Rejected",
+            "Mixed",
+            "Unlabeled",
+        ])?;
+        for entry in self.summary.0.iter() {
+            writer.write_record([
+                entry.rule_name.clone(),
+                entry.distinct_count.to_string(),
+                entry.total_count.to_string(),
+                entry.accept_count.to_string(),
+                entry.reject_count.to_string(),
+                entry.mixed_count.to_string(),
+                entry.unlabeled_count.to_string(),
+            ])?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
 }
 
 pub fn run(global_args: &GlobalArgs, args: &SummarizeArgs) -> Result<()> {
+    if let Some(interval) = args.watch {
+        return watch(global_args, args, Duration::from_secs(interval));
+    }
+
     let datastore = Datastore::open(&args.datastore, global_args.advanced.sqlite_cache_size)
         .with_context(|| format!("Failed to open datastore at {}", args.datastore.display()))?;
+    warn_if_rules_changed(&datastore)?;
     let output = args
         .output_args
         .get_writer()
         .context("Failed to get output writer")?;
-    let summary = datastore
-        .get_summary()
-        .context("Failed to get finding summary")
-        .unwrap();
+    let summary = get_summary(&datastore, args.finding_status)?;
     FindingSummaryReporter {
         simple: false,
         summary,
@@ -63,6 +102,117 @@ pub fn run(global_args: &GlobalArgs, args: &SummarizeArgs) -> Result<()> {
     .report(args.output_args.format, output)
 }
 
+/// Repeatedly summarize the findings in the datastore, redrawing the summary in place, until
+/// interrupted.
+fn watch(global_args: &GlobalArgs, args: &SummarizeArgs, interval: Duration) -> Result<()> {
+    if args.output_args.format != SummarizeOutputFormat::Human {
+        bail!("`--watch` can only be used with the `human` output format");
+    }
+    if args.output_args.output.is_some() {
+        bail!("`--watch` cannot be used with `--output`");
+    }
+
+    let datastore =
+        Datastore::open_read_only(&args.datastore, global_args.advanced.sqlite_cache_size)
+            .with_context(|| {
+                format!("Failed to open datastore at {} read-only", args.datastore.display())
+            })?;
+
+    let interrupted = Arc::new(AtomicBool::new(false));
+    {
+        let interrupted = interrupted.clone();
+        ctrlc::set_handler(move || interrupted.store(true, Ordering::SeqCst))
+            .context("Failed to set Ctrl-C handler")?;
+    }
+
+    let term = Term::stdout();
+    while !interrupted.load(Ordering::SeqCst) {
+        let summary = get_summary(&datastore, args.finding_status)?;
+
+        let mut buf = Vec::new();
+        writeln!(
+            &mut buf,
+            "Findings in {} (refreshed every {}s; press Ctrl-C to exit)",
+            args.datastore.display(),
+            interval.as_secs(),
+        )?;
+        FindingSummaryReporter {
+            simple: false,
+            summary,
+        }
+        .human_format(&mut buf)?;
+
+        // When not writing to a terminal, each summary is simply appended
+        if term.is_term() {
+            term.clear_screen()?;
+        }
+        term.write_str(&String::from_utf8_lossy(&buf))?;
+
+        // Sleep in short increments so that Ctrl-C is handled promptly
+        let deadline = Instant::now() + interval;
+        while !interrupted.load(Ordering::SeqCst) {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            std::thread::sleep(remaining.min(Duration::from_millis(100)));
+        }
+    }
+
+    Ok(())
+}
+
+/// Get the summary of findings from the datastore, including only those that match the requested
+/// status, if any.
+fn get_summary(
+    datastore: &Datastore,
+    finding_status: Option<FindingStatus>,
+) -> Result<FindingSummary> {
+    match finding_status {
+        None => datastore
+            .get_summary()
+            .context("Failed to get finding summary"),
+        Some(status) => {
+            let metadata = datastore
+                .get_finding_metadata(false, &[])
+                .context("Failed to get finding metadata from datastore")?;
+            Ok(summarize_with_status(metadata, status))
+        }
+    }
+}
+
+/// Summarize the given findings by rule, including only those that match the requested status.
+fn summarize_with_status(metadata: Vec<FindingMetadata>, status: FindingStatus) -> FindingSummary {
+    let mut entries: BTreeMap<String, FindingSummaryEntry> = BTreeMap::new();
+    for md in metadata {
+        let statuses = md.statuses.0.as_slice();
+        if !statuses_match(status, statuses) {
+            continue;
+        }
+
+        let e = entries
+            .entry(md.rule_name)
+            .or_insert_with_key(|rule_name| FindingSummaryEntry {
+                rule_name: rule_name.clone(),
+                distinct_count: 0,
+                total_count: 0,
+                accept_count: 0,
+                reject_count: 0,
+                mixed_count: 0,
+                unlabeled_count: 0,
+            });
+        e.distinct_count += 1;
+        e.total_count += md.num_matches;
+        match statuses {
+            [] => e.unlabeled_count += 1,
+            [Status::Accept] => e.accept_count += 1,
+            [Status::Reject] => e.reject_count += 1,
+            _ => e.mixed_count += 1,
+        }
+    }
+    FindingSummary(entries.into_values().collect())
+}
+
 pub(crate) fn summary_table(summary: &FindingSummary, simple: bool) -> prettytable::Table {
     use prettytable::format::{FormatBuilder, LinePosition, LineSeparator};
     use prettytable::row;
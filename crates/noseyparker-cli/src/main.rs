@@ -18,7 +18,7 @@ mod reportable;
 mod rule_loader;
 mod util;
 
-use args::{CommandLineArgs, GlobalArgs};
+use args::{CommandLineArgs, GlobalArgs, LogFormat};
 
 /// Set up the logging / tracing system for the application.
 fn configure_tracing(global_args: &GlobalArgs) -> Result<()> {
@@ -50,12 +50,21 @@ fn configure_tracing(global_args: &GlobalArgs) -> Result<()> {
         .context("Failed to parse filters from NP_LOG environment variable")?;
 
     // Install the global tracing subscriber
-    let subscriber = tracing_subscriber::FmtSubscriber::builder()
-        .with_ansi(global_args.use_color(std::io::stderr()))
+    let builder = tracing_subscriber::FmtSubscriber::builder()
         .with_env_filter(env_filter)
-        .with_writer(std::io::stderr)
-        .finish();
-    tracing::subscriber::set_global_default(subscriber)?;
+        .with_writer(std::io::stderr);
+    match global_args.log_format {
+        LogFormat::Human => {
+            let subscriber = builder
+                .with_ansi(global_args.use_color(std::io::stderr()))
+                .finish();
+            tracing::subscriber::set_global_default(subscriber)?;
+        }
+        LogFormat::Json => {
+            let subscriber = builder.json().with_ansi(false).finish();
+            tracing::subscriber::set_global_default(subscriber)?;
+        }
+    }
 
     Ok(())
 }
@@ -124,6 +133,10 @@ fn try_main(args: &CommandLineArgs) -> Result<()> {
 fn main() {
     let args = &CommandLineArgs::parse_args();
     if let Err(e) = try_main(args) {
+        if e.is::<cmd_scan::FindingsPresent>() {
+            std::process::exit(cmd_scan::FindingsPresent::EXIT_CODE);
+        }
+
         // Use the more verbose format that includes a backtrace when running with -vv or higher,
         // otherwise use a more compact one-line error format.
         if args.global_args.verbose > 1 {
@@ -1,10 +1,12 @@
 use anyhow::{anyhow, bail, Context, Result};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
+use url::Url;
 
+use noseyparker::datastore::Datastore;
 use noseyparker::defaults::get_builtin_rules;
-use noseyparker_rules::{Rule, Rules, RulesetSyntax};
+use noseyparker_rules::{rules_fingerprint, Rule, Rules, RulesetSyntax, SourceLocation};
 
 use crate::args::RuleSpecifierArgs;
 use crate::util::Counted;
@@ -13,6 +15,9 @@ pub struct RuleLoader {
     load_builtins: bool,
     additional_load_paths: Vec<PathBuf>,
     enabled_ruleset_ids: Vec<String>,
+    excluded_rule_ids: Vec<String>,
+    remote_rules_cache_dir: Option<PathBuf>,
+    ignore_certs: bool,
 }
 
 impl RuleLoader {
@@ -23,6 +28,9 @@ impl RuleLoader {
             load_builtins: true,
             additional_load_paths: Vec::new(),
             enabled_ruleset_ids: Vec::new(),
+            excluded_rule_ids: Vec::new(),
+            remote_rules_cache_dir: None,
+            ignore_certs: false,
         }
     }
 
@@ -41,6 +49,19 @@ impl RuleLoader {
         self
     }
 
+    /// Cache rules fetched from URLs in the given directory, rather than in a temporary directory
+    /// that is removed after loading.
+    pub fn remote_rules_cache_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.remote_rules_cache_dir = Some(dir.as_ref().to_owned());
+        self
+    }
+
+    /// Ignore validation of TLS certificates when fetching rules from URLs.
+    pub fn ignore_certs(mut self, ignore_certs: bool) -> Self {
+        self.ignore_certs = ignore_certs;
+        self
+    }
+
     /// Add additional ruleset IDs to enable.
     pub fn enable_ruleset_ids<S: AsRef<str>, I: IntoIterator<Item = S>>(mut self, ids: I) -> Self {
         self.enabled_ruleset_ids
@@ -48,6 +69,13 @@ impl RuleLoader {
         self
     }
 
+    /// Add rule IDs or names to exclude from the enabled rules.
+    pub fn exclude_rule_ids<S: AsRef<str>, I: IntoIterator<Item = S>>(mut self, ids: I) -> Self {
+        self.excluded_rule_ids
+            .extend(ids.into_iter().map(|p| p.as_ref().to_owned()));
+        self
+    }
+
     /// Load rules according to this loader's configuration.
     pub fn load(&self) -> Result<LoadedRules> {
         let mut rules = Rules::new();
@@ -58,7 +86,34 @@ impl RuleLoader {
         }
 
         if !self.additional_load_paths.is_empty() {
-            let custom = Rules::from_paths(&self.additional_load_paths)
+            // Rules given by URL are fetched into the cache directory, or into a temporary
+            // directory if there is none, and then loaded like any other file.
+            let mut temp_dir = None;
+            let mut load_paths = Vec::with_capacity(self.additional_load_paths.len());
+            for path in self.additional_load_paths.iter() {
+                let Some(url) = rules_url(path) else {
+                    load_paths.push(path.clone());
+                    continue;
+                };
+                let cache_dir = match &self.remote_rules_cache_dir {
+                    Some(cache_dir) => cache_dir.as_path(),
+                    None => {
+                        if temp_dir.is_none() {
+                            temp_dir = Some(
+                                tempfile::tempdir()
+                                    .context("Failed to create temporary directory for rules")?,
+                            );
+                        }
+                        temp_dir
+                            .as_ref()
+                            .expect("temporary directory should exist")
+                            .path()
+                    }
+                };
+                load_paths.push(fetch_rules_url(&url, cache_dir, self.ignore_certs)?);
+            }
+
+            let custom = Rules::from_paths(&load_paths)
                 .context("Failed to load rules from additional paths")?;
             rules.update(custom);
         }
@@ -68,7 +123,17 @@ impl RuleLoader {
         enabled_ruleset_ids.sort();
         enabled_ruleset_ids.dedup();
 
-        let (mut rules, mut rulesets) = (rules.rules, rules.rulesets);
+        let mut excluded_rule_ids = self.excluded_rule_ids.clone();
+
+        excluded_rule_ids.sort();
+        excluded_rule_ids.dedup();
+
+        let Rules {
+            mut rules,
+            mut rulesets,
+            rule_sources,
+            ruleset_sources,
+        } = rules;
 
         rules.sort_by(|r1, r2| r1.id.cmp(&r2.id));
         rulesets.sort_by(|r1, r2| r1.id.cmp(&r2.id));
@@ -84,7 +149,10 @@ impl RuleLoader {
         Ok(LoadedRules {
             id_to_rule,
             id_to_ruleset,
+            rule_sources,
+            ruleset_sources,
             enabled_ruleset_ids,
+            excluded_rule_ids,
         })
     }
 
@@ -93,7 +161,74 @@ impl RuleLoader {
             .load_builtins(specs.load_builtins)
             .additional_rule_load_paths(specs.rules_path.as_slice())
             .enable_ruleset_ids(specs.ruleset.iter())
+            .exclude_rule_ids(specs.exclude_rule.iter())
+    }
+}
+
+/// Get the URL denoted by the given rules path, if it is an HTTP or HTTPS URL rather than a
+/// filesystem path.
+fn rules_url(path: &Path) -> Option<Url> {
+    let path = path.to_str()?;
+    if !(path.starts_with("http://") || path.starts_with("https://")) {
+        return None;
+    }
+    Url::parse(path).ok()
+}
+
+#[cfg(feature = "remote_rules")]
+fn fetch_rules_url(url: &Url, cache_dir: &Path, ignore_certs: bool) -> Result<PathBuf> {
+    noseyparker::remote_rules::fetch_rules_url(url, cache_dir, ignore_certs)
+}
+
+#[cfg(not(feature = "remote_rules"))]
+fn fetch_rules_url(url: &Url, _cache_dir: &Path, _ignore_certs: bool) -> Result<PathBuf> {
+    bail!("Cannot load rules from {url}: support for remote rules was not enabled at build time")
+}
+
+/// Warn if the rules used by the most recent scan run in the datastore have changed since.
+///
+/// Each rule recorded for the scan run is replaced by the current builtin rule with the same ID,
+/// if there is one, and the fingerprint of the result is compared with the one recorded for the
+/// scan run. A difference indicates that the recorded findings may not reflect current detection,
+/// and that the inputs should be rescanned.
+pub fn warn_if_rules_changed(datastore: &Datastore) -> Result<()> {
+    let runs = datastore
+        .get_scan_runs()
+        .context("Failed to get scan runs")?;
+    let Some((run_id, recorded)) = runs
+        .iter()
+        .rev()
+        .find_map(|r| Some((r.id, r.rules_fingerprint.as_deref()?)))
+    else {
+        return Ok(());
+    };
+
+    let run_rules = datastore
+        .get_scan_run_rules(run_id)
+        .context("Failed to get scan run rules")?;
+    if run_rules.is_empty() {
+        // The scan run was recorded before its rules were
+        return Ok(());
+    }
+
+    let builtin_rules: HashMap<String, Rule> = get_builtin_rules()
+        .context("Failed to load builtin rules")?
+        .rules
+        .into_iter()
+        .map(|r| (r.id.clone(), Rule::new(r)))
+        .collect();
+    let current_rules = run_rules
+        .iter()
+        .map(|r| builtin_rules.get(r.id()).unwrap_or(r));
+    let current = rules_fingerprint(current_rules);
+    if recorded != current {
+        warn!(
+            "The rules used by the most recent scan of this datastore (fingerprint {recorded}) \
+             have changed since (fingerprint {current}); \
+             findings may be out of date, and rescanning is recommended"
+        );
     }
+    Ok(())
 }
 
 /// The result of calling `RuleLoader::load`.
@@ -101,7 +236,11 @@ pub struct LoadedRules {
     id_to_rule: HashMap<String, Rule>,
     id_to_ruleset: HashMap<String, RulesetSyntax>,
 
+    rule_sources: HashMap<String, SourceLocation>,
+    ruleset_sources: HashMap<String, SourceLocation>,
+
     enabled_ruleset_ids: Vec<String>,
+    excluded_rule_ids: Vec<String>,
 }
 
 impl LoadedRules {
@@ -129,8 +268,20 @@ impl LoadedRules {
         self.id_to_ruleset.values()
     }
 
+    /// Get the location that the rule with the given ID was loaded from, if known.
+    #[inline]
+    pub fn rule_source(&self, id: &str) -> Option<&SourceLocation> {
+        self.rule_sources.get(id)
+    }
+
+    /// Get the location that the ruleset with the given ID was loaded from, if known.
+    #[inline]
+    pub fn ruleset_source(&self, id: &str) -> Option<&SourceLocation> {
+        self.ruleset_sources.get(id)
+    }
+
     /// Get the sorted, deduplicated collection of rules that are enabled according to the
-    /// requested rulesets.
+    /// requested rulesets, less any excluded rules.
     pub fn resolve_enabled_rules(&self) -> Result<Vec<&Rule>> {
         // Check that each mentioned non-special ruleset resolves
         let mut resolved_rulesets: Vec<&RulesetSyntax> = Vec::new();
@@ -182,6 +333,27 @@ impl LoadedRules {
 
         sort_and_deduplicate_rules(&mut rules);
 
+        // Drop excluded rules, which can be given by either ID or name
+        if !self.excluded_rule_ids.is_empty() {
+            for excluded in self.excluded_rule_ids.iter() {
+                if !self
+                    .iter_rules()
+                    .any(|r| r.id() == excluded || r.name() == excluded)
+                {
+                    warn!("Excluded rule `{excluded}` does not match any loaded rule");
+                }
+            }
+
+            let num_rules = rules.len();
+            rules.retain(|r| {
+                !self
+                    .excluded_rule_ids
+                    .iter()
+                    .any(|e| r.id() == e || r.name() == e)
+            });
+            info!("Excluded {}", Counted::regular(num_rules - rules.len(), "rule"));
+        }
+
         if tracing::enabled!(tracing::Level::DEBUG) {
             for rule in rules.iter() {
                 debug!("Using rule `{}`: {}", rule.id(), rule.name());
@@ -192,9 +364,35 @@ impl LoadedRules {
     }
 
     /// Get the sorted, deduplicated collection of rules that are enabled according to the given
-    /// ruleset.
+    /// ruleset, including those of any rulesets it includes, transitively.
     pub fn resolve_ruleset_rules(&self, ruleset: &RulesetSyntax) -> Result<Vec<&Rule>> {
         let mut rules = Vec::new();
+        self.collect_ruleset_rules(ruleset, &mut Vec::new(), &mut rules)?;
+
+        sort_and_deduplicate_rules(&mut rules);
+
+        Ok(rules)
+    }
+
+    /// Add the rules of the given ruleset and of the rulesets it includes to `rules`.
+    ///
+    /// `stack` holds the IDs of the rulesets whose inclusions are being resolved, and is used to
+    /// detect cycles.
+    fn collect_ruleset_rules<'r>(
+        &'r self,
+        ruleset: &RulesetSyntax,
+        stack: &mut Vec<String>,
+        rules: &mut Vec<&'r Rule>,
+    ) -> Result<()> {
+        if let Some(pos) = stack.iter().position(|id| *id == ruleset.id) {
+            let cycle: Vec<String> = stack[pos..]
+                .iter()
+                .chain([&ruleset.id])
+                .map(|id| format!("`{id}`"))
+                .collect();
+            bail!("ruleset `{}`: cycle of included rulesets: {}", stack[0], cycle.join(" -> "));
+        }
+
         for rule_id in ruleset.include_rule_ids.iter() {
             let rule = self.id_to_rule.get(rule_id).ok_or_else(|| {
                 anyhow!("ruleset `{}` ({}): unknown rule `{rule_id}`", ruleset.id, ruleset.name)
@@ -202,9 +400,20 @@ impl LoadedRules {
             rules.push(rule);
         }
 
-        sort_and_deduplicate_rules(&mut rules);
+        stack.push(ruleset.id.clone());
+        for ruleset_id in ruleset.include_ruleset_ids.iter() {
+            let included = self.id_to_ruleset.get(ruleset_id).ok_or_else(|| {
+                anyhow!(
+                    "ruleset `{}` ({}): unknown included ruleset `{ruleset_id}`",
+                    ruleset.id,
+                    ruleset.name
+                )
+            })?;
+            self.collect_ruleset_rules(included, stack, rules)?;
+        }
+        stack.pop();
 
-        Ok(rules)
+        Ok(())
     }
 }
 
@@ -288,6 +288,21 @@ pub fn create_empty_git_repo(destination: &Path) {
         .stderr(predicate::str::is_empty());
 }
 
+/// Build a `git` command that operates on the repository at `repo`.
+///
+/// A fixed committer identity is used so that commits can be made without any global Git
+/// configuration.
+pub fn git_cmd(repo: &Path) -> Command {
+    let mut cmd = Command::new("git");
+    cmd.arg("-C").arg(repo).args([
+        "-c",
+        "user.name=Nosey Parker",
+        "-c",
+        "user.email=noseyparker@example.com",
+    ]);
+    cmd
+}
+
 pub fn get_report_stdout_filters() -> Vec<(&'static str, &'static str)> {
     vec![
         (r"(?m)^(\s*File: ).*$", r"$1 <FILENAME>"),
@@ -304,3 +319,11 @@ pub fn get_report_json_redactions() -> Vec<(&'static str, Redaction)> {
         ("[].matches[].score", insta::rounded_redaction(3)),
     ]
 }
+
+pub fn get_report_sarif_redactions() -> Vec<(&'static str, Redaction)> {
+    vec![
+        (".runs[].tool.driver.fullName", Redaction::from("<FULL_NAME>")),
+        (".runs[].tool.driver.semanticVersion", Redaction::from("<VERSION>")),
+        (".runs[].tool.driver.rules", Redaction::from("<RULES>")),
+    ]
+}
@@ -14,3 +14,37 @@ fn generate_json_schema() {
     let stderr = String::from_utf8(output.stderr.clone()).unwrap();
     assert_eq!(stderr, "");
 }
+
+macro_rules! generate_shell_completions_tests {
+    ($($case_name:ident: $shell:expr,)*) => {
+        $(
+            /// Check that completions for the shell are generated and mention the main subcommands.
+            #[test]
+            fn $case_name() {
+                let cmd = noseyparker_success!("generate", "shell-completions", "--shell", $shell);
+
+                let output = cmd.get_output();
+                let stdout = String::from_utf8(output.stdout.clone()).unwrap();
+                assert!(!stdout.is_empty());
+                for subcommand in ["scan", "summarize", "report", "datastore", "rules"] {
+                    assert!(
+                        stdout.contains(subcommand),
+                        "{} completions should mention `{subcommand}`",
+                        $shell
+                    );
+                }
+                let stderr = String::from_utf8(output.stderr.clone()).unwrap();
+                assert_eq!(stderr, "");
+            }
+        )*
+    }
+}
+
+generate_shell_completions_tests! {
+    generate_shell_completions_bash: "bash",
+    generate_shell_completions_zsh: "zsh",
+    generate_shell_completions_fish: "fish",
+    generate_shell_completions_powershell: "powershell",
+    generate_shell_completions_elvish: "elvish",
+    generate_shell_completions_nushell: "nushell",
+}
@@ -110,14 +110,14 @@ fn github_repos_list_multiple_user_dedupe_jsonl_format() {
         .stdout(predicate::str::contains("\"https://github.com/octocat/Spoon-Knife.git\"\n"))
         .stderr(predicate::str::is_empty());
 
-    // Ensure that output is sorted and there are no dupes
+    // Ensure that there are no dupes; JSONL output is written in enumeration order, not sorted
     let stdout = String::from_utf8(cmd.get_output().stdout.clone())
         .expect("noseyparker output should be utf-8");
-    let stdout_lines: Vec<String> = stdout.lines().map(|s| s.to_string()).collect();
-    let mut sorted_stdout_lines = stdout_lines.clone();
-    sorted_stdout_lines.sort();
-    sorted_stdout_lines.dedup();
-    assert_eq!(stdout_lines, sorted_stdout_lines);
+    let mut stdout_lines: Vec<String> = stdout.lines().map(|s| s.to_string()).collect();
+    let num_lines = stdout_lines.len();
+    stdout_lines.sort();
+    stdout_lines.dedup();
+    assert_eq!(stdout_lines.len(), num_lines);
 }
 
 #[test]
@@ -1,6 +1,7 @@
 //! Tests for Nosey Parker's `rules` command
 
 use super::*;
+use indoc::indoc;
 
 /// Check the default list of rules in Nosey Parker using a snapshot test.
 /// This will alert us to when the default rules have changed for some reason (usually because a
@@ -27,8 +28,534 @@ fn rules_check_builtins() {
     assert_cmd_snapshot!(noseyparker_success!("rules", "check", "--warnings-as-errors"));
 }
 
+/// Report the size of the combined rules database.
+#[test]
+fn rules_check_show_db_size() {
+    let scan_env = ScanEnv::new();
+    let rules_file = scan_env.input_file_with_contents(
+        "rules.yml",
+        indoc! {r#"
+            rules:
+            - name: Custom Token
+              id: custom.token.1
+              pattern: '\b(ctok_[a-z0-9]{16})\b'
+              examples:
+              - 'token = ctok_0123456789abcdef'
+        "#},
+    );
+
+    noseyparker_success!(
+        "rules",
+        "check",
+        "--show-db-size",
+        "--load-builtins=false",
+        "--rules-path",
+        rules_file.path()
+    )
+    .stdout(is_match(r"(?m)^Combined rules database size: .+ \([1-9][0-9]* bytes\)$"));
+}
+
+/// Check only user-provided rules, without the built-in ones.
+#[test]
+fn rules_check_user_rules_only() {
+    let scan_env = ScanEnv::new();
+    let rules_file = scan_env.input_file_with_contents(
+        "rules.yml",
+        indoc! {r#"
+            rules:
+            - name: Custom Token
+              id: custom.token.1
+              pattern: '\b(ctok_[a-z0-9]{16})\b'
+              examples:
+              - 'token = ctok_0123456789abcdef'
+
+            rulesets:
+            - name: Custom Rules
+              id: custom
+              description: Rules for custom tokens
+              include_rule_ids:
+              - custom.token.1
+        "#},
+    );
+
+    noseyparker_success!(
+        "rules",
+        "check",
+        "--warnings-as-errors",
+        "--load-builtins=false",
+        "--rules-path",
+        rules_file.path()
+    )
+    .stdout(is_match(r"(?m)^1 rule and 1 ruleset: no issues detected$"));
+}
+
+/// Warnings from user-provided rules are still treated as errors when only those are checked.
+#[test]
+fn rules_check_user_rules_only_warnings_as_errors() {
+    let scan_env = ScanEnv::new();
+    let rules_file = scan_env.input_file_with_contents(
+        "rules.yml",
+        indoc! {r#"
+            rules:
+            - name: Custom Token
+              id: custom.token.1
+              pattern: '\b(ctok_[a-z0-9]{16})\b'
+              examples:
+              - 'token = ctok_0123456789abcdef'
+        "#},
+    );
+
+    noseyparker_failure!(
+        "rules",
+        "check",
+        "--warnings-as-errors",
+        "--load-builtins=false",
+        "--rules-path",
+        rules_file.path()
+    )
+    .stdout(is_match(r"(?m)^1 rule and 0 rulesets: 0 errors and 1 warnings$"))
+    .stderr(is_match(
+        r"custom\.token\.1 \(Custom Token\) is not referenced from any known ruleset",
+    ))
+    .stderr(is_match(r"1 warning; warnings being treated as errors"));
+}
+
+/// Rules and rulesets can be written in TOML as well as YAML, including within directories.
+#[test]
+fn rules_check_toml() {
+    let scan_env = ScanEnv::new();
+    let rules_dir = scan_env.input_dir("rules");
+    rules_dir
+        .child("token.toml")
+        .write_str(indoc! {r#"
+            [[rules]]
+            name = "Custom Token"
+            id = "custom.token.1"
+            pattern = '\b(ctok_[a-z0-9]{16})\b'
+            examples = ['token = ctok_0123456789abcdef']
+
+            [[rulesets]]
+            name = "Custom Rules"
+            id = "custom"
+            description = "Rules for custom tokens"
+            include_rule_ids = ["custom.token.1", "custom.other.1"]
+        "#})
+        .unwrap();
+    rules_dir
+        .child("other.yml")
+        .write_str(indoc! {r#"
+            rules:
+            - name: Other Token
+              id: custom.other.1
+              pattern: '\b(otok_[a-z0-9]{16})\b'
+              examples:
+              - 'token = otok_0123456789abcdef'
+        "#})
+        .unwrap();
+
+    noseyparker_success!(
+        "rules",
+        "check",
+        "--warnings-as-errors",
+        "--load-builtins=false",
+        "--rules-path",
+        rules_dir.path()
+    )
+    .stdout(is_match(r"(?m)^2 rules and 1 ruleset: no issues detected$"));
+
+    let assert = noseyparker_failure!(
+        "rules",
+        "check",
+        "--warnings-as-errors",
+        "--load-builtins=false",
+        "--rules-path",
+        rules_dir.child("token.toml").path(),
+        "--format=json"
+    );
+    let diagnostics: serde_json::Value =
+        serde_json::from_slice(&assert.get_output().stdout).unwrap();
+    assert_eq!(diagnostics[0]["ruleset_id"], "custom");
+    assert_eq!(diagnostics[0]["file"], rules_dir.child("token.toml").path().to_str().unwrap());
+    assert_eq!(diagnostics[0]["line"], 9);
+}
+
+/// Problems can be output as JSON, with the location of the rule they were found in.
+#[test]
+fn rules_check_json() {
+    let scan_env = ScanEnv::new();
+    let rules_file = scan_env.input_file_with_contents(
+        "rules.yml",
+        indoc! {r#"
+            rules:
+            - name: Custom Token
+              id: custom.token.1
+              pattern: '\b(ctok_[a-z0-9]{16})\b'
+              examples:
+              - 'token = ctok_0123456789abcdef'
+        "#},
+    );
+
+    let assert = noseyparker_failure!(
+        "rules",
+        "check",
+        "--warnings-as-errors",
+        "--load-builtins=false",
+        "--rules-path",
+        rules_file.path(),
+        "--format=json"
+    );
+    let diagnostics: serde_json::Value =
+        serde_json::from_slice(&assert.get_output().stdout).unwrap();
+    assert_eq!(
+        diagnostics,
+        serde_json::json!([{
+            "rule_id": "custom.token.1",
+            "ruleset_id": null,
+            "severity": "warning",
+            "message": "Rule ID custom.token.1 (Custom Token) is not referenced from any known ruleset",
+            "file": rules_file.path(),
+            "line": 3,
+        }])
+    );
+}
+
+/// Rules with the same pattern are reported in pedantic mode, even when written differently.
+#[test]
+fn rules_check_pedantic_duplicate_patterns() {
+    let scan_env = ScanEnv::new();
+    let rules_file = scan_env.input_file_with_contents(
+        "rules.yml",
+        indoc! {r#"
+            rules:
+            - name: Custom Token
+              id: custom.token.1
+              pattern: '\b(ctok_[a-z0-9]{16})\b'
+              description: A custom token
+              examples:
+              - 'token = ctok_0123456789abcdef'
+
+            - name: Custom Token Again
+              id: custom.token.2
+              pattern: |
+                (?x)
+                \b
+                (ctok_ [a-z0-9]{16})  (?# the token )
+                \b
+              description: The same custom token
+              examples:
+              - 'token = ctok_0123456789abcdef'
+
+            rulesets:
+            - name: Custom Rules
+              id: custom
+              description: Rules for custom tokens
+              include_rule_ids:
+              - custom.token.1
+              - custom.token.2
+        "#},
+    );
+
+    noseyparker_success!(
+        "rules",
+        "check",
+        "--load-builtins=false",
+        "--rules-path",
+        rules_file.path()
+    )
+    .stdout(is_match(r"(?m)^2 rules and 1 ruleset: no issues detected$"));
+
+    noseyparker_success!(
+        "rules",
+        "check",
+        "--pedantic",
+        "--load-builtins=false",
+        "--rules-path",
+        rules_file.path()
+    )
+    .stdout(is_match(r"(?m)^2 rules and 1 ruleset: 0 errors and 1 warnings$"))
+    .stderr(is_match(
+        r"Rule ID custom\.token\.2 \(Custom Token Again\) has the same pattern as rule ID custom\.token\.1",
+    ));
+}
+
+/// Checking with nothing loaded is an error.
+#[test]
+fn rules_check_nothing_loaded() {
+    noseyparker_failure!("rules", "check", "--load-builtins=false")
+        .stderr(is_match("No rules or rulesets to check"));
+}
+
+/// Test user-provided rules against their examples.
+#[test]
+fn rules_test_user_rules() {
+    let scan_env = ScanEnv::new();
+    let rules_file = scan_env.input_file_with_contents(
+        "rules.yml",
+        indoc! {r#"
+            rules:
+            - name: Custom Token
+              id: custom.token.1
+              pattern: '\b(ctok_[a-z0-9]{16})\b'
+              examples:
+              - 'token = ctok_0123456789abcdef'
+              negative_examples:
+              - 'token = ctok_0123'
+        "#},
+    );
+
+    noseyparker_success!(
+        "rules",
+        "test",
+        "--load-builtins=false",
+        "--rules-path",
+        rules_file.path()
+    )
+    .stdout(is_match(r"(?m)^1 rule and 2 examples: all passed$"));
+}
+
+/// Examples that a rule does not behave as expected on are reported as failures.
+#[test]
+fn rules_test_failures() {
+    let scan_env = ScanEnv::new();
+    let rules_file = scan_env.input_file_with_contents(
+        "rules.yml",
+        indoc! {r#"
+            rules:
+            - name: Custom Token
+              id: custom.token.1
+              pattern: '\b(ctok_[a-z0-9]{16})\b'
+              examples:
+              - 'token = ctok_0123456789abcdef'
+              - 'token = ctok_0123'
+              negative_examples:
+              - 'token = ctok_fedcba9876543210'
+        "#},
+    );
+
+    noseyparker_failure!(
+        "rules",
+        "test",
+        "--load-builtins=false",
+        "--rules-path",
+        rules_file.path()
+    )
+    .stdout(is_match(r#"(?m)^custom\.token\.1: Failed to match example 1: "token = ctok_0123"$"#))
+    .stdout(is_match(
+        r#"(?m)^custom\.token\.1: Incorrectly matched negative example 0: "token = ctok_fedcba9876543210"$"#,
+    ))
+    .stdout(is_match(r"(?m)^1 rule and 3 examples: 2 failed$"))
+    .stderr(is_match(r"2 example failures"));
+
+    let assert = noseyparker_failure!(
+        "rules",
+        "test",
+        "--load-builtins=false",
+        "--rules-path",
+        rules_file.path(),
+        "--format=json"
+    );
+    let failures: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout).unwrap();
+    assert_eq!(failures.as_array().unwrap().len(), 2);
+    assert_eq!(failures[0]["rule_id"], "custom.token.1");
+    assert_eq!(failures[0]["severity"], "error");
+    assert_eq!(failures[0]["file"], rules_file.path().to_str().unwrap());
+    assert_eq!(failures[0]["line"], 3);
+}
+
+/// Testing with no rules loaded is an error.
+#[test]
+fn rules_test_nothing_loaded() {
+    noseyparker_failure!("rules", "test", "--load-builtins=false")
+        .stderr(is_match("No rules to test"));
+}
+
 /// Check that the `rules list --builtins false` option works as expected
 #[test]
 fn rules_list_no_builtins() {
     assert_cmd_snapshot!(noseyparker_success!("rules", "list", "--load-builtins=false"));
 }
+
+#[test]
+fn rules_list_count_only() {
+    assert_cmd_snapshot!(noseyparker_success!("rules", "list", "--count-only"));
+}
+
+#[test]
+fn rules_list_count_only_json() {
+    assert_cmd_snapshot!(noseyparker_success!("rules", "list", "--count-only", "--format=json"));
+}
+
+/// Rulesets can include the rules of other rulesets, transitively.
+#[test]
+fn rules_list_ruleset_includes() {
+    let scan_env = ScanEnv::new();
+    let rules_file = scan_env.input_file_with_contents(
+        "rules.yml",
+        indoc! {r#"
+            rules:
+            - name: Custom Token A
+              id: custom.a.1
+              pattern: '\b(atok_[a-z0-9]{16})\b'
+
+            - name: Custom Token B
+              id: custom.b.1
+              pattern: '\b(btok_[a-z0-9]{16})\b'
+
+            rulesets:
+            - name: Base
+              id: base
+              description: Base rules
+              include_rule_ids:
+              - custom.a.1
+
+            - name: Extended
+              id: extended
+              description: Base rules plus more
+              include_rule_ids:
+              - custom.b.1
+              include_ruleset_ids:
+              - base
+
+            - name: Everything
+              id: everything
+              description: Only included rulesets
+              include_ruleset_ids:
+              - extended
+              - base
+        "#},
+    );
+
+    let cmd = noseyparker_success!(
+        "rules",
+        "list",
+        "--load-builtins=false",
+        "--rules-path",
+        rules_file.path(),
+        "--ruleset=everything",
+        "--count-only",
+        "--format=json"
+    );
+    let counts: serde_json::Value = serde_json::from_slice(&cmd.get_output().stdout).unwrap();
+    assert_eq!(counts["num_enabled_rules"], 2);
+    assert_eq!(
+        counts["rulesets"],
+        serde_json::json!([
+            {"id": "base", "name": "Base", "num_rules": 1},
+            {"id": "everything", "name": "Everything", "num_rules": 2},
+            {"id": "extended", "name": "Extended", "num_rules": 2},
+        ])
+    );
+}
+
+/// Individual rules can be selected by ID or name to see their full definitions.
+#[test]
+fn rules_list_rule() {
+    let scan_env = ScanEnv::new();
+    let rules_file = scan_env.input_file_with_contents(
+        "rules.yml",
+        indoc! {r#"
+            rules:
+            - name: Custom Token A
+              id: custom.a.1
+              pattern: '\b(atok_[a-z0-9]{16})(?# the token)\b'
+              examples:
+              - 'atok_0123456789abcdef'
+
+            - name: Custom Token B
+              id: custom.b.1
+              pattern: '\b(btok_[a-z0-9]{16})\b'
+        "#},
+    );
+
+    noseyparker_success!(
+        "rules",
+        "list",
+        "--load-builtins=false",
+        "--rules-path",
+        rules_file.path(),
+        "--rule=Custom Token A"
+    )
+    .stdout(is_match(r"(?m)^Rule ID: +custom\.a\.1$"))
+    .stdout(is_match(r"(?m)^  - atok_0123456789abcdef$"))
+    .stdout(is_match("custom.b.1").not());
+
+    let cmd = noseyparker_success!(
+        "rules",
+        "list",
+        "--load-builtins=false",
+        "--rules-path",
+        rules_file.path(),
+        "--rule=custom.a.1",
+        "--rule=custom.b.1",
+        "--format=json"
+    );
+    let rules: serde_json::Value = serde_json::from_slice(&cmd.get_output().stdout).unwrap();
+    assert_eq!(rules[0]["id"], "custom.a.1");
+    assert_eq!(rules[0]["syntax"]["examples"], serde_json::json!(["atok_0123456789abcdef"]));
+    assert_eq!(rules[0]["compiled_pattern"], r"\b(atok_[a-z0-9]{16})\b");
+    assert_eq!(rules[1]["id"], "custom.b.1");
+    assert_eq!(rules.as_array().unwrap().len(), 2);
+
+    noseyparker_failure!(
+        "rules",
+        "list",
+        "--load-builtins=false",
+        "--rules-path",
+        rules_file.path(),
+        "--rule=custom.c.1"
+    )
+    .stderr(is_match("Unknown rule `custom.c.1`"));
+}
+
+/// Cycles of included rulesets are detected.
+#[test]
+fn rules_check_ruleset_include_cycle() {
+    let scan_env = ScanEnv::new();
+    let rules_file = scan_env.input_file_with_contents(
+        "rules.yml",
+        indoc! {r#"
+            rules:
+            - name: Custom Token
+              id: custom.token.1
+              pattern: '\b(ctok_[a-z0-9]{16})\b'
+
+            rulesets:
+            - name: First
+              id: first
+              description: Includes the second
+              include_rule_ids:
+              - custom.token.1
+              include_ruleset_ids:
+              - second
+
+            - name: Second
+              id: second
+              description: Includes the first
+              include_ruleset_ids:
+              - first
+        "#},
+    );
+
+    noseyparker_failure!(
+        "rules",
+        "check",
+        "--load-builtins=false",
+        "--rules-path",
+        rules_file.path()
+    )
+    .stderr(is_match(r"cycle of included rulesets: `first` -> `second` -> `first`"));
+
+    noseyparker_failure!(
+        "scan",
+        "-d",
+        scan_env.dspath(),
+        "--load-builtins=false",
+        "--rules-path",
+        rules_file.path(),
+        "--ruleset=second",
+        rules_file.path()
+    )
+    .stderr(is_match(
+        r"ruleset `second`: cycle of included rulesets: `second` -> `first` -> `second`",
+    ));
+}
@@ -51,8 +51,11 @@ fn scan_workflow_from_git_url() {
         assert_snapshot!(std::fs::read_to_string(report_txt.path()).unwrap());
     });
 
-    // XXX Checking SARIF output format disabled for now until it's more actively supported
-    // let report_sarif = scan_env.child("findings.sarif");
-    // noseyparker_success!("report", datastore_arg, "--format=sarif", "-o", report_sarif.path());
-    // assert_json_snapshot!(read_json(report_sarif.path()).unwrap());
+    let report_sarif = scan_env.child("findings.sarif");
+    noseyparker_success!("report", datastore_arg, "--format=sarif", "-o", report_sarif.path());
+    with_settings!({
+        redactions => get_report_sarif_redactions()
+    }, {
+        assert_json_snapshot!(read_json(report_sarif.path()).unwrap());
+    });
 }
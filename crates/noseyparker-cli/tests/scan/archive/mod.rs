@@ -0,0 +1,95 @@
+use super::*;
+
+/// Create a tar archive with the given members, returning its bytes.
+fn make_tar(members: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    for (path, contents) in members {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, path, *contents).unwrap();
+    }
+    builder.into_inner().unwrap()
+}
+
+#[test]
+fn scan_tar_archives() {
+    let scan_env = ScanEnv::new();
+    let secret = scan_env.input_with_secret();
+    let tar = make_tar(&[
+        ("dir/secret.txt", secret.as_bytes()),
+        ("other.txt", b"nothing to see here\n"),
+    ]);
+
+    let input_dir = scan_env.input_dir("inputs");
+    input_dir.child("plain.tar").write_binary(&tar).unwrap();
+
+    let gz = {
+        use std::io::Write;
+        let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        enc.write_all(&tar).unwrap();
+        enc.finish().unwrap()
+    };
+    input_dir.child("gzipped.tar.gz").write_binary(&gz).unwrap();
+
+    let zst = zstd::encode_all(tar.as_slice(), 0).unwrap();
+    input_dir.child("zstd.TZST").write_binary(&zst).unwrap();
+
+    noseyparker_success!("scan", "-d", scan_env.dspath(), input_dir.path())
+        .stdout(is_match(r"(?m)^Scanned .* from 6 blobs in .*; 1/1 new matches$"));
+
+    let cmd = noseyparker_success!(
+        "report",
+        "-d",
+        scan_env.dspath(),
+        "--format=json",
+        "--max-provenance=0"
+    );
+    let output: serde_json::Value = serde_json::from_slice(&cmd.get_output().stdout).unwrap();
+    let ps = output[0]["matches"][0]["provenance"].as_array().unwrap();
+    let mut paths: Vec<&str> = ps
+        .iter()
+        .map(|p| {
+            assert_eq!(p["kind"], "extended");
+            assert_eq!(p["payload"]["archive"]["member"], "dir/secret.txt");
+            p["payload"]["path"].as_str().unwrap()
+        })
+        .collect();
+    paths.sort();
+    let archive_path = |name: &str| format!("{}:dir/secret.txt", input_dir.child(name).display());
+    assert_eq!(
+        paths,
+        [
+            archive_path("gzipped.tar.gz"),
+            archive_path("plain.tar"),
+            archive_path("zstd.TZST")
+        ]
+    );
+}
+
+#[test]
+fn scan_tar_archive_max_file_size() {
+    let scan_env = ScanEnv::new();
+    let big = vec![b'a'; 2 * 1024 * 1024];
+    let tar = make_tar(&[
+        ("big.dat", big.as_slice()),
+        ("secret.txt", scan_env.input_with_secret().as_bytes()),
+    ]);
+    let input = scan_env.child("input.tar");
+    input.write_binary(&tar).unwrap();
+
+    // The archive itself exceeds the maximum, but only its big member is skipped
+    noseyparker_success!("scan", "-d", scan_env.dspath(), "--max-file-size=1MiB", input.path())
+        .stdout(match_scan_stats("104 B", 1, 1, 1));
+}
+
+#[test]
+fn scan_tar_archive_corrupt() {
+    let scan_env = ScanEnv::new();
+    let input = scan_env.child("input.tar.gz");
+    input.write_binary(b"this is not gzip data").unwrap();
+
+    noseyparker_success!("scan", "-d", scan_env.dspath(), input.path())
+        .stdout(match_nothing_scanned());
+}
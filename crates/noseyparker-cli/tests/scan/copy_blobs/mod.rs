@@ -128,6 +128,127 @@ fn scan_copy_blobs_parquet_none() {
     assert_eq!(paths, Vec::<PathBuf>::new());
 }
 
+#[test]
+fn scan_copy_blobs_files_by_provenance() {
+    let scan_env = ScanEnv::new();
+    let input = scan_env.input_with_secret();
+    let i1 = scan_env.input_file_with_contents("i1.txt", input);
+    let i2 = scan_env.input_file_with_contents("i2.txt", input);
+    let i3 = scan_env.input_file_with_contents("i3.txt", "no secrets here");
+
+    noseyparker_success!(
+        "scan",
+        "-d",
+        scan_env.dspath(),
+        "--copy-blobs=matching",
+        "--copy-blobs-format=files",
+        "--copy-blobs-layout=by-provenance",
+        i1.path(),
+        i2.path(),
+        i3.path()
+    );
+
+    let (paths, blobs_dir) = list_blob_paths(&scan_env);
+    assert_eq!(
+        paths,
+        [
+            blobs_dir.join("be"),
+            blobs_dir.join("be/f17e1f92978931020b423cfcfb6f1e7381d559"),
+            blobs_dir.join("provenance.jsonl"),
+        ]
+    );
+
+    // the blob with matches was seen through 2 files; the one without matches was not copied
+    let index = std::fs::read_to_string(blobs_dir.join("provenance.jsonl")).unwrap();
+    let mut paths: Vec<String> = index
+        .lines()
+        .map(|line| {
+            let entry: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert_eq!(entry["blob_id"], "bef17e1f92978931020b423cfcfb6f1e7381d559");
+            assert_eq!(entry["path"], "be/f17e1f92978931020b423cfcfb6f1e7381d559");
+            let provenance = entry["provenance"].as_array().unwrap();
+            assert_eq!(provenance.len(), 1);
+            provenance[0]["path"].as_str().unwrap().to_owned()
+        })
+        .collect();
+    paths.sort();
+    assert_eq!(
+        paths,
+        [
+            i1.path().display().to_string(),
+            i2.path().display().to_string()
+        ]
+    );
+}
+
+#[test]
+fn scan_copy_blobs_files_size_range() {
+    let scan_env = ScanEnv::new();
+    let input = scan_env.input_with_secret();
+    let i1 = scan_env.input_file_with_contents("i1.txt", input);
+    let i2 = scan_env.input_file_with_contents("i2.txt", &format!("{input}\nand again:\n{input}"));
+    let i3 = scan_env.input_file_with_contents("i3.txt", "no secrets here");
+
+    // blobs outside of the size range are still scanned, but are not copied
+    noseyparker_success!(
+        "scan",
+        "-d",
+        scan_env.dspath(),
+        "--copy-blobs=all",
+        "--copy-blobs-format=files",
+        "--copy-blobs-min-size=100B",
+        "--copy-blobs-max-size=200B",
+        i1.path(),
+        i2.path(),
+        i3.path()
+    )
+    .stdout(match_scan_stats("339 B", 3, 3, 3));
+
+    let (paths, blobs_dir) = list_blob_paths(&scan_env);
+    assert_eq!(
+        paths,
+        [
+            blobs_dir.join("be"),
+            blobs_dir.join("be/f17e1f92978931020b423cfcfb6f1e7381d559"),
+        ]
+    );
+}
+
+#[test]
+fn scan_copy_blobs_size_range_invalid() {
+    let scan_env = ScanEnv::new();
+    let input = scan_env.input_file_with_contents("i1.txt", "no secrets here");
+
+    noseyparker_failure!(
+        "scan",
+        "-d",
+        scan_env.dspath(),
+        "--copy-blobs=all",
+        "--copy-blobs-min-size=2KiB",
+        "--copy-blobs-max-size=1KiB",
+        input.path()
+    )
+    .stderr(is_match("must not be greater than `--copy-blobs-max-size`"));
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn scan_copy_blobs_parquet_by_provenance() {
+    let scan_env = ScanEnv::new();
+    let input = scan_env.input_file_with_contents("i1.txt", "no secrets here");
+
+    noseyparker_failure!(
+        "scan",
+        "-d",
+        scan_env.dspath(),
+        "--copy-blobs=all",
+        "--copy-blobs-format=parquet",
+        "--copy-blobs-layout=by-provenance",
+        input.path()
+    )
+    .stderr(is_match("requires `--copy-blobs-format=files`"));
+}
+
 fn scan_copy_blobs_common(copy_blobs: &str, copy_blobs_format: &str) -> ScanEnv {
     let scan_env = ScanEnv::new();
     let input = scan_env.input_with_secret();
@@ -0,0 +1,22 @@
+use super::*;
+
+#[test]
+fn k8s_namespace_requires_k8s_secrets() {
+    let scan_env = ScanEnv::new();
+    let input = scan_env.input_file_with_contents("input.txt", "no secrets here");
+    noseyparker_failure!("scan", "-d", scan_env.dspath(), "--k8s-namespace=default", input.path())
+        .stderr(is_match("--k8s-secrets"));
+}
+
+#[test]
+fn k8s_secrets_no_config() {
+    let scan_env = ScanEnv::new();
+    noseyparker!("scan", "-d", scan_env.dspath(), "--k8s-secrets")
+        .env("KUBECONFIG", scan_env.root.path().join("nonexistent-kubeconfig"))
+        .env_remove("KUBERNETES_SERVICE_HOST")
+        .env_remove("KUBERNETES_SERVICE_PORT")
+        .env("HOME", scan_env.root.path())
+        .assert()
+        .failure()
+        .stderr(is_match("Failed to enumerate Kubernetes secrets"));
+}
@@ -2,10 +2,16 @@
 use super::*;
 
 mod appmaker;
+mod archive;
 mod basic;
 mod copy_blobs;
 mod git_url;
 #[cfg(feature = "github")]
 mod github;
+#[cfg(feature = "kubernetes")]
+mod kubernetes;
+#[cfg(feature = "remote_rules")]
+mod remote_rules;
+mod rule_paths;
 mod snippet_length;
 mod with_ignore;
@@ -0,0 +1,120 @@
+//! Tests for loading rules from HTTP URLs
+use indoc::indoc;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::thread::JoinHandle;
+
+use super::*;
+
+const RULES: &str = indoc! {r#"
+    rules:
+    - name: Custom Token
+      id: custom.token.1
+      pattern: '\b(ctok_[a-z0-9]{16})\b'
+
+    rulesets:
+    - name: Custom Rules
+      id: custom
+      description: Rules for custom tokens
+      include_rule_ids:
+      - custom.token.1
+"#};
+
+const ETAG: &str = r#""rules-v1""#;
+
+/// Serve `RULES` over HTTP on a background thread for the given number of requests.
+///
+/// Requests that send back the `ETag` of the rules get a `304 Not Modified` response.
+/// The thread's result is the status code of each response.
+fn serve_rules(num_requests: usize) -> (String, JoinHandle<Vec<u16>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/rules.yml", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        let mut statuses = Vec::new();
+        for stream in listener.incoming().take(num_requests) {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut not_modified = false;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.is_empty() || line == "\r\n" {
+                    break;
+                }
+                if line.to_ascii_lowercase() == format!("if-none-match: {ETAG}\r\n") {
+                    not_modified = true;
+                }
+            }
+
+            let response = if not_modified {
+                statuses.push(304);
+                format!("HTTP/1.1 304 Not Modified\r\nETag: {ETAG}\r\nConnection: close\r\n\r\n")
+            } else {
+                statuses.push(200);
+                format!(
+                    "HTTP/1.1 200 OK\r\nETag: {ETAG}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{RULES}",
+                    RULES.len()
+                )
+            };
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+        statuses
+    });
+    (url, server)
+}
+
+#[test]
+fn rules_url_cached() {
+    let scan_env = ScanEnv::new();
+    let input = scan_env.input_file_with_contents("config.yaml", "token = ctok_0123456789abcdef\n");
+    let (url, server) = serve_rules(2);
+
+    noseyparker_success!(
+        "scan",
+        "-d",
+        scan_env.dspath(),
+        "--ruleset=custom",
+        "--rules-path",
+        &url,
+        input.path()
+    )
+    .stdout(match_scan_stats("30 B", 1, 1, 1));
+
+    // The second scan uses the cached rules, as they are unchanged
+    noseyparker_success!(
+        "scan",
+        "-d",
+        scan_env.dspath(),
+        "--ruleset=custom",
+        "--rules-path",
+        &url,
+        input.path()
+    )
+    .stdout(match_scan_stats("30 B", 1, 0, 1));
+
+    assert_eq!(server.join().unwrap(), vec![200, 304]);
+}
+
+#[test]
+fn rules_url_without_datastore() {
+    let (url, server) = serve_rules(1);
+
+    noseyparker_success!("rules", "list", "--format=json", "--rules-path", &url)
+        .stdout(is_match(r#""id": "custom\.token\.1""#));
+
+    assert_eq!(server.join().unwrap(), vec![200]);
+}
+
+#[test]
+fn rules_url_unreachable() {
+    let scan_env = ScanEnv::new();
+    let input = scan_env.input_file_with_secret("input.txt");
+
+    // Find a local port that nothing is listening on
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/rules.yml", listener.local_addr().unwrap());
+    drop(listener);
+
+    noseyparker_failure!("scan", "-d", scan_env.dspath(), "--rules-path", &url, input.path())
+        .stderr(is_match(r"Failed to fetch rules from http://127\.0\.0\.1:\d+/rules\.yml"));
+}
@@ -0,0 +1,86 @@
+//! Tests for rules with path constraints
+use indoc::indoc;
+
+use super::*;
+
+#[test]
+fn path_include_and_exclude() {
+    let scan_env = ScanEnv::new();
+    let rules_file = scan_env.input_file_with_contents(
+        "rules.yml",
+        indoc! {r#"
+            rules:
+            - name: Custom Token
+              id: custom.token.1
+              pattern: '\b(ctok_[a-z0-9]{16})\b'
+              path_include:
+              - '*.yaml'
+              path_exclude:
+              - '**/test/**'
+
+            rulesets:
+            - name: Custom Rules
+              id: custom
+              description: Rules for custom tokens
+              include_rule_ids:
+              - custom.token.1
+        "#},
+    );
+
+    let input = scan_env.input_dir("input");
+    scan_env.input_file_with_contents("input/config.yaml", "token = ctok_0123456789abcdef\n");
+    scan_env.input_file_with_contents("input/config.txt", "token = ctok_1123456789abcdef\n");
+    scan_env.input_file_with_contents("input/test/config.yaml", "token = ctok_2123456789abcdef\n");
+
+    noseyparker_success!(
+        "scan",
+        "-d",
+        scan_env.dspath(),
+        "--ruleset=custom",
+        "--rules-path",
+        rules_file.path(),
+        input.path()
+    )
+    .stdout(match_scan_stats("90 B", 3, 1, 1));
+
+    noseyparker_success!("report", "-d", scan_env.dspath(), "--format=json")
+        .stdout(is_match(r#""path":"[^"]*input/config\.yaml""#))
+        .stdout(is_match(r"ctok_0123456789abcdef"))
+        .stdout(is_match(r"ctok_[12]123456789abcdef").not());
+}
+
+#[test]
+fn invalid_path_glob() {
+    let scan_env = ScanEnv::new();
+    let rules_file = scan_env.input_file_with_contents(
+        "rules.yml",
+        indoc! {r#"
+            rules:
+            - name: Custom Token
+              id: custom.token.1
+              pattern: '\b(ctok_[a-z0-9]{16})\b'
+              path_include:
+              - '[*.yaml'
+
+            rulesets:
+            - name: Custom Rules
+              id: custom
+              description: Rules for custom tokens
+              include_rule_ids:
+              - custom.token.1
+        "#},
+    );
+
+    let input = scan_env.input_file_with_contents("config.yaml", "token = ctok_0123456789abcdef\n");
+
+    noseyparker_failure!(
+        "scan",
+        "-d",
+        scan_env.dspath(),
+        "--ruleset=custom",
+        "--rules-path",
+        rules_file.path(),
+        input.path()
+    )
+    .stderr(is_match(r"Failed to compile path constraints for rule custom\.token\.1"));
+}
@@ -203,3 +203,107 @@ fn negation_01() {
     )
     .stdout(match_scan_stats("104 B", 1, 1, 1));
 }
+
+#[test]
+fn discovered_ignore_file() {
+    let scan_env = ScanEnv::new();
+    let input = scan_env.input_dir("input");
+    scan_env.input_file_with_contents(
+        "input/.noseyparkerignore",
+        indoc! {r#"
+            *.dat
+            .noseyparkerignore
+        "#},
+    );
+    scan_env.input_file_with_secret("input/subdir/input.dat");
+
+    noseyparker_success!("scan", "-d", scan_env.dspath(), input.path())
+        .stdout(match_nothing_scanned());
+}
+
+/// The rules of a discovered ignore file apply only within the directory that contains it, and
+/// not to other input roots.
+#[test]
+fn discovered_ignore_file_multiple_roots() {
+    let scan_env = ScanEnv::new();
+    let input1 = scan_env.input_dir("input1");
+    let input2 = scan_env.input_dir("input2");
+    scan_env.input_file_with_contents(
+        "input1/.noseyparkerignore",
+        indoc! {r#"
+            *.dat
+            .noseyparkerignore
+        "#},
+    );
+    scan_env.input_file_with_secret("input1/subdir/input.dat");
+    scan_env.input_file_with_secret("input2/subdir/input.dat");
+
+    noseyparker_success!("scan", "-d", scan_env.dspath(), input1.path(), input2.path())
+        .stdout(match_scan_stats("104 B", 1, 1, 1));
+}
+
+#[test]
+fn discovered_ignore_file_disabled() {
+    let scan_env = ScanEnv::new();
+    let input = scan_env.input_dir("input");
+    scan_env.input_file_with_contents(
+        "input/.noseyparkerignore",
+        indoc! {r#"
+            *.dat
+            .noseyparkerignore
+        "#},
+    );
+    scan_env.input_file_with_secret("input/subdir/input.dat");
+
+    noseyparker_success!(
+        "scan",
+        "--discover-ignore-files=false",
+        "-d",
+        scan_env.dspath(),
+        input.path()
+    )
+    .stdout(match_scan_stats("129 B", 2, 1, 1));
+}
+
+#[test]
+fn include_glob_01() {
+    let scan_env = ScanEnv::new();
+    let input = scan_env.input_dir("input");
+    let secret = scan_env.input_with_secret();
+    scan_env.input_file_with_contents("input/prod.env", secret);
+    scan_env.input_file_with_contents("input/subdir/dev.env", &format!("{secret}# dev\n"));
+    scan_env.input_file_with_contents("input/notes.txt", &format!("{secret}# notes\n"));
+
+    noseyparker_success!("scan", "--include", "*.env", "-d", scan_env.dspath(), input.path())
+        .stdout(is_match(r"(?m)^Scanned .* from 2 blobs in .*; 2/2 new matches$"));
+}
+
+#[test]
+fn include_glob_with_ignore_01() {
+    let scan_env = ScanEnv::new();
+    let ignore_file = scan_env.input_file_with_contents(
+        "npignore",
+        indoc! {r#"
+            subdir/
+        "#},
+    );
+
+    let input = scan_env.input_dir("input");
+    let secret = scan_env.input_with_secret();
+    scan_env.input_file_with_contents("input/prod.env", secret);
+    scan_env.input_file_with_contents("input/subdir/dev.env", &format!("{secret}# dev\n"));
+    scan_env.input_file_with_contents("input/notes.txt", &format!("{secret}# notes\n"));
+
+    // Ignore rules still apply to files that match an include glob
+    noseyparker_success!(
+        "scan",
+        "--include",
+        "*.env",
+        "--ignore",
+        ignore_file.path(),
+        "-d",
+        scan_env.dspath(),
+        input.path()
+    )
+    .stdout(match_scan_stats("104 B", 1, 1, 1));
+}
@@ -24,7 +24,6 @@ mod scan;
 // TODO(test): add test for scanning with `--git-clone-mode bare` and `--git-clone-mode mirror`
 // TODO(test): add test for scanning with `--github-api-url`
 // TODO(test): add test using a non-default `--github-api-url URL`
-// TODO(test): add tests for SARIF output format
 
 // TODO(test): add tests for blob metadata recording
 // TODO(test): add tests for rerunning with changing `--blob-metadata` and `--git-blob-provenance` options
@@ -3,8 +3,8 @@ mod rules;
 mod ruleset;
 mod util;
 
-pub use rule::{Rule, RuleSyntax};
-pub use rules::Rules;
+pub use rule::{rules_fingerprint, ExampleStats, PathFilter, Rule, RuleSyntax};
+pub use rules::{Rules, SourceLocation};
 pub use ruleset::RulesetSyntax;
 
 // -------------------------------------------------------------------------------------------------
@@ -27,6 +27,102 @@ mod test {
         }
     }
 
+    #[test]
+    fn rules_source_locations() {
+        use super::{Rules, SourceLocation};
+        use std::path::Path;
+
+        let path = Path::new("custom.yml");
+        let contents = concat!(
+            "rules:\n",
+            "- name: First\n",
+            "  id: custom.1\n",
+            "  pattern: '(a+)'\n",
+            "\n",
+            "- id: 'custom.2'\n",
+            "  name: Second\n",
+            "  pattern: '(b+)'\n",
+            "\n",
+            "rulesets:\n",
+            "- id: custom\n",
+            "  name: Custom\n",
+            "  description: Custom rules\n",
+            "  include_rule_ids: [custom.1, custom.2]\n",
+        );
+        let rules = Rules::from_paths_and_contents([(path, contents.as_bytes())]).unwrap();
+
+        let location = |line| SourceLocation {
+            path: path.to_owned(),
+            line: Some(line),
+        };
+        assert_eq!(rules.rule_sources.get("custom.1"), Some(&location(3)));
+        assert_eq!(rules.rule_sources.get("custom.2"), Some(&location(6)));
+        assert_eq!(rules.ruleset_sources.get("custom"), Some(&location(11)));
+    }
+
+    #[test]
+    fn rules_from_toml() {
+        use super::{Rules, SourceLocation};
+        use std::path::Path;
+
+        let path = Path::new("custom.toml");
+        let contents = concat!(
+            "[[rules]]\n",
+            "name = \"First\"\n",
+            "id = \"custom.1\"\n",
+            "pattern = '(a+)'\n",
+            "examples = ['aaa']\n",
+            "\n",
+            "[[rulesets]]\n",
+            "id = \"custom\"\n",
+            "name = \"Custom\"\n",
+            "description = \"Custom rules\"\n",
+            "include_rule_ids = [\"custom.1\"]\n",
+        );
+        let rules = Rules::from_paths_and_contents([(path, contents.as_bytes())]).unwrap();
+
+        assert_eq!(rules.num_rules(), 1);
+        assert_eq!(rules.rules[0].pattern, "(a+)");
+        assert_eq!(rules.rules[0].examples, vec!["aaa".to_string()]);
+        assert_eq!(rules.rules[0].description, None);
+        assert_eq!(rules.num_rulesets(), 1);
+        assert_eq!(rules.rulesets[0].include_rule_ids, vec!["custom.1".to_string()]);
+
+        let location = |line| SourceLocation {
+            path: path.to_owned(),
+            line: Some(line),
+        };
+        assert_eq!(rules.rule_sources.get("custom.1"), Some(&location(3)));
+        assert_eq!(rules.ruleset_sources.get("custom"), Some(&location(8)));
+    }
+
+    #[test]
+    fn rules_fingerprint() {
+        use super::{rules_fingerprint, Rule, RuleSyntax};
+
+        let rule = |id: &str, pattern: &str| {
+            Rule::new(RuleSyntax {
+                name: id.to_string(),
+                id: id.to_string(),
+                pattern: pattern.to_string(),
+                examples: vec![],
+                negative_examples: vec![],
+                references: vec![],
+                categories: vec![],
+                description: None,
+                path_include: vec![],
+                path_exclude: vec![],
+            })
+        };
+        let r1 = rule("r.1", "(a+)");
+        let r2 = rule("r.2", "(b+)");
+        let r2_renamed = rule("other.2", "(b+)");
+        let r3 = rule("r.3", "(c+)");
+
+        assert_eq!(rules_fingerprint([&r1, &r2]), rules_fingerprint([&r2_renamed, &r1]));
+        assert_ne!(rules_fingerprint([&r1, &r2]), rules_fingerprint([&r1, &r2, &r3]));
+    }
+
     #[test]
     #[should_panic]
     fn failure() {
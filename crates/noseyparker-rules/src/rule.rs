@@ -1,10 +1,12 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use lazy_static::lazy_static;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
+use std::path::Path;
 
-use noseyparker_digest::sha1_hexdigest;
+use noseyparker_digest::{sha1_hexdigest, Sha1};
 
 /// A pattern-based rule as represented syntactically.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
@@ -37,6 +39,16 @@ pub struct RuleSyntax {
     /// A list of string categories for the rule
     #[serde(default)]
     pub categories: Vec<String>,
+
+    /// Glob patterns for the paths of blobs in which matches from this rule are reported.
+    ///
+    /// If nonempty, matches from a blob are dropped unless one of its paths matches one of these.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path_include: Vec<String>,
+
+    /// Glob patterns for the paths of blobs in which matches from this rule are _not_ reported
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path_exclude: Vec<String>,
 }
 
 lazy_static! {
@@ -86,6 +98,8 @@ impl RuleSyntax {
     ///     negative_examples: vec![],
     ///     references: vec![],
     ///     categories: vec![],
+    ///     path_include: vec![],
+    ///     path_exclude: vec![],
     /// };
     /// assert_eq!(r.as_anchored_regex().unwrap().as_str(), r"hello\s*world\z");
     /// ```
@@ -98,10 +112,144 @@ impl RuleSyntax {
         sha1_hexdigest(self.pattern.as_bytes())
     }
 
+    /// Compile the `path_include` and `path_exclude` globs of this rule into a `PathFilter`.
+    ///
+    /// Returns `None` if the rule has no path constraints.
+    pub fn path_filter(&self) -> Result<Option<PathFilter>> {
+        if self.path_include.is_empty() && self.path_exclude.is_empty() {
+            return Ok(None);
+        }
+
+        fn build_globset(globs: &[String]) -> Result<Option<GlobSet>> {
+            if globs.is_empty() {
+                return Ok(None);
+            }
+            let mut builder = GlobSetBuilder::new();
+            for glob in globs {
+                builder.add(Glob::new(glob).with_context(|| format!("Invalid glob `{glob}`"))?);
+            }
+            Ok(Some(builder.build()?))
+        }
+
+        Ok(Some(PathFilter {
+            include: build_globset(&self.path_include)?,
+            exclude: build_globset(&self.path_exclude)?,
+        }))
+    }
+
     /// Return a JSON serialization of this rule.
     pub fn to_json(&self) -> String {
         serde_json::to_string(self).expect("should be able to serialize rule syntax as JSON")
     }
+
+    /// Check this rule's pattern against its examples and negative examples.
+    ///
+    /// Examples:
+    ///
+    /// ```
+    /// # use pretty_assertions::assert_eq;
+    /// # use noseyparker_rules::{ExampleStats, RuleSyntax};
+    /// let r = RuleSyntax {
+    ///     name: "Test rule".to_string(),
+    ///     id: "test.1".to_string(),
+    ///     pattern: r"hello\s*(world)".to_string(),
+    ///     description: None,
+    ///     examples: vec!["hello world".to_string(), "hello there".to_string()],
+    ///     negative_examples: vec!["goodbye world".to_string()],
+    ///     references: vec![],
+    ///     categories: vec![],
+    ///     path_include: vec![],
+    ///     path_exclude: vec![],
+    /// };
+    /// let stats = r.check_examples().unwrap();
+    /// assert_eq!(stats, ExampleStats { num_examples: 2, num_negative_examples: 1, num_passed: 2 });
+    /// assert!(!stats.all_passed());
+    /// ```
+    pub fn check_examples(&self) -> Result<ExampleStats> {
+        let pat = self.as_regex()?;
+        let num_passed = self
+            .examples
+            .iter()
+            .filter(|e| pat.is_match(e.as_bytes()))
+            .count()
+            + self
+                .negative_examples
+                .iter()
+                .filter(|e| !pat.is_match(e.as_bytes()))
+                .count();
+        Ok(ExampleStats {
+            num_examples: self.examples.len(),
+            num_negative_examples: self.negative_examples.len(),
+            num_passed,
+        })
+    }
+}
+
+/// The compiled path constraints of a rule, used to decide whether matches from a blob with a
+/// given path are reported.
+///
+/// A path is accepted if it matches some `path_include` glob (or there are none), and does not
+/// match any `path_exclude` glob.
+///
+/// Examples:
+///
+/// ```
+/// # use noseyparker_rules::RuleSyntax;
+/// # use std::path::Path;
+/// let r = RuleSyntax {
+///     name: "Test rule".to_string(),
+///     id: "test.1".to_string(),
+///     pattern: r"secret=(\w+)".to_string(),
+///     description: None,
+///     examples: vec![],
+///     negative_examples: vec![],
+///     references: vec![],
+///     categories: vec![],
+///     path_include: vec!["*.yaml".to_string(), "*.yml".to_string()],
+///     path_exclude: vec!["**/test/**".to_string()],
+/// };
+/// let filter = r.path_filter().unwrap().unwrap();
+/// assert!(filter.is_match(Path::new("deploy/secret.yaml")));
+/// assert!(!filter.is_match(Path::new("deploy/secret.json")));
+/// assert!(!filter.is_match(Path::new("src/test/secret.yaml")));
+/// ```
+#[derive(Clone, Debug)]
+pub struct PathFilter {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl PathFilter {
+    /// Does the given path satisfy these path constraints?
+    pub fn is_match(&self, path: &Path) -> bool {
+        self.include.as_ref().map_or(true, |g| g.is_match(path))
+            && !self.exclude.as_ref().is_some_and(|g| g.is_match(path))
+    }
+}
+
+/// The results of checking a rule's pattern against its examples.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ExampleStats {
+    /// The number of examples the rule is expected to match
+    pub num_examples: usize,
+
+    /// The number of examples the rule is expected _not_ to match
+    pub num_negative_examples: usize,
+
+    /// The number of examples and negative examples for which the rule behaves as expected
+    pub num_passed: usize,
+}
+
+impl ExampleStats {
+    /// The total number of examples and negative examples
+    pub fn num_total(&self) -> usize {
+        self.num_examples + self.num_negative_examples
+    }
+
+    /// Did the rule behave as expected for every example and negative example?
+    pub fn all_passed(&self) -> bool {
+        self.num_passed == self.num_total()
+    }
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
@@ -139,3 +287,20 @@ impl Rule {
         &self.syntax.id
     }
 }
+
+/// Compute a fingerprint for a set of rules.
+///
+/// This is a hex-encoded SHA-1 digest of the distinct structural IDs of the rules, so it depends
+/// only on what the rules detect, and not on their order, names, or other metadata.
+pub fn rules_fingerprint<'a, I: IntoIterator<Item = &'a Rule>>(rules: I) -> String {
+    let mut structural_ids: Vec<&str> = rules.into_iter().map(|r| r.structural_id()).collect();
+    structural_ids.sort_unstable();
+    structural_ids.dedup();
+
+    let mut h = Sha1::new();
+    for structural_id in structural_ids {
+        h.update(structural_id.as_bytes());
+        h.update(b"\n");
+    }
+    h.hexdigest()
+}
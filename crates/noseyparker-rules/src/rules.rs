@@ -2,10 +2,21 @@ use anyhow::{bail, Context, Result};
 use ignore::types::TypesBuilder;
 use ignore::WalkBuilder;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use tracing::{debug, debug_span};
 
-use crate::{util, RuleSyntax, RulesetSyntax};
+use crate::{RuleSyntax, RulesetSyntax};
+
+/// The location that a rule or ruleset was loaded from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// The path of the file that the rule or ruleset was loaded from
+    pub path: PathBuf,
+
+    /// The 1-based line number of the `id` field of the rule or ruleset, if it could be determined
+    pub line: Option<usize>,
+}
 
 /// A collection of rules and rulesets
 #[derive(Serialize, Deserialize, Clone)]
@@ -15,6 +26,14 @@ pub struct Rules {
 
     #[serde(default)]
     pub rulesets: Vec<RulesetSyntax>,
+
+    /// Where each rule was loaded from, keyed by rule ID
+    #[serde(skip)]
+    pub rule_sources: HashMap<String, SourceLocation>,
+
+    /// Where each ruleset was loaded from, keyed by ruleset ID
+    #[serde(skip)]
+    pub ruleset_sources: HashMap<String, SourceLocation>,
 }
 
 impl Rules {
@@ -23,13 +42,46 @@ impl Rules {
         Self {
             rules: Vec::new(),
             rulesets: Vec::new(),
+            rule_sources: HashMap::new(),
+            ruleset_sources: HashMap::new(),
         }
     }
 
     /// Update this collection of rules by adding those from another collection.
+    ///
+    /// If a rule or ruleset ID appears in both collections, the existing source location is kept.
     pub fn update(&mut self, other: Rules) {
         self.rules.extend(other.rules);
         self.rulesets.extend(other.rulesets);
+        for (id, source) in other.rule_sources {
+            self.rule_sources.entry(id).or_insert(source);
+        }
+        for (id, source) in other.ruleset_sources {
+            self.ruleset_sources.entry(id).or_insert(source);
+        }
+    }
+
+    /// Record `path` as the source of the rules and rulesets in this collection that don't already
+    /// have one, using `contents` (the YAML or TOML that was loaded from `path`) to determine line
+    /// numbers.
+    fn record_sources(&mut self, path: &Path, contents: &str) {
+        let id_lines = find_id_lines(contents);
+        let location = |id: &str| SourceLocation {
+            path: path.to_owned(),
+            line: id_lines.get(id).copied(),
+        };
+        for rule in self.rules.iter() {
+            if !self.rule_sources.contains_key(&rule.id) {
+                self.rule_sources
+                    .insert(rule.id.clone(), location(&rule.id));
+            }
+        }
+        for ruleset in self.rulesets.iter() {
+            if !self.ruleset_sources.contains_key(&ruleset.id) {
+                self.ruleset_sources
+                    .insert(ruleset.id.clone(), location(&ruleset.id));
+            }
+        }
     }
 
     // Load from an iterable of `(path, contents)`.
@@ -38,15 +90,16 @@ impl Rules {
     ) -> Result<Self> {
         let mut rules = Self::new();
         for (path, contents) in iterable.into_iter() {
-            let rs: Self = serde_yaml::from_reader(contents)
-                .with_context(|| format!("Failed to load rules YAML from {}", path.display()))?;
-            rules.update(rs);
+            let contents = std::str::from_utf8(contents)
+                .with_context(|| format!("Failed to load rules from {}", path.display()))?;
+            rules.update(Self::from_path_and_str(path, contents)?);
         }
 
         Ok(rules)
     }
 
-    /// Load rules from the given paths, which may refer either to YAML files or to directories.
+    /// Load rules from the given paths, which may refer either to YAML or TOML files or to
+    /// directories.
     pub fn from_paths<P: AsRef<Path>, I: IntoIterator<Item = P>>(paths: I) -> Result<Self> {
         let mut num_paths = 0;
         let mut rules = Rules::new();
@@ -54,7 +107,7 @@ impl Rules {
             num_paths += 1;
             let input = input.as_ref();
             if input.is_file() {
-                rules.update(Rules::from_yaml_file(input)?);
+                rules.update(Rules::from_file(input)?);
             } else if input.is_dir() {
                 rules.update(Rules::from_directory(input)?);
             } else {
@@ -69,12 +122,28 @@ impl Rules {
         Ok(rules)
     }
 
-    /// Load rules from the given YAML file.
-    pub fn from_yaml_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+    /// Parse rules from `contents`, which were loaded from `path`.
+    ///
+    /// Files with a `.toml` extension are parsed as TOML; anything else is parsed as YAML.
+    fn from_path_and_str(path: &Path, contents: &str) -> Result<Self> {
+        let mut rules: Self = if is_toml_path(path) {
+            toml::from_str(contents)
+                .with_context(|| format!("Failed to load rules TOML from {}", path.display()))?
+        } else {
+            serde_yaml::from_str(contents)
+                .with_context(|| format!("Failed to load rules YAML from {}", path.display()))?
+        };
+        rules.record_sources(path, contents);
+        Ok(rules)
+    }
+
+    /// Load rules from the given YAML or TOML file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
         let path = path.as_ref();
-        let _span = debug_span!("Rules::from_yaml_file", "{}", path.display()).entered();
-        let rules: Self = util::load_yaml_file(path)
-            .with_context(|| format!("Failed to load rules YAML from {}", path.display()))?;
+        let _span = debug_span!("Rules::from_file", "{}", path.display()).entered();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to load rules from {}", path.display()))?;
+        let rules = Self::from_path_and_str(path, &contents)?;
         debug!(
             "Loaded {} rules and {} rulesets from {}",
             rules.num_rules(),
@@ -84,13 +153,13 @@ impl Rules {
         Ok(rules)
     }
 
-    /// Load rules from the given YAML files.
-    pub fn from_yaml_files<P: AsRef<Path>, I: IntoIterator<Item = P>>(paths: I) -> Result<Self> {
+    /// Load rules from the given YAML or TOML files.
+    pub fn from_files<P: AsRef<Path>, I: IntoIterator<Item = P>>(paths: I) -> Result<Self> {
         let mut num_paths = 0;
         let mut rules = Rules::new();
         for path in paths {
             num_paths += 1;
-            rules.update(Rules::from_yaml_file(path.as_ref())?);
+            rules.update(Rules::from_file(path.as_ref())?);
         }
         debug!(
             "Loaded {} rules and {} rulesets from {num_paths} paths",
@@ -100,29 +169,33 @@ impl Rules {
         Ok(rules)
     }
 
-    /// Load rules from YAML files found recursively within the given directory.
+    /// Load rules from YAML and TOML files found recursively within the given directory.
     pub fn from_directory<P: AsRef<Path>>(path: P) -> Result<Self> {
         let path = path.as_ref();
         let _span = debug_span!("Rules::from_directory", "{}", path.display()).entered();
 
-        let yaml_types = TypesBuilder::new().add_defaults().select("yaml").build()?;
+        let rules_types = TypesBuilder::new()
+            .add_defaults()
+            .select("yaml")
+            .select("toml")
+            .build()?;
 
         let walker = WalkBuilder::new(path)
-            .types(yaml_types)
+            .types(rules_types)
             .follow_links(true)
             .standard_filters(false)
             .build();
-        let mut yaml_files = Vec::new();
+        let mut rules_files = Vec::new();
         for entry in walker {
             let entry = entry?;
             if entry.file_type().map_or(false, |t| !t.is_dir()) {
-                yaml_files.push(entry.into_path());
+                rules_files.push(entry.into_path());
             }
         }
-        yaml_files.sort();
-        debug!("Found {} rules files to load within {}", yaml_files.len(), path.display());
+        rules_files.sort();
+        debug!("Found {} rules files to load within {}", rules_files.len(), path.display());
 
-        Self::from_yaml_files(&yaml_files)
+        Self::from_files(&rules_files)
     }
 
     /// How many rules are in this collection?
@@ -154,6 +227,40 @@ impl Rules {
     }
 }
 
+/// Is the given path a TOML file, according to its extension?
+fn is_toml_path(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"))
+}
+
+/// Find the 1-based line number of each `id` field in the given YAML or TOML text.
+///
+/// This is a line-oriented approximation rather than a real YAML or TOML parse, but it handles the
+/// way that rules files are conventionally written, e.g., `- id: np.aws.1`, `  id: 'np.aws.1'`, or
+/// `id = "np.aws.1"`.
+fn find_id_lines(contents: &str) -> HashMap<&str, usize> {
+    let mut id_lines = HashMap::new();
+    for (line_num, line) in contents.lines().enumerate() {
+        let line = line.trim_start();
+        let line = line.strip_prefix('-').map_or(line, str::trim_start);
+        let Some(value) = line.strip_prefix("id:").or_else(|| {
+            line.strip_prefix("id")
+                .map(str::trim_start)?
+                .strip_prefix('=')
+        }) else {
+            continue;
+        };
+        let value = value.trim();
+        let value = value
+            .strip_prefix('\'')
+            .and_then(|v| v.strip_suffix('\''))
+            .or_else(|| value.strip_prefix('"').and_then(|v| v.strip_suffix('"')))
+            .unwrap_or(value);
+        id_lines.entry(value).or_insert(line_num + 1);
+    }
+    id_lines
+}
+
 /// Creates an empty collection of rules.
 impl Default for Rules {
     fn default() -> Self {
@@ -18,7 +18,12 @@ pub struct RulesetSyntax {
     pub description: String,
 
     /// A list of rule IDs included in the ruleset
+    #[serde(default)]
     pub include_rule_ids: Vec<String>,
+
+    /// A list of other ruleset IDs whose rules are included in the ruleset
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include_ruleset_ids: Vec<String>,
 }
 
 impl RulesetSyntax {
@@ -32,7 +37,9 @@ impl RulesetSyntax {
         Ok(ruleset)
     }
 
-    /// How many rules are listed in this ruleset?
+    /// How many rules are listed directly in this ruleset?
+    ///
+    /// This does not count the rules of included rulesets.
     pub fn num_rules(&self) -> usize {
         self.include_rule_ids.len()
     }
@@ -1,7 +1,9 @@
 use anyhow::Result;
+use noseyparker_digest::Sha1;
+use std::io::{Read, Write};
 use std::path::Path;
 
-pub use crate::blob_id::BlobId;
+pub use crate::blob_id::{BlobId, BlobIdAlgorithm};
 
 // -------------------------------------------------------------------------------------------------
 // Blob
@@ -19,12 +21,47 @@ impl Blob {
         Ok(Blob { id, bytes })
     }
 
+    /// Create a `Blob` by reading all of the given input, computing its ID as the input is read
+    /// rather than in a second pass over the content.
+    ///
+    /// The `size_hint` should be the exact size of the input, such as from file metadata, as the
+    /// ID of a blob depends on its size. If the input turns out to have a different size, such as
+    /// from a file being modified while it is read, the ID is recomputed from the content read.
+    pub fn from_reader<R: Read>(
+        mut reader: R,
+        size_hint: u64,
+        algorithm: BlobIdAlgorithm,
+    ) -> Result<Self> {
+        let mut writer = HashingWriter {
+            bytes: Vec::with_capacity(size_hint.try_into().unwrap_or(0)),
+            hasher: BlobIdHasher::new(algorithm, size_hint),
+        };
+        std::io::copy(&mut reader, &mut writer)?;
+
+        let HashingWriter { bytes, hasher } = writer;
+        if bytes.len() as u64 == size_hint {
+            Ok(Blob {
+                id: hasher.finish(),
+                bytes,
+            })
+        } else {
+            Ok(Blob::from_bytes_with_algorithm(bytes, algorithm))
+        }
+    }
+
     #[inline]
     pub fn from_bytes(bytes: Vec<u8>) -> Self {
         let id = BlobId::compute_from_bytes(&bytes);
         Blob { id, bytes }
     }
 
+    /// Create a `Blob` with the given content, computing its ID using the given algorithm.
+    #[inline]
+    pub fn from_bytes_with_algorithm(bytes: Vec<u8>, algorithm: BlobIdAlgorithm) -> Self {
+        let id = algorithm.compute(&bytes);
+        Blob { id, bytes }
+    }
+
     /// Create a new `Blob` with the given ID and content.
     ///
     /// It is not checked that the ID matches that of the provided content.
@@ -45,3 +82,105 @@ impl Blob {
         self.bytes.is_empty()
     }
 }
+
+/// An incremental computation of a blob ID
+enum BlobIdHasher {
+    Sha1(Sha1),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl BlobIdHasher {
+    /// Begin computing the ID of a blob of the given size.
+    fn new(algorithm: BlobIdAlgorithm, size: u64) -> Self {
+        match algorithm {
+            BlobIdAlgorithm::Sha1 => {
+                let mut hasher = Sha1::new();
+                write!(&mut hasher, "blob {size}\0").unwrap();
+                BlobIdHasher::Sha1(hasher)
+            }
+            BlobIdAlgorithm::Blake3 => BlobIdHasher::Blake3(Box::default()),
+        }
+    }
+
+    #[inline]
+    fn update(&mut self, buf: &[u8]) {
+        match self {
+            BlobIdHasher::Sha1(h) => h.update(buf),
+            BlobIdHasher::Blake3(h) => {
+                h.update(buf);
+            }
+        }
+    }
+
+    fn finish(self) -> BlobId {
+        match self {
+            BlobIdHasher::Sha1(h) => BlobId::from_digest(h.digest()),
+            BlobIdHasher::Blake3(h) => BlobId::from_blake3(h.finalize()),
+        }
+    }
+}
+
+/// A writer that accumulates its input while computing its blob ID
+struct HashingWriter {
+    bytes: Vec<u8>,
+    hasher: BlobIdHasher,
+}
+
+impl Write for HashingWriter {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.bytes.extend_from_slice(buf);
+        self.hasher.update(buf);
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn from_reader_matches_from_file() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let path = tmp.path().join("input.txt");
+        // larger than the buffer size used by `std::io::copy`, to exercise incremental hashing
+        let content: Vec<u8> = (0..100_000u32).flat_map(|i| i.to_le_bytes()).collect();
+        std::fs::write(&path, &content)?;
+
+        let expected = Blob::from_file(&path)?;
+        let size = std::fs::metadata(&path)?.len();
+        let actual = Blob::from_reader(std::fs::File::open(&path)?, size, BlobIdAlgorithm::Sha1)?;
+        assert_eq!(actual.id, expected.id);
+        assert_eq!(actual.bytes, expected.bytes);
+        Ok(())
+    }
+
+    #[test]
+    fn from_reader_blake3() -> Result<()> {
+        let content: Vec<u8> = (0..100_000u32).flat_map(|i| i.to_le_bytes()).collect();
+        let size = content.len() as u64;
+        let expected = BlobIdAlgorithm::Blake3.compute(&content);
+        let actual = Blob::from_reader(&content[..], size, BlobIdAlgorithm::Blake3)?;
+        assert_eq!(actual.id, expected);
+        assert_ne!(actual.id, BlobId::compute_from_bytes(&content));
+        assert_eq!(Blob::from_reader(&content[..], 5, BlobIdAlgorithm::Blake3)?.id, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn from_reader_wrong_size_hint() -> Result<()> {
+        let content = b"hello world\n";
+        let expected = BlobId::compute_from_bytes(content);
+        let sha1 = BlobIdAlgorithm::Sha1;
+        assert_eq!(Blob::from_reader(&content[..], 5, sha1)?.id, expected);
+        assert_eq!(Blob::from_reader(&content[..], 100, sha1)?.id, expected);
+        assert_eq!(Blob::from_reader(&b""[..], 0, sha1)?.id, BlobId::compute_from_bytes(b""));
+        Ok(())
+    }
+}
@@ -60,6 +60,20 @@ impl BlobId {
         BlobId(h.digest())
     }
 
+    /// Create a `BlobId` from an already-computed SHA-1 digest of the Git blob header and content.
+    #[inline]
+    pub(crate) fn from_digest(digest: noseyparker_digest::Sha1Digest) -> Self {
+        BlobId(digest)
+    }
+
+    /// Create a `BlobId` from a BLAKE3 digest of a blob's content, truncated to 20 bytes.
+    #[inline]
+    pub(crate) fn from_blake3(hash: blake3::Hash) -> Self {
+        let mut id = [0; 20];
+        id.copy_from_slice(&hash.as_bytes()[..20]);
+        BlobId(id)
+    }
+
     /// Create new new `BlobId` from a hexadecimal string.
     #[inline]
     pub fn from_hex(v: &str) -> Result<Self> {
@@ -138,6 +152,61 @@ impl From<BlobId> for gix::ObjectId {
     }
 }
 
+// -------------------------------------------------------------------------------------------------
+// BlobIdAlgorithm
+// -------------------------------------------------------------------------------------------------
+/// The algorithm used to compute the ID of a blob from its content
+///
+/// This only applies to blobs whose IDs are not already known: blobs from Git repositories always
+/// use the IDs that Git assigns them.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlobIdAlgorithm {
+    /// The SHA-1 digest of the content with a Git blob header, i.e., the ID Git would assign
+    #[default]
+    Sha1,
+
+    /// The BLAKE3 digest of the content, truncated to 20 bytes.
+    ///
+    /// This is considerably faster to compute than SHA-1, but does not match the ID Git would
+    /// assign.
+    Blake3,
+}
+
+impl BlobIdAlgorithm {
+    /// Compute the ID of a blob with the given content.
+    #[inline]
+    pub fn compute(self, input: &[u8]) -> BlobId {
+        match self {
+            Self::Sha1 => BlobId::compute_from_bytes(input),
+            Self::Blake3 => BlobId::from_blake3(blake3::hash(input)),
+        }
+    }
+
+    /// The name of this algorithm, as recorded in a datastore.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sha1 => "sha1",
+            Self::Blake3 => "blake3",
+        }
+    }
+
+    /// Get the algorithm with the given name, as recorded in a datastore.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sha1" => Some(Self::Sha1),
+            "blake3" => Some(Self::Blake3),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for BlobIdAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 // -------------------------------------------------------------------------------------------------
 // sql
 // -------------------------------------------------------------------------------------------------
@@ -179,4 +248,21 @@ mod test {
             "06d7405020018ddf3cacee90fd4af10487da3d20"
         );
     }
+
+    #[test]
+    fn algorithms() {
+        assert_eq!(
+            BlobIdAlgorithm::Sha1.compute(b"").hex(),
+            "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391"
+        );
+        assert_eq!(
+            BlobIdAlgorithm::Blake3.compute(b"").hex(),
+            "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9"
+        );
+        for algorithm in [BlobIdAlgorithm::Sha1, BlobIdAlgorithm::Blake3] {
+            assert_eq!(BlobIdAlgorithm::from_name(algorithm.name()), Some(algorithm));
+            let json = serde_json::to_string(&algorithm).unwrap();
+            assert_eq!(json, format!("\"{algorithm}\""));
+        }
+    }
 }
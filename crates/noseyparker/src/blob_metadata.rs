@@ -1,7 +1,7 @@
 use crate::blob_id::BlobId;
 
 /// Metadata about a blob
-#[derive(Debug, serde::Serialize, schemars::JsonSchema)]
+#[derive(Debug, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
 pub struct BlobMetadata {
     /// The blob ID this metadata applies to
     pub id: BlobId,
@@ -14,6 +14,10 @@ pub struct BlobMetadata {
 
     /// The guessed charset of the blob
     pub charset: Option<String>,
+
+    /// The number of matches found in the blob that were not recorded, if any were omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_truncated_matches: Option<usize>,
 }
 
 impl BlobMetadata {
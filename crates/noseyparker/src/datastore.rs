@@ -1,11 +1,13 @@
 use anyhow::{bail, Context, Result};
 use bstr::BString;
 use indoc::indoc;
-use noseyparker_rules::Rule;
+use noseyparker_digest::sha1_hexdigest;
+use noseyparker_rules::{Rule, RuleSyntax};
 use rusqlite::Connection;
 use std::path::{Path, PathBuf};
 use tracing::{debug, debug_span, info, trace};
 
+use crate::blob_id::{BlobId, BlobIdAlgorithm};
 use crate::blob_metadata::BlobMetadata;
 use crate::git_url::GitUrl;
 use crate::location::{Location, OffsetSpan, SourcePoint, SourceSpan};
@@ -14,19 +16,42 @@ use crate::provenance::Provenance;
 use crate::provenance_set::ProvenanceSet;
 use crate::snippet::Snippet;
 
-const CURRENT_SCHEMA_VERSION: u64 = 70;
-const CURRENT_SCHEMA: &str = include_str!("datastore/schema_70.sql");
+const CURRENT_SCHEMA_VERSION: u64 = 76;
+
+/// The oldest schema version that can be migrated to the current one
+const MIN_SUPPORTED_SCHEMA_VERSION: u64 = 70;
+const SCHEMA_70: &str = include_str!("datastore/schema_70.sql");
+const MIGRATION_71: &str = include_str!("datastore/migration_71.sql");
+const MIGRATION_72: &str = include_str!("datastore/migration_72.sql");
+const MIGRATION_73: &str = include_str!("datastore/migration_73.sql");
+const MIGRATION_74: &str = include_str!("datastore/migration_74.sql");
+const MIGRATION_75: &str = include_str!("datastore/migration_75.sql");
+const MIGRATION_76: &str = include_str!("datastore/migration_76.sql");
+
+/// The default fraction of two overlapping matches that must overlap for one to be considered
+/// redundant to the other
+pub const DEFAULT_REDUNDANCY_OVERLAP: f64 = 0.20;
 
 pub mod annotation;
+pub mod blob_data;
+pub mod check;
+pub mod export_record;
 pub mod finding_data;
 pub mod finding_metadata;
 pub mod finding_summary;
+pub mod scan_run;
 pub mod status;
 
-pub use annotation::{Annotations, FindingAnnotation, MatchAnnotation};
-pub use finding_data::{FindingData, FindingDataEntry};
+pub use annotation::{Annotations, BulkFindingAnnotation, FindingAnnotation, MatchAnnotation};
+pub use blob_data::{BlobData, BlobDataEntry};
+pub use check::DatastoreCheck;
+pub use export_record::{
+    BlobRecord, ExportHeader, ExportRecord, ScoredMatch, EXPORT_SCHEMA_VERSION,
+};
+pub use finding_data::{FindingData, FindingDataEntry, FindingOccurrences};
 pub use finding_metadata::FindingMetadata;
 pub use finding_summary::{FindingSummary, FindingSummaryEntry};
+pub use scan_run::{ScanRun, ScanRunMetadata};
 pub use status::{Status, Statuses};
 
 // -------------------------------------------------------------------------------------------------
@@ -71,10 +96,23 @@ impl Datastore {
     }
 
     /// Open the existing datastore at `root_dir`.
+    ///
+    /// If the datastore's database cannot be written, e.g., because it resides on a read-only
+    /// filesystem, it is opened read-only: no schema migration is attempted, no subdirectories are
+    /// created, and any attempt to write to the datastore will fail.
     pub fn open(root_dir: &Path, cache_size: i64) -> Result<Self> {
         debug!("Attempting to open existing datastore at {}", root_dir.display());
 
-        let ds = Self::open_impl(root_dir, cache_size)?;
+        let mut ds = Self::open_impl(root_dir, cache_size)?;
+        if ds.is_read_only()? {
+            debug!("Opened datastore at {} read-only", root_dir.display());
+            ds.check_schema_version()?;
+            return Ok(ds);
+        }
+
+        if ds.get_schema_version()? != 0 {
+            ds.migrate().context("Failed to migrate database schema")?;
+        }
         ds.check_schema_version()?;
 
         let scratch_dir = ds.scratch_dir();
@@ -95,6 +133,100 @@ impl Datastore {
         Ok(ds)
     }
 
+    /// Open the existing datastore at `root_dir` read-only, regardless of whether it could be
+    /// written.
+    ///
+    /// This is suitable for reading a datastore that another process may be writing to
+    /// concurrently, such as during a scan.
+    pub fn open_read_only(root_dir: &Path, cache_size: i64) -> Result<Self> {
+        debug!("Attempting to open existing datastore at {} read-only", root_dir.display());
+
+        let db_path = root_dir.join("datastore.db");
+        let conn = Self::new_read_only_connection(&db_path, cache_size)?;
+        let root_dir = root_dir.to_path_buf();
+        let ds = Self { root_dir, conn };
+        ds.check_schema_version()?;
+        Ok(ds)
+    }
+
+    /// Check the integrity of the existing datastore at `root_dir`, without modifying it.
+    ///
+    /// This checks the integrity of the datastore's sqlite database, its schema version, the
+    /// consistency of its foreign keys, and the presence of its subdirectories.
+    /// An error is returned only if the checks could not be run at all.
+    pub fn check(root_dir: &Path, cache_size: i64) -> Result<Vec<DatastoreCheck>> {
+        let db_path = root_dir.join("datastore.db");
+        if !db_path.is_file() {
+            bail!("No datastore database at {}", db_path.display());
+        }
+        let conn = Self::new_read_only_connection(&db_path, cache_size)?;
+        let ds = Self {
+            root_dir: root_dir.to_path_buf(),
+            conn,
+        };
+
+        let mut checks = Vec::new();
+
+        let mut problems: Vec<String> = Vec::new();
+        {
+            let mut stmt = ds.conn.prepare("pragma integrity_check")?;
+            let mut rows = stmt.query(())?;
+            while let Some(row) = rows.next()? {
+                let msg: String = row.get(0)?;
+                if msg != "ok" {
+                    problems.push(msg);
+                }
+            }
+        }
+        checks.push(DatastoreCheck {
+            name: "database integrity",
+            problems,
+        });
+
+        let mut problems = Vec::new();
+        let user_version = ds.get_schema_version()?;
+        if user_version != CURRENT_SCHEMA_VERSION {
+            problems.push(format!(
+                "schema version is {user_version}, but {CURRENT_SCHEMA_VERSION} was expected"
+            ));
+        }
+        checks.push(DatastoreCheck {
+            name: "schema version",
+            problems,
+        });
+
+        let mut problems = Vec::new();
+        {
+            let mut stmt = ds.conn.prepare("pragma foreign_key_check")?;
+            let mut rows = stmt.query(())?;
+            while let Some(row) = rows.next()? {
+                let table: String = row.get(0)?;
+                let rowid: Option<i64> = row.get(1)?;
+                let parent: String = row.get(2)?;
+                let rowid = rowid.map_or("?".to_string(), |r| r.to_string());
+                problems.push(format!(
+                    "row {rowid} of table `{table}` refers to a missing row in table `{parent}`"
+                ));
+            }
+        }
+        checks.push(DatastoreCheck {
+            name: "foreign keys",
+            problems,
+        });
+
+        let problems = [ds.scratch_dir(), ds.clones_dir(), ds.blobs_dir()]
+            .into_iter()
+            .filter(|dir| !dir.is_dir())
+            .map(|dir| format!("directory {} is missing", dir.display()))
+            .collect();
+        checks.push(DatastoreCheck {
+            name: "directories",
+            problems,
+        });
+
+        Ok(checks)
+    }
+
     /// Create a new datastore at `root_dir` and open it.
     pub fn create(root_dir: &Path, cache_size: i64) -> Result<Self> {
         debug!("Attempting to create new datastore at {}", root_dir.display());
@@ -111,7 +243,7 @@ impl Datastore {
 
         let mut ds = Self::open_impl(root_dir, cache_size)?;
 
-        ds.migrate_0_70()
+        ds.migrate()
             .context("Failed to initialize database schema")?;
 
         Self::open(root_dir, cache_size)
@@ -137,11 +269,30 @@ impl Datastore {
         &self.root_dir
     }
 
+    /// Was this `Datastore` opened read-only?
+    pub fn is_read_only(&self) -> Result<bool> {
+        Ok(self.conn.is_readonly(rusqlite::DatabaseName::Main)?)
+    }
+
     /// Get a path for a local clone of the given git URL within this datastore's clones directory.
+    ///
+    /// URLs that are equivalent after normalization get the same path.
     pub fn clone_destination(&self, repo: &GitUrl) -> Result<std::path::PathBuf> {
         clone_destination(&self.clones_dir(), repo)
     }
 
+    /// Get a path for a local clone of the given Git bundle file within this datastore's clones
+    /// directory.
+    ///
+    /// Each bundle file gets a distinct path, based on its canonicalized path.
+    pub fn bundle_clone_destination(&self, bundle_path: &Path) -> Result<PathBuf> {
+        let bundle_path = std::fs::canonicalize(bundle_path).with_context(|| {
+            format!("Failed to canonicalize bundle path {}", bundle_path.display())
+        })?;
+        let digest = sha1_hexdigest(bundle_path.as_os_str().as_encoded_bytes());
+        Ok(self.clones_dir().join("bundle").join(digest))
+    }
+
     /// Analyze the datastore's sqlite database, potentially allowing for better query planning
     pub fn analyze(&self) -> Result<()> {
         let _span = debug_span!("Datastore::analyze", "{}", self.root_dir.display()).entered();
@@ -149,8 +300,179 @@ impl Datastore {
         // self.conn.execute("pragma wal_checkpoint(truncate)", [])?;
         Ok(())
     }
+
+    /// Reclaim unused space in the datastore's sqlite database.
+    ///
+    /// This rebuilds the database using `VACUUM` and then truncates its write-ahead log.
+    /// If another process holds a lock on the database, this fails after a short timeout rather
+    /// than waiting indefinitely.
+    pub fn vacuum(&mut self) -> Result<()> {
+        let _span = debug_span!("Datastore::vacuum", "{}", self.root_dir.display()).entered();
+        self.conn.busy_timeout(VACUUM_BUSY_TIMEOUT)?;
+        self.conn
+            .execute_batch("vacuum")
+            .context("Failed to vacuum database; is the datastore in use by another process?")?;
+        let busy: i64 = self
+            .conn
+            .query_row("pragma wal_checkpoint(truncate)", [], |row| row.get(0))
+            .context("Failed to checkpoint database write-ahead log")?;
+        if busy != 0 {
+            bail!(
+                "Failed to checkpoint database write-ahead log; \
+                   is the datastore in use by another process?"
+            );
+        }
+        Ok(())
+    }
+
+    /// Delete all findings of the rule with the given name or ID, along with their matches and
+    /// everything that refers to them: statuses, comments, scores, and redundancy records.
+    ///
+    /// Returns the number of findings deleted.
+    pub fn delete_findings_by_rule(&mut self, rule_name: &str) -> Result<u64> {
+        let _span =
+            debug_span!("Datastore::delete_findings_by_rule", "{}", self.root_dir.display())
+                .entered();
+        let tx = self.conn.transaction()?;
+
+        tx.execute_batch(indoc! {r#"
+            create temp table pruned_finding(id integer primary key);
+            create temp table pruned_match(id integer primary key);
+        "#})?;
+        tx.execute(
+            indoc! {r#"
+            insert into pruned_finding(id)
+            select finding.id
+            from finding inner join rule on (finding.rule_id = rule.id)
+            where rule.name = ?1 or rule.text_id = ?1
+        "#},
+            (rule_name,),
+        )?;
+        let num_deleted = Self::delete_pruned_findings(&tx)?;
+        tx.commit()?;
+        Ok(num_deleted)
+    }
+
+    /// Delete the findings with the given finding IDs, along with their matches and everything
+    /// that refers to them, like `delete_findings_by_rule`.
+    ///
+    /// Returns the number of findings deleted.
+    pub fn delete_findings(&mut self, finding_ids: &[String]) -> Result<u64> {
+        let _span =
+            debug_span!("Datastore::delete_findings", "{}", self.root_dir.display()).entered();
+        let tx = self.conn.transaction()?;
+
+        tx.execute_batch(indoc! {r#"
+            create temp table pruned_finding(id integer primary key);
+            create temp table pruned_match(id integer primary key);
+        "#})?;
+        {
+            let mut stmt = tx.prepare(indoc! {r#"
+                insert or ignore into pruned_finding(id)
+                select id from finding where finding_id = ?1
+            "#})?;
+            for finding_id in finding_ids {
+                stmt.execute((finding_id,))?;
+            }
+        }
+        let num_deleted = Self::delete_pruned_findings(&tx)?;
+        tx.commit()?;
+        Ok(num_deleted)
+    }
+
+    /// Delete the matches with the given integer IDs, along with everything that refers to them.
+    /// Findings that are left without any matches are deleted as well.
+    ///
+    /// Returns the number of matches and the number of findings deleted.
+    pub fn delete_matches(&mut self, match_ids: &[MatchIdInt]) -> Result<(u64, u64)> {
+        let _span =
+            debug_span!("Datastore::delete_matches", "{}", self.root_dir.display()).entered();
+        let tx = self.conn.transaction()?;
+
+        tx.execute_batch(indoc! {r#"
+            create temp table pruned_finding(id integer primary key);
+            create temp table pruned_match(id integer primary key);
+        "#})?;
+        {
+            let mut stmt = tx.prepare(indoc! {r#"
+                insert or ignore into pruned_match(id)
+                select id from match where id = ?1
+            "#})?;
+            for MatchIdInt(match_id) in match_ids {
+                stmt.execute((match_id,))?;
+            }
+        }
+        let num_matches_deleted: u64 =
+            tx.query_row("select count(*) from pruned_match", [], |row| row.get(0))?;
+        tx.execute_batch(indoc! {r#"
+            insert into pruned_finding(id)
+            select f.id
+            from finding f
+            where
+                f.id in (select finding_id from match where id in pruned_match)
+                and not exists (
+                    select 1 from match m
+                    where m.finding_id = f.id and m.id not in pruned_match
+                );
+        "#})?;
+        let num_findings_deleted = Self::delete_pruned_findings(&tx)?;
+        tx.commit()?;
+        Ok((num_matches_deleted, num_findings_deleted))
+    }
+
+    /// Delete the findings listed in the `pruned_finding` temporary table and the matches listed
+    /// in the `pruned_match` temporary table, along with the matches of those findings and
+    /// everything that refers to them, and then drop the temporary tables.
+    ///
+    /// Returns the number of findings deleted.
+    fn delete_pruned_findings(tx: &rusqlite::Transaction) -> Result<u64> {
+        tx.execute_batch(indoc! {r#"
+            insert or ignore into pruned_match(id)
+            select id from match where finding_id in pruned_finding;
+
+            delete from match_redundancy
+            where match_id in pruned_match or redundant_to in pruned_match;
+            delete from match_status where match_id in pruned_match;
+            delete from match_comment where match_id in pruned_match;
+            delete from match_score where match_id in pruned_match;
+            delete from match where id in pruned_match;
+
+            delete from finding_comment where finding_id in pruned_finding;
+            delete from finding where id in pruned_finding;
+        "#})?;
+        let num_deleted: u64 =
+            tx.query_row("select count(*) from pruned_finding", [], |row| row.get(0))?;
+        tx.execute_batch(indoc! {r#"
+            drop table pruned_finding;
+            drop table pruned_match;
+        "#})?;
+        Ok(num_deleted)
+    }
+
+    /// Write a compacted copy of this datastore's database into a new datastore at `root_dir`,
+    /// and open it.
+    ///
+    /// Only the database is copied; the new datastore has no blobs or clones.
+    pub fn copy_database_to(&self, root_dir: &Path, cache_size: i64) -> Result<Self> {
+        let _span =
+            debug_span!("Datastore::copy_database_to", "{}", self.root_dir.display()).entered();
+        std::fs::create_dir_all(root_dir).with_context(|| {
+            format!("Failed to create datastore root directory at {}", root_dir.display())
+        })?;
+        let db_path = root_dir.join("datastore.db");
+        let db_path_str = db_path
+            .to_str()
+            .with_context(|| format!("Path {} is not valid UTF-8", db_path.display()))?;
+        self.conn
+            .execute("vacuum into ?1", (db_path_str,))
+            .with_context(|| format!("Failed to copy database to {}", db_path.display()))?;
+        Self::open(root_dir, cache_size)
+    }
 }
 
+/// How long `Datastore::vacuum` waits to acquire a lock on the database before giving up
+const VACUUM_BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
 /// A datastore-specific ID of a blob; simply a newtype-like wrapper around an i64.
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 struct BlobIdInt(i64);
@@ -167,6 +489,10 @@ struct SnippetIdInt(i64);
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct MatchIdInt(i64);
 
+/// A datastore-specific ID of a scan run; simply a newtype-like wrapper around an i64.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ScanRunIdInt(i64);
+
 pub type BatchEntry = (ProvenanceSet, BlobMetadata, Vec<(Option<f64>, Match)>);
 
 /// A datastore transaction.
@@ -219,6 +545,72 @@ impl<'ds> Transaction<'ds> {
         Ok(())
     }
 
+    /// Record the start of a new scan run with the given metadata, returning its integer ID.
+    ///
+    /// The rules of the scan run are recorded as well.
+    pub fn record_scan_run(&self, metadata: &ScanRunMetadata) -> Result<ScanRunIdInt> {
+        let argv = if metadata.argv.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(metadata.argv)?)
+        };
+        let id: i64 = self.inner.query_row(
+            indoc! {r#"
+                insert into scan_run(started_at, rules_fingerprint, version, argv, hostname)
+                values (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), ?, ?, ?, ?)
+                returning id
+            "#},
+            (metadata.rules_fingerprint, metadata.version, argv, metadata.hostname),
+            val_from_row,
+        )?;
+
+        let mut add_label = self.inner.prepare_cached(indoc! {r#"
+            insert into scan_run_label(scan_run_id, key, value)
+            values (?, ?, ?)
+            on conflict do update set value = excluded.value
+        "#})?;
+        for (key, value) in metadata.labels {
+            add_label.execute((id, key, value))?;
+        }
+
+        let mut record_rule = self.mk_record_rule()?;
+        let mut add_rule = self.inner.prepare_cached(indoc! {r#"
+            insert or ignore into scan_run_rule(scan_run_id, rule_id)
+            values (?, ?)
+        "#})?;
+        for rule in metadata.rules {
+            let RuleIdInt(rule_id) = record_rule(rule)?;
+            add_rule.execute((id, rule_id))?;
+        }
+
+        Ok(ScanRunIdInt(id))
+    }
+
+    /// Record that the IDs of blobs that do not come from Git repositories are computed using the
+    /// given algorithm in this datastore.
+    ///
+    /// This fails if the datastore already uses a different algorithm, as identical content with
+    /// IDs from different algorithms would not be recognized as the same blob.
+    pub fn record_blob_id_algorithm(&self, algorithm: BlobIdAlgorithm) -> Result<()> {
+        if let Some(recorded) = get_blob_id_algorithm(&self.inner)? {
+            if recorded != algorithm {
+                bail!(
+                    "The datastore computes blob IDs using {recorded}, not {algorithm}; \
+                     blob ID algorithms cannot be mixed within a datastore"
+                );
+            }
+        }
+        self.inner.execute(
+            indoc! {r#"
+                insert into setting(name, value)
+                values ('blob_id_algorithm', ?)
+                on conflict do update set value = excluded.value
+            "#},
+            (algorithm.name(),),
+        )?;
+        Ok(())
+    }
+
     /// Return a closure that records a blob's metadata (only if necessary), returning its integer ID
     fn mk_record_blob_metadata(
         &'ds self,
@@ -243,6 +635,11 @@ impl<'ds> Transaction<'ds> {
             values (?, ?)
         "#})?;
 
+        let mut set_num_truncated_matches = self.inner.prepare_cached(indoc! {r#"
+            insert or ignore into blob_truncated_matches(blob_id, num_truncated_matches)
+            values (?, ?)
+        "#})?;
+
         let f = move |b: &BlobMetadata| -> rusqlite::Result<BlobIdInt> {
             let blob_id = add_if_missing_simple(
                 &mut get_id,
@@ -259,6 +656,10 @@ impl<'ds> Transaction<'ds> {
                 set_charset.execute((blob_id, charset))?;
             }
 
+            if let Some(num_truncated_matches) = b.num_truncated_matches {
+                set_num_truncated_matches.execute((blob_id, num_truncated_matches))?;
+            }
+
             Ok(BlobIdInt(blob_id))
         };
 
@@ -266,8 +667,12 @@ impl<'ds> Transaction<'ds> {
     }
 
     /// Record provenance metadata for a blob given its integer ID
+    ///
+    /// If `max_provenance` is given, provenance entries for a blob are only recorded while it has
+    /// fewer than that many.
     fn mk_record_provenance(
         &'ds self,
+        max_provenance: Option<usize>,
     ) -> Result<impl FnMut(BlobIdInt, &'ds Provenance) -> rusqlite::Result<()>> {
         let mut add_provenance = self.inner.prepare_cached(indoc! {r#"
             insert into blob_provenance(blob_id, provenance)
@@ -275,7 +680,17 @@ impl<'ds> Transaction<'ds> {
             on conflict do nothing
         "#})?;
 
+        let mut count_provenance = self.inner.prepare_cached(indoc! {r#"
+            select count(*) from blob_provenance where blob_id = ?
+        "#})?;
+
         let f = move |BlobIdInt(blob_id), provenance| -> rusqlite::Result<()> {
+            if let Some(max_provenance) = max_provenance {
+                let count: usize = count_provenance.query_row((blob_id,), val_from_row)?;
+                if count >= max_provenance {
+                    return Ok(());
+                }
+            }
             add_provenance.execute((blob_id, provenance))?;
             Ok(())
         };
@@ -470,8 +885,20 @@ impl<'ds> Transaction<'ds> {
     /// Record the given data into the datastore.
     /// Returns the number of matches that were newly added.
     pub fn record(&self, batch: &[BatchEntry]) -> Result<u64> {
+        self.record_with_max_provenance(batch, None)
+    }
+
+    /// Like `record`, but record at most `max_provenance` provenance entries for each blob, if
+    /// given, including those recorded previously.
+    ///
+    /// Additional provenance entries beyond the limit are discarded.
+    pub fn record_with_max_provenance(
+        &self,
+        batch: &[BatchEntry],
+        max_provenance: Option<usize>,
+    ) -> Result<u64> {
         let mut record_blob_metadata = self.mk_record_blob_metadata()?;
-        let mut record_provenance = self.mk_record_provenance()?;
+        let mut record_provenance = self.mk_record_provenance(max_provenance)?;
         let mut record_match = self.mk_record_match()?;
 
         let mut num_matches_added = 0;
@@ -506,6 +933,130 @@ impl Datastore {
         Ok(Transaction { inner })
     }
 
+    /// Record that the given scan run has finished, along with the number of blobs it scanned and
+    /// matches it found.
+    pub fn finish_scan_run(
+        &self,
+        ScanRunIdInt(id): ScanRunIdInt,
+        num_blobs: u64,
+        num_matches: u64,
+    ) -> Result<()> {
+        let mut stmt = self.conn.prepare_cached(indoc! {r#"
+            update scan_run
+            set
+                finished_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now'),
+                num_blobs = ?,
+                num_matches = ?
+            where id = ?
+        "#})?;
+        stmt.execute((num_blobs, num_matches, id))?;
+        Ok(())
+    }
+
+    /// Get all the scan runs recorded in this datastore, ordered from oldest to newest.
+    pub fn get_scan_runs(&self) -> Result<Vec<ScanRun>> {
+        let _span =
+            debug_span!("Datastore::get_scan_runs", "{}", self.root_dir.display()).entered();
+
+        let mut stmt = self.conn.prepare_cached(indoc! {r#"
+            select
+                id,
+                started_at,
+                finished_at,
+                rules_fingerprint,
+                version,
+                argv,
+                num_blobs,
+                num_matches,
+                hostname
+            from scan_run
+            order by id
+        "#})?;
+        let entries = stmt.query_map((), |row| {
+            let run = ScanRun {
+                id: row.get(0)?,
+                started_at: row.get(1)?,
+                finished_at: row.get(2)?,
+                rules_fingerprint: row.get(3)?,
+                labels: Default::default(),
+                version: row.get(4)?,
+                argv: None,
+                num_blobs: row.get(6)?,
+                num_matches: row.get(7)?,
+                hostname: row.get(8)?,
+            };
+            let argv: Option<String> = row.get(5)?;
+            Ok((run, argv))
+        })?;
+        let mut runs = Vec::new();
+        for (mut run, argv) in collect(entries)? {
+            if let Some(argv) = argv {
+                run.argv = Some(serde_json::from_str(&argv).with_context(|| {
+                    format!("Failed to parse scan run arguments from datastore: {argv}")
+                })?);
+            }
+            runs.push(run);
+        }
+
+        let mut stmt = self.conn.prepare_cached(indoc! {r#"
+            select key, value
+            from scan_run_label
+            where scan_run_id = ?
+        "#})?;
+        for run in runs.iter_mut() {
+            let labels = stmt.query_map((run.id,), |row| Ok((row.get(0)?, row.get(1)?)))?;
+            run.labels = collect(labels)?.into_iter().collect();
+        }
+
+        Ok(runs)
+    }
+
+    /// Get all the rules recorded in this datastore, ordered by their textual identifier.
+    pub fn get_rules(&self) -> Result<Vec<Rule>> {
+        let _span = debug_span!("Datastore::get_rules", "{}", self.root_dir.display()).entered();
+
+        let mut stmt = self.conn.prepare_cached(indoc! {r#"
+            select syntax
+            from rule
+            order by text_id, structural_id
+        "#})?;
+        let entries = stmt.query_map((), val_from_row)?;
+        let syntaxes: Vec<String> = collect(entries)?;
+        let mut rules = Vec::new();
+        for syntax in syntaxes {
+            let syntax: RuleSyntax = serde_json::from_str(&syntax)
+                .with_context(|| format!("Failed to parse rule from datastore: {syntax}"))?;
+            rules.push(Rule::new(syntax));
+        }
+        Ok(rules)
+    }
+
+    /// Get the rules recorded as used by the scan run with the given identifier, ordered by their
+    /// textual identifier.
+    ///
+    /// This is empty for scan runs recorded before their rules were.
+    pub fn get_scan_run_rules(&self, scan_run_id: i64) -> Result<Vec<Rule>> {
+        let _span =
+            debug_span!("Datastore::get_scan_run_rules", "{}", self.root_dir.display()).entered();
+
+        let mut stmt = self.conn.prepare_cached(indoc! {r#"
+            select r.syntax
+            from scan_run_rule sr
+            inner join rule r on (sr.rule_id = r.id)
+            where sr.scan_run_id = ?
+            order by r.text_id, r.structural_id
+        "#})?;
+        let entries = stmt.query_map((scan_run_id,), val_from_row)?;
+        let syntaxes: Vec<String> = collect(entries)?;
+        let mut rules = Vec::new();
+        for syntax in syntaxes {
+            let syntax: RuleSyntax = serde_json::from_str(&syntax)
+                .with_context(|| format!("Failed to parse rule from datastore: {syntax}"))?;
+            rules.push(Rule::new(syntax));
+        }
+        Ok(rules)
+    }
+
     /// How many matches are there, total, in the datastore?
     pub fn get_num_matches(&self) -> Result<u64> {
         let mut stmt = self.conn.prepare_cached(indoc! {r#"
@@ -611,6 +1162,100 @@ impl Datastore {
         })
     }
 
+    /// Expand bulk finding annotations into the equivalent `Annotations`, which can then be given
+    /// to `import_annotations`.
+    ///
+    /// The status of each bulk annotation is assigned to every match of its finding.
+    /// Bulk annotations that refer to findings not in this datastore are left out; the number of
+    /// these is returned along with the expanded annotations.
+    pub fn expand_finding_annotations(
+        &self,
+        bulk_annotations: &[BulkFindingAnnotation],
+    ) -> Result<(Annotations, usize)> {
+        use rusqlite::OptionalExtension; // for .optional()
+
+        let _span = debug_span!("Datastore::expand_finding_annotations").entered();
+
+        let mut get_finding = self.conn.prepare_cached(indoc! {r#"
+            select
+                r.name,
+                r.text_id,
+                r.structural_id,
+                f.groups
+            from
+                finding f
+                inner join rule r on (f.rule_id = r.id)
+            where f.finding_id = ?
+        "#})?;
+
+        let mut get_matches = self.conn.prepare_cached(indoc! {r#"
+            select
+                md.structural_id,
+                md.blob_id,
+                md.start_byte,
+                md.end_byte
+            from match_denorm md
+            where md.finding_id = ?
+        "#})?;
+
+        let mut annotations = Annotations {
+            match_annotations: Vec::new(),
+            finding_annotations: Vec::new(),
+        };
+        let mut num_missing = 0;
+
+        for ba in bulk_annotations {
+            let finding = get_finding
+                .query_row((&ba.finding_id,), |row| {
+                    Ok(FindingAnnotation {
+                        finding_id: ba.finding_id.clone(),
+                        rule_name: row.get(0)?,
+                        rule_text_id: row.get(1)?,
+                        rule_structural_id: row.get(2)?,
+                        groups: row.get(3)?,
+                        comment: String::new(),
+                    })
+                })
+                .optional()?;
+            let finding = match finding {
+                Some(finding) => finding,
+                None => {
+                    debug!("did not expand bulk annotation: finding not found: {ba:#?}");
+                    num_missing += 1;
+                    continue;
+                }
+            };
+
+            if let Some(status) = ba.status {
+                let entries = get_matches.query_map((&ba.finding_id,), |row| {
+                    Ok(MatchAnnotation {
+                        finding_id: finding.finding_id.clone(),
+                        rule_name: finding.rule_name.clone(),
+                        rule_text_id: finding.rule_text_id.clone(),
+                        rule_structural_id: finding.rule_structural_id.clone(),
+                        match_id: row.get(0)?,
+                        blob_id: row.get(1)?,
+                        start_byte: row.get(2)?,
+                        end_byte: row.get(3)?,
+                        groups: finding.groups.clone(),
+                        status: Some(status),
+                        comment: None,
+                    })
+                })?;
+                annotations.match_annotations.extend(collect(entries)?);
+            }
+
+            if let Some(comment) = &ba.comment {
+                annotations.finding_annotations.push(FindingAnnotation {
+                    comment: comment.clone(),
+                    ..finding
+                });
+            }
+        }
+
+        Ok((annotations, num_missing))
+    }
+
     pub fn import_annotations(&mut self, annotations: &Annotations) -> Result<()> {
         #[derive(Default, Debug)]
         struct Stats {
@@ -809,12 +1454,35 @@ impl Datastore {
     }
 
     /// Get metadata for all groups of identical matches recorded within this datastore.
+    ///
+    /// If `rules` is nonempty, only findings from rules whose text ID or name is in `rules` are
+    /// included.
+    ///
+    /// See also `iter_findings`, which avoids loading all the metadata into memory at once.
     pub fn get_finding_metadata(
         &self,
         suppress_redundant_matches: bool,
+        rules: &[String],
     ) -> Result<Vec<FindingMetadata>> {
+        self.iter_findings(suppress_redundant_matches, rules, |findings| findings.collect())
+    }
+
+    /// Like `get_finding_metadata`, but instead of returning a `Vec`, calls `f` with an iterator
+    /// that lazily yields the metadata of each finding as it is read from the datastore.
+    ///
+    /// Other methods of this `Datastore`, such as `get_finding_data`, can be called while
+    /// iterating.
+    pub fn iter_findings<T, F>(
+        &self,
+        suppress_redundant_matches: bool,
+        rules: &[String],
+        f: F,
+    ) -> Result<T>
+    where
+        F: FnOnce(&mut dyn Iterator<Item = Result<FindingMetadata>>) -> Result<T>,
+    {
         let _span =
-            debug_span!("Datastore::get_finding_metadata", "{}", self.root_dir.display()).entered();
+            debug_span!("Datastore::iter_findings", "{}", self.root_dir.display()).entered();
 
         let query_str = format!(
             indoc! {r#"
@@ -830,31 +1498,34 @@ impl Datastore {
                     match_statuses,
                     mean_score
                 from finding_denorm
-                where {}
+                where {} and {}
                 order by rule_name, rule_structural_id, mean_score desc, groups
             "#},
             if suppress_redundant_matches {
                 "num_matches != num_redundant_matches"
             } else {
                 "true"
-            }
+            },
+            rule_condition(rules),
         );
         let mut stmt = self.conn.prepare_cached(&query_str)?;
-        let entries = stmt.query_map((), |row| {
-            Ok(FindingMetadata {
-                finding_id: row.get(0)?,
-                groups: row.get(1)?,
-                rule_structural_id: row.get(2)?,
-                rule_text_id: row.get(3)?,
-                rule_name: row.get(4)?,
-                num_matches: row.get(5)?,
-                num_redundant_matches: row.get(6)?,
-                comment: row.get(7)?,
-                statuses: row.get(8)?,
-                mean_score: row.get(9)?,
-            })
-        })?;
-        collect(entries)
+        let mut entries = stmt
+            .query_map(rusqlite::params_from_iter(rules), |row| {
+                Ok(FindingMetadata {
+                    finding_id: row.get(0)?,
+                    groups: row.get(1)?,
+                    rule_structural_id: row.get(2)?,
+                    rule_text_id: row.get(3)?,
+                    rule_name: row.get(4)?,
+                    num_matches: row.get(5)?,
+                    num_redundant_matches: row.get(6)?,
+                    comment: row.get(7)?,
+                    statuses: row.get(8)?,
+                    mean_score: row.get(9)?,
+                })
+            })?
+            .map(|e| e.map_err(anyhow::Error::from));
+        f(&mut entries)
     }
 
     /// Get up to `max_matches` matches that belong to the finding with the given finding metadata.
@@ -909,7 +1580,9 @@ impl Datastore {
                 m.score,
                 m.comment,
                 m.status,
-                m.structural_id
+                m.structural_id,
+
+                b.num_truncated_matches
 
             from match_denorm m
             inner join blob_denorm b on (m.blob_id = b.blob_id)
@@ -958,11 +1631,13 @@ impl Datastore {
                 let num_bytes: usize = row.get(11)?;
                 let mime_essence: Option<String> = row.get(12)?;
                 let charset: Option<String> = row.get(13)?;
+                let num_truncated_matches: Option<usize> = row.get(19)?;
                 let b = BlobMetadata {
                     id: blob_id,
                     num_bytes,
                     mime_essence,
                     charset,
+                    num_truncated_matches,
                 };
                 let id = MatchIdInt(row.get(14)?);
                 let m_score = row.get(15)?;
@@ -990,6 +1665,248 @@ impl Datastore {
         Ok(es)
     }
 
+    /// Call `f` with the matches of each blob that has any, in order of blob ID.
+    /// Each blob will have up to `max_provenance_entries`; a value of `None` means "no limit".
+    ///
+    /// If `rules` is nonempty, only matches from rules whose text ID or name is in `rules` are
+    /// included.
+    pub fn iter_blob_data<F>(
+        &self,
+        max_provenance_entries: Option<usize>,
+        suppress_redundant_matches: bool,
+        rules: &[String],
+        mut f: F,
+    ) -> Result<()>
+    where
+        F: FnMut(BlobData) -> Result<()>,
+    {
+        let _span =
+            debug_span!("Datastore::iter_blob_data", "{}", self.root_dir.display()).entered();
+
+        let suppress_redundant = if suppress_redundant_matches {
+            "m.id not in (select match_id from match_redundancy)"
+        } else {
+            "true"
+        };
+
+        let query_str = format!(
+            indoc! {r#"
+            select
+                m.blob_id,
+                m.start_byte,
+                m.end_byte,
+                m.start_line,
+                m.start_column,
+                m.end_line,
+                m.end_column,
+
+                m.before_snippet,
+                m.matching_snippet,
+                m.after_snippet,
+
+                m.groups,
+
+                b.size,
+                b.mime_essence,
+                b.charset,
+
+                m.id,
+                m.score,
+                m.comment,
+                m.status,
+                m.structural_id,
+
+                b.num_truncated_matches,
+
+                m.finding_id,
+                m.rule_structural_id,
+                m.rule_text_id,
+                m.rule_name
+
+            from match_denorm m
+            inner join blob_denorm b on (m.blob_id = b.blob_id)
+            where {} and {}
+            order by m.blob_id, m.start_byte, m.end_byte, m.rule_name
+        "#},
+            suppress_redundant,
+            rule_condition(rules),
+        );
+
+        let mut get_blob_metadata_and_match = self.conn.prepare_cached(&query_str)?;
+
+        let entries =
+            get_blob_metadata_and_match.query_map(rusqlite::params_from_iter(rules), |row| {
+                let blob_id = row.get(0)?;
+                let m = Match {
+                    blob_id,
+                    location: Location {
+                        offset_span: OffsetSpan {
+                            start: row.get(1)?,
+                            end: row.get(2)?,
+                        },
+                        source_span: SourceSpan {
+                            start: SourcePoint {
+                                line: row.get(3)?,
+                                column: row.get(4)?,
+                            },
+                            end: SourcePoint {
+                                line: row.get(5)?,
+                                column: row.get(6)?,
+                            },
+                        },
+                    },
+                    snippet: Snippet {
+                        before: BString::new(row.get(7)?),
+                        matching: BString::new(row.get(8)?),
+                        after: BString::new(row.get(9)?),
+                    },
+                    groups: row.get(10)?,
+                    rule_structural_id: row.get(21)?,
+                    rule_text_id: row.get(22)?,
+                    rule_name: row.get(23)?,
+                    structural_id: row.get(18)?,
+                };
+                let b = BlobMetadata {
+                    id: blob_id,
+                    num_bytes: row.get(11)?,
+                    mime_essence: row.get(12)?,
+                    charset: row.get(13)?,
+                    num_truncated_matches: row.get(19)?,
+                };
+                let id = MatchIdInt(row.get(14)?);
+                let m_score = row.get(15)?;
+                let m_comment = row.get(16)?;
+                let m_status = row.get(17)?;
+                let finding_id = row.get(20)?;
+                Ok((b, id, m, m_score, m_comment, m_status, finding_id))
+            })?;
+
+        let mut current: Option<BlobData> = None;
+        for e in entries {
+            let (md, id, m, match_score, match_comment, match_status, finding_id) = e?;
+            if current
+                .as_ref()
+                .map_or(true, |b| b.blob_metadata.id != md.id)
+            {
+                if let Some(blob) = current.take() {
+                    f(blob)?;
+                }
+                let provenance = self.get_provenance_set(&md, max_provenance_entries)?;
+                current = Some(BlobData {
+                    provenance,
+                    blob_metadata: md,
+                    matches: Vec::new(),
+                });
+            }
+            let redundant_to = self.get_redundant_to(id)?;
+            current
+                .as_mut()
+                .expect("current blob should be set")
+                .matches
+                .push(BlobDataEntry {
+                    finding_id,
+                    match_id: id,
+                    match_val: m,
+                    match_comment,
+                    match_score,
+                    match_status,
+                    redundant_to,
+                });
+        }
+        if let Some(blob) = current {
+            f(blob)?;
+        }
+        Ok(())
+    }
+
+    /// Get the contents of this datastore as a sequence of records that can be imported into
+    /// another datastore with `Transaction::record`.
+    ///
+    /// A header comes first, then the rules, followed by each blob with matches, ordered by blob ID.
+    /// Match statuses and comments are not included; use `get_annotations` for those.
+    pub fn get_export_records(&self) -> Result<Vec<ExportRecord>> {
+        let _span =
+            debug_span!("Datastore::get_export_records", "{}", self.root_dir.display()).entered();
+
+        let mut blobs: std::collections::BTreeMap<BlobId, BlobRecord> = Default::default();
+        for metadata in self.get_finding_metadata(false, &[])? {
+            for entry in self.get_finding_data(&metadata, None, None, false)? {
+                let blob = blobs
+                    .entry(entry.blob_metadata.id)
+                    .or_insert_with(|| BlobRecord {
+                        provenance: entry.provenance.iter().cloned().collect(),
+                        blob_metadata: entry.blob_metadata,
+                        matches: Vec::new(),
+                    });
+                blob.matches.push(ScoredMatch {
+                    match_val: entry.match_val,
+                    score: entry.match_score,
+                });
+            }
+        }
+
+        let rules = self.get_rules()?;
+        let mut records = Vec::with_capacity(1 + rules.len() + blobs.len());
+        records.push(ExportRecord::Header(ExportHeader {
+            redundancy_overlap: self.get_redundancy_overlap()?,
+            blob_id_algorithm: self.get_blob_id_algorithm()?,
+            ..Default::default()
+        }));
+        records.extend(
+            rules
+                .into_iter()
+                .map(|r| ExportRecord::Rule(r.syntax().clone())),
+        );
+        records.extend(blobs.into_values().map(ExportRecord::Blob));
+        Ok(records)
+    }
+
+    /// Count the provenance entries of all the matches in the given finding, and the number of
+    /// distinct sources among them.
+    pub fn get_finding_occurrences(
+        &self,
+        metadata: &FindingMetadata,
+        suppress_redundant_matches: bool,
+    ) -> Result<FindingOccurrences> {
+        let _span =
+            debug_span!("Datastore::get_finding_occurrences", "{}", self.root_dir.display())
+                .entered();
+
+        let suppress_redundant = if suppress_redundant_matches {
+            "m.id not in (select match_id from match_redundancy)"
+        } else {
+            "true"
+        };
+
+        // Git repo provenance entries are identified by their repo path, file provenance entries
+        // by their path, and anything else by the entire provenance value.
+        let query_str = format!(
+            indoc! {r#"
+            select
+                count(*),
+                count(distinct coalesce(
+                    json_extract(bp.provenance, '$.repo_path'),
+                    json_extract(bp.provenance, '$.path'),
+                    bp.provenance
+                ))
+            from match_denorm m
+            inner join blob_provenance_denorm bp on (m.blob_id = bp.blob_id)
+            where m.groups = ?1 and m.rule_structural_id = ?2 and {}
+        "#},
+            suppress_redundant
+        );
+
+        let mut get = self.conn.prepare_cached(&query_str)?;
+        let occurrences =
+            get.query_row((&metadata.groups, &metadata.rule_structural_id), |row| {
+                Ok(FindingOccurrences {
+                    occurrence_count: row.get(0)?,
+                    distinct_source_count: row.get(1)?,
+                })
+            })?;
+        Ok(occurrences)
+    }
+
     fn get_provenance_set(
         &self,
         metadata: &BlobMetadata,
@@ -1041,6 +1958,9 @@ impl Datastore {
 
     fn new_connection(path: &Path, cache_size: i64) -> Result<Connection> {
         let conn = Connection::open(path)?;
+        if conn.is_readonly(rusqlite::DatabaseName::Main)? {
+            return Self::new_read_only_connection(path, cache_size);
+        }
 
         conn.pragma_update(None, "journal_mode", "wal")?; // https://www.sqlite.org/wal.html
         conn.pragma_update(None, "foreign_keys", "on")?; // https://sqlite.org/foreignkeys.html
@@ -1050,10 +1970,42 @@ impl Datastore {
         Ok(conn)
     }
 
-    fn check_schema_version(&self) -> Result<()> {
+    fn new_read_only_connection(path: &Path, cache_size: i64) -> Result<Connection> {
+        use rusqlite::OpenFlags;
+
+        let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        let conn = Connection::open_with_flags(path, flags)?;
+
+        // Reading a database in WAL mode requires its `-shm` file to exist or to be creatable.
+        // When neither is possible, as on a read-only filesystem, the database can still be read
+        // when opened as immutable.
+        let conn = match conn.pragma_query_value(None, "user_version", val_from_row::<u64>) {
+            Ok(_) => conn,
+            Err(e) => {
+                debug!("Failed to read database at {}: {e}; opening as immutable", path.display());
+                let path = std::path::absolute(path)?;
+                let mut uri = url::Url::from_file_path(&path)
+                    .map_err(|()| anyhow::anyhow!("Failed to make URI for {}", path.display()))?;
+                uri.set_query(Some("immutable=1"));
+                Connection::open_with_flags(uri.as_str(), flags | OpenFlags::SQLITE_OPEN_URI)?
+            }
+        };
+
+        conn.pragma_update(None, "foreign_keys", "on")?; // https://sqlite.org/foreignkeys.html
+        conn.pragma_update(None, "cache_size", cache_size)?; // sqlite.org/pragma.html#pragma_cache_size
+
+        Ok(conn)
+    }
+
+    fn get_schema_version(&self) -> Result<u64> {
         let user_version: u64 = self
             .conn
             .pragma_query_value(None, "user_version", val_from_row)?;
+        Ok(user_version)
+    }
+
+    fn check_schema_version(&self) -> Result<()> {
+        let user_version = self.get_schema_version()?;
         if user_version != CURRENT_SCHEMA_VERSION {
             bail!(
                 "Unsupported schema version {user_version} (expected {}): \
@@ -1064,8 +2016,9 @@ impl Datastore {
         Ok(())
     }
 
-    fn migrate_0_70(&mut self) -> Result<()> {
-        let _span = debug_span!("Datastore::migrate_0_70", "{}", self.root_dir.display()).entered();
+    /// Initialize the database schema if it is empty, or bring an older supported schema up to date.
+    fn migrate(&mut self) -> Result<()> {
+        let _span = debug_span!("Datastore::migrate", "{}", self.root_dir.display()).entered();
         let tx = self.conn.transaction()?;
 
         let get_user_version = || -> Result<u64> {
@@ -1078,8 +2031,8 @@ impl Datastore {
             Ok(())
         };
 
-        let user_version: u64 = get_user_version()?;
-        if user_version > 0 && user_version < CURRENT_SCHEMA_VERSION {
+        let mut user_version: u64 = get_user_version()?;
+        if user_version > 0 && user_version < MIN_SUPPORTED_SCHEMA_VERSION {
             bail!(
                 "This datastore has schema version {user_version}. \
                    Datastores from other Nosey Parker versions are not supported. \
@@ -1091,9 +2044,57 @@ impl Datastore {
         }
 
         if user_version == 0 {
-            let new_user_version = CURRENT_SCHEMA_VERSION;
+            let new_user_version = 70;
+            debug!("Migrating database schema from version {user_version} to {new_user_version}");
+            tx.execute_batch(SCHEMA_70)?;
+            set_user_version(new_user_version)?;
+            user_version = new_user_version;
+        }
+
+        if user_version == 70 {
+            let new_user_version = 71;
+            debug!("Migrating database schema from version {user_version} to {new_user_version}");
+            tx.execute_batch(MIGRATION_71)?;
+            set_user_version(new_user_version)?;
+            user_version = new_user_version;
+        }
+
+        if user_version == 71 {
+            let new_user_version = 72;
+            debug!("Migrating database schema from version {user_version} to {new_user_version}");
+            tx.execute_batch(MIGRATION_72)?;
+            set_user_version(new_user_version)?;
+            user_version = new_user_version;
+        }
+
+        if user_version == 72 {
+            let new_user_version = 73;
             debug!("Migrating database schema from version {user_version} to {new_user_version}");
-            tx.execute_batch(CURRENT_SCHEMA)?;
+            tx.execute_batch(MIGRATION_73)?;
+            set_user_version(new_user_version)?;
+            user_version = new_user_version;
+        }
+
+        if user_version == 73 {
+            let new_user_version = 74;
+            debug!("Migrating database schema from version {user_version} to {new_user_version}");
+            tx.execute_batch(MIGRATION_74)?;
+            set_user_version(new_user_version)?;
+            user_version = new_user_version;
+        }
+
+        if user_version == 74 {
+            let new_user_version = 75;
+            debug!("Migrating database schema from version {user_version} to {new_user_version}");
+            tx.execute_batch(MIGRATION_75)?;
+            set_user_version(new_user_version)?;
+            user_version = new_user_version;
+        }
+
+        if user_version == 75 {
+            let new_user_version = 76;
+            debug!("Migrating database schema from version {user_version} to {new_user_version}");
+            tx.execute_batch(MIGRATION_76)?;
             set_user_version(new_user_version)?;
         }
 
@@ -1106,8 +2107,21 @@ impl Datastore {
     /// Analyze the recorded matches to determine which matches are redundant.
     /// This populates the `match_redundancy` table.
     /// This information is needed for suppressing redundant matches at reporting time.
-    pub fn check_match_redundancies(&mut self) -> Result<()> {
-        self.conn.execute(indoc! {r#"
+    ///
+    /// Two matches in the same blob are considered to overlap when at least `overlap_threshold` of
+    /// each of them is overlapping, as a fraction from 0.0 to 1.0 of its length.
+    /// See `DEFAULT_REDUNDANCY_OVERLAP` for the usual value.
+    ///
+    /// Any previously-determined redundancies are replaced, and the threshold is recorded in the
+    /// datastore, so that it can be reused; see `get_redundancy_overlap`.
+    pub fn check_match_redundancies(&mut self, overlap_threshold: f64) -> Result<()> {
+        if !(0.0..=1.0).contains(&overlap_threshold) {
+            bail!("Redundancy overlap threshold {overlap_threshold} is not between 0.0 and 1.0");
+        }
+
+        let tx = self.conn.transaction()?;
+        tx.execute("delete from match_redundancy", [])?;
+        tx.execute(indoc! {r#"
             insert into match_redundancy (match_id, redundant_to)
             with
                 match_overlap_metadata as (
@@ -1133,9 +2147,9 @@ impl Datastore {
                                 m1.blob_id = m2.blob_id
                             and m1.id != m2.id
                             and (m1.start_byte <= m2.start_byte and m2.start_byte < m1.end_byte)
-                            -- require at least 20% of both matches to be overlapping to be considered as an overlap
-                            and (cast(m1.end_byte - m2.start_byte as real) / cast(m1.end_byte - m1.start_byte as real) >= 0.20)
-                            and (cast(m1.end_byte - m2.start_byte as real) / cast(m2.end_byte - m2.start_byte as real) >= 0.20)
+                            -- require at least the threshold fraction of both matches to be overlapping to be considered as an overlap
+                            and (cast(m1.end_byte - m2.start_byte as real) / cast(m1.end_byte - m1.start_byte as real) >= ?1)
+                            and (cast(m1.end_byte - m2.start_byte as real) / cast(m2.end_byte - m2.start_byte as real) >= ?1)
                         )
                 ),
 
@@ -1177,16 +2191,69 @@ impl Datastore {
                         md2.pattern_len > md1.pattern_len
                     )
                 )
-        "#}, [])?;
+        "#}, (overlap_threshold,))?;
+        tx.execute(
+            indoc! {r#"
+                insert into setting(name, value)
+                values ('redundancy_overlap', ?)
+                on conflict do update set value = excluded.value
+            "#},
+            (overlap_threshold,),
+        )?;
+        tx.commit()?;
 
         Ok(())
     }
+
+    /// Get the overlap threshold that was last used to determine redundant matches in this
+    /// datastore, if redundant matches have been determined.
+    pub fn get_redundancy_overlap(&self) -> Result<Option<f64>> {
+        use rusqlite::OptionalExtension; // for .optional()
+
+        let overlap_threshold = self
+            .conn
+            .query_row(
+                "select value from setting where name = 'redundancy_overlap'",
+                [],
+                val_from_row,
+            )
+            .optional()?;
+        Ok(overlap_threshold)
+    }
+
+    /// Get the algorithm used to compute the IDs of blobs that do not come from Git repositories
+    /// in this datastore, if one has been recorded or any blobs have been.
+    pub fn get_blob_id_algorithm(&self) -> Result<Option<BlobIdAlgorithm>> {
+        get_blob_id_algorithm(&self.conn)
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
 // Implementation Utilities
 // -------------------------------------------------------------------------------------------------
 
+/// Get the blob ID algorithm recorded in the datastore with the given connection.
+///
+/// Datastores with blobs but no recorded algorithm predate `BlobIdAlgorithm`, and use SHA-1.
+fn get_blob_id_algorithm(conn: &Connection) -> Result<Option<BlobIdAlgorithm>> {
+    use rusqlite::OptionalExtension; // for .optional()
+
+    let name: Option<String> = conn
+        .query_row("select value from setting where name = 'blob_id_algorithm'", [], val_from_row)
+        .optional()?;
+    match name {
+        Some(name) => match BlobIdAlgorithm::from_name(&name) {
+            Some(algorithm) => Ok(Some(algorithm)),
+            None => bail!("Unknown blob ID algorithm `{name}` recorded in datastore"),
+        },
+        None => {
+            let has_blobs: bool =
+                conn.query_row("select exists (select 1 from blob)", [], val_from_row)?;
+            Ok(has_blobs.then_some(BlobIdAlgorithm::Sha1))
+        }
+    }
+}
+
 fn collect<T, F>(rows: rusqlite::MappedRows<'_, F>) -> Result<Vec<T>>
 where
     F: FnMut(&rusqlite::Row<'_>) -> rusqlite::Result<T>,
@@ -1198,6 +2265,22 @@ where
     Ok(entries)
 }
 
+/// Make a SQL condition that restricts results to the rules whose text ID or name is in `rules`,
+/// which are bound as the numbered parameters `?1` through `?N`.
+///
+/// If `rules` is empty, the condition is always true.
+fn rule_condition(rules: &[String]) -> String {
+    if rules.is_empty() {
+        "true".to_string()
+    } else {
+        let placeholders = (1..=rules.len())
+            .map(|i| format!("?{i}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("(rule_text_id in ({placeholders}) or rule_name in ({placeholders}))")
+    }
+}
+
 /// Convert a row into a single value.
 ///
 /// This function exists to work around an ergonomic deficiency in Rust's type system, which
@@ -1260,7 +2343,7 @@ where
 
 /// Get a path for a local clone of the given git URL underneath `root`.
 fn clone_destination(root: &std::path::Path, repo: &GitUrl) -> Result<std::path::PathBuf> {
-    Ok(root.join(repo.to_path_buf()))
+    Ok(root.join(repo.normalized().to_path_buf()))
 }
 
 #[cfg(test)]
@@ -1289,7 +2372,288 @@ mod test {
     }
 
     clone_destination_success_tests! {
-        https_01: ("rel_root", "https://example.com/testrepo.git") => "rel_root/https/example.com/testrepo.git",
-        https_02: ("/abs_root", "https://example.com/testrepo.git") => "/abs_root/https/example.com/testrepo.git",
+        https_01: ("rel_root", "https://example.com/testrepo.git") => "rel_root/https/example.com/testrepo",
+        https_02: ("/abs_root", "https://example.com/testrepo.git") => "/abs_root/https/example.com/testrepo",
+        https_03: ("rel_root", "https://example.com/testrepo") => "rel_root/https/example.com/testrepo",
+        https_04: ("rel_root", "https://example.com/testrepo/") => "rel_root/https/example.com/testrepo",
+        ssh_01: ("rel_root", "ssh://git@example.com/testrepo.git") => "rel_root/ssh/example.com/testrepo",
+        ssh_02: ("rel_root", "ssh://git@example.com:2222/org/testrepo.git") => "rel_root/ssh/example.com:2222/org/testrepo",
+        scp_01: ("rel_root", "git@example.com:org/testrepo.git") => "rel_root/ssh/example.com/org/testrepo",
+        scp_02: ("rel_root", "example.com:/srv/testrepo.git") => "rel_root/ssh/example.com/srv/testrepo",
+    }
+
+    #[cfg(unix)]
+    mod read_only {
+        use super::super::*;
+        use pretty_assertions::assert_eq;
+        use std::os::unix::fs::PermissionsExt;
+
+        fn set_mode(path: &Path, mode: u32) -> Result<()> {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+            Ok(())
+        }
+
+        #[test]
+        fn open_from_read_only_dir() -> Result<()> {
+            let tmp = tempfile::tempdir()?;
+            let root_dir = tmp.path().join("datastore.np");
+            {
+                let mut ds = Datastore::create(&root_dir, 1000)?;
+                assert!(!ds.is_read_only()?);
+                let tx = ds.begin()?;
+                tx.record_scan_run(&ScanRunMetadata::default())?;
+                tx.commit()?;
+            }
+
+            // Without these subdirectories, opening would try to create them
+            std::fs::remove_dir(root_dir.join("scratch"))?;
+            std::fs::remove_dir(root_dir.join("clones"))?;
+            std::fs::remove_dir(root_dir.join("blobs"))?;
+            set_mode(&root_dir.join("datastore.db"), 0o444)?;
+            set_mode(&root_dir, 0o555)?;
+
+            // Permissions are not enforced for privileged users, in which case the datastore is
+            // simply opened writably
+            let enforced = std::fs::File::create(root_dir.join("probe")).is_err();
+
+            let result = || -> Result<()> {
+                let mut ds = Datastore::open(&root_dir, 1000)?;
+                assert_eq!(ds.get_scan_runs()?.len(), 1);
+                assert_eq!(ds.get_num_findings()?, 0);
+                if enforced {
+                    assert!(ds.is_read_only()?);
+                    assert!(!root_dir.join("scratch").exists());
+                    assert!(ds
+                        .begin()
+                        .and_then(|tx| tx.record_scan_run(&ScanRunMetadata::default()))
+                        .is_err());
+                }
+                Ok(())
+            }();
+
+            set_mode(&root_dir, 0o755)?;
+            result
+        }
+
+        #[test]
+        fn open_read_only_sees_concurrent_writes() -> Result<()> {
+            let tmp = tempfile::tempdir()?;
+            let root_dir = tmp.path().join("datastore.np");
+            let mut writer = Datastore::create(&root_dir, 1000)?;
+
+            let reader = Datastore::open_read_only(&root_dir, 1000)?;
+            assert!(reader.is_read_only()?);
+            assert_eq!(reader.get_scan_runs()?.len(), 0);
+
+            let tx = writer.begin()?;
+            tx.record_scan_run(&ScanRunMetadata::default())?;
+            tx.commit()?;
+
+            assert_eq!(reader.get_scan_runs()?.len(), 1);
+            Ok(())
+        }
+    }
+
+    mod check {
+        use super::super::*;
+        use pretty_assertions::assert_eq;
+
+        fn failed(checks: &[DatastoreCheck]) -> Vec<&'static str> {
+            checks
+                .iter()
+                .filter(|c| !c.passed())
+                .map(|c| c.name)
+                .collect()
+        }
+
+        #[test]
+        fn check_ok() -> Result<()> {
+            let tmp = tempfile::tempdir()?;
+            let root_dir = tmp.path().join("datastore.np");
+            Datastore::create(&root_dir, 1000)?;
+
+            let checks = Datastore::check(&root_dir, 1000)?;
+            assert_eq!(checks.len(), 4);
+            assert_eq!(failed(&checks), Vec::<&str>::new());
+            Ok(())
+        }
+
+        #[test]
+        fn check_problems() -> Result<()> {
+            let tmp = tempfile::tempdir()?;
+            let root_dir = tmp.path().join("datastore.np");
+            Datastore::create(&root_dir, 1000)?;
+
+            std::fs::remove_dir(root_dir.join("clones"))?;
+            {
+                let conn = Connection::open(root_dir.join("datastore.db"))?;
+                conn.pragma_update(None, "foreign_keys", "off")?;
+                conn.execute(
+                    "insert into scan_run_label(scan_run_id, key, value) values (42, 'k', 'v')",
+                    (),
+                )?;
+                conn.pragma_update(None, "user_version", 1)?;
+            }
+
+            let checks = Datastore::check(&root_dir, 1000)?;
+            assert_eq!(failed(&checks), vec!["schema version", "foreign keys", "directories"]);
+            Ok(())
+        }
+
+        #[test]
+        fn check_missing() {
+            let tmp = tempfile::tempdir().unwrap();
+            assert!(Datastore::check(&tmp.path().join("nothere.np"), 1000).is_err());
+        }
+    }
+
+    mod vacuum {
+        use super::super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn vacuum() -> Result<()> {
+            let tmp = tempfile::tempdir()?;
+            let mut ds = Datastore::create(&tmp.path().join("datastore.np"), 1000)?;
+            let tx = ds.begin()?;
+            tx.record_scan_run(&ScanRunMetadata::default())?;
+            tx.commit()?;
+
+            ds.vacuum()?;
+            assert_eq!(ds.get_scan_runs()?.len(), 1);
+            Ok(())
+        }
+
+        #[test]
+        fn vacuum_locked() -> Result<()> {
+            let tmp = tempfile::tempdir()?;
+            let root_dir = tmp.path().join("datastore.np");
+            let mut ds = Datastore::create(&root_dir, 1000)?;
+
+            let conn = Connection::open(root_dir.join("datastore.db"))?;
+            conn.execute_batch("begin immediate")?;
+            assert!(ds.vacuum().is_err());
+            conn.execute_batch("rollback")?;
+
+            ds.vacuum()?;
+            Ok(())
+        }
+    }
+
+    mod schema {
+        use super::super::*;
+        use pretty_assertions::assert_eq;
+
+        #[test]
+        fn migrate_from_70() -> Result<()> {
+            let tmp = tempfile::tempdir()?;
+            let root_dir = tmp.path().join("datastore.np");
+            std::fs::create_dir(&root_dir)?;
+
+            // Set up a database with the version 70 schema
+            {
+                let conn = Connection::open(root_dir.join("datastore.db"))?;
+                conn.execute_batch(SCHEMA_70)?;
+                conn.pragma_update(None, "user_version", 70)?;
+            }
+
+            let ds = Datastore::open(&root_dir, 1000)?;
+            assert_eq!(ds.get_schema_version()?, CURRENT_SCHEMA_VERSION);
+            assert!(ds.get_scan_runs()?.is_empty());
+            Ok(())
+        }
+
+        #[test]
+        fn blob_id_algorithm() -> Result<()> {
+            let tmp = tempfile::tempdir()?;
+            let mut ds = Datastore::create(&tmp.path().join("datastore.np"), 1000)?;
+            assert_eq!(ds.get_blob_id_algorithm()?, None);
+
+            let tx = ds.begin()?;
+            tx.record_blob_id_algorithm(BlobIdAlgorithm::Blake3)?;
+            tx.record_blob_id_algorithm(BlobIdAlgorithm::Blake3)?;
+            assert!(tx.record_blob_id_algorithm(BlobIdAlgorithm::Sha1).is_err());
+            tx.commit()?;
+            assert_eq!(ds.get_blob_id_algorithm()?, Some(BlobIdAlgorithm::Blake3));
+            Ok(())
+        }
+
+        #[test]
+        fn scan_runs() -> Result<()> {
+            let tmp = tempfile::tempdir()?;
+            let mut ds = Datastore::create(&tmp.path().join("datastore.np"), 1000)?;
+
+            let labels = [
+                ("team".to_string(), "appsec".to_string()),
+                ("env".to_string(), "prod".to_string()),
+            ];
+            let tx = ds.begin()?;
+            let argv = vec!["noseyparker".to_string(), "scan".to_string()];
+            let rules = vec![Rule::new(RuleSyntax {
+                id: "test.1".to_string(),
+                name: "test".to_string(),
+                pattern: r"secret=(\w+)".to_string(),
+                examples: vec![],
+                negative_examples: vec![],
+                references: vec![],
+                categories: vec![],
+                description: None,
+                path_include: vec![],
+                path_exclude: vec![],
+            })];
+            let id1 = tx.record_scan_run(&ScanRunMetadata {
+                labels: &labels,
+                rules_fingerprint: Some("abc123"),
+                version: Some("1.2.3"),
+                argv: &argv,
+                hostname: Some("scanner"),
+                rules: &rules,
+            })?;
+            let id2 = tx.record_scan_run(&ScanRunMetadata::default())?;
+            tx.commit()?;
+            ds.finish_scan_run(id1, 10, 3)?;
+
+            let runs = ds.get_scan_runs()?;
+            assert_eq!(runs.len(), 2);
+            assert_eq!(ScanRunIdInt(runs[0].id), id1);
+            assert!(runs[0].finished_at.is_some());
+            assert_eq!(runs[0].labels, labels.into_iter().collect());
+            assert_eq!(runs[0].rules_fingerprint.as_deref(), Some("abc123"));
+            assert_eq!(runs[0].version.as_deref(), Some("1.2.3"));
+            assert_eq!(runs[0].argv.as_ref(), Some(&argv));
+            assert_eq!(runs[0].hostname.as_deref(), Some("scanner"));
+            assert_eq!(runs[0].num_blobs, Some(10));
+            assert_eq!(runs[0].num_matches, Some(3));
+            assert_eq!(ScanRunIdInt(runs[1].id), id2);
+            assert!(runs[1].finished_at.is_none());
+            assert!(runs[1].labels.is_empty());
+            assert!(runs[1].rules_fingerprint.is_none());
+            assert!(runs[1].version.is_none());
+            assert!(runs[1].argv.is_none());
+            assert!(runs[1].hostname.is_none());
+            assert!(runs[1].num_blobs.is_none());
+
+            let run_rules = ds.get_scan_run_rules(runs[0].id)?;
+            assert_eq!(run_rules.len(), 1);
+            assert_eq!(run_rules[0].structural_id(), rules[0].structural_id());
+            assert!(ds.get_scan_run_rules(runs[1].id)?.is_empty());
+            Ok(())
+        }
+
+        #[test]
+        fn redundancy_overlap() -> Result<()> {
+            let tmp = tempfile::tempdir()?;
+            let mut ds = Datastore::create(&tmp.path().join("datastore.np"), 1000)?;
+            assert_eq!(ds.get_redundancy_overlap()?, None);
+
+            ds.check_match_redundancies(0.5)?;
+            assert_eq!(ds.get_redundancy_overlap()?, Some(0.5));
+            ds.check_match_redundancies(DEFAULT_REDUNDANCY_OVERLAP)?;
+            assert_eq!(ds.get_redundancy_overlap()?, Some(DEFAULT_REDUNDANCY_OVERLAP));
+
+            assert!(ds.check_match_redundancies(1.5).is_err());
+            assert_eq!(ds.get_redundancy_overlap()?, Some(DEFAULT_REDUNDANCY_OVERLAP));
+            Ok(())
+        }
     }
 }
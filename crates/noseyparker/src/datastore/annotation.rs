@@ -92,6 +92,23 @@ impl FindingAnnotation {
     }
 }
 
+// -------------------------------------------------------------------------------------------------
+// BulkFindingAnnotation
+// -------------------------------------------------------------------------------------------------
+/// Represents an user-assigned annotation on a finding given in bulk, such as from a spreadsheet:
+/// a status for each of the finding's matches and/or a comment
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct BulkFindingAnnotation {
+    /// The content-based finding identifier
+    pub finding_id: String,
+
+    /// The status to assign to each match of the finding
+    pub status: Option<Status>,
+
+    /// The comment to assign to the finding
+    pub comment: Option<String>,
+}
+
 // -------------------------------------------------------------------------------------------------
 // Annotations
 // -------------------------------------------------------------------------------------------------
@@ -0,0 +1,33 @@
+use crate::blob_metadata::BlobMetadata;
+use crate::match_type::Match;
+use crate::provenance_set::ProvenanceSet;
+
+use super::MatchIdInt;
+use super::Status;
+
+// -------------------------------------------------------------------------------------------------
+// BlobData
+// -------------------------------------------------------------------------------------------------
+/// The matches found in a single blob, from any rule
+#[derive(Debug)]
+pub struct BlobData {
+    pub provenance: ProvenanceSet,
+    pub blob_metadata: BlobMetadata,
+    pub matches: Vec<BlobDataEntry>,
+}
+
+// -------------------------------------------------------------------------------------------------
+// BlobDataEntry
+// -------------------------------------------------------------------------------------------------
+/// Data for a single `Match` within a blob
+#[derive(Debug)]
+pub struct BlobDataEntry {
+    /// The ID of the finding that the match belongs to
+    pub finding_id: String,
+    pub match_id: MatchIdInt,
+    pub match_val: Match,
+    pub match_comment: Option<String>,
+    pub match_score: Option<f64>,
+    pub match_status: Option<Status>,
+    pub redundant_to: Vec<String>,
+}
@@ -0,0 +1,21 @@
+use serde::Serialize;
+
+// -------------------------------------------------------------------------------------------------
+// DatastoreCheck
+// -------------------------------------------------------------------------------------------------
+/// The outcome of one of the integrity checks performed by `Datastore::check`.
+#[derive(Debug, Serialize)]
+pub struct DatastoreCheck {
+    /// A short description of what was checked
+    pub name: &'static str,
+
+    /// A description of each problem that was found; empty if the check passed
+    pub problems: Vec<String>,
+}
+
+impl DatastoreCheck {
+    /// Did this check pass?
+    pub fn passed(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
@@ -0,0 +1,127 @@
+use anyhow::{bail, Context, Result};
+use noseyparker_rules::RuleSyntax;
+use serde::{Deserialize, Serialize};
+
+use super::BatchEntry;
+use crate::blob_id::BlobIdAlgorithm;
+use crate::blob_metadata::BlobMetadata;
+use crate::match_type::Match;
+use crate::provenance::Provenance;
+use crate::provenance_set::ProvenanceSet;
+
+/// The version of the export format.
+///
+/// This is incremented whenever the format changes in a way that older versions of Nosey Parker
+/// cannot read.
+pub const EXPORT_SCHEMA_VERSION: u32 = 1;
+
+// -------------------------------------------------------------------------------------------------
+// ExportRecord
+// -------------------------------------------------------------------------------------------------
+/// A single record of a datastore in JSON Lines format, as written by `datastore export` and read
+/// by `datastore import`.
+///
+/// A header record comes first.
+/// Each rule record appears before any blob record with matches from that rule.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportRecord {
+    /// Information about the export as a whole
+    Header(ExportHeader),
+
+    /// A rule that produced matches
+    Rule(RuleSyntax),
+
+    /// A blob, where it was seen, and the matches found in it
+    Blob(BlobRecord),
+}
+
+// -------------------------------------------------------------------------------------------------
+// ExportHeader
+// -------------------------------------------------------------------------------------------------
+/// Information about an export as a whole
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportHeader {
+    /// The version of the export format; see `EXPORT_SCHEMA_VERSION`
+    pub schema_version: u32,
+
+    /// The overlap threshold that was used to determine redundant matches in the exported
+    /// datastore, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redundancy_overlap: Option<f64>,
+
+    /// The algorithm used to compute the IDs of blobs that do not come from Git repositories in
+    /// the exported datastore, if it has recorded any blobs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blob_id_algorithm: Option<BlobIdAlgorithm>,
+}
+
+impl Default for ExportHeader {
+    fn default() -> Self {
+        Self {
+            schema_version: EXPORT_SCHEMA_VERSION,
+            redundancy_overlap: None,
+            blob_id_algorithm: None,
+        }
+    }
+}
+
+impl ExportHeader {
+    /// Check that this header is from a version of the export format that can be read.
+    pub fn check_schema_version(&self) -> Result<()> {
+        if self.schema_version != EXPORT_SCHEMA_VERSION {
+            bail!(
+                "Unsupported export schema version {}; expected version {EXPORT_SCHEMA_VERSION}",
+                self.schema_version
+            );
+        }
+        Ok(())
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// BlobRecord
+// -------------------------------------------------------------------------------------------------
+/// A blob with matches, along with its provenance and metadata
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BlobRecord {
+    /// Where the blob was seen; this must be nonempty
+    pub provenance: Vec<Provenance>,
+
+    /// Metadata about the blob
+    pub blob_metadata: BlobMetadata,
+
+    /// The matches found in the blob
+    pub matches: Vec<ScoredMatch>,
+}
+
+/// A match along with its score, if it has one
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScoredMatch {
+    #[serde(flatten)]
+    pub match_val: Match,
+
+    pub score: Option<f64>,
+}
+
+impl BlobRecord {
+    /// Convert this record into an entry that can be recorded with `Transaction::record`.
+    pub fn into_batch_entry(self) -> Result<BatchEntry> {
+        let provenance = ProvenanceSet::try_from_iter(self.provenance)
+            .context("Blob record has no provenance entries")?;
+
+        let blob_id = self.blob_metadata.id;
+        let mut matches = Vec::with_capacity(self.matches.len());
+        for m in self.matches {
+            if m.match_val.blob_id != blob_id {
+                bail!(
+                    "Blob record for {blob_id} has a match from a different blob {}",
+                    m.match_val.blob_id
+                );
+            }
+            matches.push((m.score, m.match_val));
+        }
+
+        Ok((provenance, self.blob_metadata, matches))
+    }
+}
@@ -1,3 +1,6 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+
 use crate::blob_metadata::BlobMetadata;
 use crate::match_type::Match;
 use crate::provenance_set::ProvenanceSet;
@@ -26,3 +29,17 @@ pub struct FindingDataEntry {
     pub match_status: Option<Status>,
     pub redundant_to: Vec<String>,
 }
+
+// -------------------------------------------------------------------------------------------------
+// FindingOccurrences
+// -------------------------------------------------------------------------------------------------
+/// Counts of where the matches of a finding were seen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct FindingOccurrences {
+    /// The total number of provenance entries across all matches in the finding
+    pub occurrence_count: usize,
+
+    /// The number of distinct sources among those provenance entries.
+    /// A source is a plain file, a Git repository, or an extended provenance payload.
+    pub distinct_source_count: usize,
+}
@@ -0,0 +1,31 @@
+--------------------------------------------------------------------------------
+-- scan runs
+--------------------------------------------------------------------------------
+CREATE TABLE scan_run
+-- This table records each invocation of the `scan` command against the datastore.
+(
+    -- An arbitrary integer identifier for the scan run
+    id integer primary key,
+
+    -- When the scan run started, as an RFC 3339 UTC timestamp
+    started_at text not null,
+
+    -- When the scan run finished, as an RFC 3339 UTC timestamp.
+    -- This is null if the scan run did not complete.
+    finished_at text
+) STRICT;
+
+CREATE TABLE scan_run_label
+-- This table records arbitrary user-supplied key-value labels for scan runs.
+(
+    -- The integer identifier of the scan run
+    scan_run_id integer not null references scan_run(id),
+
+    -- The label key
+    key text not null,
+
+    -- The label value
+    value text not null,
+
+    unique(scan_run_id, key)
+) STRICT;
@@ -0,0 +1,23 @@
+--------------------------------------------------------------------------------
+-- scan run rule fingerprints
+--------------------------------------------------------------------------------
+ALTER TABLE scan_run
+-- A fingerprint of the set of rules used by the scan run, derived from their structural IDs.
+-- This is null for scan runs recorded before fingerprints were.
+ADD COLUMN rules_fingerprint text;
+
+--------------------------------------------------------------------------------
+-- scan run rules
+--------------------------------------------------------------------------------
+CREATE TABLE scan_run_rule
+-- This table records which rules were used by each scan run.
+-- Scan runs recorded before this table existed have no entries.
+(
+    -- The integer identifier of the scan run
+    scan_run_id integer not null references scan_run(id),
+
+    -- The integer identifier of the rule
+    rule_id integer not null references rule(id),
+
+    unique(scan_run_id, rule_id)
+) STRICT;
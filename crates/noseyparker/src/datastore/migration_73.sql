@@ -0,0 +1,22 @@
+--------------------------------------------------------------------------------
+-- scan run invocation details and totals
+--------------------------------------------------------------------------------
+ALTER TABLE scan_run
+-- The version of Nosey Parker that performed the scan run.
+-- This is null for scan runs recorded before versions were.
+ADD COLUMN version text;
+
+ALTER TABLE scan_run
+-- The command-line arguments of the scan run, as a JSON array of strings.
+-- This is null for scan runs recorded before arguments were.
+ADD COLUMN argv text;
+
+ALTER TABLE scan_run
+-- The number of blobs scanned by the scan run.
+-- This is null if the scan run did not complete, or was recorded before totals were.
+ADD COLUMN num_blobs integer;
+
+ALTER TABLE scan_run
+-- The number of matches found by the scan run.
+-- This is null if the scan run did not complete, or was recorded before totals were.
+ADD COLUMN num_matches integer;
@@ -0,0 +1,13 @@
+--------------------------------------------------------------------------------
+-- datastore settings
+--------------------------------------------------------------------------------
+CREATE TABLE setting
+-- This table records datastore-wide settings that are reused by later commands, such as the
+-- overlap threshold used to determine redundant matches.
+(
+    -- The name of the setting
+    name text primary key,
+
+    -- The value of the setting
+    value any not null
+) STRICT;
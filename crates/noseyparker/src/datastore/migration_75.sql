@@ -0,0 +1,41 @@
+--------------------------------------------------------------------------------
+-- truncated blob matches
+--------------------------------------------------------------------------------
+CREATE TABLE blob_truncated_matches
+-- This table records blobs for which some matches were not recorded, such as when the number of
+-- matches recorded per blob and rule was limited.
+(
+    -- The integer identifier of the blob
+    blob_id integer primary key references blob(id),
+
+    -- The number of matches found in the blob that were not recorded
+    num_truncated_matches integer not null
+) STRICT;
+
+DROP VIEW blob_denorm;
+
+CREATE VIEW blob_denorm
+-- A convenience view for blobs in denormalized form rather than the low-level
+-- datastore form that involves numerous indirection.
+(
+    id,
+    blob_id,
+    size,
+    mime_essence,
+    charset,
+    num_truncated_matches
+)
+as
+select
+    b.id,
+    b.blob_id,
+    b.size,
+    bm.mime_essence,
+    bc.charset,
+    bt.num_truncated_matches
+from
+    blob b
+    left outer join blob_mime_essence bm on (b.id = bm.blob_id)
+    left outer join blob_charset bc on (b.id = bc.blob_id)
+    left outer join blob_truncated_matches bt on (b.id = bt.blob_id)
+;
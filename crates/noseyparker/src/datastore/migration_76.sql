@@ -0,0 +1,8 @@
+--------------------------------------------------------------------------------
+-- scan run host
+--------------------------------------------------------------------------------
+ALTER TABLE scan_run
+-- The name of the host that performed the scan run.
+-- This is null if the host name could not be determined, or for scan runs recorded before host
+-- names were.
+ADD COLUMN hostname text;
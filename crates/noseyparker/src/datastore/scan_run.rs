@@ -0,0 +1,65 @@
+use noseyparker_rules::Rule;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+// -------------------------------------------------------------------------------------------------
+// ScanRun
+// -------------------------------------------------------------------------------------------------
+/// A record of a single invocation of the `scan` command against a `Datastore`.
+#[derive(Debug, Serialize)]
+pub struct ScanRun {
+    /// The identifier of this scan run within its datastore
+    pub id: i64,
+
+    /// When the scan run started, as an RFC 3339 UTC timestamp
+    pub started_at: String,
+
+    /// When the scan run finished, as an RFC 3339 UTC timestamp, if it completed
+    pub finished_at: Option<String>,
+
+    /// A fingerprint of the set of rules used by the scan run, if recorded
+    pub rules_fingerprint: Option<String>,
+
+    /// The user-supplied labels attached to the scan run
+    pub labels: BTreeMap<String, String>,
+
+    /// The version of Nosey Parker that performed the scan run, if recorded
+    pub version: Option<String>,
+
+    /// The command-line arguments of the scan run, if recorded
+    pub argv: Option<Vec<String>>,
+
+    /// The number of blobs scanned, if the scan run completed
+    pub num_blobs: Option<u64>,
+
+    /// The number of matches found, if the scan run completed
+    pub num_matches: Option<u64>,
+
+    /// The name of the host that performed the scan run, if recorded
+    pub hostname: Option<String>,
+}
+
+// -------------------------------------------------------------------------------------------------
+// ScanRunMetadata
+// -------------------------------------------------------------------------------------------------
+/// The details recorded about a scan run when it starts.
+#[derive(Debug, Default)]
+pub struct ScanRunMetadata<'a> {
+    /// The user-supplied labels to attach to the scan run
+    pub labels: &'a [(String, String)],
+
+    /// A fingerprint of the set of rules used by the scan run
+    pub rules_fingerprint: Option<&'a str>,
+
+    /// The version of Nosey Parker performing the scan run
+    pub version: Option<&'a str>,
+
+    /// The command-line arguments of the scan run
+    pub argv: &'a [String],
+
+    /// The name of the host performing the scan run
+    pub hostname: Option<&'a str>,
+
+    /// The rules used by the scan run
+    pub rules: &'a [Rule],
+}
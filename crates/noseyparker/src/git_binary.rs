@@ -1,5 +1,8 @@
+use anyhow::{bail, Context};
+use std::io::Read;
 use std::path::Path;
 use std::process::{Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
 use tracing::{debug, debug_span};
 
 use crate::git_url::GitUrl;
@@ -18,11 +21,17 @@ pub enum GitError {
         stderr: Vec<u8>,
         status: ExitStatus,
     },
+
+    #[error("git execution timed out after {}s", .0.as_secs_f64())]
+    Timeout(Duration),
 }
 
 pub struct Git {
     credentials: Vec<String>,
     ignore_certs: bool,
+    netrc: Option<Netrc>,
+    clone_timeout: Option<Duration>,
+    clone_depth: Option<u32>,
 }
 
 impl Git {
@@ -42,6 +51,38 @@ impl Git {
         Self {
             credentials,
             ignore_certs,
+            netrc: None,
+            clone_timeout: None,
+            clone_depth: None,
+        }
+    }
+
+    /// Use credentials from the given netrc file when fetching from hosts it lists.
+    pub fn with_netrc(mut self, netrc: Netrc) -> Self {
+        self.netrc = Some(netrc);
+        self
+    }
+
+    /// Kill any clone or update operation that does not complete within the given duration.
+    ///
+    /// A killed operation results in a `GitError::Timeout` error.
+    pub fn with_clone_timeout(mut self, timeout: Duration) -> Self {
+        self.clone_timeout = Some(timeout);
+        self
+    }
+
+    /// Fetch only the most recent `depth` commits of history when cloning or updating.
+    ///
+    /// This creates shallow clones, which contain less history to scan.
+    pub fn with_clone_depth(mut self, depth: u32) -> Self {
+        self.clone_depth = Some(depth);
+        self
+    }
+
+    /// Add a `--depth` argument to the given clone or fetch command, if a depth is configured.
+    fn add_depth_arg(&self, cmd: &mut Command) {
+        if let Some(depth) = self.clone_depth {
+            cmd.arg(format!("--depth={depth}"));
         }
     }
 
@@ -58,18 +99,37 @@ impl Git {
         cmd
     }
 
-    pub fn update_clone(&self, repo_url: &GitUrl, output_dir: &Path) -> Result<(), GitError> {
-        let _span = debug_span!("git_update", "{repo_url} {}", output_dir.display()).entered();
-        debug!("Attempting to update clone of {repo_url} at {}", output_dir.display());
-
+    /// Like `git`, but also configure credentials from the netrc file for `repo_url`, if it has
+    /// any for its host.
+    ///
+    /// The credentials are configured for the URL's origin only, so that git never offers them to
+    /// another host, such as when following a redirect. They are passed to git through environment
+    /// variables rather than command-line arguments.
+    ///
+    /// Netrc credentials are only used for `https` URLs; `ssh` authentication is left to ssh.
+    fn git_for_url(&self, repo_url: &GitUrl) -> Command {
         let mut cmd = self.git();
-        cmd.arg("--git-dir")
-            .arg(output_dir)
-            .arg("remote")
-            .arg("update")
-            .arg("--prune");
+        let creds = self
+            .netrc
+            .as_ref()
+            .filter(|_| repo_url.scheme() == "https")
+            .and_then(|n| n.credentials_for(repo_url.host_str()));
+        if let Some((login, password)) = creds {
+            let origin = repo_url.origin();
+            cmd.arg("-c").arg(format!("credential.{origin}.helper="));
+            cmd.arg("-c").arg(format!(
+                r#"credential.{origin}.helper=!_netrccreds() {{ echo username="$NP_NETRC_LOGIN"; echo password="$NP_NETRC_PASSWORD"; }}; _netrccreds"#
+            ));
+            cmd.env("NP_NETRC_LOGIN", login);
+            cmd.env("NP_NETRC_PASSWORD", password);
+        }
+        cmd
+    }
 
-        debug!("{cmd:#?}");
+    /// Run the given command, returning an error if it exits unsuccessfully.
+    fn run(mut cmd: Command) -> Result<(), GitError> {
+        // Only the arguments are logged, as the environment may contain credentials
+        debug!("Running git {:?}", cmd.get_args().collect::<Vec<_>>());
         let output = cmd.output()?;
         if !output.status.success() {
             return Err(GitError::GitError {
@@ -81,31 +141,179 @@ impl Git {
         Ok(())
     }
 
+    /// Run the given command like `run`, but kill it if it does not exit within `timeout`.
+    ///
+    /// The command's output is read on separate threads, which are not waited for if the command
+    /// is killed, as any processes it started may still hold its output open.
+    fn run_with_timeout(mut cmd: Command, timeout: Option<Duration>) -> Result<(), GitError> {
+        let Some(timeout) = timeout else {
+            return Self::run(cmd);
+        };
+
+        debug!("Running git {:?} with {timeout:?} timeout", cmd.get_args().collect::<Vec<_>>());
+        cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+        let mut child = cmd.spawn()?;
+
+        let read_all = |pipe: Option<Box<dyn Read + Send>>| {
+            std::thread::spawn(move || {
+                let mut buf = Vec::new();
+                if let Some(mut pipe) = pipe {
+                    let _ = pipe.read_to_end(&mut buf);
+                }
+                buf
+            })
+        };
+        let stdout = read_all(child.stdout.take().map(|p| Box::new(p) as _));
+        let stderr = read_all(child.stderr.take().map(|p| Box::new(p) as _));
+
+        let deadline = Instant::now() + timeout;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                child.kill()?;
+                child.wait()?;
+                return Err(GitError::Timeout(timeout));
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        };
+
+        if !status.success() {
+            return Err(GitError::GitError {
+                stdout: stdout.join().unwrap_or_default(),
+                stderr: stderr.join().unwrap_or_default(),
+                status,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn update_clone(&self, repo_url: &GitUrl, output_dir: &Path) -> Result<(), GitError> {
+        let _span = debug_span!("git_update", "{repo_url} {}", output_dir.display()).entered();
+        debug!("Attempting to update clone of {repo_url} at {}", output_dir.display());
+
+        let mut cmd = self.git_for_url(repo_url);
+        cmd.arg("--git-dir").arg(output_dir);
+        if self.clone_depth.is_some() {
+            // `git remote update` has no `--depth` option; this is otherwise equivalent
+            cmd.arg("fetch").arg("--all").arg("--prune");
+            self.add_depth_arg(&mut cmd);
+        } else {
+            cmd.arg("remote").arg("update").arg("--prune");
+        }
+
+        Self::run_with_timeout(cmd, self.clone_timeout)
+    }
+
+    /// Create a fresh clone of `repo_url` at `output_dir`.
+    ///
+    /// If `fetch_refspecs` is non-empty, only refs matching those refspecs are fetched, rather than
+    /// the ones that `clone_mode` would otherwise fetch.
+    /// The refspecs are recorded in the clone's configuration, so they are also used when it is
+    /// later updated.
     pub fn create_fresh_clone(
         &self,
         repo_url: &GitUrl,
         output_dir: &Path,
         clone_mode: CloneMode,
+        fetch_refspecs: &[String],
     ) -> Result<(), GitError> {
         let _span = debug_span!("git_clone", "{repo_url} {}", output_dir.display()).entered();
         debug!("Attempting to create fresh clone of {} at {}", repo_url, output_dir.display());
 
+        if !fetch_refspecs.is_empty() {
+            let res = self.create_fresh_clone_with_refspecs(
+                repo_url,
+                output_dir,
+                clone_mode,
+                fetch_refspecs,
+            );
+            if res.is_err() {
+                // Like `git clone`, don't leave a partial clone behind
+                let _ = std::fs::remove_dir_all(output_dir);
+            }
+            return res;
+        }
+
+        let mut cmd = self.git_for_url(repo_url);
+        cmd.arg("clone").arg(clone_mode.arg());
+        self.add_depth_arg(&mut cmd);
+        cmd.arg("--").arg(repo_url.as_str()).arg(output_dir);
+
+        let res = Self::run_with_timeout(cmd, self.clone_timeout);
+        if let Err(GitError::Timeout(_)) = res {
+            // A killed `git clone` does not get the chance to clean up after itself
+            let _ = std::fs::remove_dir_all(output_dir);
+        }
+        res
+    }
+
+    /// Create a fresh clone of the Git bundle file at `bundle_path` at `output_dir`.
+    pub fn clone_bundle(
+        &self,
+        bundle_path: &Path,
+        output_dir: &Path,
+        clone_mode: CloneMode,
+    ) -> Result<(), GitError> {
+        let _span =
+            debug_span!("git_clone_bundle", "{} {}", bundle_path.display(), output_dir.display())
+                .entered();
+        debug!(
+            "Attempting to create fresh clone of bundle {} at {}",
+            bundle_path.display(),
+            output_dir.display()
+        );
+
         let mut cmd = self.git();
         cmd.arg("clone")
             .arg(clone_mode.arg())
-            .arg(repo_url.as_str())
+            .arg("--")
+            .arg(bundle_path)
             .arg(output_dir);
 
-        debug!("{cmd:#?}");
-        let output = cmd.output()?;
-        if !output.status.success() {
-            return Err(GitError::GitError {
-                stdout: output.stdout,
-                stderr: output.stderr,
-                status: output.status,
-            });
+        Self::run(cmd)
+    }
+
+    /// `git clone` has no way to replace the default refspecs, so instead the clone is done in
+    /// steps: initialize an empty bare repository, configure its remote, and then fetch.
+    fn create_fresh_clone_with_refspecs(
+        &self,
+        repo_url: &GitUrl,
+        output_dir: &Path,
+        clone_mode: CloneMode,
+        fetch_refspecs: &[String],
+    ) -> Result<(), GitError> {
+        let mut cmd = self.git();
+        cmd.arg("init").arg("--bare").arg("--quiet").arg(output_dir);
+        Self::run(cmd)?;
+
+        let config = |args: &[&str]| {
+            let mut cmd = self.git();
+            cmd.arg("--git-dir")
+                .arg(output_dir)
+                .arg("config")
+                .args(args);
+            Self::run(cmd)
+        };
+
+        config(&["remote.origin.url", repo_url.as_str()])?;
+        for refspec in fetch_refspecs {
+            config(&["--add", "remote.origin.fetch", refspec])?;
         }
-        Ok(())
+        if let CloneMode::Mirror = clone_mode {
+            config(&["remote.origin.mirror", "true"])?;
+        }
+
+        let mut cmd = self.git_for_url(repo_url);
+        cmd.arg("--git-dir")
+            .arg(output_dir)
+            .arg("fetch")
+            .arg("--quiet");
+        self.add_depth_arg(&mut cmd);
+        cmd.arg("origin");
+
+        Self::run_with_timeout(cmd, self.clone_timeout)
     }
 }
 
@@ -134,3 +342,236 @@ impl CloneMode {
         }
     }
 }
+
+// -------------------------------------------------------------------------------------------------
+// Netrc
+// -------------------------------------------------------------------------------------------------
+/// Credentials read from a netrc file, for HTTP basic authentication when fetching Git
+/// repositories.
+///
+/// Only `machine` entries with both a login and a password are used.
+/// Any `default` entry is ignored, so that credentials are never offered to a host that is not
+/// explicitly listed.
+#[derive(Default, Clone)]
+pub struct Netrc {
+    entries: Vec<NetrcEntry>,
+}
+
+#[derive(Clone)]
+struct NetrcEntry {
+    /// `None` for the `default` entry
+    machine: Option<String>,
+    login: Option<String>,
+    password: Option<String>,
+}
+
+impl std::fmt::Debug for Netrc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Don't expose credentials in debug output
+        f.debug_list()
+            .entries(self.entries.iter().map(|e| &e.machine))
+            .finish()
+    }
+}
+
+impl Netrc {
+    /// Load a netrc file from the given path.
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read netrc file {}", path.display()))?;
+        Self::parse(&contents)
+            .with_context(|| format!("Failed to parse netrc file {}", path.display()))
+    }
+
+    /// Parse the contents of a netrc file.
+    pub fn parse(contents: &str) -> anyhow::Result<Self> {
+        let mut entries: Vec<NetrcEntry> = Vec::new();
+        let mut in_macdef = false;
+        let mut pending_key: Option<&str> = None;
+
+        for line in contents.lines() {
+            // A macro definition continues until the next blank line
+            if in_macdef {
+                if line.trim().is_empty() {
+                    in_macdef = false;
+                }
+                continue;
+            }
+
+            let mut starts_macdef = false;
+            for token in line.split_whitespace() {
+                if let Some(key) = pending_key.take() {
+                    match key {
+                        "machine" => entries.push(NetrcEntry {
+                            machine: Some(token.to_string()),
+                            login: None,
+                            password: None,
+                        }),
+                        "macdef" => starts_macdef = true,
+                        _ => {
+                            let entry = match entries.last_mut() {
+                                Some(entry) => entry,
+                                None => bail!("`{key}` appears before any `machine` or `default`"),
+                            };
+                            match key {
+                                "login" => entry.login = Some(token.to_string()),
+                                "password" => entry.password = Some(token.to_string()),
+                                _ => {} // "account"
+                            }
+                        }
+                    }
+                    continue;
+                }
+
+                match token {
+                    "machine" | "login" | "password" | "account" | "macdef" => {
+                        pending_key = Some(token)
+                    }
+                    "default" => entries.push(NetrcEntry {
+                        machine: None,
+                        login: None,
+                        password: None,
+                    }),
+                    _ => bail!("Unexpected token `{token}`"),
+                }
+            }
+            if starts_macdef {
+                in_macdef = true;
+            }
+        }
+
+        if let Some(key) = pending_key {
+            bail!("Expected a value after `{key}`");
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Get the login and password for the given host, if any.
+    ///
+    /// If there are several entries for the host, the first one is used.
+    pub fn credentials_for(&self, host: &str) -> Option<(&str, &str)> {
+        let entry = self.entries.iter().find(|e| {
+            e.machine
+                .as_deref()
+                .is_some_and(|m| m.eq_ignore_ascii_case(host))
+        })?;
+        Some((entry.login.as_deref()?, entry.password.as_deref()?))
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// tests
+// -------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::io::Write;
+    use std::str::FromStr;
+
+    const NETRC: &str = "\
+machine git.example.com login alice password s3cret
+macdef init
+machine evil.example.com login mallory password nope
+
+machine other.example.com
+    login bob
+default login anonymous password guest
+";
+
+    #[cfg(unix)]
+    #[test]
+    fn run_with_timeout() {
+        let mut cmd = Command::new("sleep");
+        cmd.arg("10");
+        let start = Instant::now();
+        let res = Git::run_with_timeout(cmd, Some(Duration::from_millis(100)));
+        assert!(matches!(res, Err(GitError::Timeout(_))), "{res:?}");
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        let res = Git::run_with_timeout(Command::new("true"), Some(Duration::from_secs(10)));
+        assert!(res.is_ok(), "{res:?}");
+
+        let res = Git::run_with_timeout(Command::new("false"), Some(Duration::from_secs(10)));
+        assert!(matches!(res, Err(GitError::GitError { .. })), "{res:?}");
+    }
+
+    #[test]
+    fn netrc_parse() {
+        let netrc = Netrc::parse(NETRC).unwrap();
+        assert_eq!(netrc.credentials_for("git.example.com"), Some(("alice", "s3cret")));
+        assert_eq!(netrc.credentials_for("GIT.example.com"), Some(("alice", "s3cret")));
+
+        // within a macro definition
+        assert_eq!(netrc.credentials_for("evil.example.com"), None);
+
+        // no password
+        assert_eq!(netrc.credentials_for("other.example.com"), None);
+
+        // `default` is not used
+        assert_eq!(netrc.credentials_for("unlisted.example.com"), None);
+    }
+
+    #[test]
+    fn netrc_parse_errors() {
+        assert!(Netrc::parse("login alice").is_err());
+        assert!(Netrc::parse("machine git.example.com login").is_err());
+        assert!(Netrc::parse("machine git.example.com user alice").is_err());
+    }
+
+    /// Ask git for the credentials it would use for `host` when running commands for `repo_url`.
+    fn credential_fill(git: &Git, repo_url: &str, host: &str) -> String {
+        let repo_url = GitUrl::from_str(repo_url).unwrap();
+        let mut cmd = git.git_for_url(&repo_url);
+        cmd.env("NP_GITHUB_TOKEN", "");
+        cmd.arg("credential").arg("fill");
+        cmd.stdin(Stdio::piped()).stdout(Stdio::piped());
+        let mut child = cmd.spawn().expect("should be able to run git");
+        write!(child.stdin.take().unwrap(), "protocol=https\nhost={host}\n\n").unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[test]
+    fn netrc_credentials_only_for_matching_host() {
+        let git = Git::new(false).with_netrc(Netrc::parse(NETRC).unwrap());
+
+        let out = credential_fill(&git, "https://git.example.com/repo.git", "git.example.com");
+        assert!(out.contains("username=alice\npassword=s3cret\n"), "{out}");
+
+        // Even when fetching from the matching host, the credentials are not offered to others,
+        // such as when redirected
+        let out = credential_fill(&git, "https://git.example.com/repo.git", "other.example.com");
+        assert!(!out.contains("s3cret"), "{out}");
+        assert!(out.contains("username=noseyparker\n"), "{out}");
+
+        let out =
+            credential_fill(&git, "https://unlisted.example.com/repo.git", "unlisted.example.com");
+        assert!(!out.contains("s3cret"), "{out}");
+        assert!(!out.contains("guest"), "{out}");
+    }
+
+    #[test]
+    fn netrc_credentials_not_used_for_ssh() {
+        let git = Git::new(false).with_netrc(Netrc::parse(NETRC).unwrap());
+
+        for repo_url in [
+            "ssh://git@git.example.com/repo.git",
+            "git@git.example.com:repo.git",
+        ] {
+            let cmd = git.git_for_url(&GitUrl::from_str(repo_url).unwrap());
+            assert!(
+                cmd.get_envs()
+                    .all(|(k, _)| !k.to_string_lossy().starts_with("NP_NETRC_")),
+                "{repo_url}"
+            );
+            assert!(
+                cmd.get_args()
+                    .all(|a| !a.to_string_lossy().starts_with("credential.")),
+                "{repo_url}"
+            );
+        }
+    }
+}
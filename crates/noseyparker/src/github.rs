@@ -1,8 +1,10 @@
+use std::path::Path;
 use url::Url;
 
 mod auth;
 mod client;
 mod client_builder;
+mod cursor_cache;
 mod error;
 mod models;
 mod repo_enumerator;
@@ -11,6 +13,7 @@ mod result;
 pub use auth::Auth;
 pub use client::Client;
 pub use client_builder::ClientBuilder;
+pub use cursor_cache::CursorCache;
 pub use error::Error;
 pub use repo_enumerator::{RepoEnumerator, RepoSpecifiers, RepoType};
 pub use result::Result;
@@ -21,12 +24,45 @@ use progress::Progress;
 ///
 /// This is a high-level wrapper for enumerating GitHub repositories that handles the details of
 /// creating an async runtime and a GitHub REST API client.
+///
+/// If a `cursor_cache` path is given, the progress of enumeration is recorded there after each
+/// page of results, and a previous enumeration that failed partway through is resumed from it.
+/// The file is removed once enumeration completes.
+///
+/// The resulting URLs are sorted and deduplicated.
 pub fn enumerate_repo_urls(
     repo_specifiers: &RepoSpecifiers,
     github_url: Url,
     ignore_certs: bool,
+    cursor_cache: Option<&Path>,
     progress: Option<&mut Progress>,
 ) -> anyhow::Result<Vec<String>> {
+    let mut repo_urls = Vec::new();
+    for_each_repo_url(repo_specifiers, github_url, ignore_certs, cursor_cache, progress, |url| {
+        repo_urls.push(url);
+        Ok(())
+    })?;
+    repo_urls.sort();
+    Ok(repo_urls)
+}
+
+/// Like `enumerate_repo_urls`, but calls `f` with each repository URL as each page of results is
+/// fetched, rather than collecting them.
+///
+/// The URLs are given in the order they are enumerated.
+/// They are deduplicated across all pages, so each URL already given is kept in memory until
+/// enumeration completes; this is far smaller than the full repository metadata of each page.
+pub fn for_each_repo_url<F>(
+    repo_specifiers: &RepoSpecifiers,
+    github_url: Url,
+    ignore_certs: bool,
+    cursor_cache: Option<&Path>,
+    progress: Option<&mut Progress>,
+    mut f: F,
+) -> anyhow::Result<()>
+where
+    F: FnMut(String) -> anyhow::Result<()>,
+{
     use anyhow::{bail, Context};
     use tracing::{debug, warn};
 
@@ -36,9 +72,15 @@ pub fn enumerate_repo_urls(
         .personal_access_token_from_env()
         .context("Failed to get GitHub access token from environment")?
         .ignore_certs(ignore_certs)
+        .progress(progress.as_deref().cloned())
         .build()
         .context("Failed to initialize GitHub client")?;
 
+    let cursor_cache = cursor_cache
+        .map(|path| CursorCache::load(path, &client.base_url))
+        .transpose()
+        .context("Failed to load GitHub cursor cache")?;
+
     let runtime = tokio::runtime::Builder::new_current_thread()
         .enable_all()
         .build()
@@ -54,15 +96,26 @@ pub fn enumerate_repo_urls(
         let rate_limit = client.get_rate_limit().await?;
         debug!("GitHub rate limits: {:?}", rate_limit.rate);
 
-        let repo_enumerator = RepoEnumerator::new(&client);
-        let repo_urls = repo_enumerator
-            .enumerate_repo_urls(repo_specifiers, progress)
-            .await?;
-        Ok(repo_urls) // ::<Vec<String>, Error>(repo_urls)
+        let mut repo_enumerator = RepoEnumerator::new(&client);
+        if let Some(cursor_cache) = &cursor_cache {
+            repo_enumerator = repo_enumerator.with_cursor_cache(cursor_cache);
+        }
+        repo_enumerator
+            .for_each_repo_url(repo_specifiers, progress, |url| Ok(f(url)?))
+            .await
     });
 
     match result {
-        Ok(repo_urls) => Ok(repo_urls),
+        Ok(()) => {
+            // Enumeration is complete, so there is nothing left to resume
+            if let Some(cursor_cache) = cursor_cache {
+                cursor_cache
+                    .remove()
+                    .context("Failed to remove GitHub cursor cache")?;
+            }
+            Ok(())
+        }
+        Err(Error::Other(err)) => Err(err),
         Err(err) => {
             if let Error::RateLimited { wait, .. } = err {
                 let suggestion = if client.is_authenticated() {
@@ -1,4 +1,7 @@
 use secrecy::SecretString;
+use tracing::debug;
+
+use super::{Error, Result};
 
 // -------------------------------------------------------------------------------------------------
 // Auth
@@ -11,3 +14,80 @@ pub enum Auth {
     /// Authenticate with a GitHub Personal Access Token
     PersonalAccessToken(SecretString),
 }
+
+impl Auth {
+    /// The environment variable from which a GitHub personal access token is loaded when no
+    /// host-specific one is set
+    pub const TOKEN_ENV_VAR: &'static str = "NP_GITHUB_TOKEN";
+
+    /// Get the name of the environment variable from which a GitHub personal access token for the
+    /// given API host is loaded.
+    ///
+    /// This is `NP_GITHUB_TOKEN_<HOST>`, where `<HOST>` is the host converted to uppercase, with
+    /// each character that is not an ASCII letter or digit replaced by an underscore.
+    /// For example, the variable for `github.example.com` is `NP_GITHUB_TOKEN_GITHUB_EXAMPLE_COM`.
+    pub fn host_token_env_var(host: &str) -> String {
+        let host: String = host
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_uppercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        format!("{}_{host}", Self::TOKEN_ENV_VAR)
+    }
+
+    /// Load authentication for the given API host from the environment.
+    ///
+    /// A personal access token is loaded from the host-specific environment variable (see
+    /// `host_token_env_var`) if it is set, and otherwise from `NP_GITHUB_TOKEN`.
+    /// If neither variable is set, unauthenticated access is used.
+    pub fn from_env(host: Option<&str>) -> Result<Self> {
+        if let Some(host) = host {
+            if let Some(auth) = Self::from_env_var(&Self::host_token_env_var(host))? {
+                return Ok(auth);
+            }
+        }
+        if let Some(auth) = Self::from_env_var(Self::TOKEN_ENV_VAR)? {
+            return Ok(auth);
+        }
+        debug!("No GitHub access token provided; using unauthenticated API access.");
+        Ok(Auth::Unauthenticated)
+    }
+
+    fn from_env_var(env_var_name: &str) -> Result<Option<Self>> {
+        match std::env::var(env_var_name) {
+            Err(std::env::VarError::NotPresent) => Ok(None),
+            Err(std::env::VarError::NotUnicode(_s)) => {
+                Err(Error::InvalidTokenEnvVar(env_var_name.to_string()))
+            }
+            Ok(val) => {
+                debug!(
+                    "Using GitHub personal access token from {env_var_name} environment variable"
+                );
+                Ok(Some(Auth::PersonalAccessToken(SecretString::from(val))))
+            }
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// tests
+// -------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn host_token_env_var() {
+        assert_eq!(Auth::host_token_env_var("api.github.com"), "NP_GITHUB_TOKEN_API_GITHUB_COM");
+        assert_eq!(
+            Auth::host_token_env_var("github-1.Example.com"),
+            "NP_GITHUB_TOKEN_GITHUB_1_EXAMPLE_COM"
+        );
+    }
+}
@@ -1,13 +1,15 @@
 use chrono::{DateTime, Duration, TimeDelta, TimeZone, Utc};
+use progress::Progress;
 use reqwest;
 use reqwest::{header, header::HeaderValue, StatusCode, Url};
 use secrecy::ExposeSecret;
+use tracing::warn;
 
 use super::models::{OrganizationShort, Page, RateLimitOverview, Repository, User};
-use super::{Auth, ClientBuilder, Error, Result};
+use super::{Auth, ClientBuilder, CursorCache, Error, Result};
 
 // TODO: debug logging
-// TODO: retry combinators, to handle rate limiting and HTTP errors
+// TODO: retry combinators, to handle HTTP errors other than rate limiting
 
 // -------------------------------------------------------------------------------------------------
 // Client
@@ -16,6 +18,9 @@ pub struct Client {
     pub(super) base_url: Url,
     pub(super) inner: reqwest::Client,
     pub(super) auth: Auth,
+    pub(super) max_rate_limit_retries: u32,
+    pub(super) max_rate_limit_wait: std::time::Duration,
+    pub(super) progress: Option<Progress>,
 }
 
 const MAX_PER_PAGE: (&str, &str) = ("per_page", "100");
@@ -92,13 +97,85 @@ impl Client {
         T: serde::de::DeserializeOwned,
     {
         let mut results = Vec::new();
+        self.for_each_page(page, |items| {
+            results.extend(items);
+            Ok(())
+        })
+        .await?;
+        Ok(results)
+    }
+
+    /// Like `get_all`, but calls `f` with the items of each page as it is fetched, rather than
+    /// collecting them.
+    pub async fn for_each_page<T, F>(&self, page: Page<T>, mut f: F) -> Result<()>
+    where
+        T: serde::de::DeserializeOwned,
+        F: FnMut(Vec<T>) -> Result<()>,
+    {
         let mut next_page = Some(page);
         while let Some(page) = next_page {
-            results.extend(page.items.into_iter());
+            f(page.items)?;
             next_page = self.next_page_inner(page.links.next).await?;
         }
+        Ok(())
+    }
+
+    /// Like `get_all`, but records the progress of the listing under `key` in the given cursor
+    /// cache after each page is fetched.
+    ///
+    /// If the cache already has progress recorded for the listing, fetching resumes from there,
+    /// and `first_page` is not awaited.
+    pub async fn get_all_resumable<T, F>(
+        &self,
+        first_page: F,
+        cache: &CursorCache,
+        key: &str,
+    ) -> Result<Vec<T>>
+    where
+        T: serde::de::DeserializeOwned + serde::Serialize,
+        F: std::future::Future<Output = Result<Page<T>>>,
+    {
+        let mut results = Vec::new();
+        self.for_each_page_resumable(first_page, cache, key, |items| {
+            results.extend(items);
+            Ok(())
+        })
+        .await?;
         Ok(results)
     }
+
+    /// Like `get_all_resumable`, but calls `f` with the items of each page as it is fetched,
+    /// rather than collecting them.
+    ///
+    /// When resuming, `f` is first called with all the items recorded in the cache.
+    pub async fn for_each_page_resumable<T, F, G>(
+        &self,
+        first_page: F,
+        cache: &CursorCache,
+        key: &str,
+        mut f: G,
+    ) -> Result<()>
+    where
+        T: serde::de::DeserializeOwned + serde::Serialize,
+        F: std::future::Future<Output = Result<Page<T>>>,
+        G: FnMut(Vec<T>) -> Result<()>,
+    {
+        let (items, mut next) = match cache.get(key)? {
+            Some(progress) => progress,
+            None => {
+                let page = first_page.await?;
+                cache.record_page(key, &page.items, page.links.next.as_ref())?;
+                (page.items, page.links.next)
+            }
+        };
+        f(items)?;
+        while let Some(page) = self.next_page_inner::<T>(next).await? {
+            cache.record_page(key, &page.items, page.links.next.as_ref())?;
+            f(page.items)?;
+            next = page.links.next;
+        }
+        Ok(())
+    }
 }
 
 /// Create a URL from the given base, path parts, and parameters.
@@ -140,6 +217,28 @@ fn url_from_path_parts_and_params(
     Ok(url)
 }
 
+/// The base delay for exponential backoff when a rate-limited response does not indicate how
+/// long to wait. GitHub recommends waiting at least a minute in that case.
+const RATE_LIMIT_BACKOFF_BASE: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Determine how long to wait before making the given retry (counting from 1) of a rate-limited
+/// request.
+///
+/// This is the indicated `wait` if there is one, and otherwise exponential backoff, in either case
+/// capped at `max_wait`.
+fn rate_limit_delay(
+    wait: Option<Duration>,
+    retry: u32,
+    max_wait: std::time::Duration,
+) -> std::time::Duration {
+    let delay = match wait.and_then(|w| w.to_std().ok()) {
+        // Wait an extra second, as the reset time has only second granularity
+        Some(wait) => wait + std::time::Duration::from_secs(1),
+        None => RATE_LIMIT_BACKOFF_BASE.saturating_mul(2u32.saturating_pow(retry - 1)),
+    };
+    delay.min(max_wait)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -208,6 +307,31 @@ mod test {
         }
     }
 
+    #[test]
+    fn rate_limit_delay_with_wait() {
+        let max_wait = std::time::Duration::from_secs(600);
+        assert_eq!(
+            rate_limit_delay(TimeDelta::try_seconds(30), 1, max_wait),
+            std::time::Duration::from_secs(31)
+        );
+        assert_eq!(rate_limit_delay(TimeDelta::try_seconds(3600), 1, max_wait), max_wait);
+
+        // A reset time in the past doesn't count as an indicated wait
+        assert_eq!(
+            rate_limit_delay(TimeDelta::try_seconds(-5), 1, max_wait),
+            std::time::Duration::from_secs(60)
+        );
+    }
+
+    #[test]
+    fn rate_limit_delay_backoff() {
+        let max_wait = std::time::Duration::from_secs(600);
+        let delays: Vec<u64> = (1..=5)
+            .map(|retry| rate_limit_delay(None, retry, max_wait).as_secs())
+            .collect();
+        assert_eq!(delays, vec![60, 120, 240, 480, 600]);
+    }
+
     #[test]
     fn url_from_path_parts_and_params_7() {
         let res = make_url("mailto:blah@example.com", &[], &[]);
@@ -251,7 +375,43 @@ impl Client {
         Page::from_response(response).await
     }
 
+    /// Get the given URL, retrying if the request is rate-limited.
     async fn get_url(&self, url: Url) -> Result<reqwest::Response> {
+        let mut retry = 0;
+        loop {
+            match self.get_url_once(url.clone()).await {
+                Err(Error::RateLimited { client_error, wait })
+                    if retry < self.max_rate_limit_retries =>
+                {
+                    retry += 1;
+                    let delay = rate_limit_delay(wait, retry, self.max_rate_limit_wait);
+                    let message = format!(
+                        "GitHub rate limit exceeded ({}); waiting {}s before retrying \
+                         ({retry}/{})",
+                        client_error.message,
+                        delay.as_secs(),
+                        self.max_rate_limit_retries,
+                    );
+                    match &self.progress {
+                        Some(progress) if progress.is_enabled() => {
+                            let mut progress = progress.clone();
+                            let prev_message = progress.message();
+                            progress.set_message(format!("{message}..."));
+                            tokio::time::sleep(delay).await;
+                            progress.set_message(prev_message);
+                        }
+                        _ => {
+                            warn!("{message}");
+                            tokio::time::sleep(delay).await;
+                        }
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn get_url_once(&self, url: Url) -> Result<reqwest::Response> {
         // build request, handling authentication if any
         let request_builder = self
             .inner
@@ -268,8 +428,8 @@ impl Client {
 
         // Check for rate limiting.
         //
-        // Instead of using an HTTP 429 response code, GitHub uses 403 and sets the
-        // `x-ratelimit-remaining` header to 0.
+        // Instead of using an HTTP 429 response code, GitHub usually uses 403 and sets the
+        // `x-ratelimit-remaining` header to 0. Either code may be used for secondary rate limits.
         //
         // Also from the GitHub docs on secondary rate limits:
         //
@@ -282,7 +442,8 @@ impl Client {
         //     Otherwise, retry your request after the time specified by the x-ratelimit-reset
         //     header. The x-ratelimit-reset header will always be an integer representing the
         //     time at which the current rate limit window resets in UTC epoch seconds.
-        if response.status() == StatusCode::FORBIDDEN {
+        let status = response.status();
+        if status == StatusCode::FORBIDDEN || status == StatusCode::TOO_MANY_REQUESTS {
             if let Some(retry_after) = response.headers().get("Retry-After") {
                 let wait =
                     atoi::atoi::<i64>(retry_after.as_bytes()).and_then(TimeDelta::try_seconds);
@@ -314,6 +475,14 @@ impl Client {
                 let client_error = response.json().await?;
                 return Err(Error::RateLimited { client_error, wait });
             }
+
+            if status == StatusCode::TOO_MANY_REQUESTS {
+                let client_error = response.json().await?;
+                return Err(Error::RateLimited {
+                    client_error,
+                    wait: None,
+                });
+            }
         }
 
         let response = response.error_for_status()?;
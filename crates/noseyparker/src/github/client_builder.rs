@@ -1,7 +1,8 @@
+use progress::Progress;
 use reqwest::{IntoUrl, Url};
-use tracing::debug;
+use std::time::Duration;
 
-use super::{Auth, Client, Error, Result};
+use super::{Auth, Client, Result};
 
 // -------------------------------------------------------------------------------------------------
 // ClientBuilder
@@ -10,18 +11,30 @@ pub struct ClientBuilder {
     base_url: reqwest::Url,
     auth: Auth,
     ignore_certs: bool,
+    max_rate_limit_retries: u32,
+    max_rate_limit_wait: Duration,
+    progress: Option<Progress>,
 }
 
 impl ClientBuilder {
     /// The user agent string sent when accessing the GitHub REST API
     const USER_AGENT: &'static str = "noseyparker";
 
+    /// The default number of times a rate-limited request is retried
+    pub const DEFAULT_MAX_RATE_LIMIT_RETRIES: u32 = 3;
+
+    /// The default maximum time to wait before retrying a rate-limited request
+    pub const DEFAULT_MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(15 * 60);
+
     /// Create a new `ClientBuilder` that uses unauthenticated access to <https://api.github.com>.
     pub fn new() -> Self {
         ClientBuilder {
             base_url: Url::parse("https://api.github.com").expect("default base URL should parse"),
             auth: Auth::Unauthenticated,
             ignore_certs: false,
+            max_rate_limit_retries: Self::DEFAULT_MAX_RATE_LIMIT_RETRIES,
+            max_rate_limit_wait: Self::DEFAULT_MAX_RATE_LIMIT_WAIT,
+            progress: None,
         }
     }
 
@@ -43,27 +56,39 @@ impl ClientBuilder {
         self
     }
 
-    /// Load an optional personal access token token from the `NP_GITHUB_TOKEN` environment variable.
-    /// If that variable is not set, unauthenticated access is used.
-    pub fn personal_access_token_from_env(self) -> Result<Self> {
-        self.personal_access_token_from_env_var("NP_GITHUB_TOKEN")
+    /// Retry a rate-limited request at most this many times before giving up.
+    ///
+    /// Before each retry, the client waits until the rate limit resets, if GitHub indicates when
+    /// that is, and otherwise backs off exponentially.
+    pub fn max_rate_limit_retries(mut self, max_rate_limit_retries: u32) -> Self {
+        self.max_rate_limit_retries = max_rate_limit_retries;
+        self
     }
 
-    fn personal_access_token_from_env_var(mut self, env_var_name: &str) -> Result<Self> {
-        match std::env::var(env_var_name) {
-            Err(std::env::VarError::NotPresent) => {
-                debug!("No GitHub access token provided; using unauthenticated API access.");
-            }
-            Err(std::env::VarError::NotUnicode(_s)) => {
-                return Err(Error::InvalidTokenEnvVar(env_var_name.to_string()));
-            }
-            Ok(val) => {
-                debug!(
-                    "Using GitHub personal access token from {env_var_name} environment variable"
-                );
-                self.auth = Auth::PersonalAccessToken(secrecy::SecretString::from(val));
-            }
-        }
+    /// Wait at most this long before retrying a rate-limited request.
+    pub fn max_rate_limit_wait(mut self, max_rate_limit_wait: Duration) -> Self {
+        self.max_rate_limit_wait = max_rate_limit_wait;
+        self
+    }
+
+    /// Show waits for rate limits to reset in the given progress bar.
+    ///
+    /// Without one, waits are logged as warnings instead.
+    pub fn progress(mut self, progress: Option<Progress>) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Load an optional personal access token from the environment.
+    ///
+    /// The token is read from a variable specific to the host of the base URL, such as
+    /// `NP_GITHUB_TOKEN_GITHUB_EXAMPLE_COM` for `https://github.example.com/api/v3`, falling back to
+    /// the `NP_GITHUB_TOKEN` environment variable.
+    /// If neither variable is set, unauthenticated access is used.
+    ///
+    /// This uses the base URL in effect at the time it is called.
+    pub fn personal_access_token_from_env(mut self) -> Result<Self> {
+        self.auth = Auth::from_env(self.base_url.host_str())?;
         Ok(self)
     }
 
@@ -77,6 +102,9 @@ impl ClientBuilder {
             base_url: self.base_url,
             auth: self.auth,
             inner,
+            max_rate_limit_retries: self.max_rate_limit_retries,
+            max_rate_limit_wait: self.max_rate_limit_wait,
+            progress: self.progress,
         })
     }
 }
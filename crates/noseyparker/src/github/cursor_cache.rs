@@ -0,0 +1,322 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{debug, warn};
+use url::Url;
+
+use super::{Error, Result};
+
+// -------------------------------------------------------------------------------------------------
+// CursorCache
+// -------------------------------------------------------------------------------------------------
+/// Records the progress of paginated GitHub REST API listings in a JSON Lines file, so that an
+/// enumeration that fails partway through can be resumed without fetching the same pages again.
+///
+/// Each listing is identified by a key, such as `orgs/praetorian-inc/repos`.
+/// The file is written in full only when the first page is recorded; each page after that is
+/// appended to it as a single line.
+pub struct CursorCache {
+    path: PathBuf,
+    state: Mutex<State>,
+}
+
+struct State {
+    /// The GitHub API URL that the listings come from
+    base_url: String,
+
+    listings: BTreeMap<String, Listing>,
+
+    /// The cache file, once it has been written and opened for appending
+    file: Option<File>,
+}
+
+/// The first line of the cache file
+#[derive(Deserialize, Serialize)]
+struct Header {
+    /// The GitHub API URL that the listings come from
+    base_url: String,
+}
+
+/// A line of the cache file after the header, giving the items from one or more pages of a
+/// listing
+#[derive(Deserialize, Serialize)]
+struct PageRecord<'a> {
+    /// The key of the listing
+    key: Cow<'a, str>,
+
+    items: Cow<'a, [serde_json::Value]>,
+
+    /// The URL of the next page to fetch, or `None` if all pages have been fetched
+    next_page: Option<Cow<'a, str>>,
+}
+
+/// The progress of a single paginated listing
+#[derive(Default)]
+struct Listing {
+    /// The items from all pages fetched so far
+    items: Vec<serde_json::Value>,
+
+    /// The URL of the next page to fetch, or `None` if all pages have been fetched
+    next_page: Option<String>,
+}
+
+impl CursorCache {
+    /// Load the cursor cache at the given path, for listings from the given GitHub API URL.
+    ///
+    /// If the file does not exist, or if it was written for a different API URL, the cache starts
+    /// out empty.
+    pub fn load(path: &Path, base_url: &Url) -> Result<Self> {
+        let listings = match std::fs::read(path) {
+            Ok(bytes) => Self::parse(path, &bytes, base_url)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(Error::CursorCacheIoError(path.to_owned(), e)),
+        };
+
+        Ok(Self {
+            path: path.to_owned(),
+            state: Mutex::new(State {
+                base_url: base_url.to_string(),
+                listings,
+                file: None,
+            }),
+        })
+    }
+
+    /// Parse the contents of a cache file, returning its listings if it was written for the given
+    /// API URL.
+    fn parse(path: &Path, bytes: &[u8], base_url: &Url) -> Result<BTreeMap<String, Listing>> {
+        let parse_error = |e| Error::CursorCacheParseError(path.to_owned(), e);
+        let mut listings = BTreeMap::new();
+
+        let mut lines = bytes
+            .split(|b| *b == b'\n')
+            .filter(|l| !l.is_empty())
+            .peekable();
+        let header: Header = match lines.next() {
+            Some(line) => serde_json::from_slice(line).map_err(parse_error)?,
+            None => return Ok(listings),
+        };
+        if header.base_url != base_url.as_str() {
+            warn!(
+                "Ignoring cursor cache {}: it was written for {}, not {base_url}",
+                path.display(),
+                header.base_url
+            );
+            return Ok(listings);
+        }
+
+        while let Some(line) = lines.next() {
+            let record: PageRecord = match serde_json::from_slice(line) {
+                Ok(record) => record,
+                // The last line is cut short if appending it was interrupted
+                Err(e) if lines.peek().is_none() && !bytes.ends_with(b"\n") => {
+                    warn!("Ignoring truncated last record of cursor cache {}: {e}", path.display());
+                    break;
+                }
+                Err(e) => return Err(parse_error(e)),
+            };
+            let listing: &mut Listing = listings.entry(record.key.into_owned()).or_default();
+            listing.items.extend(record.items.into_owned());
+            listing.next_page = record.next_page.map(Cow::into_owned);
+        }
+
+        debug!(
+            "Resuming {} GitHub listings from cursor cache {}",
+            listings.len(),
+            path.display()
+        );
+        Ok(listings)
+    }
+
+    /// Delete the cache file, such as once enumeration has completed.
+    pub fn remove(self) -> Result<()> {
+        // Close the file first
+        let Self { path, state } = self;
+        drop(state);
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(Error::CursorCacheIoError(path, e))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Get the items fetched so far for the listing with the given key, along with the URL of
+    /// its next page, if any.
+    ///
+    /// Returns `None` if no pages of the listing have been recorded.
+    pub(super) fn get<T: DeserializeOwned>(
+        &self,
+        key: &str,
+    ) -> Result<Option<(Vec<T>, Option<Url>)>> {
+        let parse_error = |e| Error::CursorCacheParseError(self.path.clone(), e);
+        let state = self.state.lock().unwrap();
+        let listing = match state.listings.get(key) {
+            Some(listing) => listing,
+            None => return Ok(None),
+        };
+        let items = listing
+            .items
+            .iter()
+            .map(|v| T::deserialize(v).map_err(parse_error))
+            .collect::<Result<Vec<T>>>()?;
+        let next_page = listing.next_page.as_deref().map(Url::parse).transpose()?;
+        Ok(Some((items, next_page)))
+    }
+
+    /// Record a newly fetched page of the listing with the given key, and append it to the cache
+    /// file.
+    pub(super) fn record_page<T: Serialize>(
+        &self,
+        key: &str,
+        items: &[T],
+        next_page: Option<&Url>,
+    ) -> Result<()> {
+        let parse_error = |e| Error::CursorCacheParseError(self.path.clone(), e);
+        let items = items
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<serde_json::Result<Vec<_>>>()
+            .map_err(parse_error)?;
+        let record = PageRecord {
+            key: key.into(),
+            items: items.into(),
+            next_page: next_page.map(|u| u.as_str().into()),
+        };
+        let mut line = serde_json::to_vec(&record).map_err(parse_error)?;
+        line.push(b'\n');
+
+        let mut state = self.state.lock().unwrap();
+        self.file(&mut state)?
+            .write_all(&line)
+            .map_err(|e| Error::CursorCacheIoError(self.path.clone(), e))?;
+        let listing = state.listings.entry(key.to_owned()).or_default();
+        listing.items.extend(record.items.into_owned());
+        listing.next_page = next_page.map(|u| u.to_string());
+        Ok(())
+    }
+
+    /// Get the cache file, opened for appending.
+    ///
+    /// The first time this is called, the file is written with the listings loaded so far, which
+    /// also drops any truncated record or listings for a different API URL.
+    fn file<'s>(&self, state: &'s mut State) -> Result<&'s mut File> {
+        if state.file.is_none() {
+            let io_error = |e| Error::CursorCacheIoError(self.path.clone(), e);
+            let parse_error = |e| Error::CursorCacheParseError(self.path.clone(), e);
+
+            let header = Header {
+                base_url: state.base_url.clone(),
+            };
+            let mut contents = serde_json::to_vec(&header).map_err(parse_error)?;
+            contents.push(b'\n');
+            for (key, listing) in state.listings.iter() {
+                let record = PageRecord {
+                    key: key.into(),
+                    items: listing.items.as_slice().into(),
+                    next_page: listing.next_page.as_deref().map(Into::into),
+                };
+                serde_json::to_writer(&mut contents, &record).map_err(parse_error)?;
+                contents.push(b'\n');
+            }
+
+            // Write to a temporary file and rename it into place, so that an interruption does not
+            // leave a truncated cache behind
+            let tmp_path = self.path.with_extension("tmp");
+            std::fs::write(&tmp_path, contents)
+                .and_then(|()| std::fs::rename(&tmp_path, &self.path))
+                .map_err(io_error)?;
+            let file = File::options()
+                .append(true)
+                .open(&self.path)
+                .map_err(io_error)?;
+            state.file = Some(file);
+        }
+        Ok(state.file.as_mut().expect("cache file should be open"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn api_url() -> Url {
+        Url::parse("https://api.github.com/").unwrap()
+    }
+
+    #[test]
+    fn resume() -> Result<()> {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("cursor.json");
+        let next = Url::parse("https://api.github.com/orgs/example/repos?page=2").unwrap();
+
+        let cache = CursorCache::load(&path, &api_url())?;
+        assert_eq!(cache.get::<String>("orgs/example/repos")?, None);
+        cache.record_page("orgs/example/repos", &["a", "b"], Some(&next))?;
+        cache.record_page("users/example/repos", &["c"], None)?;
+
+        let cache = CursorCache::load(&path, &api_url())?;
+        assert_eq!(
+            cache.get::<String>("orgs/example/repos")?,
+            Some((vec!["a".to_string(), "b".to_string()], Some(next)))
+        );
+        assert_eq!(
+            cache.get::<String>("users/example/repos")?,
+            Some((vec!["c".to_string()], None))
+        );
+
+        cache.remove()?;
+        assert!(!path.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn truncated_record() -> Result<()> {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("cursor.json");
+        let next = Url::parse("https://api.github.com/orgs/example/repos?page=2").unwrap();
+
+        let cache = CursorCache::load(&path, &api_url())?;
+        cache.record_page("orgs/example/repos", &["a"], Some(&next))?;
+        cache.record_page("orgs/example/repos", &["b"], None)?;
+        drop(cache);
+
+        // Cut the last record short, as if appending it had been interrupted
+        let contents = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &contents[..contents.len() - 10]).unwrap();
+
+        let cache = CursorCache::load(&path, &api_url())?;
+        assert_eq!(
+            cache.get::<String>("orgs/example/repos")?,
+            Some((vec!["a".to_string()], Some(next)))
+        );
+
+        // Recording another page drops the truncated record from the file
+        cache.record_page("orgs/example/repos", &["c"], None)?;
+        let cache = CursorCache::load(&path, &api_url())?;
+        assert_eq!(
+            cache.get::<String>("orgs/example/repos")?,
+            Some((vec!["a".to_string(), "c".to_string()], None))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn different_api_url() -> Result<()> {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("cursor.json");
+
+        let cache = CursorCache::load(&path, &api_url())?;
+        cache.record_page("organizations", &["a"], None)?;
+
+        let other_url = Url::parse("https://github.example.com/api/v3/").unwrap();
+        let cache = CursorCache::load(&path, &other_url)?;
+        assert_eq!(cache.get::<String>("organizations")?, None);
+        Ok(())
+    }
+}
@@ -29,4 +29,14 @@ pub enum Error {
 
     #[error("error loading token: ill-formed value of {0} environment variable")]
     InvalidTokenEnvVar(String),
+
+    #[error("error accessing cursor cache {}: {}", .0.display(), .1)]
+    CursorCacheIoError(std::path::PathBuf, std::io::Error),
+
+    #[error("error reading cursor cache {}: {}", .0.display(), .1)]
+    CursorCacheParseError(std::path::PathBuf, serde_json::Error),
+
+    /// An error from a caller-supplied function that handles results as they are enumerated
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
 }
@@ -1,4 +1,4 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 pub mod page;
 pub use page::Page;
@@ -125,7 +125,7 @@ pub struct User {
 // -------------------------------------------------------------------------------------------------
 // Repository
 // -------------------------------------------------------------------------------------------------
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Repository {
     pub id: i64,
     pub node_id: String,
@@ -203,6 +203,8 @@ pub struct Repository {
     pub updated_at: Option<String>,
     // pub permissions: Option<Box<crate::models::RepositoryTemplateRepositoryPermissions>>,
     pub role_name: Option<String>,
+    // not persisted in a `CursorCache`, as it is a credential
+    #[serde(skip_serializing)]
     pub temp_clone_token: Option<String>,
     pub delete_branch_on_merge: Option<bool>,
     pub subscribers_count: Option<i64>,
@@ -221,7 +223,7 @@ pub struct Repository {
 // OrganizationShort
 // Defined as in: https://docs.github.com/en/rest/orgs/orgs?apiVersion=2022-11-28#list-organizations
 // -------------------------------------------------------------------------------------------------
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct OrganizationShort {
     pub login: String,
     pub id: i64,
@@ -1,5 +1,7 @@
-use super::models::{OrganizationShort, Repository};
-use super::{Client, Result};
+use std::collections::HashSet;
+
+use super::models::{OrganizationShort, Page, Repository};
+use super::{Client, CursorCache, Result};
 
 use progress::Progress;
 
@@ -7,29 +9,69 @@ use progress::Progress;
 /// repositories belonging to specific users or organizations.
 pub struct RepoEnumerator<'c> {
     client: &'c Client,
+    cursor_cache: Option<&'c CursorCache>,
 }
 
 impl<'c> RepoEnumerator<'c> {
     pub fn new(client: &'c Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            cursor_cache: None,
+        }
+    }
+
+    /// Record the progress of each listing in the given cursor cache, resuming from any progress
+    /// already recorded there.
+    pub fn with_cursor_cache(mut self, cursor_cache: &'c CursorCache) -> Self {
+        self.cursor_cache = Some(cursor_cache);
+        self
+    }
+
+    /// Get all pages of a listing, using the cursor cache if there is one.
+    async fn get_all<T, F>(&self, key: &str, first_page: F) -> Result<Vec<T>>
+    where
+        T: serde::de::DeserializeOwned + serde::Serialize,
+        F: std::future::Future<Output = Result<Page<T>>>,
+    {
+        match self.cursor_cache {
+            Some(cache) => self.client.get_all_resumable(first_page, cache, key).await,
+            None => self.client.get_all(first_page.await?).await,
+        }
+    }
+
+    /// Like `get_all`, but calls `f` with the items of each page as it is fetched.
+    async fn for_each_page<T, F, G>(&self, key: &str, first_page: F, f: G) -> Result<()>
+    where
+        T: serde::de::DeserializeOwned + serde::Serialize,
+        F: std::future::Future<Output = Result<Page<T>>>,
+        G: FnMut(Vec<T>) -> Result<()>,
+    {
+        match self.cursor_cache {
+            Some(cache) => {
+                self.client
+                    .for_each_page_resumable(first_page, cache, key, f)
+                    .await
+            }
+            None => self.client.for_each_page(first_page.await?, f).await,
+        }
     }
 
     /// Enumerate the accessible repositories that belong to the given user.
     pub async fn enumerate_user_repos(&self, username: &str) -> Result<Vec<Repository>> {
-        let repo_page = self.client.get_user_repos(username).await?;
-        self.client.get_all(repo_page).await
+        let key = format!("users/{username}/repos");
+        self.get_all(&key, self.client.get_user_repos(username))
+            .await
     }
 
     /// Enumerate the accessible repositories that belong to the given organization.
     pub async fn enumerate_org_repos(&self, orgname: &str) -> Result<Vec<Repository>> {
-        let repo_page = self.client.get_org_repos(orgname).await?;
-        self.client.get_all(repo_page).await
+        let key = format!("orgs/{orgname}/repos");
+        self.get_all(&key, self.client.get_org_repos(orgname)).await
     }
 
     /// Enumerate the accessible repositories that belong to the given organization.
     pub async fn enumerate_instance_orgs(&self) -> Result<Vec<OrganizationShort>> {
-        let org_page = self.client.get_orgs().await?;
-        self.client.get_all(org_page).await
+        self.get_all("organizations", self.client.get_orgs()).await
     }
 
     /// Enumerate the repository clone URLs found from the according to the given `RepoSpecifiers`,
@@ -39,17 +81,51 @@ impl<'c> RepoEnumerator<'c> {
     pub async fn enumerate_repo_urls(
         &self,
         repo_specifiers: &RepoSpecifiers,
-        mut progress: Option<&mut Progress>,
+        progress: Option<&mut Progress>,
     ) -> Result<Vec<String>> {
         let mut repo_urls = Vec::new();
+        self.for_each_repo_url(repo_specifiers, progress, |repo_url| {
+            repo_urls.push(repo_url);
+            Ok(())
+        })
+        .await?;
+        repo_urls.sort();
+        Ok(repo_urls)
+    }
 
-        for username in &repo_specifiers.user {
-            let mut to_add = self.enumerate_user_repos(username).await?;
-            to_add.retain(|r| repo_specifiers.repo_filter.filter(r));
+    /// Like `enumerate_repo_urls`, but calls `f` with each repository clone URL as each page of
+    /// results is fetched, rather than collecting them.
+    ///
+    /// The URLs are given in the order they are enumerated.
+    /// They are deduplicated across all pages, so each URL already given is kept in memory until
+    /// enumeration completes.
+    pub async fn for_each_repo_url<F>(
+        &self,
+        repo_specifiers: &RepoSpecifiers,
+        mut progress: Option<&mut Progress>,
+        mut f: F,
+    ) -> Result<()>
+    where
+        F: FnMut(String) -> Result<()>,
+    {
+        let mut seen_urls = HashSet::new();
+        let mut handle_page = |mut repos: Vec<Repository>| -> Result<()> {
+            repos.retain(|r| repo_specifiers.repo_filter.filter(r));
             if let Some(progress) = progress.as_mut() {
-                progress.inc(to_add.len() as u64);
+                progress.inc(repos.len() as u64);
+            }
+            for repo in repos {
+                if seen_urls.insert(repo.clone_url.clone()) {
+                    f(repo.clone_url)?;
+                }
             }
-            repo_urls.extend(to_add.into_iter().map(|r| r.clone_url));
+            Ok(())
+        };
+
+        for username in &repo_specifiers.user {
+            let key = format!("users/{username}/repos");
+            self.for_each_page(&key, self.client.get_user_repos(username), &mut handle_page)
+                .await?;
         }
 
         let instance_orgs: Vec<_> = if repo_specifiers.all_organizations {
@@ -68,18 +144,12 @@ impl<'c> RepoEnumerator<'c> {
             .collect();
 
         for orgname in orgs {
-            let mut to_add = self.enumerate_org_repos(orgname).await?;
-            to_add.retain(|r| repo_specifiers.repo_filter.filter(r));
-            if let Some(progress) = progress.as_mut() {
-                progress.inc(to_add.len() as u64);
-            }
-            repo_urls.extend(to_add.into_iter().map(|r| r.clone_url));
+            let key = format!("orgs/{orgname}/repos");
+            self.for_each_page(&key, self.client.get_org_repos(orgname), &mut handle_page)
+                .await?;
         }
 
-        repo_urls.sort();
-        repo_urls.dedup();
-
-        Ok(repo_urls)
+        Ok(())
     }
 }
 
@@ -0,0 +1,97 @@
+use anyhow::{Context, Result};
+use k8s_openapi::api::core::v1::Secret;
+use kube::api::{Api, ListParams};
+use tracing::debug;
+
+use progress::Progress;
+
+/// The number of Secret objects to request per page when listing.
+const PAGE_SIZE: u32 = 500;
+
+/// A single data value from a Kubernetes Secret object.
+#[derive(Debug)]
+pub struct SecretValue {
+    /// The namespace of the Secret object
+    pub namespace: String,
+
+    /// The name of the Secret object
+    pub name: String,
+
+    /// The key of the value within the Secret object's data
+    pub key: String,
+
+    /// The decoded value
+    pub value: Vec<u8>,
+}
+
+/// List the data values of accessible Kubernetes Secret objects.
+///
+/// The Kubernetes API server and credentials are determined from the ambient configuration, i.e.,
+/// from the kubeconfig file or from the in-cluster environment.
+/// If `namespaces` is empty, Secret objects from all namespaces are listed.
+///
+/// This is a high-level wrapper that handles the details of creating an async runtime and a
+/// Kubernetes API client.
+pub fn enumerate_secrets(
+    namespaces: &[String],
+    ignore_certs: bool,
+    mut progress: Option<&mut Progress>,
+) -> Result<Vec<SecretValue>> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .context("Failed to initialize async runtime")?;
+
+    runtime.block_on(async {
+        let mut config = kube::Config::infer()
+            .await
+            .context("Failed to load Kubernetes configuration")?;
+        config.accept_invalid_certs = ignore_certs;
+        debug!("Using Kubernetes API server at {}", config.cluster_url);
+        let client =
+            kube::Client::try_from(config).context("Failed to initialize Kubernetes client")?;
+
+        let apis: Vec<Api<Secret>> = if namespaces.is_empty() {
+            vec![Api::all(client)]
+        } else {
+            namespaces
+                .iter()
+                .map(|ns| Api::namespaced(client.clone(), ns))
+                .collect()
+        };
+
+        let mut values = Vec::new();
+        for api in apis {
+            let mut params = ListParams::default().limit(PAGE_SIZE);
+            loop {
+                let list = api
+                    .list(&params)
+                    .await
+                    .context("Failed to list Kubernetes secrets")?;
+
+                for secret in list.items {
+                    let namespace = secret.metadata.namespace.unwrap_or_default();
+                    let name = secret.metadata.name.unwrap_or_default();
+                    for (key, value) in secret.data.into_iter().flatten() {
+                        values.push(SecretValue {
+                            namespace: namespace.clone(),
+                            name: name.clone(),
+                            key,
+                            value: value.0,
+                        });
+                    }
+                    if let Some(progress) = progress.as_mut() {
+                        progress.inc(1);
+                    }
+                }
+
+                match list.metadata.continue_ {
+                    Some(token) if !token.is_empty() => params = params.continue_token(&token),
+                    _ => break,
+                }
+            }
+        }
+
+        Ok(values)
+    })
+}
@@ -10,13 +10,20 @@ pub mod git_binary;
 pub mod git_url;
 #[cfg(feature = "github")]
 pub mod github;
+#[cfg(feature = "kubernetes")]
+pub mod kubernetes;
 pub mod location;
 pub mod match_type;
 pub mod matcher;
 pub mod matcher_stats;
 pub mod provenance;
 pub mod provenance_set;
+#[cfg(feature = "remote_rules")]
+pub mod remote_rules;
 #[cfg(feature = "rule_profiling")]
 pub mod rule_profiling;
 pub mod rules_database;
+#[cfg(feature = "s3")]
+pub mod s3;
+pub mod scoring;
 pub mod snippet;
@@ -160,8 +160,88 @@ impl LocationMapping {
 // Location
 // -------------------------------------------------------------------------------------------------
 /// A span, including both the byte- and source-based representation.
-#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+///
+/// When serialized, the byte offsets of the span are also given directly as `start_byte` and
+/// `end_byte`.
+#[derive(Debug, Clone, Deserialize)]
 pub struct Location {
+    /// The byte offsets of the span within the blob.
+    ///
+    /// These are relative to the start of the original blob content, not to any snippet.
     pub offset_span: OffsetSpan,
+
+    /// The line and column of the span within the blob
     pub source_span: SourceSpan,
 }
+
+// The serialized form of a `Location`
+/// A span, including both the byte- and source-based representation.
+#[derive(Serialize, JsonSchema)]
+struct SerializedLocation<'a> {
+    /// The byte offsets of the span within the blob.
+    ///
+    /// These are relative to the start of the original blob content, not to any snippet.
+    offset_span: &'a OffsetSpan,
+
+    /// The line and column of the span within the blob
+    source_span: &'a SourceSpan,
+
+    /// The byte offset of the start of the span within the blob, the same as `offset_span.start`
+    start_byte: usize,
+
+    /// The byte offset just past the end of the span within the blob, the same as
+    /// `offset_span.end`
+    end_byte: usize,
+}
+
+impl Serialize for Location {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializedLocation {
+            offset_span: &self.offset_span,
+            source_span: &self.source_span,
+            start_byte: self.offset_span.start,
+            end_byte: self.offset_span.end,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl JsonSchema for Location {
+    fn schema_name() -> String {
+        "Location".into()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        SerializedLocation::json_schema(gen)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// test
+// -------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn location_byte_offsets() {
+        let location = Location {
+            offset_span: OffsetSpan::from_range(14..28),
+            source_span: SourceSpan {
+                start: SourcePoint { line: 2, column: 5 },
+                end: SourcePoint {
+                    line: 2,
+                    column: 18,
+                },
+            },
+        };
+        let json = serde_json::to_value(&location).unwrap();
+        assert_eq!(json["start_byte"], 14);
+        assert_eq!(json["end_byte"], 28);
+
+        let parsed: Location = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.offset_span, location.offset_span);
+        assert_eq!(parsed.source_span, location.source_span);
+    }
+}
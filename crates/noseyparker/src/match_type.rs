@@ -10,7 +10,7 @@ use tracing::debug;
 use crate::blob_id::BlobId;
 use crate::location::{Location, LocationMapping, OffsetSpan};
 use crate::matcher::BlobMatch;
-use crate::snippet::Snippet;
+use crate::snippet::{self, Snippet, SnippetMode};
 
 // -------------------------------------------------------------------------------------------------
 // Group
@@ -66,7 +66,7 @@ mod sql {
 // -------------------------------------------------------------------------------------------------
 // Match
 // -------------------------------------------------------------------------------------------------
-#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Match {
     /// The blob this match comes from
     pub blob_id: BlobId,
@@ -98,25 +98,22 @@ impl Match {
     pub fn convert<'a>(
         loc_mapping: &'a LocationMapping,
         blob_match: &'a BlobMatch<'a>,
-        snippet_context_bytes: usize,
+        snippet_before_bytes: usize,
+        snippet_after_bytes: usize,
+        snippet_mode: SnippetMode,
     ) -> Self {
         let offset_span = blob_match.matching_input_offset_span;
 
-        // FIXME: have the snippets start from a line break in the input when feasible, and include an ellipsis otherwise to indicate truncation
-        let before_snippet = {
-            let start = offset_span.start.saturating_sub(snippet_context_bytes);
-            let end = offset_span.start;
-            &blob_match.blob.bytes[start..end]
-        };
-
-        let after_snippet = {
-            let start = offset_span.end;
-            let end = offset_span
-                .end
-                .saturating_add(snippet_context_bytes)
-                .min(blob_match.blob.len());
-            &blob_match.blob.bytes[start..end]
-        };
+        // FIXME: include an ellipsis to indicate truncation of snippets
+        let (before_range, after_range) = snippet::context_ranges(
+            &blob_match.blob.bytes,
+            offset_span,
+            snippet_before_bytes,
+            snippet_after_bytes,
+            snippet_mode,
+        );
+        let before_snippet = &blob_match.blob.bytes[before_range];
+        let after_snippet = &blob_match.blob.bytes[after_range];
         let source_span = loc_mapping.get_source_span(&offset_span);
 
         debug_assert!(
@@ -199,3 +196,55 @@ impl Match {
         h.hexdigest()
     }
 }
+
+// -------------------------------------------------------------------------------------------------
+// test
+// -------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use noseyparker_rules::{Rule, RuleSyntax};
+    use pretty_assertions::assert_eq;
+
+    use crate::blob::Blob;
+    use crate::location::OffsetPoint;
+
+    #[test]
+    fn convert_offsets_are_blob_relative() {
+        let rule = Rule::new(RuleSyntax {
+            id: "test.1".to_string(),
+            name: "test".to_string(),
+            pattern: r"secret=(\w+)".to_string(),
+            examples: vec![],
+            negative_examples: vec![],
+            references: vec![],
+            categories: vec![],
+            description: None,
+            path_include: vec![],
+            path_exclude: vec![],
+        });
+        let blob = Blob::from_bytes(b"line one\nsome secret=hunter2 here\n".to_vec());
+        let re = rule.syntax().as_regex().unwrap();
+        let captures = re.captures(&blob.bytes).unwrap();
+        let whole = captures.get(0).unwrap();
+        let blob_match = BlobMatch {
+            rule: &rule,
+            blob: &blob,
+            matching_input: whole.as_bytes(),
+            matching_input_offset_span: OffsetSpan::from_offsets(
+                OffsetPoint(whole.start()),
+                OffsetPoint(whole.end()),
+            ),
+            captures,
+        };
+        let loc_mapping = LocationMapping::new(&blob.bytes);
+
+        let m = Match::convert(&loc_mapping, &blob_match, 5, 5, SnippetMode::Bytes);
+        let span = m.location.offset_span;
+        assert_eq!((span.start, span.end), (14, 28));
+        assert_eq!(&blob.bytes[span.start..span.end], b"secret=hunter2");
+        assert_eq!(m.snippet.before, "some ");
+        assert_eq!(m.location.source_span.start.line, 2);
+    }
+}
@@ -1,10 +1,12 @@
 use anyhow::Result;
+use std::collections::HashSet;
+use std::path::Path;
 use std::sync::Mutex;
-use tracing::error;
+use tracing::{debug, error};
 
 use noseyparker_rules::Rule;
 
-use crate::blob::Blob;
+use crate::blob::{Blob, BlobId};
 use crate::blob_id_map::BlobIdMap;
 use crate::location::{OffsetPoint, OffsetSpan};
 use crate::matcher_stats::MatcherStats;
@@ -91,6 +93,18 @@ pub struct Matcher<'a> {
 
     /// Data passed to the Vectorscan callback
     user_data: UserData,
+
+    /// The index of a rule whose matching to log in detail at the debug level, if any
+    trace_rule: Option<usize>,
+
+    /// The set of blobs that should not be scanned, if any
+    excluded_blobs: Option<&'a HashSet<BlobId>>,
+
+    /// Whether to skip scanning blobs that have already been seen
+    dedupe_blobs: bool,
+
+    /// Whether to skip scanning blobs that look minified
+    skip_minified: bool,
 }
 
 /// This `Drop` implementation updates the `global_stats` with the local stats
@@ -113,6 +127,7 @@ impl<'a> Drop for Matcher<'a> {
 pub enum ScanResult<'a> {
     SeenWithMatches,
     SeenSansMatches,
+    Excluded,
     New(Vec<BlobMatch<'a>>),
 }
 
@@ -137,9 +152,45 @@ impl<'a> Matcher<'a> {
             global_stats,
             seen_blobs,
             user_data,
+            trace_rule: None,
+            excluded_blobs: None,
+            dedupe_blobs: true,
+            skip_minified: false,
         })
     }
 
+    /// Log the details of matching the rule with the given index in the `RulesDatabase` at the
+    /// debug level: each raw Vectorscan match, and the reason for each one that is discarded.
+    ///
+    /// This is intended for debugging rules; it has no effect on which matches are found.
+    pub fn set_trace_rule(&mut self, rule_index: Option<usize>) {
+        self.trace_rule = rule_index;
+    }
+
+    /// Skip scanning blobs whose IDs are in the given set.
+    ///
+    /// Such blobs are reported as `ScanResult::Excluded`, and are not recorded as seen.
+    pub fn set_excluded_blobs(&mut self, excluded_blobs: Option<&'a HashSet<BlobId>>) {
+        self.excluded_blobs = excluded_blobs;
+    }
+
+    /// Set whether blobs that have already been seen are skipped rather than scanned again.
+    /// This is enabled by default.
+    ///
+    /// Disabling this is intended for measuring matching throughput; it has no effect on which
+    /// matches are found, and a blob's matches are still only reported as `ScanResult::New` once.
+    pub fn set_dedupe_blobs(&mut self, dedupe_blobs: bool) {
+        self.dedupe_blobs = dedupe_blobs;
+    }
+
+    /// Set whether blobs that look minified or machine-generated are skipped rather than scanned.
+    /// This is disabled by default.
+    ///
+    /// Such blobs are reported as `ScanResult::Excluded`, and are not recorded as seen.
+    pub fn set_skip_minified(&mut self, skip_minified: bool) {
+        self.skip_minified = skip_minified;
+    }
+
     fn scan_bytes_raw(&mut self, input: &[u8]) -> Result<()> {
         self.user_data.raw_matches_scratch.clear();
         self.vs_scanner
@@ -159,7 +210,9 @@ impl<'a> Matcher<'a> {
     /// If the blob was already scanned, `None` is returned.
     /// Otherwise, the matches found within the blob are returned.
     ///
-    /// NOTE: `provenance` is used only for diagnostic purposes if something goes wrong.
+    /// NOTE: `provenance` is used to check the path constraints of rules, and for diagnostic
+    /// purposes if something goes wrong. Path constraints are checked only against the provenance
+    /// given when the blob is first scanned.
     ///
     /// NOTE: There is a race condition in determining if a blob was already scanned.
     /// There is a chance that when using multiple scanning threads that a blob will be scanned
@@ -180,8 +233,25 @@ impl<'a> Matcher<'a> {
         self.local_stats.blobs_seen += 1;
         let nbytes: u64 = blob.bytes.len().try_into().unwrap();
         self.local_stats.bytes_seen += nbytes;
+        self.local_stats.blob_sizes.record(nbytes);
+
+        let trace_rule = self.trace_rule;
 
-        if let Some(had_matches) = self.seen_blobs.get(&blob.id) {
+        if self
+            .excluded_blobs
+            .is_some_and(|excluded| excluded.contains(&blob.id))
+        {
+            if trace_rule.is_some() {
+                debug!(blob_id = %blob.id, "trace-rule: blob is excluded; not scanning");
+            }
+            self.local_stats.blobs_excluded += 1;
+            return Ok(ScanResult::Excluded);
+        }
+
+        if let Some(had_matches) = self.seen_blobs.get(&blob.id).filter(|_| self.dedupe_blobs) {
+            if trace_rule.is_some() {
+                debug!(blob_id = %blob.id, "trace-rule: blob already seen; not scanning again");
+            }
             return Ok(if had_matches {
                 // debug!("blob already seen with matches; skipping");
                 ScanResult::SeenWithMatches
@@ -191,6 +261,17 @@ impl<'a> Matcher<'a> {
             });
         }
 
+        // This is checked after deduplication, so that each distinct minified blob is only
+        // examined and counted once
+        if self.skip_minified && content_guesser::is_minified(&blob.bytes) {
+            if trace_rule.is_some() {
+                debug!(blob_id = %blob.id, "trace-rule: blob looks minified; not scanning");
+            }
+            self.local_stats.blobs_minified += 1;
+            self.seen_blobs.insert(blob.id, false);
+            return Ok(ScanResult::Excluded);
+        }
+
         self.local_stats.blobs_scanned += 1;
         self.local_stats.bytes_scanned += nbytes;
 
@@ -200,6 +281,20 @@ impl<'a> Matcher<'a> {
         self.scan_bytes_raw(&blob.bytes)?;
 
         let raw_matches_scratch = &mut self.user_data.raw_matches_scratch;
+
+        if let Some(trace_rule) = trace_rule {
+            for m in raw_matches_scratch.iter() {
+                if m.rule_id as usize == trace_rule {
+                    debug!(
+                        blob_id = %blob.id,
+                        "trace-rule: raw Vectorscan match at [{}..{}]",
+                        m.start_idx,
+                        m.end_idx
+                    );
+                }
+            }
+        }
+
         if raw_matches_scratch.is_empty() {
             // No matches! We can exit early and save work.
             return Ok(match self.seen_blobs.insert(blob.id, false) {
@@ -214,8 +309,10 @@ impl<'a> Matcher<'a> {
         // -----------------------------------------------------------------------------------------
         // Update rule raw match stats
         // -----------------------------------------------------------------------------------------
-        #[cfg(feature = "rule_profiling")]
         for m in raw_matches_scratch.iter() {
+            self.local_stats.rule_match_counts.increment(m.rule_id as usize);
+
+            #[cfg(feature = "rule_profiling")]
             self.local_stats
                 .rule_stats
                 .increment_match_count(m.rule_id as usize, 1);
@@ -246,6 +343,19 @@ impl<'a> Matcher<'a> {
 
         let rules = &self.rules_db.rules;
         let anchored_regexes = &self.rules_db.anchored_regexes;
+
+        // The paths of the blob, for checking rule path constraints. Blobs without any known path
+        // are not subject to path constraints.
+        let blob_paths: Vec<&Path> = if self.rules_db.has_path_filters() {
+            provenance.iter().filter_map(|p| p.blob_path()).collect()
+        } else {
+            Vec::new()
+        };
+
+        // Were any matches discarded because of rule path constraints? If so, the blob may need to
+        // be scanned again when seen with other paths.
+        let mut path_filtered = false;
+
         // (rule id, regex captures) from most recently emitted match
         let mut previous: Option<(usize, OffsetSpan)> = None;
         // detect and suppress overlapping matches in a single pass
@@ -253,6 +363,20 @@ impl<'a> Matcher<'a> {
             .filter_map(|/*raw_match @*/ &RawMatch{ rule_id, start_idx, end_idx }| {
                 let rule_id: usize = rule_id.try_into().unwrap();
 
+                if let Some(path_filter) = self.rules_db.path_filter(rule_id) {
+                    if !blob_paths.is_empty() && !blob_paths.iter().any(|p| path_filter.is_match(p)) {
+                        if trace_rule == Some(rule_id) {
+                            debug!(
+                                blob_id = %blob.id,
+                                "trace-rule: discarding raw match at [{start_idx}..{end_idx}]: \
+                                 blob paths do not satisfy rule path constraints"
+                            );
+                        }
+                        path_filtered = true;
+                        return None;
+                    }
+                }
+
                 #[cfg(feature = "rule_profiling")]
                 let _rule_profiler = self.local_stats.rule_stats.time_stage2(rule_id);
 
@@ -264,6 +388,13 @@ impl<'a> Matcher<'a> {
                 // second-stage regex match
                 let captures = match re.captures(&blob.bytes[start_idx..end_idx]) {
                     None => {
+                        if trace_rule == Some(rule_id) {
+                            debug!(
+                                blob_id = %blob.id,
+                                "trace-rule: discarding raw match at [{start_idx}..{end_idx}]: \
+                                 second-stage regex did not match"
+                            );
+                        }
                         let cxt = String::from_utf8_lossy(
                             &blob.bytes[end_idx.saturating_sub(400)..end_idx]
                         );
@@ -295,6 +426,17 @@ impl<'a> Matcher<'a> {
                 // deduplicate overlaps
                 if let Some((prev_rule_id, prev_loc)) = previous {
                     if prev_rule_id == rule_id && prev_loc.fully_contains(&matching_input_offset_span) {
+                        if trace_rule == Some(rule_id) {
+                            debug!(
+                                blob_id = %blob.id,
+                                "trace-rule: discarding match at [{}..{}]: \
+                                 contained within previous match at [{}..{}]",
+                                matching_input_offset_span.start,
+                                matching_input_offset_span.end,
+                                prev_loc.start,
+                                prev_loc.end
+                            );
+                        }
                         // debug!("suppressing:\n    match: {raw_match:?}\n    previous: {previous:?}\n       match offset: {matching_input_offset_span:?}\n    previous offset: {prev_loc:?}");
                         return None
                     } else {
@@ -303,6 +445,15 @@ impl<'a> Matcher<'a> {
                 }
                 previous = Some((rule_id, matching_input_offset_span));
 
+                if trace_rule == Some(rule_id) {
+                    debug!(
+                        blob_id = %blob.id,
+                        "trace-rule: keeping match at [{}..{}]",
+                        matching_input_offset_span.start,
+                        matching_input_offset_span.end
+                    );
+                }
+
                 // Not a duplicate! Turn the RawMatch into a BlobMatch
                 let m = BlobMatch {
                     rule,
@@ -315,8 +466,48 @@ impl<'a> Matcher<'a> {
             }).collect();
         // debug!("postprocessed {} down to {}", raw_matches_scratch.len(), matches.len());
 
+        // The blob is not marked as seen if rule path constraints discarded some of its matches,
+        // so that the results don't depend on which of its paths is scanned first. Rescanning is
+        // harmless: recording the same match again has no effect.
+        if path_filtered {
+            if trace_rule.is_some() {
+                debug!(
+                    blob_id = %blob.id,
+                    "trace-rule: not marking blob as seen: matches discarded by path constraints"
+                );
+            }
+            if !matches.is_empty() {
+                self.local_stats.blobs_with_matches += 1;
+            }
+            return Ok(ScanResult::New(matches));
+        }
+
+        // Whether a match of the traced rule would be discarded if another thread won the race
+        let has_traced_match = trace_rule.is_some_and(|trace_rule| {
+            let traced = &rules[trace_rule];
+            matches.iter().any(|m| std::ptr::eq(m.rule, traced))
+        });
+
         Ok(match self.seen_blobs.insert(blob.id, !matches.is_empty()) {
-            None => ScanResult::New(matches),
+            None => {
+                if !matches.is_empty() {
+                    self.local_stats.blobs_with_matches += 1;
+                }
+                ScanResult::New(matches)
+            }
+
+            // We raced with another thread, which beat us, but we ended up scanning anyway.
+            Some(had_matches) if has_traced_match => {
+                debug!(
+                    blob_id = %blob.id,
+                    "trace-rule: discarding matches: blob concurrently scanned by another thread"
+                );
+                if had_matches {
+                    ScanResult::SeenWithMatches
+                } else {
+                    ScanResult::SeenSansMatches
+                }
+            }
 
             // We raced with another thread, which beat us, but we ended up scanning anyway.
             Some(true) => ScanResult::SeenWithMatches,
@@ -332,6 +523,7 @@ impl<'a> Matcher<'a> {
 mod test {
     use super::*;
 
+    use crate::provenance::Provenance;
     use noseyparker_rules::RuleSyntax;
 
     use pretty_assertions::assert_eq;
@@ -347,6 +539,8 @@ mod test {
             references: vec![],
             categories: vec![],
             description: None,
+            path_include: vec![],
+            path_exclude: vec![],
         })];
         let rules_db = RulesDatabase::from_rules(rules)?;
         let input = "some test data for vectorscan";
@@ -363,4 +557,40 @@ mod test {
         );
         Ok(())
     }
+
+    #[test]
+    pub fn test_path_filter_independent_of_scan_order() -> Result<()> {
+        let rules = vec![Rule::new(RuleSyntax {
+            id: "test.1".to_string(),
+            name: "test".to_string(),
+            pattern: "test".to_string(),
+            examples: vec![],
+            negative_examples: vec![],
+            references: vec![],
+            categories: vec![],
+            description: None,
+            path_include: vec!["*.env".to_string()],
+            path_exclude: vec![],
+        })];
+        let rules_db = RulesDatabase::from_rules(rules)?;
+        let blob = Blob::from_bytes(b"some test data for vectorscan".to_vec());
+        let excluded = ProvenanceSet::single(Provenance::from_file("a.txt".into()));
+        let included = ProvenanceSet::single(Provenance::from_file("b.env".into()));
+        let num_matches = |res: ScanResult| match res {
+            ScanResult::New(matches) => Some(matches.len()),
+            _ => None,
+        };
+
+        let seen_blobs = BlobIdMap::new();
+        let mut matcher = Matcher::new(&rules_db, &seen_blobs, None)?;
+        assert_eq!(num_matches(matcher.scan_blob(&blob, &excluded)?), Some(0));
+        assert_eq!(num_matches(matcher.scan_blob(&blob, &included)?), Some(1));
+        assert_eq!(num_matches(matcher.scan_blob(&blob, &excluded)?), None);
+
+        let seen_blobs = BlobIdMap::new();
+        let mut matcher = Matcher::new(&rules_db, &seen_blobs, None)?;
+        assert_eq!(num_matches(matcher.scan_blob(&blob, &included)?), Some(1));
+        assert_eq!(num_matches(matcher.scan_blob(&blob, &excluded)?), None);
+        Ok(())
+    }
 }
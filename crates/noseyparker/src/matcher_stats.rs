@@ -8,6 +8,21 @@ pub struct MatcherStats {
     pub bytes_seen: u64,
     pub bytes_scanned: u64,
 
+    /// The number of blobs that were skipped because they were explicitly excluded
+    pub blobs_excluded: u64,
+
+    /// The number of blobs that were skipped because they looked minified
+    pub blobs_minified: u64,
+
+    /// The number of scanned blobs that had at least one match
+    pub blobs_with_matches: u64,
+
+    /// The sizes of the blobs seen
+    pub blob_sizes: SizeHistogram,
+
+    /// The number of raw matches from the first-stage matcher for each rule
+    pub rule_match_counts: RuleMatchCounts,
+
     #[cfg(feature = "rule_profiling")]
     pub rule_stats: crate::rule_profiling::RuleProfile,
 }
@@ -18,8 +33,146 @@ impl MatcherStats {
         self.blobs_scanned += other.blobs_scanned;
         self.bytes_seen += other.bytes_seen;
         self.bytes_scanned += other.bytes_scanned;
+        self.blobs_excluded += other.blobs_excluded;
+        self.blobs_minified += other.blobs_minified;
+        self.blobs_with_matches += other.blobs_with_matches;
+        self.blob_sizes.update(&other.blob_sizes);
+        self.rule_match_counts.update(&other.rule_match_counts);
 
         #[cfg(feature = "rule_profiling")]
         self.rule_stats.update(&other.rule_stats);
     }
 }
+
+// -------------------------------------------------------------------------------------------------
+// SizeHistogram
+// -------------------------------------------------------------------------------------------------
+/// A histogram of sizes with power-of-two buckets.
+///
+/// Bucket 0 counts sizes of 0; bucket `i > 0` counts sizes in `[2^(i-1), 2^i)`.
+#[derive(Debug, Clone)]
+pub struct SizeHistogram {
+    counts: [u64; 65],
+}
+
+impl Default for SizeHistogram {
+    fn default() -> Self {
+        Self { counts: [0; 65] }
+    }
+}
+
+impl SizeHistogram {
+    #[inline]
+    pub fn record(&mut self, size: u64) {
+        self.counts[(u64::BITS - size.leading_zeros()) as usize] += 1;
+    }
+
+    pub fn update(&mut self, other: &Self) {
+        for (count, other_count) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *count += other_count;
+        }
+    }
+
+    /// Iterate over the non-empty buckets as `(min_size, max_size, count)` triples, where the
+    /// size bounds are inclusive.
+    pub fn buckets(&self) -> impl Iterator<Item = (u64, u64, u64)> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(i, count)| match i {
+                0 => (0, 0, *count),
+                i => (1 << (i - 1), u64::MAX >> (64 - i), *count),
+            })
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// RuleMatchCounts
+// -------------------------------------------------------------------------------------------------
+/// Counts of raw matches, indexed by rule ID.
+///
+/// Unlike the `rule_profiling` feature, this involves no timing, so it is cheap enough to always
+/// be collected.
+#[derive(Debug, Default, Clone)]
+pub struct RuleMatchCounts {
+    counts: Vec<u64>,
+}
+
+impl RuleMatchCounts {
+    #[inline]
+    pub fn increment(&mut self, rule_id: usize) {
+        if rule_id >= self.counts.len() {
+            self.counts.resize(rule_id + 1, 0);
+        }
+        self.counts[rule_id] += 1;
+    }
+
+    pub fn update(&mut self, other: &Self) {
+        if other.counts.len() > self.counts.len() {
+            self.counts.resize(other.counts.len(), 0);
+        }
+        for (count, other_count) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *count += other_count;
+        }
+    }
+
+    /// Get the `(rule_id, count)` pairs for rules with nonzero counts, in descending order of
+    /// count, with ties broken by rule ID.
+    pub fn sorted_entries(&self) -> Vec<(usize, u64)> {
+        let mut entries: Vec<(usize, u64)> = self
+            .counts
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        entries.sort_by_key(|(rule_id, count)| (std::cmp::Reverse(*count), *rule_id));
+        entries
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn size_histogram_buckets() {
+        let mut h1 = SizeHistogram::default();
+        for size in [0, 1, 2, 3, 4, 1000, 1023, 1024, u64::MAX] {
+            h1.record(size);
+        }
+        let mut h2 = SizeHistogram::default();
+        h2.record(3);
+        h1.update(&h2);
+
+        assert_eq!(
+            h1.buckets().collect::<Vec<_>>(),
+            vec![
+                (0, 0, 1),
+                (1, 1, 1),
+                (2, 3, 3),
+                (4, 7, 1),
+                (512, 1023, 2),
+                (1024, 2047, 1),
+                (1 << 63, u64::MAX, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn rule_match_counts_sorted_entries() {
+        let mut c1 = RuleMatchCounts::default();
+        for rule_id in [3, 1, 3, 0] {
+            c1.increment(rule_id);
+        }
+        let mut c2 = RuleMatchCounts::default();
+        for rule_id in [5, 0, 3] {
+            c2.increment(rule_id);
+        }
+        c1.update(&c2);
+
+        assert_eq!(c1.sorted_entries(), vec![(3, 3), (0, 2), (1, 1), (5, 1)]);
+    }
+}
@@ -25,6 +25,13 @@ impl Provenance {
         Provenance::File(FileProvenance { path })
     }
 
+    /// Create a `Provenance` entry for content read from standard input.
+    ///
+    /// This is recorded as a file with the path `<stdin>`.
+    pub fn from_stdin() -> Self {
+        Self::from_file(PathBuf::from("<stdin>"))
+    }
+
     /// Create a `Provenance` entry for a blob found within a Git repo's history, without any extra
     /// commit provenance.
     ///
@@ -33,6 +40,19 @@ impl Provenance {
         Provenance::GitRepo(GitRepoProvenance {
             repo_path,
             first_commit: None,
+            blob_path: None,
+        })
+    }
+
+    /// Create a `Provenance` entry for a blob found within a Git repo's history, with a
+    /// representative path for the blob but without any commit provenance.
+    ///
+    /// See also `from_git_repo_with_first_commit`.
+    pub fn from_git_repo_with_path(repo_path: Arc<PathBuf>, blob_path: BString) -> Self {
+        Provenance::GitRepo(GitRepoProvenance {
+            repo_path,
+            first_commit: None,
+            blob_path: Some(blob_path),
         })
     }
 
@@ -52,6 +72,7 @@ impl Provenance {
         Provenance::GitRepo(GitRepoProvenance {
             repo_path,
             first_commit,
+            blob_path: None,
         })
     }
 
@@ -65,10 +86,7 @@ impl Provenance {
         use bstr::ByteSlice;
         match self {
             Self::File(e) => Some(&e.path),
-            Self::GitRepo(e) => e
-                .first_commit
-                .as_ref()
-                .and_then(|c| c.blob_path.to_path().ok()),
+            Self::GitRepo(e) => e.blob_path().and_then(|p| p.to_path().ok()),
             Self::Extended(e) => e.path(),
         }
     }
@@ -86,7 +104,10 @@ impl std::fmt::Display for Provenance {
                     md.commit_metadata.commit_id,
                     md.blob_path,
                 ),
-                None => write!(f, "git repo {}", e.repo_path.display()),
+                None => match &e.blob_path {
+                    Some(p) => write!(f, "git repo {}: seen as {}", e.repo_path.display(), p),
+                    None => write!(f, "git repo {}", e.repo_path.display()),
+                },
             },
             Provenance::Extended(e) => {
                 write!(f, "extended {}", e)
@@ -112,6 +133,26 @@ pub struct FileProvenance {
 pub struct GitRepoProvenance {
     pub repo_path: Arc<PathBuf>,
     pub first_commit: Option<CommitProvenance>,
+
+    /// A representative path for the blob, recorded when `first_commit` is not
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "bstring_serde::option_lossy_utf8"
+    )]
+    #[schemars(with = "Option<String>")]
+    pub blob_path: Option<BString>,
+}
+
+impl GitRepoProvenance {
+    /// Get the path for the blob, from the commit provenance if available, or else from the
+    /// representative path.
+    pub fn blob_path(&self) -> Option<&BString> {
+        match &self.first_commit {
+            Some(c) => Some(&c.blob_path),
+            None => self.blob_path.as_ref(),
+        }
+    }
 }
 
 // -------------------------------------------------------------------------------------------------
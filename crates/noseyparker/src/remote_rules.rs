@@ -0,0 +1,87 @@
+use anyhow::{Context, Result};
+use reqwest::header::{ETAG, IF_NONE_MATCH};
+use reqwest::StatusCode;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+use url::Url;
+
+const USER_AGENT: &str = "noseyparker";
+
+/// Fetch the rules file at the given HTTP or HTTPS URL into the given cache directory, returning
+/// the path of the cached file.
+///
+/// The cached file is named according to a hash of the URL, so that each URL has a single cached
+/// copy. The `ETag` of a fetched file, if any, is recorded alongside it; later fetches of the same
+/// URL send it back, and a server reporting that the file is unchanged is answered from the cache
+/// rather than downloading the file again.
+///
+/// This is a high-level wrapper that handles the details of creating an async runtime and an HTTP
+/// client.
+pub fn fetch_rules_url(url: &Url, cache_dir: &Path, ignore_certs: bool) -> Result<PathBuf> {
+    let key = noseyparker_digest::sha1_hexdigest(url.as_str().as_bytes());
+    // keep a TOML extension, so that the cached file is loaded in the same format as the original
+    let ext = if url.path().to_ascii_lowercase().ends_with(".toml") {
+        "toml"
+    } else {
+        "yml"
+    };
+    let path = cache_dir.join(format!("remote-rules-{key}.{ext}"));
+    let etag_path = path.with_extension("etag");
+
+    let cached_etag = if path.is_file() {
+        std::fs::read_to_string(&etag_path).ok()
+    } else {
+        None
+    };
+
+    let client = reqwest::ClientBuilder::new()
+        .user_agent(USER_AGENT)
+        .danger_accept_invalid_certs(ignore_certs)
+        .build()
+        .context("Failed to initialize HTTP client")?;
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .context("Failed to initialize async runtime")?;
+
+    let fetched = runtime
+        .block_on(async {
+            let mut request = client.get(url.clone());
+            if let Some(etag) = &cached_etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            let response = request.send().await?.error_for_status()?;
+            if response.status() == StatusCode::NOT_MODIFIED {
+                return Ok(None);
+            }
+            let etag = response
+                .headers()
+                .get(ETAG)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_owned);
+            let body = response.bytes().await?;
+            Ok::<_, reqwest::Error>(Some((etag, body)))
+        })
+        .with_context(|| format!("Failed to fetch rules from {url}"))?;
+
+    match fetched {
+        None => {
+            debug!("Rules at {url} are unchanged; using cached copy at {}", path.display());
+        }
+        Some((etag, body)) => {
+            debug!("Fetched {} bytes of rules from {url} to {}", body.len(), path.display());
+            std::fs::write(&path, &body)
+                .with_context(|| format!("Failed to write rules to {}", path.display()))?;
+            match etag {
+                Some(etag) => std::fs::write(&etag_path, etag)
+                    .with_context(|| format!("Failed to write {}", etag_path.display()))?,
+                None => {
+                    let _ = std::fs::remove_file(&etag_path);
+                }
+            }
+        }
+    }
+
+    Ok(path)
+}
@@ -1,16 +1,19 @@
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use regex::bytes::Regex;
 use std::time::Instant;
 use tracing::{debug, debug_span};
 use vectorscan_rs::{BlockDatabase, Flag, Pattern};
 
-use noseyparker_rules::Rule;
+use noseyparker_rules::{PathFilter, Rule};
 
 pub struct RulesDatabase {
     // NOTE: pub(crate) here so that `Matcher` can access these
     pub(crate) rules: Vec<Rule>,
     pub(crate) anchored_regexes: Vec<Regex>,
     pub(crate) vsdb: BlockDatabase,
+
+    /// The compiled path constraints of each rule, if it has any
+    path_filters: Vec<Option<PathFilter>>,
 }
 
 impl RulesDatabase {
@@ -46,11 +49,21 @@ impl RulesDatabase {
             .collect::<Result<Vec<Regex>>>()?;
         let d2 = t2.elapsed().as_secs_f64();
 
+        let path_filters = rules
+            .iter()
+            .map(|r| {
+                r.syntax().path_filter().with_context(|| {
+                    format!("Failed to compile path constraints for rule {}", r.id())
+                })
+            })
+            .collect::<Result<Vec<Option<PathFilter>>>>()?;
+
         debug!("Compiled {} rules: vectorscan {}s; regex {}s", rules.len(), d1, d2);
         Ok(RulesDatabase {
             rules,
             vsdb,
             anchored_regexes,
+            path_filters,
         })
     }
 
@@ -65,6 +78,25 @@ impl RulesDatabase {
     pub fn rules(&self) -> &[Rule] {
         self.rules.as_slice()
     }
+
+    /// Get the path constraints of the rule at the given index, if it has any.
+    pub fn path_filter(&self, index: usize) -> Option<&PathFilter> {
+        self.path_filters.get(index).and_then(Option::as_ref)
+    }
+
+    /// Do any of the rules have path constraints?
+    pub fn has_path_filters(&self) -> bool {
+        self.path_filters.iter().any(Option::is_some)
+    }
+
+    /// Get the size in bytes of the compiled Vectorscan database.
+    pub fn database_size(&self) -> Result<usize> {
+        let size = self
+            .vsdb
+            .size()
+            .context("Failed to get Vectorscan database size")?;
+        Ok(size)
+    }
 }
 
 #[cfg(test)]
@@ -92,4 +124,25 @@ mod test {
         assert_eq!(matches, vec![(5, 9)]);
         Ok(())
     }
+
+    #[test]
+    pub fn test_database_size() -> Result<()> {
+        use noseyparker_rules::RuleSyntax;
+
+        let rules = vec![Rule::new(RuleSyntax {
+            id: "test.1".to_string(),
+            name: "test".to_string(),
+            pattern: r"secret=(\w+)".to_string(),
+            examples: vec![],
+            negative_examples: vec![],
+            references: vec![],
+            categories: vec![],
+            description: None,
+            path_include: vec![],
+            path_exclude: vec![],
+        })];
+        let rules_db = RulesDatabase::from_rules(rules)?;
+        assert!(rules_db.database_size()? > 0);
+        Ok(())
+    }
 }
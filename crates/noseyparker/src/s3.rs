@@ -0,0 +1,198 @@
+use anyhow::{Context, Result};
+use tracing::debug;
+
+use progress::Progress;
+
+/// A URL of the form `s3://bucket/prefix`, denoting the objects in an S3 bucket whose keys start
+/// with a prefix.
+///
+/// The prefix may be empty, denoting all the objects in the bucket.
+#[derive(Clone, PartialEq, Eq, Debug, PartialOrd, Ord)]
+pub struct S3Url {
+    bucket: String,
+    prefix: String,
+}
+
+impl S3Url {
+    /// Get the name of the bucket of this URL.
+    pub fn bucket(&self) -> &str {
+        &self.bucket
+    }
+
+    /// Get the key prefix of this URL, which may be empty.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+}
+
+impl std::fmt::Display for S3Url {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "s3://{}/{}", self.bucket, self.prefix)
+    }
+}
+
+const S3_URL_ERROR_MESSAGE: &str = "only URLs of the form `s3://bucket/prefix` are supported";
+
+impl std::str::FromStr for S3Url {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rest = s.strip_prefix("s3://").ok_or(S3_URL_ERROR_MESSAGE)?;
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        let bucket_ok = !bucket.is_empty()
+            && bucket
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b"-.".contains(&b));
+        if !bucket_ok {
+            return Err(S3_URL_ERROR_MESSAGE);
+        }
+        Ok(S3Url {
+            bucket: bucket.to_owned(),
+            prefix: prefix.to_owned(),
+        })
+    }
+}
+
+/// An object listed from an S3 bucket.
+#[derive(Debug)]
+pub struct S3Object {
+    /// The name of the bucket containing the object
+    pub bucket: String,
+
+    /// The key of the object
+    pub key: String,
+
+    /// The size of the object in bytes
+    pub size: u64,
+}
+
+impl S3Object {
+    /// Get the `s3://bucket/key` URL of this object.
+    pub fn url(&self) -> String {
+        format!("s3://{}/{}", self.bucket, self.key)
+    }
+}
+
+/// A client for listing and fetching S3 objects.
+///
+/// This is a high-level wrapper that handles the details of creating an async runtime and an S3
+/// API client. Credentials and region are determined from the standard AWS environment variables,
+/// configuration files, and credential providers.
+///
+/// A single client can be used from several threads at once.
+pub struct S3Client {
+    runtime: tokio::runtime::Runtime,
+    client: aws_sdk_s3::Client,
+}
+
+impl S3Client {
+    pub fn new() -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .context("Failed to initialize async runtime")?;
+        let config =
+            runtime.block_on(aws_config::load_defaults(aws_config::BehaviorVersion::latest()));
+        debug!("Using AWS region {:?}", config.region());
+        let client = aws_sdk_s3::Client::new(&config);
+        Ok(Self { runtime, client })
+    }
+
+    /// List the objects in the bucket of the given URL whose keys start with its prefix.
+    ///
+    /// Objects whose keys end with `/`, as created by some tools to represent directories, are
+    /// not listed.
+    pub fn list_objects(
+        &self,
+        url: &S3Url,
+        mut progress: Option<&mut Progress>,
+    ) -> Result<Vec<S3Object>> {
+        self.runtime.block_on(async {
+            let mut pages = self
+                .client
+                .list_objects_v2()
+                .bucket(url.bucket())
+                .prefix(url.prefix())
+                .into_paginator()
+                .send();
+
+            let mut objects = Vec::new();
+            while let Some(page) = pages.next().await {
+                let page = page.with_context(|| format!("Failed to list objects at {url}"))?;
+                for object in page.contents() {
+                    let Some(key) = object.key() else { continue };
+                    if key.ends_with('/') {
+                        continue;
+                    }
+                    objects.push(S3Object {
+                        bucket: url.bucket().to_owned(),
+                        key: key.to_owned(),
+                        size: object.size().unwrap_or(0).try_into().unwrap_or(0),
+                    });
+                    if let Some(progress) = progress.as_mut() {
+                        progress.inc(1);
+                    }
+                }
+            }
+            Ok(objects)
+        })
+    }
+
+    /// Fetch the entire content of the given object.
+    pub fn get_object(&self, object: &S3Object) -> Result<Vec<u8>> {
+        self.runtime.block_on(async {
+            let response = self
+                .client
+                .get_object()
+                .bucket(&object.bucket)
+                .key(&object.key)
+                .send()
+                .await
+                .with_context(|| format!("Failed to fetch {}", object.url()))?;
+            let body = response
+                .body
+                .collect()
+                .await
+                .with_context(|| format!("Failed to read {}", object.url()))?;
+            Ok(body.into_bytes().to_vec())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::str::FromStr;
+
+    #[test]
+    fn ok_bucket_and_prefix() {
+        let url = S3Url::from_str("s3://my-bucket/some/prefix/").unwrap();
+        assert_eq!(url.bucket(), "my-bucket");
+        assert_eq!(url.prefix(), "some/prefix/");
+        assert_eq!(url.to_string(), "s3://my-bucket/some/prefix/");
+    }
+
+    #[test]
+    fn ok_bucket_only() {
+        let url = S3Url::from_str("s3://my.bucket").unwrap();
+        assert_eq!(url.bucket(), "my.bucket");
+        assert_eq!(url.prefix(), "");
+        assert_eq!(url.to_string(), "s3://my.bucket/");
+    }
+
+    #[test]
+    fn bad_scheme() {
+        assert!(S3Url::from_str("https://my-bucket/prefix").is_err());
+    }
+
+    #[test]
+    fn bad_empty_bucket() {
+        assert!(S3Url::from_str("s3:///prefix").is_err());
+    }
+
+    #[test]
+    fn bad_bucket_name() {
+        assert!(S3Url::from_str("s3://My_Bucket/prefix").is_err());
+    }
+}
@@ -0,0 +1,218 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::match_type::Match;
+
+// -------------------------------------------------------------------------------------------------
+// Scorer
+// -------------------------------------------------------------------------------------------------
+/// A `Scorer` assigns scores to matches, indicating how likely each one is to be a true positive.
+///
+/// Scores are floating point numbers in the range [0, 1]; higher scores are more likely to be true
+/// positives.
+pub trait Scorer: Send + Sync {
+    /// Compute the score for the given match, if one can be computed.
+    fn score(&self, m: &Match) -> Option<f64>;
+}
+
+// -------------------------------------------------------------------------------------------------
+// MatchFeatures
+// -------------------------------------------------------------------------------------------------
+/// Simple numeric features of a match's content, for use by scoring models.
+///
+/// These are computed from the match's capture groups, or from the matching input if it has no
+/// capture groups.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MatchFeatures {
+    /// The natural logarithm of 1 plus the content length in bytes
+    pub log_length: f64,
+
+    /// The Shannon entropy of the content, in bits per byte
+    pub entropy: f64,
+
+    /// The fraction of content bytes that are ASCII digits
+    pub digit_fraction: f64,
+
+    /// The fraction of content bytes that are not ASCII alphanumeric
+    pub symbol_fraction: f64,
+}
+
+impl MatchFeatures {
+    pub fn new(m: &Match) -> Self {
+        let content: Vec<u8> = if m.groups.0.is_empty() {
+            m.snippet.matching.to_vec()
+        } else {
+            m.groups
+                .0
+                .iter()
+                .flat_map(|g| g.0.iter().copied())
+                .collect()
+        };
+        Self::from_bytes(&content)
+    }
+
+    pub fn from_bytes(content: &[u8]) -> Self {
+        let len = content.len();
+        if len == 0 {
+            return Self {
+                log_length: 0.0,
+                entropy: 0.0,
+                digit_fraction: 0.0,
+                symbol_fraction: 0.0,
+            };
+        }
+
+        let mut counts = [0usize; 256];
+        for &b in content {
+            counts[b as usize] += 1;
+        }
+        let len_f = len as f64;
+        let entropy = counts
+            .iter()
+            .filter(|&&c| c > 0)
+            .map(|&c| {
+                let p = c as f64 / len_f;
+                -p * p.log2()
+            })
+            .sum();
+        let num_digits = content.iter().filter(|b| b.is_ascii_digit()).count();
+        let num_symbols = content
+            .iter()
+            .filter(|b| !b.is_ascii_alphanumeric())
+            .count();
+
+        Self {
+            log_length: (len_f + 1.0).ln(),
+            entropy,
+            digit_fraction: num_digits as f64 / len_f,
+            symbol_fraction: num_symbols as f64 / len_f,
+        }
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// LinearModel
+// -------------------------------------------------------------------------------------------------
+/// A logistic regression scoring model over `MatchFeatures`.
+///
+/// The score for a match is `sigmoid(intercept + rule_offsets[rule] + sum(weights * features))`,
+/// where `rule` is the text identifier of the rule that produced the match.
+///
+/// This is loaded from a JSON file like the following, where every field is optional:
+///
+/// ```json
+/// {
+///   "intercept": -1.0,
+///   "rule_offsets": { "np.generic.1": -2.0 },
+///   "weights": { "log_length": 0.1, "entropy": 0.8, "digit_fraction": 0.0, "symbol_fraction": -1.5 }
+/// }
+/// ```
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LinearModel {
+    #[serde(default)]
+    pub intercept: f64,
+
+    #[serde(default)]
+    pub rule_offsets: HashMap<String, f64>,
+
+    #[serde(default)]
+    pub weights: FeatureWeights,
+}
+
+/// Weights for each of the `MatchFeatures` in a `LinearModel`.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FeatureWeights {
+    #[serde(default)]
+    pub log_length: f64,
+
+    #[serde(default)]
+    pub entropy: f64,
+
+    #[serde(default)]
+    pub digit_fraction: f64,
+
+    #[serde(default)]
+    pub symbol_fraction: f64,
+}
+
+impl LinearModel {
+    /// Load a model from the given JSON file.
+    pub fn from_json_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read scoring model from {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse scoring model from {}", path.display()))
+    }
+
+    /// Compute the score for the given features, as produced by the given rule.
+    pub fn score_features(&self, rule_text_id: &str, f: &MatchFeatures) -> f64 {
+        let w = &self.weights;
+        let z = self.intercept
+            + self.rule_offsets.get(rule_text_id).copied().unwrap_or(0.0)
+            + w.log_length * f.log_length
+            + w.entropy * f.entropy
+            + w.digit_fraction * f.digit_fraction
+            + w.symbol_fraction * f.symbol_fraction;
+        1.0 / (1.0 + (-z).exp())
+    }
+}
+
+impl Scorer for LinearModel {
+    fn score(&self, m: &Match) -> Option<f64> {
+        let score = self.score_features(&m.rule_text_id, &MatchFeatures::new(m));
+        score.is_finite().then_some(score)
+    }
+}
+
+// -------------------------------------------------------------------------------------------------
+// tests
+// -------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn features_empty() {
+        let f = MatchFeatures::from_bytes(b"");
+        assert_eq!(f.log_length, 0.0);
+        assert_eq!(f.entropy, 0.0);
+    }
+
+    #[test]
+    fn features_basic() {
+        let f = MatchFeatures::from_bytes(b"ab12");
+        assert_eq!(f.entropy, 2.0);
+        assert_eq!(f.digit_fraction, 0.5);
+        assert_eq!(f.symbol_fraction, 0.0);
+
+        let f = MatchFeatures::from_bytes(b"aaaa");
+        assert_eq!(f.entropy, 0.0);
+    }
+
+    #[test]
+    fn linear_model() -> Result<()> {
+        let model: LinearModel = serde_json::from_str(
+            r#"{"intercept": 0.0, "rule_offsets": {"r.1": 100.0}, "weights": {"entropy": -100.0}}"#,
+        )?;
+        let f = MatchFeatures::from_bytes(b"ab12");
+        assert!(model.score_features("r.1", &f) < 0.01);
+        assert!(model.score_features("r.2", &f) < 0.01);
+
+        let f = MatchFeatures::from_bytes(b"aaaa");
+        assert!(model.score_features("r.1", &f) > 0.99);
+        assert_eq!(model.score_features("r.2", &f), 0.5);
+        Ok(())
+    }
+
+    #[test]
+    fn linear_model_unknown_field() {
+        let res: Result<LinearModel, _> = serde_json::from_str(r#"{"weights": {"bogus": 1.0}}"#);
+        assert!(res.is_err());
+    }
+}
@@ -4,8 +4,10 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 // use std::borrow::Cow;
 use std::fmt::{Display, Formatter};
+use std::ops::Range;
 
 use crate::bstring_escape::Escaped;
+use crate::location::OffsetSpan;
 
 #[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct Snippet {
@@ -33,3 +35,374 @@ impl Display for Snippet {
         )
     }
 }
+
+// -------------------------------------------------------------------------------------------------
+// SnippetMode
+// -------------------------------------------------------------------------------------------------
+/// How the context before and after a match is delimited when extracting a snippet.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SnippetMode {
+    /// Use up to a fixed number of bytes of context
+    #[default]
+    Bytes,
+
+    /// Use up to a fixed number of bytes of context, trimmed to whole lines when possible
+    Line,
+
+    /// Use up to a fixed number of bytes of context, but only from the lines containing the match
+    MatchingLines,
+
+    /// Use the structure enclosing the match in a blob of the given format.
+    ///
+    /// If the enclosing structure cannot be determined, or it extends much further than the
+    /// fixed number of bytes of context, this falls back to `Bytes`.
+    Structured(StructuredFormat),
+}
+
+/// A format of structured data that is understood by `SnippetMode::Structured`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StructuredFormat {
+    Json,
+    Yaml,
+}
+
+impl StructuredFormat {
+    /// Get the structured format corresponding to the given MIME type essence, if there is one.
+    pub fn from_mime_essence(essence: &str) -> Option<Self> {
+        match essence {
+            "application/json" => Some(Self::Json),
+            "application/yaml" | "application/x-yaml" | "text/yaml" | "text/x-yaml" => {
+                Some(Self::Yaml)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// In `SnippetMode::Structured`, how many times the fixed number of context bytes the enclosing
+/// structure can extend before and after a match.
+const STRUCTURED_CONTEXT_FACTOR: usize = 4;
+
+/// Compute the ranges of `bytes` to use as context before and after the match at `span`, using
+/// up to `before_bytes` and `after_bytes` of context, respectively.
+pub fn context_ranges(
+    bytes: &[u8],
+    span: OffsetSpan,
+    before_bytes: usize,
+    after_bytes: usize,
+    mode: SnippetMode,
+) -> (Range<usize>, Range<usize>) {
+    let before = span.start.saturating_sub(before_bytes)..span.start;
+    let after = span.end..span.end.saturating_add(after_bytes).min(bytes.len());
+
+    match mode {
+        SnippetMode::Bytes => (before, after),
+        SnippetMode::Line => (trim_to_line_start(bytes, before), trim_to_line_end(bytes, after)),
+        SnippetMode::MatchingLines => {
+            (after_last_line_break(bytes, before), before_first_line_break(bytes, after))
+        }
+        SnippetMode::Structured(format) => {
+            let limits = (
+                before_bytes.saturating_mul(STRUCTURED_CONTEXT_FACTOR),
+                after_bytes.saturating_mul(STRUCTURED_CONTEXT_FACTOR),
+            );
+            let enclosing = match format {
+                StructuredFormat::Json => enclosing_json(bytes, span, limits),
+                StructuredFormat::Yaml => enclosing_yaml(bytes, span, limits),
+            };
+            match enclosing {
+                Some(r) => (r.start..span.start, span.end..r.end),
+                None => (before, after),
+            }
+        }
+    }
+}
+
+/// Move the start of `range` to the start of a line, unless that would leave it empty.
+fn trim_to_line_start(bytes: &[u8], range: Range<usize>) -> Range<usize> {
+    if range.start == 0 || bytes[range.start - 1] == b'\n' {
+        return range;
+    }
+    match bytes[range.clone()].iter().position(|&b| b == b'\n') {
+        Some(i) => range.start + i + 1..range.end,
+        None => range,
+    }
+}
+
+/// Move the end of `range` to the end of a line, unless that would leave it empty.
+fn trim_to_line_end(bytes: &[u8], range: Range<usize>) -> Range<usize> {
+    if range.end == bytes.len() || bytes[range.end] == b'\n' {
+        return range;
+    }
+    match bytes[range.clone()].iter().rposition(|&b| b == b'\n') {
+        Some(i) if i > 0 => range.start..range.start + i,
+        _ => range,
+    }
+}
+
+/// Move the start of `range` to just after the last line break within it, if there is one.
+fn after_last_line_break(bytes: &[u8], range: Range<usize>) -> Range<usize> {
+    match bytes[range.clone()]
+        .iter()
+        .rposition(|&b| b == b'\n' || b == b'\r')
+    {
+        Some(i) => range.start + i + 1..range.end,
+        None => range,
+    }
+}
+
+/// Move the end of `range` to just before the first line break within it, if there is one.
+fn before_first_line_break(bytes: &[u8], range: Range<usize>) -> Range<usize> {
+    match bytes[range.clone()]
+        .iter()
+        .position(|&b| b == b'\n' || b == b'\r')
+    {
+        Some(i) => range.start..range.start + i,
+        None => range,
+    }
+}
+
+/// Find the nearest JSON object or array that encloses `span`, extending at most the given
+/// `(before, after)` limits of bytes before and after it.
+///
+/// This is a heuristic that does not fully parse the input.
+fn enclosing_json(
+    bytes: &[u8],
+    span: OffsetSpan,
+    (before_limit, after_limit): (usize, usize),
+) -> Option<Range<usize>> {
+    let min_start = span.start.saturating_sub(before_limit);
+    let max_end = span.end.saturating_add(after_limit).min(bytes.len());
+
+    (min_start..span.start)
+        .rev()
+        .filter(|&i| matches!(bytes[i], b'{' | b'['))
+        .find_map(|start| {
+            let end = matching_json_close(&bytes[start..max_end])? + start + 1;
+            (end >= span.end).then_some(start..end)
+        })
+}
+
+/// Given input that starts with a JSON `{` or `[`, find the offset of its matching close bracket.
+fn matching_json_close(bytes: &[u8]) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, &b) in bytes.iter().enumerate() {
+        if in_string {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match b {
+            b'"' => in_string = true,
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Find the YAML block that encloses `span`, extending at most the given `(before, after)` limits
+/// of bytes before and after it.
+///
+/// The block consists of the line with the nearest less-indented key before the match, followed by
+/// the contiguous lines that are indented at least as much as the line of the match.
+/// This is a heuristic that does not fully parse the input.
+fn enclosing_yaml(
+    bytes: &[u8],
+    span: OffsetSpan,
+    (before_limit, after_limit): (usize, usize),
+) -> Option<Range<usize>> {
+    let min_start = span.start.saturating_sub(before_limit);
+    let max_end = span.end.saturating_add(after_limit).min(bytes.len());
+
+    let line_start = |i: usize| {
+        bytes[..i]
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |p| p + 1)
+    };
+    let line_end = |i: usize| {
+        bytes[i..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(bytes.len(), |p| i + p)
+    };
+    // The indentation of the line starting at `i`, or `None` if it is blank or a comment
+    let indent = |i: usize| {
+        let line = &bytes[i..line_end(i)];
+        let n = line.iter().take_while(|&&b| b == b' ').count();
+        match line.get(n) {
+            None | Some(b'#') => None,
+            Some(_) => Some(n),
+        }
+    };
+
+    let match_line = line_start(span.start);
+    let match_indent = indent(match_line)?;
+
+    // Find the parent line before the match
+    let mut start = match_line;
+    loop {
+        if start == 0 {
+            // top-level entries have no enclosing block
+            return None;
+        }
+        start = line_start(start - 1);
+        if start < min_start {
+            return None;
+        }
+        if indent(start).is_some_and(|n| n < match_indent) {
+            break;
+        }
+    }
+
+    // Extend to the last sibling or nested line after the match
+    let mut end = line_end(span.end);
+    while end < bytes.len() {
+        let next = end + 1;
+        if next >= bytes.len() {
+            break;
+        }
+        match indent(next) {
+            Some(n) if n < match_indent => break,
+            _ => {}
+        }
+        let next_end = line_end(next);
+        if next_end > max_end {
+            return None;
+        }
+        end = next_end;
+    }
+
+    Some(start..end)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Get the before and after context of the first occurrence of `needle` in `input`.
+    fn context<'a>(
+        input: &'a str,
+        needle: &str,
+        context_bytes: usize,
+        mode: SnippetMode,
+    ) -> (&'a str, &'a str) {
+        let start = input.find(needle).unwrap();
+        let span = OffsetSpan {
+            start,
+            end: start + needle.len(),
+        };
+        let (before, after) =
+            context_ranges(input.as_bytes(), span, context_bytes, context_bytes, mode);
+        (&input[before], &input[after])
+    }
+
+    #[test]
+    fn bytes_mode() {
+        let input = "aaaa\nbb secret cc\ndddd\n";
+        assert_eq!(context(input, "secret", 5, SnippetMode::Bytes), ("a\nbb ", " cc\nd"));
+    }
+
+    #[test]
+    fn bytes_mode_asymmetric() {
+        let input = "aaaa\nbb secret cc\ndddd\n";
+        let span = OffsetSpan { start: 8, end: 14 };
+        let (before, after) = context_ranges(input.as_bytes(), span, 2, 7, SnippetMode::Bytes);
+        assert_eq!((&input[before], &input[after]), ("b ", " cc\nddd"));
+    }
+
+    #[test]
+    fn line_mode() {
+        let input = "aaaa\nbb secret cc\ndddd\n";
+        assert_eq!(context(input, "secret", 5, SnippetMode::Line), ("bb ", " cc"));
+    }
+
+    #[test]
+    fn line_mode_no_line_break() {
+        let input = "aaaa bb secret cc dddd";
+        assert_eq!(context(input, "secret", 5, SnippetMode::Line), ("a bb ", " cc d"));
+    }
+
+    #[test]
+    fn matching_lines_mode() {
+        let input = "aaaa\nbb secret cc\r\ndddd\n";
+        assert_eq!(context(input, "secret", 64, SnippetMode::Line), ("aaaa\nbb ", " cc\r\ndddd\n"));
+        assert_eq!(context(input, "secret", 64, SnippetMode::MatchingLines), ("bb ", " cc"));
+    }
+
+    #[test]
+    fn matching_lines_mode_multiline_match() {
+        let input = "aaaa\nbb BEGIN\nkey\nEND cc\ndddd\n";
+        assert_eq!(
+            context(input, "BEGIN\nkey\nEND", 64, SnippetMode::MatchingLines),
+            ("bb ", " cc")
+        );
+    }
+
+    #[test]
+    fn matching_lines_mode_long_line() {
+        let input = "aaaa bb secret cc dddd";
+        assert_eq!(context(input, "secret", 5, SnippetMode::MatchingLines), ("a bb ", " cc d"));
+    }
+
+    #[test]
+    fn json_mode() {
+        let input = r#"{"a": 1, "creds": {"user": "x", "pass": "secret", "note": "}"}, "b": 2}"#;
+        let mode = SnippetMode::Structured(StructuredFormat::Json);
+        assert_eq!(
+            context(input, "secret", 64, mode),
+            (r#"{"user": "x", "pass": ""#, r#"", "note": "}"}"#)
+        );
+    }
+
+    #[test]
+    fn json_mode_too_large() {
+        let input = r#"{"pass": "secret", "padding": "................................"}"#;
+        let mode = SnippetMode::Structured(StructuredFormat::Json);
+        assert_eq!(context(input, "secret", 4, mode), (r#"": ""#, r#"", ""#));
+    }
+
+    #[test]
+    fn yaml_mode() {
+        let input = "a: 1\ncreds:\n  user: x\n  pass: secret\n  nested:\n    k: v\nb: 2\n";
+        let mode = SnippetMode::Structured(StructuredFormat::Yaml);
+        assert_eq!(
+            context(input, "secret", 64, mode),
+            ("creds:\n  user: x\n  pass: ", "\n  nested:\n    k: v")
+        );
+    }
+
+    #[test]
+    fn yaml_mode_top_level() {
+        let input = "a: 1\npass: secret\nb: 2\n";
+        let mode = SnippetMode::Structured(StructuredFormat::Yaml);
+        assert_eq!(context(input, "secret", 3, mode), ("s: ", "\nb:"));
+    }
+
+    #[test]
+    fn structured_format_from_mime_essence() {
+        assert_eq!(
+            StructuredFormat::from_mime_essence("application/json"),
+            Some(StructuredFormat::Json)
+        );
+        assert_eq!(
+            StructuredFormat::from_mime_essence("text/x-yaml"),
+            Some(StructuredFormat::Yaml)
+        );
+        assert_eq!(StructuredFormat::from_mime_essence("text/plain"), None);
+    }
+}
@@ -1,5 +1,9 @@
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::style::ProgressTracker;
+use indicatif::{HumanBytes, ProgressBar, ProgressState, ProgressStyle};
 use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 /// How often should progress bars be redrawn?
@@ -9,10 +13,81 @@ pub const PROGRESS_UPDATE_INTERVAL: Duration = Duration::from_millis(500);
 //       work well for Nosey Parker, resulting in wildly variable and inaccurate values.
 //       The problem is with the library's internal `Estimator` type.
 //
-//       Until that's fixed or we otherwise work around it, we avoid showing ETAs and rates.
+//       Until that's fixed or we otherwise work around it, we avoid showing ETAs, and show
+//       throughput using our own `Throughput` estimate rather than indicatif's built-in keys.
 //
 //       See https://github.com/console-rs/indicatif/issues/394.
 
+/// The span of time over which throughput is averaged
+pub const THROUGHPUT_WINDOW: Duration = Duration::from_secs(10);
+
+/// A throughput estimate, computed from position samples over a sliding window of time
+#[derive(Default)]
+struct ThroughputWindow {
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl ThroughputWindow {
+    /// Record the position of a progress bar at the given time.
+    fn record(&mut self, now: Instant, pos: u64) {
+        self.samples.push_back((now, pos));
+
+        // Keep the newest sample that is older than the window, so that the estimate always
+        // covers the entire window once enough time has passed.
+        while self.samples.len() > 2 && now.duration_since(self.samples[1].0) >= THROUGHPUT_WINDOW {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Get the average rate of progress over the window, in units per second.
+    ///
+    /// This is `None` until samples spanning at least one second have been recorded.
+    fn rate(&self) -> Option<f64> {
+        let (t0, p0) = *self.samples.front()?;
+        let (t1, p1) = *self.samples.back()?;
+        let secs = t1.duration_since(t0).as_secs_f64();
+        if secs < 1.0 {
+            return None;
+        }
+        Some(p1.saturating_sub(p0) as f64 / secs)
+    }
+}
+
+/// A custom progress bar template key, `{throughput}`, that displays a smoothed bytes-per-second
+/// rate.
+///
+/// The window is shared by all clones, so that a progress bar that changes style keeps its
+/// history.
+#[derive(Clone, Default)]
+struct Throughput(Arc<Mutex<ThroughputWindow>>);
+
+impl Throughput {
+    fn add_to(&self, style: ProgressStyle) -> ProgressStyle {
+        style.with_key("throughput", self.clone())
+    }
+}
+
+impl ProgressTracker for Throughput {
+    fn clone_box(&self) -> Box<dyn ProgressTracker> {
+        Box::new(self.clone())
+    }
+
+    fn tick(&mut self, state: &ProgressState, now: Instant) {
+        self.0.lock().unwrap().record(now, state.pos());
+    }
+
+    fn reset(&mut self, _state: &ProgressState, _now: Instant) {
+        self.0.lock().unwrap().samples.clear();
+    }
+
+    fn write(&self, _state: &ProgressState, w: &mut dyn Write) {
+        let _ = match self.0.lock().unwrap().rate() {
+            Some(rate) => write!(w, "{}/s", HumanBytes(rate as u64)),
+            None => write!(w, "-- B/s"),
+        };
+    }
+}
+
 // XXX Consider switching from indicatif to status_line: https://docs.rs/status-line/latest/status_line/struct.StatusLine.html
 
 /// Wraps an `indicatif::ProgressBar` with a local buffer to reduce update contention overhead.
@@ -26,6 +101,7 @@ pub struct Progress {
     last_sync: Instant,
     inner: ProgressBar,
     finish_style: Option<ProgressStyle>,
+    throughput: Throughput,
 }
 
 impl Progress {
@@ -52,6 +128,7 @@ impl Progress {
             last_sync: Instant::now(),
             inner,
             finish_style: Some(finish_style),
+            throughput: Throughput::default(),
         }
     }
 
@@ -60,6 +137,12 @@ impl Progress {
         self.inner.set_message(message);
     }
 
+    /// Get the current message of this progress bar.
+    #[inline]
+    pub fn message(&self) -> String {
+        self.inner.message()
+    }
+
     pub fn new_countup_spinner<T: Into<Cow<'static, str>>>(message: T, enabled: bool) -> Self {
         let inner = if enabled {
             let style =
@@ -84,17 +167,22 @@ impl Progress {
             last_sync: Instant::now(),
             inner,
             finish_style: Some(finish_style),
+            throughput: Throughput::default(),
         }
     }
 
     pub fn new_bytes_spinner<T: Into<Cow<'static, str>>>(message: T, enabled: bool) -> Self {
+        let throughput = Throughput::default();
         let inner = if enabled {
-            let style =
-                ProgressStyle::with_template("{spinner} {msg} {total_bytes} [{elapsed_precise}]")
-                    .expect("progress bar style template should compile");
+            let style = ProgressStyle::with_template(
+                "{spinner} {msg} {bytes} ({throughput}) [{elapsed_precise}]",
+            )
+            .expect("progress bar style template should compile");
 
-            let inner = ProgressBar::new_spinner()
-                .with_style(style)
+            // The length is not displayed, but it starts at zero rather than unknown so that it can
+            // be increased with `inc_length`, for use with `switch_to_bytes_bar`.
+            let inner = ProgressBar::new(0)
+                .with_style(throughput.add_to(style))
                 .with_message(message);
             inner.enable_steady_tick(PROGRESS_UPDATE_INTERVAL);
 
@@ -111,6 +199,7 @@ impl Progress {
             last_sync: Instant::now(),
             inner,
             finish_style: Some(finish_style),
+            throughput,
         }
     }
 
@@ -136,22 +225,27 @@ impl Progress {
             last_sync: Instant::now(),
             inner,
             finish_style: None,
+            throughput: Throughput::default(),
         }
     }
 
+    fn bytes_bar_style(throughput: &Throughput) -> ProgressStyle {
+        let style = ProgressStyle::with_template(
+            "{msg}  {bar} {percent:>3}%  {bytes}/{total_bytes}  {throughput}  [{elapsed_precise}]",
+        )
+        .expect("progress bar style template should compile");
+        throughput.add_to(style)
+    }
+
     pub fn new_bytes_bar<T: Into<Cow<'static, str>>>(
         total_bytes: u64,
         message: T,
         enabled: bool,
     ) -> Self {
-        let style = ProgressStyle::with_template(
-            "{msg}  {bar} {percent:>3}%  {bytes}/{total_bytes}  [{elapsed_precise}]",
-        )
-        .expect("progress bar style template should compile");
-
+        let throughput = Throughput::default();
         let inner = if enabled {
             let inner = ProgressBar::new(total_bytes)
-                .with_style(style)
+                .with_style(Self::bytes_bar_style(&throughput))
                 .with_message(message);
 
             inner.enable_steady_tick(PROGRESS_UPDATE_INTERVAL);
@@ -166,9 +260,35 @@ impl Progress {
             last_sync: Instant::now(),
             inner,
             finish_style: None,
+            throughput,
+        }
+    }
+
+    /// Switch a progress spinner from `new_bytes_spinner` to display like one from
+    /// `new_bytes_bar`, showing a percentage of its total length.
+    ///
+    /// This is useful when the total is only known partway through, having been accumulated with
+    /// `inc_length`. The switch applies to all clones of this progress bar, and the throughput
+    /// estimate carries over.
+    pub fn switch_to_bytes_bar(&self) {
+        if !self.inner.is_hidden() {
+            self.inner
+                .set_style(Self::bytes_bar_style(&self.throughput));
         }
     }
 
+    /// Increase the total length of this progress bar by the given amount.
+    #[inline]
+    pub fn inc_length(&self, delta: u64) {
+        self.inner.inc_length(delta);
+    }
+
+    /// Is this progress bar displayed at all?
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        !self.inner.is_hidden()
+    }
+
     #[inline]
     pub fn suspend<F: FnOnce() -> R, R>(&self, f: F) -> R {
         self.inner.suspend(f)
@@ -218,6 +338,74 @@ impl Clone for Progress {
             last_sync: Instant::now(),
             inner: self.inner.clone(),
             finish_style: self.finish_style.clone(),
+            throughput: self.throughput.clone(),
+        }
+    }
+}
+
+/// Wraps an `indicatif::MultiProgress`, allowing several `Progress` bars to be displayed
+/// simultaneously, e.g., one for each phase of a long-running command.
+///
+/// When disabled, bars are not registered with the underlying `MultiProgress` at all, as doing so
+/// would give hidden bars a visible draw target.
+#[derive(Clone)]
+pub struct MultiProgress {
+    inner: Option<indicatif::MultiProgress>,
+}
+
+impl MultiProgress {
+    pub fn new(enabled: bool) -> Self {
+        let inner = if enabled {
+            Some(indicatif::MultiProgress::new())
+        } else {
+            None
+        };
+        MultiProgress { inner }
+    }
+
+    /// Register the given progress bar to be displayed below any previously-added ones.
+    ///
+    /// Hidden progress bars are returned unchanged.
+    pub fn add(&self, mut progress: Progress) -> Progress {
+        if let Some(multi) = &self.inner {
+            if !progress.inner.is_hidden() {
+                progress.inner = multi.add(progress.inner.clone());
+            }
+        }
+        progress
+    }
+
+    #[inline]
+    pub fn suspend<F: FnOnce() -> R, R>(&self, f: F) -> R {
+        match &self.inner {
+            Some(multi) => multi.suspend(f),
+            None => f(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn throughput_window() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        let mut window = ThroughputWindow::default();
+        window.record(at(0), 0);
+        assert_eq!(window.rate(), None);
+
+        // 100 units per second for the first 20 seconds, then 10 per second
+        for secs in 1..=20 {
+            window.record(at(secs), secs * 100);
+        }
+        assert_eq!(window.rate(), Some(100.0));
+        for secs in 21..=40 {
+            window.record(at(secs), 2000 + (secs - 20) * 10);
         }
+        assert_eq!(window.rate(), Some(10.0));
+        assert_eq!(window.samples.len(), 11);
     }
 }